@@ -14,6 +14,18 @@ async fn do_localstate_query(client: &mut NodeClient) {
         .unwrap();
 
     info!("system start result: {:?}", result);
+
+    let result = client
+        .statequery()
+        .query(localstate::queries::RequestV10::BlockQuery(
+            localstate::queries::BlockQuery::HardFork(
+                localstate::queries::HardForkQuery::GetInterpreter,
+            ),
+        ))
+        .await
+        .unwrap();
+
+    info!("era history result: {:?}", result);
 }
 
 async fn do_chainsync(client: &mut NodeClient) {
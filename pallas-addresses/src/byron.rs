@@ -3,7 +3,14 @@ use pallas_codec::{
     utils::{OrderPreservingProperties, TagWrap},
 };
 
-use pallas_crypto::hash::Hash;
+use cryptoxide::{
+    chacha20poly1305::ChaCha20Poly1305, hashing::sha3_256, hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512,
+};
+use pallas_crypto::{
+    hash::{Hash, Hasher},
+    key::bip32::{harden, Role, XPrv, XPub, COIN_TYPE},
+    memsec::Scrubbed as _,
+};
 
 use crate::Error;
 
@@ -173,6 +180,74 @@ pub struct ByronAddress {
     crc: u32,
 }
 
+/// The purpose of the BIP44 derivation paths used by Icarus wallets
+pub const BIP44_PURPOSE: u32 = harden(44);
+
+const HD_PAYLOAD_SALT: &[u8] = b"address-hashing";
+const HD_PAYLOAD_ITERATIONS: u32 = 500;
+const HD_PAYLOAD_NONCE: &[u8] = b"serokellfore";
+const HD_PAYLOAD_TAG_SIZE: usize = 16;
+
+/// Key protecting the derivation path stored in the addresses of legacy
+/// random (Daedalus) wallets, derived from the root public key of the wallet
+pub struct HdPayloadKey([u8; 32]);
+
+impl HdPayloadKey {
+    pub fn new(root: &XPub) -> Self {
+        let mut key = [0; 32];
+        let mut mac = Hmac::new(Sha512::new(), root.as_ref());
+        pbkdf2(&mut mac, HD_PAYLOAD_SALT, HD_PAYLOAD_ITERATIONS, &mut key);
+        Self(key)
+    }
+
+    /// encrypt the derivation path into the payload of the
+    /// [`AddrAttrProperty::DerivationPath`] attribute
+    pub fn encrypt_path(&self, path: &[u32]) -> Vec<u8> {
+        let mut encoder = minicbor::Encoder::new(Vec::new());
+        encoder.begin_array().unwrap();
+        for index in path {
+            encoder.u32(*index).unwrap();
+        }
+        encoder.end().unwrap();
+        let plaintext = encoder.into_writer();
+
+        let mut out = vec![0; plaintext.len() + HD_PAYLOAD_TAG_SIZE];
+        let (data, tag) = out.split_at_mut(plaintext.len());
+
+        ChaCha20Poly1305::new(&self.0, HD_PAYLOAD_NONCE, &[]).encrypt(&plaintext, data, tag);
+
+        out
+    }
+
+    /// decrypt the payload of a [`AddrAttrProperty::DerivationPath`]
+    /// attribute, failing if it wasn't encrypted with this key
+    pub fn decrypt_path(&self, payload: &[u8]) -> Result<Vec<u32>, Error> {
+        if payload.len() < HD_PAYLOAD_TAG_SIZE {
+            return Err(Error::InvalidHdPayload);
+        }
+
+        let (data, tag) = payload.split_at(payload.len() - HD_PAYLOAD_TAG_SIZE);
+        let mut plaintext = vec![0; data.len()];
+
+        if !ChaCha20Poly1305::new(&self.0, HD_PAYLOAD_NONCE, &[]).decrypt(data, &mut plaintext, tag)
+        {
+            return Err(Error::InvalidHdPayload);
+        }
+
+        minicbor::decode(&plaintext).map_err(|_| Error::InvalidHdPayload)
+    }
+}
+
+impl Drop for HdPayloadKey {
+    fn drop(&mut self) {
+        self.0.scrub();
+    }
+}
+
+fn network_tag(network_magic: u32) -> AddrAttrProperty {
+    AddrAttrProperty::NetworkTag(minicbor::to_vec(network_magic).unwrap().into())
+}
+
 const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
 
 impl ByronAddress {
@@ -211,6 +286,129 @@ impl ByronAddress {
         ByronAddress::new(&payload, c)
     }
 
+    /// build the address paying to the given extended public key, with the
+    /// given attributes. Addresses of the bootstrap era leave out the
+    /// [`AddrAttrProperty::AddrDistr`] attribute.
+    pub fn new_pubkey(xpub: &XPub, attributes: AddrAttrs) -> Self {
+        let mut spending = minicbor::Encoder::new(Vec::new());
+        spending
+            .array(3)
+            .unwrap()
+            .encode(AddrType::PubKey)
+            .unwrap()
+            .array(2)
+            .unwrap()
+            .u8(0)
+            .unwrap()
+            .bytes(xpub.as_ref())
+            .unwrap()
+            .encode(&attributes)
+            .unwrap();
+
+        let root = Hasher::<224>::hash(&sha3_256(&spending.into_writer()));
+
+        let payload = AddressPayload {
+            root,
+            attributes,
+            addrtype: AddrType::PubKey,
+        };
+
+        let payload = minicbor::to_vec(payload).unwrap();
+
+        let c = CRC.checksum(&payload);
+
+        ByronAddress::new(&payload, c)
+    }
+
+    /// build the address of an Icarus (or Byron era Ledger / Trezor) wallet
+    /// for the given key, mainnet addresses have no network magic
+    pub fn new_icarus(xpub: &XPub, network_magic: Option<u32>) -> Self {
+        let attributes = network_magic
+            .map(network_tag)
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        Self::new_pubkey(xpub, attributes.into())
+    }
+
+    /// build the address of a random (Daedalus) wallet for the given key,
+    /// carrying its derivation path encrypted with the key of the wallet
+    pub fn new_random(
+        xpub: &XPub,
+        hd_key: &HdPayloadKey,
+        path: &[u32],
+        network_magic: Option<u32>,
+    ) -> Self {
+        let payload = hd_key.encrypt_path(path);
+        let payload = minicbor::to_vec(ByteVec::from(payload)).unwrap();
+
+        let mut attributes = vec![AddrAttrProperty::DerivationPath(payload.into())];
+        attributes.extend(network_magic.map(network_tag));
+
+        Self::new_pubkey(xpub, attributes.into())
+    }
+
+    /// derive the address of a random (Daedalus) wallet at
+    /// `m / account' / index'` of the root key, using the legacy
+    /// [`DerivationScheme::V1`](pallas_crypto::key::bip32::DerivationScheme)
+    pub fn derive_random(
+        root: &XPrv,
+        account: u32,
+        index: u32,
+        network_magic: Option<u32>,
+    ) -> Self {
+        use pallas_crypto::key::bip32::DerivationScheme::V1;
+
+        let path = [harden(account), harden(index)];
+
+        let key = path
+            .iter()
+            .fold(root.clone(), |key, index| key.derive_with(V1, *index));
+
+        let hd_key = HdPayloadKey::new(&root.public());
+
+        Self::new_random(&key.public(), &hd_key, &path, network_magic)
+    }
+
+    /// derive the address of an Icarus wallet at
+    /// `m / 44' / 1815' / account' / role / index` of the root key
+    pub fn derive_icarus(
+        root: &XPrv,
+        account: u32,
+        role: Role,
+        index: u32,
+        network_magic: Option<u32>,
+    ) -> Self {
+        let key = root.derive_path(&[
+            BIP44_PURPOSE,
+            COIN_TYPE,
+            harden(account),
+            role as u32,
+            index,
+        ]);
+
+        Self::new_icarus(&key.public(), network_magic)
+    }
+
+    /// the derivation path stored in the address, if any, decrypted with the
+    /// key of the wallet. Fails if the address belongs to another wallet.
+    pub fn derivation_path(&self, hd_key: &HdPayloadKey) -> Result<Option<Vec<u32>>, Error> {
+        let payload = self.decode()?;
+
+        let attribute = payload.attributes.iter().find_map(|x| match x {
+            AddrAttrProperty::DerivationPath(x) => Some(x),
+            _ => None,
+        });
+
+        match attribute {
+            Some(x) => {
+                let x: ByteVec = minicbor::decode(x).map_err(Error::InvalidByronCbor)?;
+                hd_key.decrypt_path(&x).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn from_bytes(value: &[u8]) -> Result<Self, Error> {
         pallas_codec::minicbor::decode(value).map_err(Error::InvalidByronCbor)
     }
@@ -260,6 +458,87 @@ mod tests {
         assert_eq!(TEST_VECTOR, ours);
     }
 
+    // vectors generated with `cardano-serialization-lib` (Icarus) and a
+    // reference implementation of the Daedalus HD payload
+    const ROOT_KEY: &str = "000a11181f262d343b424950575e656c737a81888f969da4abb2b9c0c7ced55ce3eaf1f8ff060d141b222930373e454c535a61686f767d848b9299a0a7aeb5bcc3cad1d8dfe6edf4fb020910171e252c333a41484f565d646b727980878e959c";
+    const TESTNET_MAGIC: u32 = 1097911063;
+
+    const ICARUS_MAINNET: &str = "Ae2tdPwUPEZ1KuPW9dMcy56mY6hAdmkh9P2gN1U6NzUQoqd6Tx9EirNEtkH";
+    const ICARUS_TESTNET: &str =
+        "2cWKMJemoBaiPjaCFZM1hMVoue5bgwVPMUY1NWb2cDy5ofYofetP4RUaXtcZtWTftgCnA";
+
+    const RANDOM_PAYLOAD: &str = "a8a7d2402d30285e78480ba525f8cacae38adc66191926b0b9616926";
+    const RANDOM_MAINNET: &str = "DdzFFzCqrhstbssh3cgvRDXjk2T54KCBr5T7HHDCHBHfoqQxBDYSwhH2AG8TDBLx1foZRSchDEsvMEHgmp3sRzD6ahpVafLLyDKQoDuc";
+    const RANDOM_TESTNET: &str = "37btjrVyb4KCZkFavR7rfUb9wttZKzM9NkEZHDCza7UYfFucEQiTkHgb45APQFPyGyAmvPmD6cUj281HMADTwguHBbT8VTGfhwMQV5mKLMNi53kd2V";
+
+    fn root_key(legacy: bool) -> XPrv {
+        let mut bytes = [0; XPrv::SIZE];
+        hex::decode_to_slice(ROOT_KEY, &mut bytes).unwrap();
+
+        if legacy {
+            // legacy root keys don't have the third highest bit cleared
+            bytes[31] |= 0b0010_0000;
+        }
+
+        XPrv::from_bytes_unchecked(bytes)
+    }
+
+    #[test]
+    fn icarus_derivation() {
+        let root = root_key(false);
+
+        let addr = ByronAddress::derive_icarus(&root, 0, Role::External, 7, None);
+        assert_eq!(addr.to_base58(), ICARUS_MAINNET);
+
+        let addr = ByronAddress::derive_icarus(&root, 0, Role::External, 7, Some(TESTNET_MAGIC));
+        assert_eq!(addr.to_base58(), ICARUS_TESTNET);
+
+        let hd_key = HdPayloadKey::new(&root.public());
+        assert_eq!(addr.derivation_path(&hd_key).unwrap(), None);
+    }
+
+    #[test]
+    fn random_derivation() {
+        let root = root_key(true);
+        let hd_key = HdPayloadKey::new(&root.public());
+
+        let path = [harden(0), harden(5)];
+        assert_eq!(hex::encode(hd_key.encrypt_path(&path)), RANDOM_PAYLOAD);
+
+        let addr = ByronAddress::derive_random(&root, 0, 5, None);
+        assert_eq!(addr.to_base58(), RANDOM_MAINNET);
+
+        let addr = ByronAddress::derive_random(&root, 0, 5, Some(TESTNET_MAGIC));
+        assert_eq!(addr.to_base58(), RANDOM_TESTNET);
+
+        let addr = ByronAddress::from_base58(RANDOM_TESTNET).unwrap();
+        assert_eq!(addr.derivation_path(&hd_key).unwrap(), Some(path.to_vec()));
+    }
+
+    #[test]
+    fn hd_payload_rejects_other_wallets() {
+        let hd_key = HdPayloadKey::new(&root_key(true).public());
+        let other_key = HdPayloadKey::new(&root_key(false).public());
+
+        let addr = ByronAddress::from_base58(RANDOM_MAINNET).unwrap();
+        assert!(matches!(
+            addr.derivation_path(&other_key),
+            Err(Error::InvalidHdPayload)
+        ));
+
+        let mut payload = hd_key.encrypt_path(&[harden(1), harden(2)]);
+        assert_eq!(
+            hd_key.decrypt_path(&payload).unwrap(),
+            vec![harden(1), harden(2)]
+        );
+
+        payload[0] ^= 1;
+        assert!(matches!(
+            hd_key.decrypt_path(&payload),
+            Err(Error::InvalidHdPayload)
+        ));
+    }
+
     #[test]
     fn payload_matches() {
         let addr = ByronAddress::from_base58(TEST_VECTOR).unwrap();
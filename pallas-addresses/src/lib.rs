@@ -44,6 +44,9 @@ pub enum Error {
     #[error("invalid CBOR for Byron address {0}")]
     InvalidByronCbor(pallas_codec::minicbor::decode::Error),
 
+    #[error("can't decrypt the derivation path of the Byron address")]
+    InvalidHdPayload,
+
     #[error("unkown hrp for network {0:08b}")]
     UnknownNetworkHrp(u8),
 
@@ -876,6 +879,59 @@ mod tests {
         assert_eq!(addr.to_bech32().unwrap(), MAINNET_TEST_VECTORS[0].0);
     }
 
+    #[test]
+    fn construct_from_derived_keys() {
+        use pallas_crypto::key::bip32::{Role, XPrv};
+
+        let root = hex::decode("000a11181f262d343b424950575e656c737a81888f969da4abb2b9c0c7ced55ce3eaf1f8ff060d141b222930373e454c535a61686f767d848b9299a0a7aeb5bcc3cad1d8dfe6edf4fb020910171e252c333a41484f565d646b727980878e959c").unwrap();
+        let account = XPrv::try_from(root.as_slice())
+            .unwrap()
+            .derive_account(0)
+            .public();
+
+        let payment = account.derive_key(Role::External, 0).unwrap();
+        let stake = account.derive_key(Role::Stake, 0).unwrap();
+
+        let addr = ShelleyAddress::new(
+            Network::Testnet,
+            ShelleyPaymentPart::key_hash(payment.key_hash()),
+            ShelleyDelegationPart::key_hash(stake.key_hash()),
+        );
+
+        let parsed = Address::from_bech32(&addr.to_bech32().unwrap()).unwrap();
+
+        match parsed {
+            Address::Shelley(x) => {
+                assert_eq!(x.payment().as_hash(), &payment.key_hash());
+                assert_eq!(x.delegation().as_hash(), Some(&stake.key_hash()));
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn construct_from_mnemonic() {
+        use pallas_crypto::key::bip32::Role;
+        use pallas_crypto::key::bip39::Mnemonic;
+
+        // the mnemonic behind the payment key of the test vectors of CIP-19
+        let mnemonic = Mnemonic::parse(
+            "test walk nut penalty hip pave soap entry language right filter choice",
+        )
+        .unwrap();
+
+        let account = mnemonic.to_icarus_root("").derive_account(0).public();
+        let payment = account.derive_key(Role::External, 0).unwrap();
+
+        let addr = ShelleyAddress::new(
+            Network::Mainnet,
+            ShelleyPaymentPart::key_hash(payment.key_hash()),
+            ShelleyDelegationPart::Null,
+        );
+
+        assert_eq!(addr.to_bech32().unwrap(), MAINNET_TEST_VECTORS[6].0);
+    }
+
     #[test]
     fn test_minted_invalid_pointed_address() {
         let addr = Address::from_hex("40C19D7D05E90EEB6394B53313FE79D47077DE33068C6B813BBE5C9D5681FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF7F81FFFFFFFFFFFFFFFF7F81FFFFFFFFFFFFFFFF7F");
@@ -0,0 +1,298 @@
+//! Untyped Plutus Core evaluation through the CEK machine of the `uplc` crate
+//!
+//! The interpreter is built upon a different release of pallas, so values
+//! cross the boundary encoded as CBOR.
+
+use std::panic;
+
+use pallas_codec::minicbor;
+use pallas_primitives::{
+    alonzo::{ExUnits, PlutusData},
+    babbage::Language,
+};
+use thiserror::Error;
+use uplc::{
+    ast::{FakeNamedDeBruijn, NamedDeBruijn, Program},
+    machine::{
+        cost_model::{initialize_cost_model, ExBudget},
+        Machine,
+    },
+};
+
+use crate::phase2::Evaluator;
+
+#[derive(Debug, Error)]
+pub enum CekError {
+    #[error("Invalid script: {0}")]
+    InvalidScript(String),
+
+    #[error("Invalid Plutus data: {0}")]
+    InvalidData(String),
+
+    #[error("Cost model of {0:?} with {1} params is rejected by the interpreter")]
+    InvalidCostModel(Language, usize),
+
+    #[error("Execution units out of range: {mem} mem, {cpu} cpu")]
+    ExUnitsOutOfRange { mem: i64, cpu: i64 },
+
+    #[error("Evaluation failed: {error}")]
+    Failure { error: String, logs: Vec<String> },
+}
+
+/// The CEK machine of the `uplc` crate, as an [Evaluator]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CekMachine;
+
+impl Evaluator for CekMachine {
+    type Error = CekError;
+
+    fn eval(
+        &self,
+        language: Language,
+        script: &[u8],
+        args: &[PlutusData],
+        cost_model: &[i64],
+        budget: ExUnits,
+    ) -> Result<ExUnits, Self::Error> {
+        let version = match language {
+            Language::PlutusV1 => uplc_primitives::babbage::Language::PlutusV1,
+            Language::PlutusV2 => uplc_primitives::babbage::Language::PlutusV2,
+        };
+
+        // the interpreter reads the params it knows by position and panics if
+        // the model is too short for the language, which is the only check
+        // it does
+        let costs = panic::catch_unwind(|| initialize_cost_model(&version, cost_model))
+            .map_err(|_| CekError::InvalidCostModel(language, cost_model.len()))?;
+
+        let mut buffer = vec![];
+        let mut program: Program<NamedDeBruijn> =
+            Program::<FakeNamedDeBruijn>::from_cbor(script, &mut buffer)
+                .map_err(|e| CekError::InvalidScript(e.to_string()))?
+                .into();
+
+        for arg in args {
+            let cbor = minicbor::to_vec(arg).unwrap();
+            let arg = uplc::plutus_data(&cbor).map_err(|e| CekError::InvalidData(e.to_string()))?;
+
+            program = program.apply_data(arg);
+        }
+
+        let budget = ExBudget {
+            mem: budget.mem as i64,
+            cpu: budget.steps as i64,
+        };
+
+        // same slippage as the evaluation helpers of the interpreter
+        let mut machine = Machine::new(version, costs, budget, 200);
+        let result = machine.run(program.term);
+
+        let cost = budget - machine.ex_budget;
+
+        result.map_err(|e| CekError::Failure {
+            error: e.to_string(),
+            logs: machine.logs,
+        })?;
+
+        let out_of_range = || CekError::ExUnitsOutOfRange {
+            mem: cost.mem,
+            cpu: cost.cpu,
+        };
+
+        Ok(ExUnits {
+            mem: u32::try_from(cost.mem).map_err(|_| out_of_range())?,
+            steps: u64::try_from(cost.cpu).map_err(|_| out_of_range())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor::Decoder;
+    use pallas_primitives::{
+        alonzo::{RedeemerTag, TransactionInput},
+        babbage::CostMdls,
+    };
+    use pallas_traverse::{
+        history::EraHistory, wellknown::GenesisValues, Era, MultiEraTx, OutputRef,
+    };
+
+    use super::*;
+    use crate::phase2::{
+        eval_redeemers, eval_tx, EvalParams, EvalResult, Phase2Error, ResolvedInputs,
+    };
+    use crate::utxo::EraCbor;
+
+    const PLUTUS_V1_COSTS: [i64; 166] = [
+        197209, 0, 1, 1, 396231, 621, 0, 1, 150000, 1000, 0, 1, 150000, 32, 2477736, 29175, 4,
+        29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 100, 100, 29773,
+        100, 150000, 32, 150000, 32, 150000, 32, 150000, 1000, 0, 1, 150000, 32, 150000, 1000, 0,
+        8, 148000, 425507, 118, 0, 1, 1, 150000, 1000, 0, 8, 150000, 112536, 247, 1, 150000, 10000,
+        1, 136542, 1326, 1, 1000, 150000, 1000, 1, 150000, 32, 150000, 32, 150000, 32, 1, 1,
+        150000, 1, 150000, 4, 103599, 248, 1, 103599, 248, 1, 145276, 1366, 1, 179690, 497, 1,
+        150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 148000, 425507,
+        118, 0, 1, 1, 61516, 11218, 0, 1, 150000, 32, 148000, 425507, 118, 0, 1, 1, 148000, 425507,
+        118, 0, 1, 1, 2477736, 29175, 4, 0, 82363, 4, 150000, 5000, 0, 1, 150000, 32, 197209, 0, 1,
+        1, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32,
+        3345831, 1, 1,
+    ];
+
+    /// the costs of the secp256k1 builtins, which weren't enabled yet
+    const UNSET: i64 = 20_000_000_000;
+
+    const PLUTUS_V2_COSTS: [i64; 175] = [
+        205665, 812, 1, 1, 1000, 571, 0, 1, 1000, 24177, 4, 1, 1000, 32, 117366, 10475, 4, 23000,
+        100, 23000, 100, 23000, 100, 23000, 100, 23000, 100, 23000, 100, 100, 100, 23000, 100,
+        19537, 32, 175354, 32, 46417, 4, 221973, 511, 0, 1, 89141, 32, 497525, 14068, 4, 2, 196500,
+        453240, 220, 0, 1, 1, 1000, 28662, 4, 2, 245000, 216773, 62, 1, 1060367, 12586, 1, 208512,
+        421, 1, 187000, 1000, 52998, 1, 80436, 32, 43249, 32, 1000, 32, 80556, 1, 57667, 4, 1000,
+        10, 197145, 156, 1, 197145, 156, 1, 204924, 473, 1, 208896, 511, 1, 52467, 32, 64832, 32,
+        65493, 32, 22558, 32, 16563, 32, 76511, 32, 196500, 453240, 220, 0, 1, 1, 69522, 11687, 0,
+        1, 60091, 32, 196500, 453240, 220, 0, 1, 1, 196500, 453240, 220, 0, 1, 1, 1159724, 392670,
+        0, 2, 806990, 30482, 4, 1927926, 82523, 4, 265318, 0, 4, 0, 85931, 32, 205665, 812, 1, 1,
+        41182, 32, 212342, 32, 31220, 32, 32696, 32, 43357, 32, 32247, 32, 38314, 32, UNSET, UNSET,
+        9462713, 1021, 10, UNSET, 0, UNSET,
+    ];
+
+    /// the outputs consumed or referenced by the tx, as two arrays of matching
+    /// inputs and outputs
+    fn resolved_inputs(inputs: &str, outputs: &str) -> ResolvedInputs {
+        let inputs = hex::decode(inputs).unwrap();
+        let inputs: Vec<TransactionInput> = minicbor::decode(&inputs).unwrap();
+
+        let outputs = hex::decode(outputs).unwrap();
+        let mut decoder = Decoder::new(&outputs);
+        decoder.array().unwrap();
+
+        inputs
+            .into_iter()
+            .map(|input| {
+                let start = decoder.position();
+                decoder.skip().unwrap();
+                let output = outputs[start..decoder.position()].to_vec();
+
+                (
+                    OutputRef::new(input.transaction_id, input.index),
+                    EraCbor(Era::Babbage, output),
+                )
+            })
+            .collect()
+    }
+
+    fn eval_mainnet_tx(tx: &str, inputs: &str, outputs: &str) -> Vec<(EvalResult, ExUnits)> {
+        let tx = hex::decode(tx).unwrap();
+        let tx = MultiEraTx::decode(Era::Babbage, &tx).unwrap();
+
+        let utxos = resolved_inputs(inputs, outputs);
+        let history = EraHistory::from(&GenesisValues::mainnet());
+
+        let cost_models = CostMdls {
+            plutus_v1: Some(PLUTUS_V1_COSTS.to_vec()),
+            plutus_v2: Some(PLUTUS_V2_COSTS.to_vec()),
+            layout: Default::default(),
+        };
+
+        let params = EvalParams {
+            cost_models: &cost_models,
+            max_ex_units: ExUnits {
+                mem: 14_000_000,
+                steps: 10_000_000_000,
+            },
+            history: &history,
+        };
+
+        let results = eval_tx(&tx, &utxos, &params).unwrap();
+
+        results
+            .into_iter()
+            .map(|result| {
+                let on_chain = tx
+                    .redeemers()
+                    .iter()
+                    .find(|x| x.tag == result.tag && x.index == result.index)
+                    .map(|x| x.ex_units.clone())
+                    .unwrap();
+
+                (result, on_chain)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mainnet_redeemers_match_on_chain_units() {
+        let txs = [
+            (
+                include_str!("../../test_data/babbage11.tx"),
+                include_str!("../test_data/babbage11.inputs"),
+                include_str!("../test_data/babbage11.outputs"),
+            ),
+            (
+                include_str!("../../test_data/babbage12.tx"),
+                include_str!("../test_data/babbage12.inputs"),
+                include_str!("../test_data/babbage12.outputs"),
+            ),
+            (
+                include_str!("../../test_data/babbage13.tx"),
+                include_str!("../test_data/babbage13.inputs"),
+                include_str!("../test_data/babbage13.outputs"),
+            ),
+        ];
+
+        let mut evaluated = vec![];
+
+        for (tx, inputs, outputs) in txs {
+            for (result, on_chain) in eval_mainnet_tx(tx, inputs, outputs) {
+                assert_eq!(result.tag, RedeemerTag::Mint);
+                assert_eq!(result.ex_units, on_chain);
+
+                evaluated.push((result.ex_units.mem, result.ex_units.steps));
+            }
+        }
+
+        assert_eq!(
+            evaluated,
+            vec![(4002, 6231248), (2954794, 1221507148), (2691678, 711173018)]
+        );
+    }
+
+    #[test]
+    fn invalid_evaluations_fail() {
+        let tx = hex::decode(include_str!("../../test_data/babbage12.tx")).unwrap();
+        let tx = MultiEraTx::decode(Era::Babbage, &tx).unwrap();
+
+        let utxos = resolved_inputs(
+            include_str!("../test_data/babbage12.inputs"),
+            include_str!("../test_data/babbage12.outputs"),
+        );
+
+        let history = EraHistory::from(&GenesisValues::mainnet());
+
+        let cost_models = CostMdls {
+            plutus_v1: Some(PLUTUS_V1_COSTS.to_vec()),
+            plutus_v2: None,
+            layout: Default::default(),
+        };
+
+        let params = EvalParams {
+            cost_models: &cost_models,
+            max_ex_units: ExUnits {
+                mem: 1_000_000,
+                steps: 10_000_000_000,
+            },
+            history: &history,
+        };
+
+        // the script needs almost 3M units of memory
+        assert!(matches!(
+            eval_redeemers(&tx, &utxos, &params, &CekMachine),
+            Err(Phase2Error::ScriptFailure(RedeemerTag::Mint, 0, _))
+        ));
+
+        let units = params.max_ex_units.clone();
+
+        assert!(matches!(
+            CekMachine.eval(Language::PlutusV1, &[], &[], &PLUTUS_V1_COSTS[..100], units),
+            Err(CekError::InvalidCostModel(Language::PlutusV1, 100))
+        ));
+    }
+}
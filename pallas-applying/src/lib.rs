@@ -0,0 +1,31 @@
+//! Logic for validating and applying new blocks and txs to the chain state
+
+use std::fmt::Display;
+
+use pallas_crypto::hash::Hash;
+use thiserror::Error;
+
+pub mod phase2;
+pub mod praos;
+pub mod utxo;
+pub mod witness;
+
+mod non_integral;
+
+#[cfg(feature = "uplc")]
+pub mod cek;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Invalid CBOR structure: {0}")]
+    InvalidCbor(String),
+
+    #[error("Can't rollback to unknown point: {0} {1}")]
+    UnknownRollbackPoint(u64, Hash<32>),
+}
+
+impl Error {
+    pub fn invalid_cbor(error: impl Display) -> Self {
+        Error::InvalidCbor(format!("{error}"))
+    }
+}
@@ -0,0 +1,307 @@
+//! Fixed point arithmetic of the ledger, as used by the leader check
+//!
+//! A port of the reference implementation of the non-integral calculations of
+//! the ledger spec: numbers have 34 decimal digits and every step rounds the
+//! same way, so that all nodes reach the same result no matter the platform.
+//! Only what's required to compute `ln`, `exp` and to compare `exp` against a
+//! value is provided.
+
+use std::ops::{Mul, Neg, Sub};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+
+/// Decimal digits of the fixed point numbers
+const DIGITS: u32 = 34;
+
+/// Iterations of the series before giving up
+const MAX_ITERATIONS: u32 = 1000;
+
+fn precision() -> BigInt {
+    BigInt::from(10).pow(DIGITS)
+}
+
+/// The series stop once a term is smaller than this (`10^-24`)
+fn epsilon() -> BigInt {
+    BigInt::from(10).pow(DIGITS - 24)
+}
+
+/// Drops the extra digits of a product, rounding down
+fn scale(x: BigInt) -> BigInt {
+    x.div_floor(&precision())
+}
+
+/// Division of two fixed point numbers, as done by the reference
+/// implementation (the integral and fractional parts are truncated apart)
+fn div(x: &BigInt, y: &BigInt) -> BigInt {
+    let (q, r) = x.div_rem(y);
+    q * precision() + (r * precision()) / y
+}
+
+/// The outcome of comparing `exp(x)` against a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExpCmp {
+    /// the value is guaranteed to be above `exp(x)`
+    Above,
+    /// the value is guaranteed to be below `exp(x)`
+    Below,
+    /// no conclusion was possible with the available precision
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct FixedPoint(BigInt);
+
+impl FixedPoint {
+    pub fn one() -> Self {
+        Self(precision())
+    }
+
+    /// The fixed point value of `numerator / denominator`, rounding down
+    pub fn from_ratio(numerator: impl Into<BigInt>, denominator: impl Into<BigInt>) -> Self {
+        let numerator = numerator.into() * precision();
+        Self(numerator.div_floor(&denominator.into()))
+    }
+
+    /// The natural logarithm, `None` outside of its domain
+    pub fn ln(&self) -> Option<Self> {
+        if self.0 <= BigInt::from(0) {
+            return None;
+        }
+
+        Some(Self(ln(&self.0)))
+    }
+
+    /// Compares `exp(self)` against `value` with the Taylor series of `exp`,
+    /// stopping as soon as the Lagrange remainder, given that `exp` is at most
+    /// `bound` over the interval of `self`, is enough to tell
+    pub fn exp_cmp(&self, bound: u32, value: &Self) -> ExpCmp {
+        let x = &self.0;
+        let mut acc = precision();
+        let mut divisor = precision();
+        let mut error = x.clone();
+
+        for _ in 0..MAX_ITERATIONS {
+            let next = error.clone();
+
+            if next.magnitude() < epsilon().magnitude() {
+                break;
+            }
+
+            divisor += precision();
+
+            // the error is bound * x^(n+1) / (n+1)!, `error` keeps the part
+            // without the bound
+            error = div(&scale(error * x), &divisor);
+            let error_term = &error * bound;
+            acc += next;
+
+            if value.0 > &acc + &error_term {
+                return ExpCmp::Above;
+            }
+
+            if value.0 < &acc - &error_term {
+                return ExpCmp::Below;
+            }
+        }
+
+        ExpCmp::Unknown
+    }
+}
+
+impl Mul for FixedPoint {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self(scale(self.0 * rhs.0))
+    }
+}
+
+impl Sub for FixedPoint {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Neg for FixedPoint {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+/// `x^n` by repeated squaring
+fn ipow(x: &BigInt, n: i64) -> BigInt {
+    fn go(x: &BigInt, n: i64) -> BigInt {
+        match n {
+            0 => precision(),
+            n if n % 2 == 0 => {
+                let half = go(x, n / 2);
+                scale(&half * &half)
+            }
+            n => scale(go(x, n - 1) * x),
+        }
+    }
+
+    match n {
+        n if n < 0 => div(&precision(), &go(x, -n)),
+        n => go(x, n),
+    }
+}
+
+/// The Maclaurin series of `exp`, meant for `x` in `[0, 1]`
+fn exp_taylor(x: &BigInt) -> BigInt {
+    let mut acc = precision();
+    let mut divisor = precision();
+    let mut last = precision();
+
+    for _ in 0..MAX_ITERATIONS {
+        let next = div(&scale(x * &last), &divisor);
+
+        if next.magnitude() < epsilon().magnitude() {
+            break;
+        }
+
+        divisor += precision();
+        acc += &next;
+        last = next;
+    }
+
+    acc
+}
+
+/// `exp(x) = exp(x / n)^n`, with `n` such that `x / n` is in `[0, 1]`
+fn exp(x: &BigInt) -> BigInt {
+    match x.sign() {
+        num_bigint::Sign::NoSign => precision(),
+        num_bigint::Sign::Minus => div(&precision(), &exp(&-x)),
+        num_bigint::Sign::Plus => {
+            let n = x.div_ceil(&precision());
+            let w = exp_taylor(&(x / &n));
+
+            let n = i64::try_from(n).expect("exponent out of range");
+            ipow(&w, n)
+        }
+    }
+}
+
+/// The continued fraction of `ln(1 + x)`, stopping when two convergents are
+/// close enough
+fn ln_cf(x: &BigInt) -> BigInt {
+    let mut convergent = BigInt::from(0);
+    let mut last: Option<BigInt> = None;
+
+    let mut b = precision();
+    let mut an_m2 = precision();
+    let mut bn_m2 = BigInt::from(0);
+    let mut an_m1 = BigInt::from(0);
+    let mut bn_m1 = precision();
+
+    let mut curr_a: u64 = 1;
+
+    for n in 1..=MAX_ITERATIONS + 2 {
+        let a = x * (curr_a * curr_a);
+
+        if n > 1 && n % 2 == 1 {
+            curr_a += 1;
+        }
+
+        let an = scale(&b * &an_m1) + scale(&a * &an_m2);
+        let bn = scale(&b * &bn_m1) + scale(&a * &bn_m2);
+
+        convergent = div(&an, &bn);
+
+        if let Some(last) = &last {
+            if (&convergent - last).magnitude() < epsilon().magnitude() {
+                break;
+            }
+        }
+
+        last = Some(convergent.clone());
+
+        an_m2 = std::mem::replace(&mut an_m1, an);
+        bn_m2 = std::mem::replace(&mut bn_m1, bn);
+
+        b += precision();
+    }
+
+    convergent
+}
+
+/// The integer `n` such that `e^n <= x < e^(n+1)`
+fn find_e(x: &BigInt) -> i64 {
+    let e = exp(&precision());
+
+    let mut lower = div(&precision(), &e);
+    let mut upper = e.clone();
+
+    let (mut l, mut u) = (-1, 1);
+
+    while &lower > x || &upper < x {
+        lower = scale(&lower * &lower);
+        upper = scale(&upper * &upper);
+
+        l *= 2;
+        u *= 2;
+    }
+
+    while l + 1 != u {
+        let mid = l + (u - l) / 2;
+
+        if x < &ipow(&e, mid) {
+            u = mid;
+        } else {
+            l = mid;
+        }
+    }
+
+    l
+}
+
+/// `ln(x) = n + ln(x / e^n)`, with the latter computed as a continued fraction
+fn ln(x: &BigInt) -> BigInt {
+    let n = BigInt::from(find_e(x)) * precision();
+    let factor = exp(&n);
+
+    let x = div(x, &factor) - precision();
+
+    n + ln_cf(&x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(x: &FixedPoint) -> String {
+        x.0.to_string()
+    }
+
+    #[test]
+    fn exp_and_ln_match_the_reference() {
+        let e = FixedPoint(exp(&precision()));
+        assert_eq!(digits(&e), "27182818284590452353602874043083282");
+
+        // the log of the active slot coefficient of mainnet, ln(1 - 1/20)
+        let ln = FixedPoint::from_ratio(19, 20).ln().unwrap();
+        assert_eq!(digits(&ln), "-512932943875505334261962382072846");
+
+        let ln = FixedPoint::from_ratio(2, 1).ln().unwrap();
+        assert_eq!(digits(&ln), "6931471805599453094172321818152860");
+
+        assert_eq!(FixedPoint::one().ln(), Some(FixedPoint::from_ratio(0, 1)));
+        assert_eq!(FixedPoint::from_ratio(0, 1).ln(), None);
+    }
+
+    #[test]
+    fn exp_comparison() {
+        let x = FixedPoint::from_ratio(1, 2);
+
+        // exp(0.5) is about 1.6487
+        assert_eq!(x.exp_cmp(3, &FixedPoint::from_ratio(3, 2)), ExpCmp::Below);
+        assert_eq!(x.exp_cmp(3, &FixedPoint::from_ratio(17, 10)), ExpCmp::Above);
+        assert_eq!(x.exp_cmp(3, &FixedPoint(exp(&x.0))), ExpCmp::Unknown);
+    }
+}
@@ -0,0 +1,1099 @@
+//! Phase-2 (Plutus script) validation of transactions
+//!
+//! This module builds the `ScriptContext` that the ledger hands to Plutus V1
+//! and V2 scripts, resolves the script, datum and redeemer of each redeemer
+//! pointer and runs them through an [`Evaluator`], collecting the execution
+//! units consumed by each one. Everything happens offline, given the resolved
+//! inputs of the tx and the protocol params.
+//!
+//! The opt-in `uplc` feature provides [`eval_tx`], which runs the scripts on
+//! the CEK machine of the [`cek`](crate::cek) module. Other interpreters can
+//! be plugged in by implementing [`Evaluator`], which receives the
+//! flat-encoded script, its arguments as [`PlutusData`] and the cost model to
+//! use.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pallas_addresses::{Address, ShelleyDelegationPart, ShelleyPaymentPart, StakePayload};
+use pallas_codec::utils::{Int, KeyValuePairs};
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{
+    alonzo::{
+        BigInt, BoundedBytes, Certificate, Constr, ExUnits, PlutusData, RedeemerTag,
+        StakeCredential,
+    },
+    babbage::{CostMdls, Language, PseudoDatumOption, Script},
+};
+use pallas_traverse::{
+    history::EraHistory, ComputeHash, Era, MultiEraOutput, MultiEraTx, OriginalHash, OutputRef,
+};
+use thiserror::Error;
+
+use crate::utxo::EraCbor;
+
+/// The outputs consumed or referenced by a tx, indexed by their reference
+pub type ResolvedInputs = HashMap<OutputRef, EraCbor>;
+
+#[derive(Debug, Error)]
+pub enum Phase2Error {
+    #[error("Plutus scripts are not available in era {0:?}")]
+    InvalidEra(Era),
+
+    #[error("Input {0} is not resolved")]
+    UnresolvedInput(OutputRef),
+
+    #[error("Invalid output: {0}")]
+    InvalidOutput(String),
+
+    #[error("Redeemer {0:?} {1} doesn't point to anything in the tx")]
+    DanglingRedeemer(RedeemerTag, u32),
+
+    #[error("Redeemer {0:?} {1} points to something not locked by a script")]
+    NotAScript(RedeemerTag, u32),
+
+    #[error("Missing Plutus script {0}")]
+    MissingScript(Hash<28>),
+
+    #[error("Missing datum for input {0}")]
+    MissingDatum(OutputRef),
+
+    #[error("Missing cost model for {0:?}")]
+    MissingCostModel(Language),
+
+    #[error("Byron address in the context of a Plutus V2 script")]
+    ByronAddress,
+
+    #[error("Plutus V1 scripts can't see {0}")]
+    NotSupportedByV1(&'static str),
+
+    #[error("Slot out of the era history: {0}")]
+    Time(#[from] pallas_traverse::Error),
+
+    #[error("Script of redeemer {0:?} {1} failed: {2}")]
+    ScriptFailure(RedeemerTag, u32, String),
+}
+
+/// An Untyped Plutus Core interpreter able to run scripts
+pub trait Evaluator {
+    type Error: std::fmt::Display;
+
+    /// run the script (flat encoded, as found in the witness set or reference
+    /// script) applied to the arguments, within the given budget, returning
+    /// the execution units consumed
+    fn eval(
+        &self,
+        language: Language,
+        script: &[u8],
+        args: &[PlutusData],
+        cost_model: &[i64],
+        budget: ExUnits,
+    ) -> Result<ExUnits, Self::Error>;
+}
+
+/// The protocol params and chain info required to evaluate scripts
+pub struct EvalParams<'a> {
+    pub cost_models: &'a CostMdls,
+
+    /// budget given to each redeemer, usually the `max_tx_ex_units` param
+    pub max_ex_units: ExUnits,
+
+    /// used to convert the validity interval of the tx into POSIX time
+    pub history: &'a EraHistory,
+}
+
+/// The execution units consumed by the script of a redeemer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalResult {
+    pub tag: RedeemerTag,
+    pub index: u32,
+    pub ex_units: ExUnits,
+}
+
+/// A credential as seen by Plutus scripts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Credential {
+    Key(Hash<28>),
+    Script(Hash<28>),
+}
+
+/// What a script is run for, the target of a redeemer
+///
+/// The variants (and their fields) are in the order used by the ledger to
+/// sort the redeemers of the V2 context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScriptPurpose {
+    Minting(Hash<28>),
+    Spending(Hash<32>, u64),
+    Rewarding(Credential),
+    /// the index of the certificate in the body
+    Certifying(usize),
+}
+
+/* PlutusData helpers ****************************************************** */
+
+fn constr(tag: u64, fields: Vec<PlutusData>) -> PlutusData {
+    let (tag, any_constructor) = match tag {
+        0..=6 => (121 + tag, None),
+        7..=127 => (1280 + tag - 7, None),
+        _ => (102, Some(tag)),
+    };
+
+    PlutusData::Constr(Constr {
+        tag,
+        any_constructor,
+        fields,
+    })
+}
+
+fn integer(x: i128) -> PlutusData {
+    match Int::try_from(x) {
+        Ok(x) => PlutusData::BigInt(BigInt::Int(x)),
+        Err(_) if x >= 0 => {
+            PlutusData::BigInt(BigInt::BigUInt(Vec::from(trim_be(&x.to_be_bytes())).into()))
+        }
+        Err(_) => PlutusData::BigInt(BigInt::BigNInt(
+            Vec::from(trim_be(&(-1 - x).to_be_bytes())).into(),
+        )),
+    }
+}
+
+fn trim_be(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|x| *x != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn bytes(x: &[u8]) -> PlutusData {
+    PlutusData::BoundedBytes(BoundedBytes::from(x.to_vec()))
+}
+
+fn list(items: Vec<PlutusData>) -> PlutusData {
+    PlutusData::Array(items)
+}
+
+fn map(entries: Vec<(PlutusData, PlutusData)>) -> PlutusData {
+    PlutusData::Map(KeyValuePairs::from(entries))
+}
+
+fn boolean(x: bool) -> PlutusData {
+    constr(x as u64, vec![])
+}
+
+fn maybe(x: Option<PlutusData>) -> PlutusData {
+    match x {
+        Some(x) => constr(0, vec![x]),
+        None => constr(1, vec![]),
+    }
+}
+
+fn tx_id(hash: &Hash<32>) -> PlutusData {
+    constr(0, vec![bytes(hash.as_ref())])
+}
+
+fn tx_out_ref(hash: &Hash<32>, index: u64) -> PlutusData {
+    constr(0, vec![tx_id(hash), integer(index as i128)])
+}
+
+/* Conversion ************************************************************** */
+
+impl Credential {
+    fn to_plutus_data(self) -> PlutusData {
+        match self {
+            Credential::Key(x) => constr(0, vec![bytes(x.as_ref())]),
+            Credential::Script(x) => constr(1, vec![bytes(x.as_ref())]),
+        }
+    }
+
+    fn from_reward_account(account: &[u8]) -> Option<Self> {
+        match Address::from_bytes(account).ok()? {
+            Address::Stake(x) => match x.payload() {
+                StakePayload::Stake(x) => Some(Credential::Key(*x)),
+                StakePayload::Script(x) => Some(Credential::Script(*x)),
+            },
+            _ => None,
+        }
+    }
+}
+
+impl From<&StakeCredential> for Credential {
+    fn from(value: &StakeCredential) -> Self {
+        match value {
+            StakeCredential::AddrKeyhash(x) => Credential::Key(*x),
+            StakeCredential::Scripthash(x) => Credential::Script(*x),
+        }
+    }
+}
+
+fn staking_hash(credential: Credential) -> PlutusData {
+    constr(0, vec![credential.to_plutus_data()])
+}
+
+fn address_to_data(address: &Address) -> Result<Option<PlutusData>, Phase2Error> {
+    let address = match address {
+        Address::Shelley(x) => x,
+        Address::Byron(_) => return Ok(None),
+        Address::Stake(_) => {
+            return Err(Phase2Error::InvalidOutput(
+                "stake address in output".to_string(),
+            ))
+        }
+    };
+
+    let payment = match address.payment() {
+        ShelleyPaymentPart::Key(x) => Credential::Key(*x),
+        ShelleyPaymentPart::Script(x) => Credential::Script(*x),
+    };
+
+    let delegation = match address.delegation() {
+        ShelleyDelegationPart::Key(x) => Some(staking_hash(Credential::Key(*x))),
+        ShelleyDelegationPart::Script(x) => Some(staking_hash(Credential::Script(*x))),
+        ShelleyDelegationPart::Pointer(x) => Some(constr(
+            1,
+            vec![
+                integer(x.slot() as i128),
+                integer(x.tx_idx() as i128),
+                integer(x.cert_idx() as i128),
+            ],
+        )),
+        ShelleyDelegationPart::Null => None,
+    };
+
+    Ok(Some(constr(
+        0,
+        vec![payment.to_plutus_data(), maybe(delegation)],
+    )))
+}
+
+/// a multi-asset value, ADA being the asset with empty policy and name
+fn value_to_data<'a>(
+    lovelace: i128,
+    assets: impl IntoIterator<Item = (&'a [u8], &'a [u8], i128)>,
+) -> PlutusData {
+    let mut value: BTreeMap<&[u8], BTreeMap<&[u8], i128>> = BTreeMap::new();
+    value.entry(&[]).or_default().insert(&[], lovelace);
+
+    for (policy, name, amount) in assets {
+        *value.entry(policy).or_default().entry(name).or_default() += amount;
+    }
+
+    map(value
+        .into_iter()
+        .map(|(policy, assets)| {
+            let assets = assets
+                .into_iter()
+                .map(|(name, amount)| (bytes(name), integer(amount)))
+                .collect();
+
+            (bytes(policy), map(assets))
+        })
+        .collect())
+}
+
+fn output_value(output: &MultiEraOutput) -> PlutusData {
+    let policies = output.non_ada_assets();
+    let assets: Vec<_> = policies
+        .iter()
+        .flat_map(|x| x.assets())
+        .map(|x| (*x.policy(), x.name().to_vec(), x.any_coin()))
+        .collect();
+
+    value_to_data(
+        output.lovelace_amount() as i128,
+        assets
+            .iter()
+            .map(|(policy, name, amount)| (policy.as_ref(), name.as_slice(), *amount)),
+    )
+}
+
+fn output_to_data(
+    language: &Language,
+    output: &MultiEraOutput,
+) -> Result<Option<PlutusData>, Phase2Error> {
+    let address = output
+        .address()
+        .map_err(|x| Phase2Error::InvalidOutput(x.to_string()))?;
+
+    let address = match address_to_data(&address)? {
+        Some(x) => x,
+        // V1 contexts silently leave out outputs with Byron addresses
+        None if *language == Language::PlutusV1 => return Ok(None),
+        None => return Err(Phase2Error::ByronAddress),
+    };
+
+    let value = output_value(output);
+
+    let out = match language {
+        Language::PlutusV1 => {
+            let datum_hash = match output.datum() {
+                Some(PseudoDatumOption::Hash(x)) => Some(bytes(x.as_ref())),
+                Some(PseudoDatumOption::Data(_)) => {
+                    return Err(Phase2Error::NotSupportedByV1("inline datums"))
+                }
+                None => None,
+            };
+
+            if output.script_ref().is_some() {
+                return Err(Phase2Error::NotSupportedByV1("reference scripts"));
+            }
+
+            constr(0, vec![address, value, maybe(datum_hash)])
+        }
+        Language::PlutusV2 => {
+            let datum = match output.datum() {
+                Some(PseudoDatumOption::Hash(x)) => constr(1, vec![bytes(x.as_ref())]),
+                Some(PseudoDatumOption::Data(x)) => constr(2, vec![x.0.unwrap()]),
+                None => constr(0, vec![]),
+            };
+
+            let script_hash = output
+                .script_ref()
+                .map(|x| bytes(script_hash(&x.0).as_ref()));
+
+            constr(0, vec![address, value, datum, maybe(script_hash)])
+        }
+    };
+
+    Ok(Some(out))
+}
+
+fn script_hash(script: &Script) -> Hash<28> {
+    match script {
+        Script::NativeScript(x) => x.compute_hash(),
+        Script::PlutusV1Script(x) => x.compute_hash(),
+        Script::PlutusV2Script(x) => x.compute_hash(),
+    }
+}
+
+fn cert_to_data(cert: &Certificate) -> PlutusData {
+    match cert {
+        Certificate::StakeRegistration(x) => constr(0, vec![staking_hash(x.into())]),
+        Certificate::StakeDeregistration(x) => constr(1, vec![staking_hash(x.into())]),
+        Certificate::StakeDelegation(x, pool) => {
+            constr(2, vec![staking_hash(x.into()), bytes(pool.as_ref())])
+        }
+        Certificate::PoolRegistration {
+            operator,
+            vrf_keyhash,
+            ..
+        } => constr(
+            3,
+            vec![bytes(operator.as_ref()), bytes(vrf_keyhash.as_ref())],
+        ),
+        Certificate::PoolRetirement(pool, epoch) => {
+            constr(4, vec![bytes(pool.as_ref()), integer(*epoch as i128)])
+        }
+        Certificate::GenesisKeyDelegation(..) => constr(5, vec![]),
+        Certificate::MoveInstantaneousRewardsCert(_) => constr(6, vec![]),
+    }
+}
+
+/* Context ***************************************************************** */
+
+/// The resolved parts of a tx that are needed to build contexts and run
+/// scripts
+struct TxParts<'a, 'b> {
+    tx: &'a MultiEraTx<'b>,
+    inputs: Vec<(OutputRef, MultiEraOutput<'a>)>,
+    reference_inputs: Vec<(OutputRef, MultiEraOutput<'a>)>,
+    policies: Vec<Hash<28>>,
+    certs: Vec<Certificate>,
+    withdrawals: Vec<(Credential, u64)>,
+}
+
+fn resolve<'a>(
+    refs: Vec<OutputRef>,
+    utxos: &'a ResolvedInputs,
+) -> Result<Vec<(OutputRef, MultiEraOutput<'a>)>, Phase2Error> {
+    let mut refs = refs;
+    refs.sort_by_key(|x| (*x.hash(), x.index()));
+
+    refs.into_iter()
+        .map(|x| {
+            let output = utxos
+                .get(&x)
+                .ok_or_else(|| Phase2Error::UnresolvedInput(x.clone()))?
+                .output()
+                .map_err(|e| Phase2Error::InvalidOutput(e.to_string()))?;
+
+            Ok((x, output))
+        })
+        .collect()
+}
+
+impl<'a, 'b> TxParts<'a, 'b> {
+    fn new(tx: &'a MultiEraTx<'b>, utxos: &'a ResolvedInputs) -> Result<Self, Phase2Error> {
+        if tx.era() < Era::Alonzo {
+            return Err(Phase2Error::InvalidEra(tx.era()));
+        }
+
+        let inputs = resolve(tx.inputs().iter().map(|x| x.output_ref()).collect(), utxos)?;
+
+        let reference_inputs = resolve(
+            tx.reference_inputs()
+                .iter()
+                .map(|x| x.output_ref())
+                .collect(),
+            utxos,
+        )?;
+
+        let mut policies: Vec<_> = tx.mints().iter().map(|x| *x.policy()).collect();
+        policies.sort();
+        policies.dedup();
+
+        let certs = tx
+            .certs()
+            .iter()
+            .filter_map(|x| x.as_alonzo().cloned())
+            .collect();
+
+        let withdrawals = tx
+            .withdrawals()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|(account, amount)| {
+                Credential::from_reward_account(account).map(|x| (x, amount))
+            })
+            .collect();
+
+        Ok(Self {
+            tx,
+            inputs,
+            reference_inputs,
+            policies,
+            certs,
+            withdrawals,
+        })
+    }
+
+    fn purpose(&self, tag: &RedeemerTag, index: u32) -> Option<ScriptPurpose> {
+        let i = index as usize;
+
+        match tag {
+            RedeemerTag::Spend => self
+                .inputs
+                .get(i)
+                .map(|(x, _)| ScriptPurpose::Spending(*x.hash(), x.index())),
+            RedeemerTag::Mint => self.policies.get(i).map(|x| ScriptPurpose::Minting(*x)),
+            RedeemerTag::Cert => self.certs.get(i).map(|_| ScriptPurpose::Certifying(i)),
+            RedeemerTag::Reward => self
+                .withdrawals
+                .get(i)
+                .map(|(x, _)| ScriptPurpose::Rewarding(*x)),
+        }
+    }
+
+    fn purpose_to_data(&self, purpose: &ScriptPurpose) -> PlutusData {
+        match purpose {
+            ScriptPurpose::Minting(x) => constr(0, vec![bytes(x.as_ref())]),
+            ScriptPurpose::Spending(hash, index) => constr(1, vec![tx_out_ref(hash, *index)]),
+            ScriptPurpose::Rewarding(x) => constr(2, vec![staking_hash(*x)]),
+            ScriptPurpose::Certifying(i) => constr(3, vec![cert_to_data(&self.certs[*i])]),
+        }
+    }
+
+    /// the credential of the script that needs to validate the purpose
+    fn script_credential(&self, purpose: &ScriptPurpose) -> Option<Credential> {
+        match purpose {
+            ScriptPurpose::Minting(x) => Some(Credential::Script(*x)),
+            ScriptPurpose::Spending(hash, index) => {
+                let (_, output) = self
+                    .inputs
+                    .iter()
+                    .find(|(x, _)| x.hash() == hash && x.index() == *index)?;
+
+                match output.address().ok()? {
+                    Address::Shelley(x) => match x.payment() {
+                        ShelleyPaymentPart::Key(x) => Some(Credential::Key(*x)),
+                        ShelleyPaymentPart::Script(x) => Some(Credential::Script(*x)),
+                    },
+                    _ => None,
+                }
+            }
+            ScriptPurpose::Rewarding(x) => Some(*x),
+            ScriptPurpose::Certifying(i) => match &self.certs[*i] {
+                Certificate::StakeDeregistration(x) | Certificate::StakeDelegation(x, _) => {
+                    Some(x.into())
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn validity_range(&self, history: &EraHistory) -> Result<PlutusData, Phase2Error> {
+        let lower = match self.tx.validity_start() {
+            Some(slot) => constr(
+                1,
+                vec![integer(history.slot_to_posix_millis(slot)? as i128)],
+            ),
+            None => constr(0, vec![]),
+        };
+
+        let upper = match self.tx.ttl() {
+            Some(slot) => (
+                constr(
+                    1,
+                    vec![integer(history.slot_to_posix_millis(slot)? as i128)],
+                ),
+                false,
+            ),
+            None => (constr(2, vec![]), true),
+        };
+
+        Ok(constr(
+            0,
+            vec![
+                constr(0, vec![lower, boolean(true)]),
+                constr(0, vec![upper.0, boolean(upper.1)]),
+            ],
+        ))
+    }
+
+    fn datums(&self) -> Vec<(PlutusData, PlutusData)> {
+        let mut datums: Vec<_> = self
+            .tx
+            .plutus_data()
+            .iter()
+            .map(|x| (x.original_hash(), x.clone().unwrap()))
+            .collect();
+
+        datums.sort_by_key(|(hash, _)| *hash);
+
+        datums
+            .into_iter()
+            .map(|(hash, datum)| (bytes(hash.as_ref()), datum))
+            .collect()
+    }
+
+    fn tx_info(
+        &self,
+        language: &Language,
+        history: &EraHistory,
+    ) -> Result<PlutusData, Phase2Error> {
+        let tx = self.tx;
+
+        let inputs = |x: &[(OutputRef, MultiEraOutput)]| -> Result<PlutusData, Phase2Error> {
+            let mut items = vec![];
+
+            for (r, output) in x {
+                if let Some(output) = output_to_data(language, output)? {
+                    items.push(constr(0, vec![tx_out_ref(r.hash(), r.index()), output]));
+                }
+            }
+
+            Ok(list(items))
+        };
+
+        let mut outputs = vec![];
+        for output in tx.outputs() {
+            outputs.extend(output_to_data(language, &output)?);
+        }
+
+        let fee = value_to_data(tx.fee().unwrap_or_default() as i128, []);
+
+        let mints: Vec<_> = tx
+            .mints()
+            .iter()
+            .flat_map(|x| x.assets())
+            .map(|x| (*x.policy(), x.name().to_vec(), x.any_coin()))
+            .collect();
+        let mint = value_to_data(
+            0,
+            mints
+                .iter()
+                .map(|(policy, name, amount)| (policy.as_ref(), name.as_slice(), *amount)),
+        );
+
+        let certs = list(self.certs.iter().map(cert_to_data).collect());
+
+        let withdrawals = self
+            .withdrawals
+            .iter()
+            .map(|(x, amount)| (staking_hash(*x), integer(*amount as i128)));
+
+        let signers = tx.required_signers();
+        let mut signers: Vec<_> = signers.collect();
+        signers.sort();
+        signers.dedup();
+        let signers = list(signers.into_iter().map(|x| bytes(x.as_ref())).collect());
+
+        let id = tx_id(&tx.hash());
+
+        match language {
+            Language::PlutusV1 => {
+                if !self.reference_inputs.is_empty() {
+                    return Err(Phase2Error::NotSupportedByV1("reference inputs"));
+                }
+
+                let withdrawals = withdrawals.map(|(k, v)| constr(0, vec![k, v])).collect();
+                let datums = self
+                    .datums()
+                    .into_iter()
+                    .map(|(k, v)| constr(0, vec![k, v]))
+                    .collect();
+
+                Ok(constr(
+                    0,
+                    vec![
+                        inputs(&self.inputs)?,
+                        list(outputs),
+                        fee,
+                        mint,
+                        certs,
+                        list(withdrawals),
+                        self.validity_range(history)?,
+                        signers,
+                        list(datums),
+                        id,
+                    ],
+                ))
+            }
+            Language::PlutusV2 => {
+                let mut redeemers: Vec<_> = tx
+                    .redeemers()
+                    .iter()
+                    .filter_map(|x| Some((self.purpose(&x.tag, x.index)?, x.data.clone())))
+                    .collect();
+                redeemers.sort_by_key(|(purpose, _)| *purpose);
+
+                let redeemers = redeemers
+                    .into_iter()
+                    .map(|(purpose, data)| (self.purpose_to_data(&purpose), data))
+                    .collect();
+
+                Ok(constr(
+                    0,
+                    vec![
+                        inputs(&self.inputs)?,
+                        inputs(&self.reference_inputs)?,
+                        list(outputs),
+                        fee,
+                        mint,
+                        certs,
+                        map(withdrawals.collect()),
+                        self.validity_range(history)?,
+                        signers,
+                        map(redeemers),
+                        map(self.datums()),
+                        id,
+                    ],
+                ))
+            }
+        }
+    }
+
+    /// the Plutus scripts available to the tx, from the witness set and the
+    /// reference scripts of the inputs and reference inputs
+    fn scripts(&self) -> HashMap<Hash<28>, (Language, Vec<u8>)> {
+        let mut scripts = HashMap::new();
+
+        for script in self.tx.plutus_v1_scripts() {
+            scripts.insert(
+                script.compute_hash(),
+                (Language::PlutusV1, script.0.to_vec()),
+            );
+        }
+
+        for script in self.tx.plutus_v2_scripts() {
+            scripts.insert(
+                script.compute_hash(),
+                (Language::PlutusV2, script.0.to_vec()),
+            );
+        }
+
+        for (_, output) in self.inputs.iter().chain(self.reference_inputs.iter()) {
+            match output.script_ref().map(|x| &x.0) {
+                Some(Script::PlutusV1Script(x)) => {
+                    scripts.insert(x.compute_hash(), (Language::PlutusV1, x.0.to_vec()));
+                }
+                Some(Script::PlutusV2Script(x)) => {
+                    scripts.insert(x.compute_hash(), (Language::PlutusV2, x.0.to_vec()));
+                }
+                _ => (),
+            }
+        }
+
+        scripts
+    }
+
+    /// the datum of the spent input, either inline or from the witness set
+    fn spent_datum(&self, hash: &Hash<32>, index: u64) -> Option<PlutusData> {
+        let (_, output) = self
+            .inputs
+            .iter()
+            .find(|(x, _)| x.hash() == hash && x.index() == index)?;
+
+        match output.datum()? {
+            PseudoDatumOption::Hash(x) => self
+                .tx
+                .plutus_data()
+                .iter()
+                .find(|d| d.original_hash() == x)
+                .map(|d| d.clone().unwrap()),
+            PseudoDatumOption::Data(x) => Some(x.0.unwrap()),
+        }
+    }
+}
+
+/// build the `TxInfo` of the tx as seen by scripts of the given language
+pub fn tx_info(
+    tx: &MultiEraTx,
+    utxos: &ResolvedInputs,
+    language: Language,
+    history: &EraHistory,
+) -> Result<PlutusData, Phase2Error> {
+    TxParts::new(tx, utxos)?.tx_info(&language, history)
+}
+
+/// build the `ScriptContext` of a redeemer of the tx, as seen by scripts of
+/// the given language
+pub fn script_context(
+    tx: &MultiEraTx,
+    utxos: &ResolvedInputs,
+    language: Language,
+    history: &EraHistory,
+    tag: RedeemerTag,
+    index: u32,
+) -> Result<PlutusData, Phase2Error> {
+    let parts = TxParts::new(tx, utxos)?;
+
+    let purpose = parts
+        .purpose(&tag, index)
+        .ok_or(Phase2Error::DanglingRedeemer(tag, index))?;
+
+    Ok(constr(
+        0,
+        vec![
+            parts.tx_info(&language, history)?,
+            parts.purpose_to_data(&purpose),
+        ],
+    ))
+}
+
+/// run the script of every redeemer of the tx, returning the execution units
+/// consumed by each one
+pub fn eval_redeemers<E: Evaluator>(
+    tx: &MultiEraTx,
+    utxos: &ResolvedInputs,
+    params: &EvalParams,
+    evaluator: &E,
+) -> Result<Vec<EvalResult>, Phase2Error> {
+    let parts = TxParts::new(tx, utxos)?;
+    let scripts = parts.scripts();
+
+    let mut tx_infos: Vec<(Language, PlutusData)> = vec![];
+    let mut results = vec![];
+
+    for redeemer in tx.redeemers() {
+        let (tag, index) = (redeemer.tag.clone(), redeemer.index);
+
+        let purpose = parts
+            .purpose(&tag, index)
+            .ok_or_else(|| Phase2Error::DanglingRedeemer(tag.clone(), index))?;
+
+        let hash = match parts.script_credential(&purpose) {
+            Some(Credential::Script(x)) => x,
+            _ => return Err(Phase2Error::NotAScript(tag, index)),
+        };
+
+        let (language, script) = scripts.get(&hash).ok_or(Phase2Error::MissingScript(hash))?;
+
+        let cost_model = match language {
+            Language::PlutusV1 => params.cost_models.plutus_v1.as_ref(),
+            Language::PlutusV2 => params.cost_models.plutus_v2.as_ref(),
+        }
+        .ok_or_else(|| Phase2Error::MissingCostModel(language.clone()))?;
+
+        // the tx info is the same for every script of the same language
+        let tx_info = match tx_infos.iter().find(|(x, _)| x == language) {
+            Some((_, info)) => info.clone(),
+            None => {
+                let info = parts.tx_info(language, params.history)?;
+                tx_infos.push((language.clone(), info.clone()));
+                info
+            }
+        };
+
+        let context = constr(0, vec![tx_info, parts.purpose_to_data(&purpose)]);
+
+        let args = match purpose {
+            ScriptPurpose::Spending(hash, index) => {
+                let datum = parts
+                    .spent_datum(&hash, index)
+                    .ok_or_else(|| Phase2Error::MissingDatum(OutputRef::new(hash, index)))?;
+
+                vec![datum, redeemer.data.clone(), context]
+            }
+            _ => vec![redeemer.data.clone(), context],
+        };
+
+        let ex_units = evaluator
+            .eval(
+                language.clone(),
+                script,
+                &args,
+                cost_model,
+                params.max_ex_units.clone(),
+            )
+            .map_err(|e| Phase2Error::ScriptFailure(tag.clone(), index, e.to_string()))?;
+
+        results.push(EvalResult {
+            tag,
+            index,
+            ex_units,
+        });
+    }
+
+    Ok(results)
+}
+
+/// run the script of every redeemer of the tx on the CEK machine shipped with
+/// pallas, returning the execution units consumed by each one
+#[cfg(feature = "uplc")]
+pub fn eval_tx(
+    tx: &MultiEraTx,
+    utxos: &ResolvedInputs,
+    params: &EvalParams,
+) -> Result<Vec<EvalResult>, Phase2Error> {
+    eval_redeemers(tx, utxos, params, &crate::cek::CekMachine)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use pallas_codec::minicbor;
+    use pallas_primitives::alonzo::{TransactionOutput, Value};
+    use pallas_traverse::{wellknown::GenesisValues, MultiEraBlock};
+
+    use super::*;
+
+    /// records the calls and charges one unit of memory per argument
+    #[derive(Default)]
+    struct MockEvaluator {
+        calls: RefCell<Vec<(Language, Vec<PlutusData>)>>,
+    }
+
+    impl Evaluator for MockEvaluator {
+        type Error = String;
+
+        fn eval(
+            &self,
+            language: Language,
+            script: &[u8],
+            args: &[PlutusData],
+            _cost_model: &[i64],
+            budget: ExUnits,
+        ) -> Result<ExUnits, Self::Error> {
+            if script.is_empty() || budget.mem == 0 {
+                return Err("out of budget".into());
+            }
+
+            self.calls.borrow_mut().push((language, args.to_vec()));
+
+            Ok(ExUnits {
+                mem: args.len() as u32,
+                steps: script.len() as u64,
+            })
+        }
+    }
+
+    fn fields(data: &PlutusData) -> (u64, &[PlutusData]) {
+        match data {
+            PlutusData::Constr(x) => (x.tag, &x.fields),
+            _ => panic!("not a constr: {data:?}"),
+        }
+    }
+
+    fn alonzo_output(address: Vec<u8>, datum_hash: Option<Hash<32>>) -> EraCbor {
+        let output = TransactionOutput {
+            address: address.into(),
+            amount: Value::Coin(2_000_000),
+            datum_hash,
+        };
+
+        EraCbor(Era::Alonzo, minicbor::to_vec(output).unwrap())
+    }
+
+    /// resolve the inputs of the tx to outputs locked by a key, except for the
+    /// ones with a spend redeemer, which are locked by the given script
+    fn fake_utxos(tx: &MultiEraTx, script: Hash<28>) -> ResolvedInputs {
+        let mut refs: Vec<_> = tx.inputs().iter().map(|x| x.output_ref()).collect();
+        refs.sort_by_key(|x| (*x.hash(), x.index()));
+
+        let spent: Vec<_> = tx
+            .redeemers()
+            .iter()
+            .filter(|x| x.tag == RedeemerTag::Spend)
+            .map(|x| x.index as usize)
+            .collect();
+
+        let datum = tx.plutus_data().first().map(|x| x.original_hash());
+
+        refs.into_iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let output = if spent.contains(&i) {
+                    alonzo_output([&[0x71], script.as_ref()].concat(), datum)
+                } else {
+                    alonzo_output([&[0x61], [i as u8; 28].as_slice()].concat(), None)
+                };
+
+                (x, output)
+            })
+            .collect()
+    }
+
+    fn eval_params<'a>(cost_models: &'a CostMdls, history: &'a EraHistory) -> EvalParams<'a> {
+        EvalParams {
+            cost_models,
+            max_ex_units: ExUnits {
+                mem: 14_000_000,
+                steps: 10_000_000_000,
+            },
+            history,
+        }
+    }
+
+    #[test]
+    fn plutus_v1_spend_and_mint() {
+        let cbor = hex::decode(include_str!("../../test_data/alonzo14.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().swap_remove(9);
+
+        let policy = *tx.mints().first().unwrap().policy();
+        let script = tx
+            .plutus_v1_scripts()
+            .iter()
+            .map(|x| x.compute_hash())
+            .find(|x| *x != policy)
+            .unwrap();
+
+        let utxos = fake_utxos(&tx, script);
+        let history = EraHistory::from(&GenesisValues::mainnet());
+        let cost_models = CostMdls {
+            plutus_v1: Some(vec![0; 166]),
+            plutus_v2: None,
+            layout: Default::default(),
+        };
+
+        let params = eval_params(&cost_models, &history);
+
+        let evaluator = MockEvaluator::default();
+        let results = eval_redeemers(&tx, &utxos, &params, &evaluator).unwrap();
+
+        let units: Vec<_> = results
+            .iter()
+            .map(|x| (x.tag.clone(), x.index, x.ex_units.mem))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (RedeemerTag::Spend, 0, 3),
+                (RedeemerTag::Spend, 1, 3),
+                (RedeemerTag::Mint, 0, 2)
+            ]
+        );
+
+        for (language, args) in evaluator.calls.borrow().iter() {
+            assert_eq!(*language, Language::PlutusV1);
+
+            let (tag, context) = fields(args.last().unwrap());
+            assert_eq!(tag, 121);
+            assert_eq!(context.len(), 2);
+
+            let (_, info) = fields(&context[0]);
+            assert_eq!(info.len(), 10);
+
+            // the id of the tx is the last field of the info
+            let (_, id) = fields(&info[9]);
+            assert_eq!(id, [bytes(tx.hash().as_ref())]);
+        }
+
+        let calls = evaluator.calls.borrow();
+        let (purpose, _) = fields(&fields(&calls[2].1[1]).1[1]);
+        assert_eq!(purpose, 121);
+        let (purpose, _) = fields(&fields(&calls[0].1[2]).1[1]);
+        assert_eq!(purpose, 122);
+
+        // spending scripts get the datum of the input
+        assert_eq!(calls[0].1[0], tx.plutus_data()[0].clone().unwrap());
+    }
+
+    #[test]
+    fn plutus_v2_context() {
+        let cbor = hex::decode(include_str!("../../test_data/babbage8.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().swap_remove(1);
+
+        let utxos = fake_utxos(&tx, Hash::new([0; 28]));
+        let history = EraHistory::from(&GenesisValues::mainnet());
+
+        let context = script_context(
+            &tx,
+            &utxos,
+            Language::PlutusV2,
+            &history,
+            RedeemerTag::Mint,
+            0,
+        )
+        .unwrap();
+
+        let (_, context) = fields(&context);
+        let (_, info) = fields(&context[0]);
+        assert_eq!(info.len(), 12);
+
+        // the redeemers of the tx are part of the V2 info
+        match &info[9] {
+            PlutusData::Map(x) => assert_eq!(x.len(), 1),
+            x => panic!("unexpected redeemers {x:?}"),
+        }
+
+        // the tx has no ttl, so the upper bound is +inf and closed
+        assert_eq!(tx.ttl(), None);
+        let (_, range) = fields(&info[7]);
+        let (_, upper) = fields(&range[1]);
+        assert_eq!(upper, [constr(2, vec![]), boolean(true)]);
+
+        let cost_models = CostMdls {
+            plutus_v1: Some(vec![0; 166]),
+            plutus_v2: None,
+            layout: Default::default(),
+        };
+
+        let params = eval_params(&cost_models, &history);
+
+        let evaluator = MockEvaluator::default();
+        assert!(matches!(
+            eval_redeemers(&tx, &utxos, &params, &evaluator),
+            Err(Phase2Error::MissingCostModel(Language::PlutusV2))
+        ));
+    }
+
+    #[test]
+    fn unresolved_inputs_are_reported() {
+        let cbor = hex::decode(include_str!("../../test_data/babbage8.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().swap_remove(1);
+
+        let history = EraHistory::from(&GenesisValues::mainnet());
+
+        assert!(matches!(
+            tx_info(&tx, &ResolvedInputs::new(), Language::PlutusV2, &history),
+            Err(Phase2Error::UnresolvedInput(_))
+        ));
+    }
+
+    #[test]
+    fn big_integers() {
+        assert_eq!(integer(-1), PlutusData::BigInt(BigInt::Int(Int::from(-1))));
+
+        let big = u64::MAX as i128 + 1;
+        assert_eq!(
+            integer(big),
+            PlutusData::BigInt(BigInt::BigUInt(vec![1, 0, 0, 0, 0, 0, 0, 0, 0].into()))
+        );
+        assert_eq!(
+            integer(-big - 1),
+            PlutusData::BigInt(BigInt::BigNInt(vec![1, 0, 0, 0, 0, 0, 0, 0, 0].into()))
+        );
+    }
+}
@@ -0,0 +1,714 @@
+//! Validation of Shelley-based block headers, as done by the Praos consensus
+//!
+//! The checks implemented here are the ones required to follow a chain of
+//! headers without trusting the upstream peer: each header must extend its
+//! parent, its issuer must have won the leader lottery for the slot (given
+//! the stake of the pool and the epoch nonce) and it must be signed by a hot
+//! key delegated through a valid operational certificate.
+//!
+//! Both flavours of the protocol are supported: TPraos, used by Shelley
+//! through Alonzo, and Praos, used from Babbage onwards. The overlay schedule
+//! of the early Shelley eras (when the decentralisation parameter was above
+//! zero and some slots were reserved to the genesis delegates) is not.
+
+use std::collections::HashMap;
+
+use num_bigint::{BigInt, Sign};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_crypto::kes::{self, KesError};
+use pallas_crypto::key::ed25519;
+use pallas_crypto::vrf::{self, VrfError};
+use pallas_primitives::alonzo::{self, NonceVariant, RationalNumber};
+use pallas_traverse::history::EraHistory;
+use pallas_traverse::MultiEraHeader;
+use thiserror::Error;
+
+use crate::non_integral::{ExpCmp, FixedPoint};
+
+/// A nonce used to seed the leader election, `None` is the neutral nonce
+pub type Nonce = Option<Hash<32>>;
+
+/// The hash of the cold verification key of a pool
+pub type PoolId = Hash<28>;
+
+#[derive(Debug, Error)]
+pub enum HeaderError {
+    #[error("Byron headers can't be validated by Praos")]
+    ByronHeader,
+
+    #[error(transparent)]
+    Traverse(#[from] pallas_traverse::Error),
+
+    #[error("Previous hash {1:?} doesn't match the parent {0:?}")]
+    PrevHashMismatch(Option<Hash<32>>, Option<Hash<32>>),
+
+    #[error("Block number {1} doesn't follow the parent {0}")]
+    BlockNumberMismatch(u64, u64),
+
+    #[error("Slot {1} is not after the slot of the parent {0}")]
+    SlotNotIncreasing(u64, u64),
+
+    #[error("Malformed key in header: {0}")]
+    MalformedKey(String),
+
+    #[error("Unknown pool {0}")]
+    UnknownPool(PoolId),
+
+    #[error("VRF key of the header doesn't match the one registered by the pool")]
+    VrfKeyMismatch,
+
+    #[error("Invalid VRF proof: {0}")]
+    InvalidVrf(#[from] VrfError),
+
+    #[error("VRF output doesn't match the proof")]
+    VrfOutputMismatch,
+
+    #[error("Issuer is not the leader of the slot")]
+    NotLeader,
+
+    #[error("Invalid operational certificate or signature: {0}")]
+    InvalidKes(#[from] KesError),
+}
+
+impl HeaderError {
+    fn malformed_key(error: impl std::fmt::Display) -> Self {
+        HeaderError::MalformedKey(format!("{error}"))
+    }
+}
+
+/// Consensus parameters, taken from the Shelley genesis of the network
+#[derive(Debug, Clone)]
+pub struct PraosParams {
+    /// The probability of a slot having a leader (`f`)
+    pub active_slot_coeff: RationalNumber,
+    pub slots_per_kes_period: u64,
+    pub max_kes_evolutions: u64,
+    /// Slots before the end of the epoch when the candidate nonce is frozen
+    ///
+    /// It is `3k/f` for the TPraos eras and for Babbage, `4k/f` from Conway
+    /// onwards.
+    pub randomness_stabilisation_window: u64,
+}
+
+/// The view of a pool required to validate the headers it issues
+///
+/// The stake is the one of the snapshot used for the leader schedule of the
+/// epoch of the header (the "set" snapshot).
+#[derive(Debug, Clone)]
+pub struct PoolInfo {
+    pub vrf_keyhash: Hash<32>,
+    /// Stake of the pool relative to the total active stake (`σ`)
+    pub relative_stake: RationalNumber,
+}
+
+/// Combines two nonces, the `⭒` operator of the spec
+pub fn combine_nonces(a: &Nonce, b: &Nonce) -> Nonce {
+    match (a, b) {
+        (None, x) | (x, None) => *x,
+        (Some(a), Some(b)) => Some(Hasher::<256>::hash(&[&a[..], &b[..]].concat())),
+    }
+}
+
+/// The extra entropy of a set of protocol params, as a nonce
+///
+/// Mainnet only made use of it once, during the Shelley era.
+pub fn extra_entropy(nonce: &alonzo::Nonce) -> Nonce {
+    match nonce.variant {
+        NonceVariant::NeutralNonce => None,
+        NonceVariant::Nonce => nonce.hash,
+    }
+}
+
+/// The contribution of a header to the evolving nonce
+pub fn header_nonce(header: &MultiEraHeader) -> Result<Hash<32>, HeaderError> {
+    let output = header.nonce_vrf_output()?;
+    Ok(Hasher::<256>::hash(&output))
+}
+
+/// Checks that the leader value is below the threshold of the pool
+///
+/// A pool with relative stake `σ` is leader if `p < 1 - (1 - f)^σ`, where `p`
+/// is the leader value interpreted as a fraction in `[0, 1)`: the 64 bytes of
+/// the VRF output for TPraos, the 32 bytes of its hash for Praos. Same as the
+/// `checkLeaderNatValue` of the node, this is checked as `1 / (1 - p) <
+/// exp(-σ ln(1 - f))`, using the fixed point arithmetic of the ledger and the
+/// Taylor series of `exp` until the remainder is small enough to tell.
+pub fn is_slot_leader(
+    leader_value: &[u8],
+    relative_stake: &RationalNumber,
+    active_slot_coeff: &RationalNumber,
+) -> bool {
+    if active_slot_coeff.numerator >= active_slot_coeff.denominator {
+        return true;
+    }
+
+    let cert_nat = BigInt::from_bytes_be(Sign::Plus, leader_value);
+    let cert_nat_max = BigInt::from(1) << (8 * leader_value.len());
+    let recip_q = FixedPoint::from_ratio(cert_nat_max.clone(), cert_nat_max - cert_nat);
+
+    let f = FixedPoint::from_ratio(active_slot_coeff.numerator, active_slot_coeff.denominator);
+    let c = match (FixedPoint::one() - f).ln() {
+        Some(x) => x,
+        None => return false,
+    };
+
+    let sigma = FixedPoint::from_ratio(relative_stake.numerator, relative_stake.denominator);
+    let x = -(sigma * c);
+
+    x.exp_cmp(3, &recip_q) == ExpCmp::Below
+}
+
+/// The VRF input of the TPraos eras, which is different for each proof
+fn tpraos_seed(tag: u64, slot: u64, epoch_nonce: &Nonce) -> Vec<u8> {
+    let tag = Hasher::<256>::hash(&tag.to_be_bytes());
+    let seed = praos_seed(slot, epoch_nonce);
+
+    tag.iter().zip(seed.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+/// The VRF input of the Praos eras, shared by the leader and nonce values
+fn praos_seed(slot: u64, epoch_nonce: &Nonce) -> Hash<32> {
+    let mut input = slot.to_be_bytes().to_vec();
+
+    if let Some(nonce) = epoch_nonce {
+        input.extend_from_slice(&nonce[..]);
+    }
+
+    Hasher::<256>::hash(&input)
+}
+
+fn verify_vrf(
+    vkey: &vrf::PublicKey,
+    input: &[u8],
+    output: &[u8],
+    proof: &[u8],
+) -> Result<(), HeaderError> {
+    let proof = vrf::Proof::try_from(proof)?;
+
+    if vkey.verify(input, &proof)?.as_ref() != output {
+        return Err(HeaderError::VrfOutputMismatch);
+    }
+
+    Ok(())
+}
+
+/// Checks that the header extends the given tip
+pub fn validate_linkage(header: &MultiEraHeader, tip: &Tip) -> Result<(), HeaderError> {
+    let expected = Some(tip.hash);
+
+    if header.previous_hash() != expected {
+        return Err(HeaderError::PrevHashMismatch(
+            expected,
+            header.previous_hash(),
+        ));
+    }
+
+    if header.number() != tip.number + 1 {
+        return Err(HeaderError::BlockNumberMismatch(
+            tip.number,
+            header.number(),
+        ));
+    }
+
+    if header.slot() <= tip.slot {
+        return Err(HeaderError::SlotNotIncreasing(tip.slot, header.slot()));
+    }
+
+    Ok(())
+}
+
+/// Checks the operational certificate and the KES signature of the header
+///
+/// `last_counter` is the latest opcert counter seen for the pool, zero if the
+/// pool hasn't issued any block yet.
+pub fn validate_opcert(
+    header: &MultiEraHeader,
+    last_counter: u64,
+    params: &PraosParams,
+) -> Result<(), HeaderError> {
+    let cold_vkey = header.issuer_vkey().ok_or(HeaderError::ByronHeader)?;
+    let cold_vkey = ed25519::PublicKey::try_from(cold_vkey).map_err(HeaderError::malformed_key)?;
+
+    let opcert = header.operational_cert()?;
+    opcert.verify(&cold_vkey)?;
+    opcert.check_counter(last_counter)?;
+
+    let current = header.slot() / params.slots_per_kes_period;
+    let period = opcert.evolution(current, params.max_kes_evolutions)?;
+
+    let signature = header.body_signature().ok_or(HeaderError::ByronHeader)?;
+    let signature = kes::Signature::try_from(signature)?;
+    let body = header.header_body_cbor().ok_or(HeaderError::ByronHeader)?;
+
+    opcert.hot_vkey.verify(period, body, &signature)?;
+
+    Ok(())
+}
+
+/// Checks the VRF proofs of the header and the eligibility of the issuer
+pub fn validate_leader(
+    header: &MultiEraHeader,
+    epoch_nonce: &Nonce,
+    pool: &PoolInfo,
+    params: &PraosParams,
+) -> Result<(), HeaderError> {
+    let vrf_vkey = header.vrf_vkey().ok_or(HeaderError::ByronHeader)?;
+
+    if Hasher::<256>::hash(vrf_vkey) != pool.vrf_keyhash {
+        return Err(HeaderError::VrfKeyMismatch);
+    }
+
+    let vrf_vkey = vrf::PublicKey::try_from(vrf_vkey)?;
+    let slot = header.slot();
+
+    match header {
+        MultiEraHeader::AlonzoCompatible(x) => {
+            let body = &x.header_body;
+
+            let input = tpraos_seed(1, slot, epoch_nonce);
+            verify_vrf(&vrf_vkey, &input, &body.leader_vrf.0, &body.leader_vrf.1)?;
+
+            let input = tpraos_seed(0, slot, epoch_nonce);
+            verify_vrf(&vrf_vkey, &input, &body.nonce_vrf.0, &body.nonce_vrf.1)?;
+        }
+        MultiEraHeader::Babbage(x) => {
+            let body = &x.header_body;
+
+            let input = praos_seed(slot, epoch_nonce);
+            verify_vrf(
+                &vrf_vkey,
+                &input[..],
+                &body.vrf_result.0,
+                &body.vrf_result.1,
+            )?;
+        }
+        MultiEraHeader::EpochBoundary(_) | MultiEraHeader::Byron(_) => {
+            return Err(HeaderError::ByronHeader)
+        }
+    }
+
+    let leader_value = header.leader_vrf_output()?;
+
+    if !is_slot_leader(
+        &leader_value,
+        &pool.relative_stake,
+        &params.active_slot_coeff,
+    ) {
+        return Err(HeaderError::NotLeader);
+    }
+
+    Ok(())
+}
+
+/// The nonces tracked by the protocol to derive the nonce of each epoch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Nonces {
+    /// The nonce used for the leader election of the current epoch
+    pub epoch: Nonce,
+    /// Accumulates the contribution of every header
+    pub evolving: Nonce,
+    /// The evolving nonce, frozen near the end of the epoch
+    pub candidate: Nonce,
+    /// The hash of the parent of the latest header
+    pub lab: Nonce,
+    /// The `lab` nonce as it was at the end of the previous epoch
+    pub last_epoch_block: Nonce,
+}
+
+impl Nonces {
+    /// Accumulates the header nonce, updating the candidate if the header is
+    /// outside of the stabilisation window
+    pub fn evolve(&mut self, header_nonce: Hash<32>, prev_hash: Nonce, update_candidate: bool) {
+        self.evolving = combine_nonces(&self.evolving, &Some(header_nonce));
+
+        if update_candidate {
+            self.candidate = self.evolving;
+        }
+
+        self.lab = prev_hash;
+    }
+
+    /// Derives the nonce of a new epoch, `extra_entropy` is only relevant
+    /// for the TPraos eras
+    pub fn tick(&mut self, extra_entropy: &Nonce) {
+        let epoch = combine_nonces(&self.candidate, &self.last_epoch_block);
+        self.epoch = combine_nonces(&epoch, extra_entropy);
+        self.last_epoch_block = self.lab;
+    }
+}
+
+/// The latest header accepted by the state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tip {
+    pub slot: u64,
+    pub hash: Hash<32>,
+    pub number: u64,
+}
+
+impl From<&MultiEraHeader<'_>> for Tip {
+    fn from(header: &MultiEraHeader) -> Self {
+        Self {
+            slot: header.slot(),
+            hash: header.hash(),
+            number: header.number(),
+        }
+    }
+}
+
+/// The consensus state required to follow a chain of headers
+///
+/// The state is cheap to clone; to support rollbacks, keep a copy of the state
+/// for each of the latest `k` headers.
+#[derive(Debug, Clone)]
+pub struct PraosState {
+    pub tip: Option<Tip>,
+    pub epoch: u64,
+    pub nonces: Nonces,
+    pub opcert_counters: HashMap<PoolId, u64>,
+}
+
+impl PraosState {
+    /// Starts following the chain from a known state, usually a snapshot of
+    /// the consensus state of a trusted node
+    ///
+    /// `opcert_counters` are the latest counters seen for each pool, pools
+    /// missing from the snapshot are only accepted with counters 0 or 1.
+    pub fn new(
+        tip: Option<Tip>,
+        epoch: u64,
+        nonces: Nonces,
+        opcert_counters: HashMap<PoolId, u64>,
+    ) -> Self {
+        Self {
+            tip,
+            epoch,
+            nonces,
+            opcert_counters,
+        }
+    }
+
+    /// Validates the header and applies it to the state
+    ///
+    /// `pools` provides the stake distribution of the epoch of the header, it
+    /// is queried after the epoch transition, if any, has been applied.
+    /// `entropy` provides the extra entropy of the protocol params applied at
+    /// the start of each epoch, see [`extra_entropy`]. The state is left
+    /// untouched if the header is not valid.
+    pub fn roll_forward<F, E>(
+        &mut self,
+        header: &MultiEraHeader,
+        history: &EraHistory,
+        params: &PraosParams,
+        pools: F,
+        entropy: E,
+    ) -> Result<(), HeaderError>
+    where
+        F: Fn(u64, &PoolId) -> Option<PoolInfo>,
+        E: Fn(u64) -> Nonce,
+    {
+        if let Some(tip) = &self.tip {
+            validate_linkage(header, tip)?;
+        }
+
+        let (epoch, _) = history.slot_to_epoch(header.slot())?;
+        let mut nonces = self.nonces.clone();
+
+        // the nonce ticks once when a new epoch is first seen, no matter how
+        // many epochs without blocks were skipped, same as the node does
+        if epoch > self.epoch {
+            nonces.tick(&entropy(epoch));
+        }
+
+        let issuer = header.issuer_vkey().ok_or(HeaderError::ByronHeader)?;
+        let pool_id = Hasher::<224>::hash(issuer);
+        let pool = pools(epoch, &pool_id).ok_or(HeaderError::UnknownPool(pool_id))?;
+
+        validate_leader(header, &nonces.epoch, &pool, params)?;
+
+        let last_counter = self.opcert_counters.get(&pool_id).copied().unwrap_or(0);
+        validate_opcert(header, last_counter, params)?;
+
+        let next_epoch = history.epoch_to_slot(epoch + 1, 0)?;
+        let update_candidate = header.slot() + params.randomness_stabilisation_window < next_epoch;
+
+        nonces.evolve(
+            header_nonce(header)?,
+            header.previous_hash(),
+            update_candidate,
+        );
+
+        let counter = header.operational_cert()?.sequence_number;
+        self.opcert_counters.insert(pool_id, counter);
+
+        self.nonces = nonces;
+        self.epoch = epoch;
+        self.tip = Some(Tip::from(header));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use pallas_traverse::{wellknown::GenesisValues, MultiEraBlock};
+
+    use super::*;
+
+    fn load_block(block_str: &str) -> Vec<u8> {
+        hex::decode(block_str).expect("invalid hex")
+    }
+
+    fn ratio(numerator: u64, denominator: u64) -> RationalNumber {
+        RationalNumber {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// the 64 byte leader value of `p + offset * 10^-20`
+    fn leader_value(p: RationalNumber, offset: i64) -> Vec<u8> {
+        let max = BigInt::from(1) << 512usize;
+        let value = BigInt::from(p.numerator) * &max / p.denominator;
+        let value = value + BigInt::from(offset) * max / BigInt::from(10u64).pow(20);
+
+        let (_, bytes) = value.to_bytes_be();
+        [vec![0; 64 - bytes.len()], bytes].concat()
+    }
+
+    #[test]
+    fn leader_threshold() {
+        let low = [0x00; 32];
+        let high = [0xff; 32];
+        let mid = [0x10; 64];
+        let f = ratio(1, 20);
+
+        assert!(is_slot_leader(&low, &ratio(1, 100), &f));
+        assert!(!is_slot_leader(&high, &ratio(1, 1), &f));
+
+        // 0x10.. is slightly above 1/16, the threshold for the full stake is f
+        assert!(!is_slot_leader(&mid, &ratio(1, 1), &f));
+        assert!(is_slot_leader(&mid, &ratio(1, 1), &ratio(1, 10)));
+        assert!(!is_slot_leader(&mid, &ratio(1, 2), &ratio(1, 10)));
+
+        assert!(is_slot_leader(&high, &ratio(0, 1), &ratio(1, 1)));
+        assert!(!is_slot_leader(&low, &ratio(0, 1), &f));
+
+        // values 10^-20 away from the threshold are told apart, with the
+        // full stake the threshold is exactly f
+        assert!(is_slot_leader(
+            &leader_value(ratio(1, 20), -1),
+            &ratio(1, 1),
+            &f
+        ));
+        assert!(!is_slot_leader(
+            &leader_value(ratio(1, 20), 1),
+            &ratio(1, 1),
+            &f
+        ));
+
+        // 1 - (1 - 1/20)^(1/2) = 0.02532056551...
+        assert!(is_slot_leader(
+            &leader_value(ratio(253205655, 10u64.pow(10)), 0),
+            &ratio(1, 2),
+            &f
+        ));
+        assert!(!is_slot_leader(
+            &leader_value(ratio(253205656, 10u64.pow(10)), 0),
+            &ratio(1, 2),
+            &f
+        ));
+    }
+
+    #[test]
+    fn nonce_evolution() {
+        let (ha, hb) = (Hasher::<256>::hash(b"a"), Hasher::<256>::hash(b"b"));
+        let (a, b) = (Some(ha), Some(hb));
+
+        assert_eq!(combine_nonces(&None, &a), a);
+        assert_eq!(combine_nonces(&a, &None), a);
+        assert_ne!(combine_nonces(&a, &b), combine_nonces(&b, &a));
+
+        let mut nonces = Nonces::default();
+
+        nonces.evolve(ha, b, true);
+        assert_eq!(nonces.evolving, a);
+        assert_eq!(nonces.candidate, a);
+
+        // inside of the stabilisation window the candidate is frozen
+        nonces.evolve(hb, a, false);
+        assert_eq!(nonces.evolving, combine_nonces(&a, &b));
+        assert_eq!(nonces.candidate, a);
+        assert_eq!(nonces.lab, a);
+
+        nonces.tick(&None);
+        assert_eq!(nonces.epoch, a);
+        assert_eq!(nonces.last_epoch_block, a);
+
+        nonces.tick(&None);
+        assert_eq!(nonces.epoch, combine_nonces(&a, &a));
+
+        nonces.tick(&b);
+        assert_eq!(nonces.epoch, combine_nonces(&combine_nonces(&a, &a), &b));
+    }
+
+    #[test]
+    fn extra_entropy_from_params() {
+        let hash = Hasher::<256>::hash(b"entropy");
+
+        let neutral = alonzo::Nonce {
+            variant: NonceVariant::NeutralNonce,
+            hash: None,
+        };
+
+        let nonce = alonzo::Nonce {
+            variant: NonceVariant::Nonce,
+            hash: Some(hash),
+        };
+
+        assert_eq!(extra_entropy(&neutral), None);
+        assert_eq!(extra_entropy(&nonce), Some(hash));
+    }
+
+    #[test]
+    fn ticks_once_per_new_epoch() {
+        let cbor = load_block(include_str!("../../test_data/alonzo1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let header = block.header();
+
+        let history = EraHistory::from(&GenesisValues::mainnet());
+        let (epoch, _) = history.slot_to_epoch(header.slot()).unwrap();
+
+        let params = PraosParams {
+            active_slot_coeff: ratio(1, 20),
+            slots_per_kes_period: 129600,
+            max_kes_evolutions: 62,
+            randomness_stabilisation_window: 129600,
+        };
+
+        let pool = PoolInfo {
+            vrf_keyhash: Hasher::<256>::hash(header.vrf_vkey().unwrap()),
+            relative_stake: ratio(1, 1),
+        };
+
+        let ticks = RefCell::new(vec![]);
+
+        let roll_forward = |state: &mut PraosState| {
+            state.roll_forward(
+                &header,
+                &history,
+                &params,
+                |_, _| Some(pool.clone()),
+                |epoch| {
+                    ticks.borrow_mut().push(epoch);
+                    None
+                },
+            )
+        };
+
+        // skipping empty epochs still ticks a single time, with the entropy
+        // of the new epoch. Without the real nonces the header is rejected.
+        let mut state = PraosState::new(None, epoch - 3, Nonces::default(), HashMap::new());
+        let result = roll_forward(&mut state);
+
+        assert!(matches!(result, Err(HeaderError::InvalidVrf(..))));
+        assert_eq!(ticks.take(), vec![epoch]);
+
+        assert_eq!(state.epoch, epoch - 3);
+        assert_eq!(state.nonces, Nonces::default());
+
+        // within the same epoch there's nothing to tick
+        let mut state = PraosState::new(None, epoch, Nonces::default(), HashMap::new());
+        let result = roll_forward(&mut state);
+
+        assert!(matches!(result, Err(HeaderError::InvalidVrf(..))));
+        assert!(ticks.take().is_empty());
+    }
+
+    #[test]
+    fn real_headers() {
+        // mainnet parameters, the babbage block is from a test network with
+        // shorter KES periods
+        let blocks = [
+            (include_str!("../../test_data/alonzo1.block"), 129600),
+            (include_str!("../../test_data/babbage1.block"), 86400),
+        ];
+
+        for (block_str, slots_per_kes_period) in blocks {
+            let cbor = load_block(block_str);
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+            let header = block.header();
+
+            let params = PraosParams {
+                active_slot_coeff: ratio(1, 20),
+                slots_per_kes_period,
+                max_kes_evolutions: 62,
+                randomness_stabilisation_window: 129600,
+            };
+
+            let counter = header.operational_cert().unwrap().sequence_number;
+            validate_opcert(&header, counter, &params).unwrap();
+
+            assert!(matches!(
+                validate_opcert(&header, counter + 1, &params),
+                Err(HeaderError::InvalidKes(KesError::CounterTooSmall(..)))
+            ));
+
+            let tip = Tip::from(&header);
+
+            assert!(matches!(
+                validate_linkage(&header, &tip),
+                Err(HeaderError::PrevHashMismatch(..))
+            ));
+
+            let tip = Tip {
+                hash: header.previous_hash().unwrap(),
+                ..tip
+            };
+
+            assert!(matches!(
+                validate_linkage(&header, &tip),
+                Err(HeaderError::BlockNumberMismatch(..))
+            ));
+
+            let tip = Tip {
+                number: header.number() - 1,
+                ..tip
+            };
+
+            assert!(matches!(
+                validate_linkage(&header, &tip),
+                Err(HeaderError::SlotNotIncreasing(..))
+            ));
+
+            let tip = Tip {
+                slot: header.slot() - 1,
+                ..tip
+            };
+
+            validate_linkage(&header, &tip).unwrap();
+
+            // without the real epoch nonce, the VRF proofs can't be verified
+            let pool = PoolInfo {
+                vrf_keyhash: Hasher::<256>::hash(header.vrf_vkey().unwrap()),
+                relative_stake: ratio(1, 1),
+            };
+
+            assert!(matches!(
+                validate_leader(&header, &None, &pool, &params),
+                Err(HeaderError::InvalidVrf(VrfError::VerificationFailed))
+            ));
+
+            let pool = PoolInfo {
+                vrf_keyhash: Hash::new([0; 32]),
+                ..pool
+            };
+
+            assert!(matches!(
+                validate_leader(&header, &None, &pool, &params),
+                Err(HeaderError::VrfKeyMismatch)
+            ));
+        }
+    }
+}
@@ -0,0 +1,463 @@
+//! In-memory UTxO set with support for rollbacks
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use pallas_addresses::Address;
+use pallas_crypto::hash::Hash;
+use pallas_traverse::{Era, MultiEraBlock, MultiEraOutput, MultiEraTx, OutputRef};
+
+use crate::Error;
+
+/// An owned output, kept as the original CBOR bytes tagged with its era
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EraCbor(pub Era, pub Vec<u8>);
+
+impl EraCbor {
+    pub fn era(&self) -> Era {
+        self.0
+    }
+
+    pub fn cbor(&self) -> &[u8] {
+        &self.1
+    }
+
+    /// Decodes the bytes into a multi-era output borrowing from this value
+    pub fn output(&self) -> Result<MultiEraOutput<'_>, Error> {
+        MultiEraOutput::decode(self.0, &self.1).map_err(Error::invalid_cbor)
+    }
+
+    fn address_bytes(&self) -> Option<Vec<u8>> {
+        let output = self.output().ok()?;
+        let address = output.address().ok()?;
+        Some(address.to_vec())
+    }
+}
+
+/// The changes introduced by a block, required to revert it
+#[derive(Debug, Clone)]
+pub struct BlockUndo {
+    pub slot: u64,
+    pub hash: Hash<32>,
+    consumed: Vec<(OutputRef, EraCbor)>,
+    produced: Vec<OutputRef>,
+}
+
+/// A set of unspent outputs that follows the chain block by block
+///
+/// Inputs that are not present in the set are silently ignored when applying
+/// a block, which allows starting from a snapshot or from an arbitrary point
+/// of the chain. Outputs that are not created by blocks (eg: genesis funds)
+/// can be added manually using `insert`.
+#[derive(Debug, Clone, Default)]
+pub struct UtxoSet {
+    utxos: HashMap<OutputRef, EraCbor>,
+    by_address: HashMap<Vec<u8>, HashSet<OutputRef>>,
+    undo: VecDeque<BlockUndo>,
+    max_undo: Option<usize>,
+}
+
+impl UtxoSet {
+    /// Creates an empty set that keeps undo info of up to `max_undo` blocks
+    ///
+    /// Rollbacks in Cardano can't be deeper than the security param (`k`), so
+    /// that's usually a reasonable value for the limit.
+    pub fn with_undo_limit(max_undo: usize) -> Self {
+        Self {
+            max_undo: Some(max_undo),
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.utxos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.utxos.is_empty()
+    }
+
+    pub fn get(&self, utxo: &OutputRef) -> Option<&EraCbor> {
+        self.utxos.get(utxo)
+    }
+
+    pub fn contains(&self, utxo: &OutputRef) -> bool {
+        self.utxos.contains_key(utxo)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&OutputRef, &EraCbor)> {
+        self.utxos.iter()
+    }
+
+    /// Returns all of the unspent outputs locked by the given address
+    pub fn by_address(&self, address: &Address) -> Vec<(&OutputRef, &EraCbor)> {
+        self.by_address
+            .get(&address.to_vec())
+            .into_iter()
+            .flat_map(|refs| refs.iter())
+            .filter_map(|r| self.utxos.get_key_value(r))
+            .collect()
+    }
+
+    /// Adds an output to the set, returning the previous value if any
+    pub fn insert(&mut self, utxo: OutputRef, output: EraCbor) -> Option<EraCbor> {
+        // the previous value might be locked by a different address
+        let previous = self.remove(&utxo);
+
+        if let Some(address) = output.address_bytes() {
+            self.by_address
+                .entry(address)
+                .or_default()
+                .insert(utxo.clone());
+        }
+
+        self.utxos.insert(utxo.clone(), output);
+
+        previous
+    }
+
+    /// Removes an output from the set, returning its value if present
+    pub fn remove(&mut self, utxo: &OutputRef) -> Option<EraCbor> {
+        let output = self.utxos.remove(utxo)?;
+
+        if let Some(address) = output.address_bytes() {
+            if let Some(refs) = self.by_address.get_mut(&address) {
+                refs.remove(utxo);
+
+                if refs.is_empty() {
+                    self.by_address.remove(&address);
+                }
+            }
+        }
+
+        Some(output)
+    }
+
+    fn apply_tx(&mut self, tx: &MultiEraTx, produced: &[(usize, &[u8])], undo: &mut BlockUndo) {
+        // invalid txs consume the collateral instead of the inputs, the logic
+        // lives in the traverse helpers
+        for input in tx.consumes() {
+            let utxo = input.output_ref();
+
+            if let Some(output) = self.remove(&utxo) {
+                undo.consumed.push((utxo, output));
+            }
+        }
+
+        let hash = tx.hash();
+
+        for (idx, cbor) in produced {
+            let utxo = OutputRef::new(hash, *idx as u64);
+            let output = EraCbor(tx.era(), cbor.to_vec());
+
+            self.insert(utxo.clone(), output);
+            undo.produced.push(utxo);
+        }
+    }
+
+    /// Applies the txs of the block to the set, keeping track of the changes
+    ///
+    /// Fails without altering the set if the outputs of a tx can't be read
+    /// from its original CBOR.
+    pub fn apply_block(&mut self, block: &MultiEraBlock) -> Result<(), Error> {
+        let mut undo = BlockUndo {
+            slot: block.slot(),
+            hash: block.hash(),
+            consumed: vec![],
+            produced: vec![],
+        };
+
+        let txs = block.txs();
+
+        let produced = txs
+            .iter()
+            .map(|tx| tx.produces_cbor())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::invalid_cbor)?;
+
+        for (tx, produced) in txs.iter().zip(produced.iter()) {
+            self.apply_tx(tx, produced, &mut undo);
+        }
+
+        self.undo.push_back(undo);
+
+        if let Some(max) = self.max_undo {
+            while self.undo.len() > max {
+                self.undo.pop_front();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The slot and hash of the latest applied block with undo info
+    pub fn tip(&self) -> Option<(u64, Hash<32>)> {
+        self.undo.back().map(|x| (x.slot, x.hash))
+    }
+
+    /// Reverts the latest applied block, returning its undo info
+    pub fn undo_block(&mut self) -> Option<BlockUndo> {
+        let undo = self.undo.pop_back()?;
+
+        // outputs produced and consumed within the block are in both lists,
+        // restoring them first makes sure they end up removed
+        for (utxo, output) in undo.consumed.iter().rev() {
+            self.insert(utxo.clone(), output.clone());
+        }
+
+        for utxo in undo.produced.iter().rev() {
+            self.remove(utxo);
+        }
+
+        Some(undo)
+    }
+
+    /// Reverts blocks until the one matching the point becomes the tip
+    ///
+    /// Fails without altering the set if the point is not found within the
+    /// available undo info.
+    pub fn rollback_to(&mut self, slot: u64, hash: &Hash<32>) -> Result<Vec<BlockUndo>, Error> {
+        let position = self
+            .undo
+            .iter()
+            .rposition(|x| x.slot == slot && x.hash == *hash)
+            .ok_or(Error::UnknownRollbackPoint(slot, *hash))?;
+
+        let mut reverted = vec![];
+
+        while self.undo.len() > position + 1 {
+            // we already checked the length of the undo queue
+            reverted.push(self.undo_block().unwrap());
+        }
+
+        Ok(reverted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_traverse::MultiEraBlock;
+
+    use super::*;
+
+    fn load_block(content: &str) -> Vec<u8> {
+        hex::decode(content).expect("invalid hex")
+    }
+
+    #[test]
+    fn apply_and_undo_blocks() {
+        let mut utxos = UtxoSet::default();
+
+        let blocks = [
+            include_str!("../../test_data/byron2.block"),
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+        ];
+
+        for content in blocks {
+            let cbor = load_block(content);
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+
+            let before = utxos.len();
+            utxos.apply_block(&block).unwrap();
+
+            let produced: usize = block.txs().iter().map(|tx| tx.produces().len()).sum();
+            assert!(utxos.len() <= before + produced);
+
+            for tx in block.txs() {
+                let produced = tx.produces().into_iter().zip(tx.produces_cbor().unwrap());
+
+                for ((idx, output), (_, cbor)) in produced {
+                    let utxo = OutputRef::new(tx.hash(), idx as u64);
+
+                    // outputs might have been spent by a later tx of the block
+                    if let Some(stored) = utxos.get(&utxo) {
+                        assert_eq!(stored.cbor(), cbor);
+
+                        let address = output.address().unwrap();
+                        let found = utxos.by_address(&address);
+                        assert!(found.iter().any(|(r, _)| **r == utxo));
+                    }
+                }
+            }
+        }
+
+        while utxos.undo_block().is_some() {}
+
+        assert!(utxos.is_empty());
+        assert!(utxos.by_address.is_empty());
+    }
+
+    #[test]
+    fn rollback_restores_consumed() {
+        let cbor = load_block(include_str!("../../test_data/babbage1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+
+        // seed the set with the inputs consumed by the block using a dummy
+        // output so we can check they are restored after the rollback
+        let dummy = txs[0].outputs()[0].encode();
+
+        let mut utxos = UtxoSet::default();
+
+        for tx in txs.iter() {
+            for input in tx.consumes() {
+                utxos.insert(input.output_ref(), EraCbor(Era::Babbage, dummy.clone()));
+            }
+        }
+
+        let seeded: HashSet<_> = utxos.iter().map(|(r, _)| r.clone()).collect();
+
+        utxos.apply_block(&block).unwrap();
+        assert_eq!(utxos.tip(), Some((block.slot(), block.hash())));
+
+        for utxo in seeded.iter() {
+            assert!(!utxos.contains(utxo));
+        }
+
+        // an unknown point leaves the set untouched
+        let unknown = utxos.rollback_to(0, &Hash::new([0; 32]));
+        assert!(matches!(unknown, Err(Error::UnknownRollbackPoint(..))));
+        assert_eq!(utxos.tip(), Some((block.slot(), block.hash())));
+
+        let reverted = utxos.undo_block().unwrap();
+        assert_eq!(reverted.hash, block.hash());
+
+        let current: HashSet<_> = utxos.iter().map(|(r, _)| r.clone()).collect();
+        assert_eq!(current, seeded);
+    }
+
+    #[test]
+    fn undo_outputs_spent_within_block() {
+        let cbor = load_block(include_str!("../../test_data/alonzo14.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+
+        // the 10th tx of the block spends outputs of the 9th one
+        let spent = OutputRef::new(txs[8].hash(), 0);
+        assert!(txs[9].consumes().iter().any(|x| x.output_ref() == spent));
+
+        let mut utxos = UtxoSet::default();
+        utxos.apply_block(&block).unwrap();
+        assert!(!utxos.contains(&spent));
+
+        utxos.undo_block().unwrap();
+
+        assert!(!utxos.contains(&spent));
+        assert!(utxos.is_empty());
+        assert!(utxos.by_address.is_empty());
+    }
+
+    #[test]
+    fn rollback_to_point() {
+        let mut utxos = UtxoSet::default();
+        let mut points = vec![];
+
+        let blocks = [
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/alonzo2.block"),
+            include_str!("../../test_data/alonzo3.block"),
+        ];
+
+        for content in blocks {
+            let cbor = load_block(content);
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+            utxos.apply_block(&block).unwrap();
+            points.push((block.slot(), block.hash(), utxos.len()));
+        }
+
+        let (slot, hash, len) = points[0];
+        let reverted = utxos.rollback_to(slot, &hash).unwrap();
+
+        assert_eq!(reverted.len(), 2);
+        assert_eq!(utxos.tip(), Some((slot, hash)));
+        assert_eq!(utxos.len(), len);
+    }
+
+    #[test]
+    fn invalid_tx_consumes_collateral() {
+        let cbor = load_block(include_str!("../../test_data/babbage6.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        let tx = block
+            .txs()
+            .into_iter()
+            .find(|x| x.collateral_return().is_some())
+            .expect("block should have a tx with collateral return");
+
+        // flag the tx as failing phase-2 validation
+        let tx = match tx {
+            MultiEraTx::Babbage(mut x) => {
+                x.to_mut().success = false;
+                MultiEraTx::Babbage(x)
+            }
+            _ => unreachable!(),
+        };
+
+        let mut utxos = UtxoSet::default();
+
+        for input in tx.inputs().iter().chain(tx.collateral().iter()) {
+            let dummy = EraCbor(Era::Babbage, tx.outputs()[0].encode());
+            utxos.insert(input.output_ref(), dummy);
+        }
+
+        let mut undo = BlockUndo {
+            slot: 0,
+            hash: Hash::new([0; 32]),
+            consumed: vec![],
+            produced: vec![],
+        };
+
+        utxos.apply_tx(&tx, &tx.produces_cbor().unwrap(), &mut undo);
+
+        for input in tx.collateral() {
+            assert!(!utxos.contains(&input.output_ref()));
+        }
+
+        for input in tx.inputs() {
+            let is_collateral = tx
+                .collateral()
+                .iter()
+                .any(|c| c.output_ref() == input.output_ref());
+
+            assert_eq!(utxos.contains(&input.output_ref()), !is_collateral);
+        }
+
+        // only the collateral return is produced, indexed after the outputs
+        assert_eq!(undo.produced.len(), 1);
+        assert_eq!(undo.produced[0].index(), tx.outputs().len() as u64);
+        assert!(utxos.contains(&undo.produced[0]));
+
+        let stored = utxos.get(&undo.produced[0]).unwrap();
+        let collateral_return = tx.collateral_return().unwrap();
+        assert_eq!(
+            stored.output().unwrap().lovelace_amount(),
+            collateral_return.lovelace_amount()
+        );
+    }
+
+    #[test]
+    fn insert_replaces_address_index() {
+        let cbor = load_block(include_str!("../../test_data/alonzo1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+
+        let first = txs[0].outputs()[0].clone();
+        let other = txs
+            .iter()
+            .flat_map(|tx| tx.outputs())
+            .find(|x| x.address().unwrap() != first.address().unwrap())
+            .expect("block should have outputs to different addresses");
+
+        let mut utxos = UtxoSet::default();
+        let utxo = OutputRef::new(Hash::new([0; 32]), 0);
+
+        utxos.insert(utxo.clone(), EraCbor(Era::Alonzo, first.encode()));
+        let previous = utxos.insert(utxo.clone(), EraCbor(Era::Alonzo, other.encode()));
+        assert_eq!(previous, Some(EraCbor(Era::Alonzo, first.encode())));
+
+        assert!(utxos.by_address(&first.address().unwrap()).is_empty());
+        assert_eq!(utxos.by_address(&other.address().unwrap()).len(), 1);
+    }
+}
@@ -0,0 +1,344 @@
+//! Verification of the vkey, bootstrap and native script witnesses of a tx
+//!
+//! The checks here only need the transaction itself: signatures are verified
+//! against the hash of the body and native scripts are evaluated against the
+//! witnessed keys and the validity interval. Checking that every input,
+//! certificate or withdrawal is witnessed requires the UTxO set and is out of
+//! the scope of this module.
+
+use std::collections::{HashMap, HashSet};
+
+use cryptoxide::hashing::sha3_256;
+use pallas_codec::minicbor;
+use pallas_crypto::{
+    hash::{Hash, Hasher},
+    key::ed25519::{PublicKey, Signature},
+};
+use pallas_primitives::alonzo::{BootstrapWitness, NativeScript, VKeyWitness};
+use pallas_traverse::{MultiEraTx, OriginalHash};
+
+/// The validity interval of a tx, as used to evaluate timelocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidityInterval {
+    /// first valid slot (inclusive), if any
+    pub start: Option<u64>,
+    /// first invalid slot (exclusive upper bound), if any
+    pub ttl: Option<u64>,
+}
+
+impl From<&MultiEraTx<'_>> for ValidityInterval {
+    fn from(tx: &MultiEraTx<'_>) -> Self {
+        Self {
+            start: tx.validity_start(),
+            ttl: tx.ttl(),
+        }
+    }
+}
+
+/// Outcome of the verification of the witnesses of a tx
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WitnessReport {
+    /// hashes of the keys of the vkey witnesses with a valid signature
+    pub vkey_signers: HashSet<Hash<28>>,
+
+    /// hashes of the keys of the vkey witnesses with an invalid signature
+    pub invalid_vkeys: Vec<Hash<28>>,
+
+    /// roots of the Byron addresses of the valid bootstrap witnesses
+    pub bootstrap_signers: HashSet<Hash<28>>,
+
+    /// roots of the Byron addresses of the invalid bootstrap witnesses
+    pub invalid_bootstraps: Vec<Hash<28>>,
+
+    /// hash of each native script of the witness set and whether it's satisfied
+    pub native_scripts: HashMap<Hash<28>, bool>,
+
+    /// each required signer of the body and whether it has a valid witness
+    pub required_signers: HashMap<Hash<28>, bool>,
+}
+
+impl WitnessReport {
+    /// true if every witness has a valid signature, every native script is
+    /// satisfied and every required signer has a witness
+    pub fn is_valid(&self) -> bool {
+        self.invalid_vkeys.is_empty()
+            && self.invalid_bootstraps.is_empty()
+            && self.native_scripts.values().all(|x| *x)
+            && self.required_signers.values().all(|x| *x)
+    }
+
+    /// the hashes of all the keys with a valid witness, the ones used to
+    /// evaluate scripts and required signers
+    pub fn signers(&self) -> HashSet<Hash<28>> {
+        self.vkey_signers
+            .union(&self.bootstrap_signers)
+            .cloned()
+            .collect()
+    }
+}
+
+fn verify_signature(vkey: &[u8], signature: &[u8], tx_hash: &Hash<32>) -> bool {
+    let (Ok(vkey), Ok(signature)) = (
+        <[u8; PublicKey::SIZE]>::try_from(vkey),
+        <[u8; Signature::SIZE]>::try_from(signature),
+    ) else {
+        return false;
+    };
+
+    PublicKey::from(vkey).verify(tx_hash, &Signature::from(signature))
+}
+
+/// verify the signature of a vkey witness against the hash of the tx body
+pub fn verify_vkey_witness(witness: &VKeyWitness, tx_hash: &Hash<32>) -> bool {
+    verify_signature(&witness.vkey, &witness.signature, tx_hash)
+}
+
+/// verify the signature of a bootstrap witness against the hash of the tx
+/// body
+pub fn verify_bootstrap_witness(witness: &BootstrapWitness, tx_hash: &Hash<32>) -> bool {
+    verify_signature(&witness.public_key, &witness.signature, tx_hash)
+}
+
+/// the root of the Byron address witnessed by a bootstrap witness, which
+/// plays the role of the key hash for the ledger
+pub fn bootstrap_root(witness: &BootstrapWitness) -> Hash<28> {
+    let mut e = minicbor::Encoder::new(Vec::new());
+
+    e.array(3).unwrap();
+    e.u8(0).unwrap();
+    e.array(2).unwrap();
+    e.u8(0).unwrap();
+    e.bytes(&[witness.public_key.as_slice(), witness.chain_code.as_slice()].concat())
+        .unwrap();
+    e.writer_mut().extend_from_slice(&witness.attributes);
+
+    Hasher::<224>::hash(&sha3_256(&e.into_writer()))
+}
+
+/// evaluate a native script given the hashes of the keys that witnessed the
+/// tx and its validity interval
+pub fn eval_native_script(
+    script: &NativeScript,
+    signers: &HashSet<Hash<28>>,
+    validity: &ValidityInterval,
+) -> bool {
+    match script {
+        NativeScript::ScriptPubkey(hash) => signers.contains(hash),
+        NativeScript::ScriptAll(scripts) => scripts
+            .iter()
+            .all(|x| eval_native_script(x, signers, validity)),
+        NativeScript::ScriptAny(scripts) => scripts
+            .iter()
+            .any(|x| eval_native_script(x, signers, validity)),
+        NativeScript::ScriptNOfK(n, scripts) => {
+            let satisfied = scripts
+                .iter()
+                .filter(|x| eval_native_script(x, signers, validity))
+                .count();
+
+            satisfied >= *n as usize
+        }
+        NativeScript::InvalidBefore(slot) => validity.start.is_some_and(|start| *slot <= start),
+        NativeScript::InvalidHereafter(slot) => validity.ttl.is_some_and(|ttl| ttl <= *slot),
+    }
+}
+
+/// verify the vkey and bootstrap witnesses of the tx, evaluate its native
+/// scripts and check its required signers
+///
+/// Byron txs have a different witness scheme and produce an empty report.
+pub fn verify_witnesses(tx: &MultiEraTx) -> WitnessReport {
+    let tx_hash = tx.hash();
+    let mut report = WitnessReport::default();
+
+    for witness in tx.vkey_witnesses() {
+        let hash = Hasher::<224>::hash(&witness.vkey);
+
+        if verify_vkey_witness(witness, &tx_hash) {
+            report.vkey_signers.insert(hash);
+        } else {
+            report.invalid_vkeys.push(hash);
+        }
+    }
+
+    for witness in tx.bootstrap_witnesses() {
+        let root = bootstrap_root(witness);
+
+        if verify_bootstrap_witness(witness, &tx_hash) {
+            report.bootstrap_signers.insert(root);
+        } else {
+            report.invalid_bootstraps.push(root);
+        }
+    }
+
+    let signers = report.signers();
+    let validity = ValidityInterval::from(tx);
+
+    for script in tx.native_scripts() {
+        let satisfied = eval_native_script(script, &signers, &validity);
+        report
+            .native_scripts
+            .insert(script.original_hash(), satisfied);
+    }
+
+    for hash in tx.required_signers().collect::<Vec<_>>() {
+        report
+            .required_signers
+            .insert(*hash, signers.contains(hash));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_addresses::ByronAddress;
+    use pallas_codec::utils::MaybeIndefArray;
+    use pallas_crypto::key::bip32::XPrv;
+    use pallas_traverse::{
+        sign::{Signer, Witness},
+        MultiEraBlock,
+    };
+
+    use super::*;
+
+    fn key(n: u8) -> Hash<28> {
+        Hash::new([n; 28])
+    }
+
+    #[test]
+    fn native_script_evaluation() {
+        let signers: HashSet<_> = [key(1), key(2)].into();
+        let validity = ValidityInterval {
+            start: Some(100),
+            ttl: Some(200),
+        };
+
+        let eval = |script| eval_native_script(&script, &signers, &validity);
+        let sig = |n| NativeScript::ScriptPubkey(key(n));
+
+        assert!(eval(sig(1)));
+        assert!(!eval(sig(3)));
+
+        assert!(eval(NativeScript::ScriptAll(MaybeIndefArray::Def(vec![
+            sig(1),
+            sig(2)
+        ]))));
+        assert!(!eval(NativeScript::ScriptAll(MaybeIndefArray::Def(vec![
+            sig(1),
+            sig(3)
+        ]))));
+        assert!(eval(NativeScript::ScriptAll(MaybeIndefArray::Def(vec![]))));
+
+        assert!(eval(NativeScript::ScriptAny(MaybeIndefArray::Def(vec![
+            sig(3),
+            sig(2)
+        ]))));
+        assert!(!eval(NativeScript::ScriptAny(MaybeIndefArray::Def(vec![
+            sig(3)
+        ]))));
+        assert!(!eval(NativeScript::ScriptAny(MaybeIndefArray::Def(vec![]))));
+
+        assert!(eval(NativeScript::ScriptNOfK(
+            2,
+            MaybeIndefArray::Def(vec![sig(1), sig(2), sig(3)])
+        )));
+        assert!(!eval(NativeScript::ScriptNOfK(
+            2,
+            MaybeIndefArray::Def(vec![sig(1), sig(3), sig(4)])
+        )));
+
+        assert!(eval(NativeScript::InvalidBefore(100)));
+        assert!(!eval(NativeScript::InvalidBefore(101)));
+        assert!(eval(NativeScript::InvalidHereafter(200)));
+        assert!(!eval(NativeScript::InvalidHereafter(199)));
+
+        // an open interval never satisfies a timelock
+        let open = ValidityInterval::default();
+        assert!(!eval_native_script(
+            &NativeScript::InvalidBefore(0),
+            &signers,
+            &open
+        ));
+        assert!(!eval_native_script(
+            &NativeScript::InvalidHereafter(u64::MAX),
+            &signers,
+            &open
+        ));
+    }
+
+    #[test]
+    fn mainnet_witnesses_are_valid() {
+        let test_blocks = [
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo9.block"),
+            include_str!("../../test_data/alonzo13.block"),
+            include_str!("../../test_data/alonzo14.block"),
+            include_str!("../../test_data/babbage1.block"),
+            include_str!("../../test_data/babbage9.block"),
+        ];
+
+        let mut vkeys = 0;
+        let mut bootstraps = 0;
+        let mut scripts = 0;
+        let mut required = 0;
+
+        for block_str in test_blocks {
+            let cbor = hex::decode(block_str).unwrap();
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+
+            for tx in block.txs() {
+                let report = verify_witnesses(&tx);
+                assert!(report.is_valid(), "{report:?}");
+
+                vkeys += report.vkey_signers.len();
+                bootstraps += report.bootstrap_signers.len();
+                scripts += report.native_scripts.len();
+                required += report.required_signers.len();
+            }
+        }
+
+        assert!(vkeys > 0);
+        assert!(bootstraps > 0);
+        assert!(scripts > 0);
+        assert!(required > 0);
+    }
+
+    #[test]
+    fn bootstrap_root_matches_address() {
+        let mut bytes = [0; XPrv::SIZE];
+        bytes[31] = 0b0100_0000;
+        let key = XPrv::from_bytes(bytes).unwrap();
+
+        let address = ByronAddress::new_icarus(&key.public(), Some(1097911063));
+        let signer = Signer::bootstrap(key, &address).unwrap();
+
+        let Witness::Bootstrap(witness) = signer.witness(&Hash::new([1; 32])) else {
+            unreachable!()
+        };
+
+        assert!(verify_bootstrap_witness(&witness, &Hash::new([1; 32])));
+        assert!(!verify_bootstrap_witness(&witness, &Hash::new([2; 32])));
+        assert_eq!(bootstrap_root(&witness), address.decode().unwrap().root);
+    }
+
+    #[test]
+    fn tampered_witness_is_reported() {
+        let cbor = hex::decode(include_str!("../../test_data/alonzo1.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().into_iter().next().unwrap();
+
+        let tx_hash = tx.hash();
+        let witness = tx.vkey_witnesses().first().unwrap();
+        assert!(verify_vkey_witness(witness, &tx_hash));
+
+        let mut signature = witness.signature.to_vec();
+        signature[0] ^= 1;
+
+        let tampered = VKeyWitness {
+            vkey: witness.vkey.clone(),
+            signature: signature.into(),
+        };
+        assert!(!verify_vkey_witness(&tampered, &tx_hash));
+    }
+}
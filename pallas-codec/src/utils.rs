@@ -1,6 +1,9 @@
 use minicbor::{data::Tag, Decode, Encode};
 use serde::{Deserialize, Serialize};
-use std::{fmt, ops::Deref};
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
 
 /// Utility for skipping parts of the CBOR payload, use only for debugging
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
@@ -163,6 +166,17 @@ impl<A> MaybeIndefArray<A> {
     pub fn to_vec(self) -> Vec<A> {
         self.into()
     }
+
+    /// map the items of the array, keeping the kind of its length
+    pub fn map<B, F>(self, f: F) -> MaybeIndefArray<B>
+    where
+        F: FnMut(A) -> B,
+    {
+        match self {
+            MaybeIndefArray::Def(x) => MaybeIndefArray::Def(x.into_iter().map(f).collect()),
+            MaybeIndefArray::Indef(x) => MaybeIndefArray::Indef(x.into_iter().map(f).collect()),
+        }
+    }
 }
 
 impl<A> Deref for MaybeIndefArray<A> {
@@ -176,6 +190,15 @@ impl<A> Deref for MaybeIndefArray<A> {
     }
 }
 
+impl<A> DerefMut for MaybeIndefArray<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            MaybeIndefArray::Def(x) => x,
+            MaybeIndefArray::Indef(x) => x,
+        }
+    }
+}
+
 impl<A> From<MaybeIndefArray<A>> for Vec<A> {
     fn from(other: MaybeIndefArray<A>) -> Self {
         match other {
@@ -291,6 +314,214 @@ where
     }
 }
 
+/// The order of the keys and the kind of length of a cbor map backing a struct
+///
+/// Structs encoded as cbor maps are decoded field by field, which loses the
+/// order in which the keys were found and whether the map was indef or not.
+/// Keeping track of both allows to re-encode the struct with the exact same
+/// bytes. A layout is just an encoding detail: it doesn't take part in
+/// comparisons, so that two structs with the same fields are equal regardless
+/// of how they were encoded.
+///
+/// ```
+/// use pallas_codec::minicbor;
+/// use pallas_codec::utils::MapLayout;
+///
+/// // an indef map with keys out of order
+/// let data = hex::decode("bf0102000bff").unwrap();
+///
+/// let mut d = minicbor::Decoder::new(&data);
+/// let mut entries = vec![];
+/// let layout = MapLayout::decode_entries(&mut d, |k, d| {
+///     entries.push((k, d.u8()?));
+///     Ok(())
+/// })
+/// .unwrap();
+///
+/// let mut e = minicbor::Encoder::new(vec![]);
+/// layout
+///     .encode_entries(&mut e, &[0, 1], |k, e| {
+///         e.u8(entries.iter().find(|x| x.0 == k).unwrap().1)?;
+///         Ok(())
+///     })
+///     .unwrap();
+///
+/// assert_eq!(e.into_writer(), data);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MapLayout {
+    keys: Vec<u64>,
+    indef: bool,
+}
+
+impl MapLayout {
+    /// decode the entries of a map, passing each key to `entry` to decode
+    /// its value
+    pub fn decode_entries<'b, F>(
+        d: &mut minicbor::Decoder<'b>,
+        mut entry: F,
+    ) -> Result<Self, minicbor::decode::Error>
+    where
+        F: FnMut(u64, &mut minicbor::Decoder<'b>) -> Result<(), minicbor::decode::Error>,
+    {
+        let mut layout = MapLayout::default();
+
+        let mut decode_entry = |d: &mut minicbor::Decoder<'b>| {
+            let key = d.u64()?;
+
+            if !layout.keys.contains(&key) {
+                layout.keys.push(key);
+            }
+
+            entry(key, d)
+        };
+
+        match d.map()? {
+            Some(len) => {
+                for _ in 0..len {
+                    decode_entry(d)?;
+                }
+            }
+            None => {
+                while d.datatype()? != minicbor::data::Type::Break {
+                    decode_entry(d)?;
+                }
+
+                d.skip()?;
+                layout.indef = true;
+            }
+        }
+
+        Ok(layout)
+    }
+
+    /// encode a map with the given keys, passing each key to `entry` to
+    /// encode its value
+    ///
+    /// Keys found when decoding go first, in their original order. Any other
+    /// key follows in the order given.
+    pub fn encode_entries<W, F>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        keys: &[u64],
+        mut entry: F,
+    ) -> Result<(), minicbor::encode::Error<W::Error>>
+    where
+        W: minicbor::encode::Write,
+        F: FnMut(u64, &mut minicbor::Encoder<W>) -> Result<(), minicbor::encode::Error<W::Error>>,
+    {
+        let known = self.keys.iter().filter(|k| keys.contains(k));
+        let new = keys.iter().filter(|k| !self.keys.contains(k));
+
+        match self.indef {
+            true => e.begin_map()?,
+            false => e.map(keys.len() as u64)?,
+        };
+
+        for key in known.chain(new) {
+            e.u64(*key)?;
+            entry(*key, e)?;
+        }
+
+        if self.indef {
+            e.end()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether the cbor array backing a struct was indef or not
+///
+/// Like [MapLayout], this is just an encoding detail that doesn't take part in
+/// comparisons.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArrayLayout {
+    indef: bool,
+}
+
+impl ArrayLayout {
+    /// decode an array, using `items` to decode its contents
+    pub fn decode_items<'b, T, F>(
+        d: &mut minicbor::Decoder<'b>,
+        items: F,
+    ) -> Result<(T, Self), minicbor::decode::Error>
+    where
+        F: FnOnce(&mut minicbor::Decoder<'b>) -> Result<T, minicbor::decode::Error>,
+    {
+        let indef = d.array()?.is_none();
+        let x = items(d)?;
+
+        if indef {
+            if d.datatype()? != minicbor::data::Type::Break {
+                return Err(minicbor::decode::Error::message(
+                    "unexpected items in indef array",
+                ));
+            }
+
+            d.skip()?;
+        }
+
+        Ok((x, ArrayLayout { indef }))
+    }
+
+    /// encode an array of `len` items, using `items` to encode its contents
+    pub fn encode_items<W, F>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        len: u64,
+        items: F,
+    ) -> Result<(), minicbor::encode::Error<W::Error>>
+    where
+        W: minicbor::encode::Write,
+        F: FnOnce(&mut minicbor::Encoder<W>) -> Result<(), minicbor::encode::Error<W::Error>>,
+    {
+        match self.indef {
+            true => e.begin_array()?,
+            false => e.array(len)?,
+        };
+
+        items(e)?;
+
+        if self.indef {
+            e.end()?;
+        }
+
+        Ok(())
+    }
+}
+
+macro_rules! encoding_detail {
+    ($name:ident) => {
+        impl PartialEq for $name {
+            fn eq(&self, _: &Self) -> bool {
+                true
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, _: &Self) -> std::cmp::Ordering {
+                std::cmp::Ordering::Equal
+            }
+        }
+
+        impl std::hash::Hash for $name {
+            fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+        }
+    };
+}
+
+encoding_detail!(MapLayout);
+encoding_detail!(ArrayLayout);
+
 /// Wraps a struct so that it is encoded/decoded as a cbor bytes
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd)]
 #[serde(transparent)]
@@ -475,7 +706,8 @@ where
 }
 
 /// A uint structure that preserves original int length
-#[derive(Debug, PartialEq, Copy, Clone, PartialOrd, Eq, Ord)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone, PartialOrd, Eq, Ord)]
+#[serde(from = "u64", into = "u64")]
 pub enum AnyUInt {
     MajorByte(u8),
     U8(u8),
@@ -582,6 +814,19 @@ impl From<&AnyUInt> for u64 {
     }
 }
 
+impl From<u64> for AnyUInt {
+    /// uses the shortest encoding for the value
+    fn from(x: u64) -> Self {
+        match x {
+            0..=0x17 => AnyUInt::MajorByte(x as u8),
+            0x18..=0xff => AnyUInt::U8(x as u8),
+            0x100..=0xffff => AnyUInt::U16(x as u16),
+            0x1_0000..=0xffff_ffff => AnyUInt::U32(x as u32),
+            _ => AnyUInt::U64(x),
+        }
+    }
+}
+
 /// Decodes a struct while preserving original CBOR
 ///
 /// # Examples
@@ -0,0 +1,137 @@
+//! Parsing of Alonzo genesis files
+
+use std::collections::BTreeMap;
+
+use pallas_primitives::alonzo::{CostModel, ExUnitPrices, RationalNumber};
+use serde::Deserialize;
+
+use crate::{support::deserialize_rational, Error};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPrices {
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub pr_steps: RationalNumber,
+
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub pr_mem: RationalNumber,
+}
+
+impl From<&ExecutionPrices> for ExUnitPrices {
+    fn from(value: &ExecutionPrices) -> Self {
+        ExUnitPrices {
+            mem_price: value.pr_mem.clone(),
+            step_price: value.pr_steps.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExUnits {
+    pub ex_units_mem: u32,
+    pub ex_units_steps: u64,
+}
+
+impl From<&ExUnits> for pallas_primitives::alonzo::ExUnits {
+    fn from(value: &ExUnits) -> Self {
+        Self {
+            mem: value.ex_units_mem,
+            steps: value.ex_units_steps,
+        }
+    }
+}
+
+/// A cost model can be specified as a map of named params or as a list of
+/// values already sorted in the canonical order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CostModelRepr {
+    Named(BTreeMap<String, i64>),
+    Positional(Vec<i64>),
+}
+
+impl<'de> Deserialize<'de> for CostModelRepr {
+    // not an untagged enum, those break numbers when serde_json is built with
+    // `arbitrary_precision`
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+
+        let repr = match value {
+            serde_json::Value::Array(_) => serde_json::from_value(value).map(Self::Positional),
+            _ => serde_json::from_value(value).map(Self::Named),
+        };
+
+        repr.map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<&CostModelRepr> for CostModel {
+    fn from(value: &CostModelRepr) -> Self {
+        match value {
+            // the canonical order of the Alonzo / Babbage params matches the
+            // lexicographic order of their names
+            CostModelRepr::Named(x) => x.values().copied().collect(),
+            CostModelRepr::Positional(x) => x.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CostModelPerLanguage {
+    #[serde(rename = "PlutusV1")]
+    pub plutus_v1: Option<CostModelRepr>,
+
+    #[serde(rename = "PlutusV2")]
+    pub plutus_v2: Option<CostModelRepr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisFile {
+    #[serde(rename = "lovelacePerUTxOWord", alias = "coinsPerUTxOWord")]
+    pub lovelace_per_utxo_word: u64,
+
+    pub execution_prices: ExecutionPrices,
+    pub max_tx_ex_units: ExUnits,
+    pub max_block_ex_units: ExUnits,
+    pub max_value_size: u32,
+    pub collateral_percentage: u32,
+    pub max_collateral_inputs: u32,
+    pub cost_models: CostModelPerLanguage,
+}
+
+pub fn from_file(path: &std::path::Path) -> Result<GenesisFile, Error> {
+    let file = std::fs::File::open(path)?;
+    let reader = std::io::BufReader::new(file);
+    let parsed = serde_json::from_reader(reader)?;
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file() {
+        let json = include_str!("../../test_data/devnet-alonzo-genesis.json");
+        let genesis: GenesisFile = serde_json::from_str(json).unwrap();
+
+        assert_eq!(genesis.lovelace_per_utxo_word, 34482);
+        assert_eq!(genesis.max_tx_ex_units.ex_units_steps, 10000000000);
+
+        assert_eq!(
+            genesis.execution_prices.pr_mem,
+            RationalNumber {
+                numerator: 577,
+                denominator: 10000
+            }
+        );
+
+        let v1 = CostModel::from(genesis.cost_models.plutus_v1.as_ref().unwrap());
+        assert_eq!(v1[..3], [205665, 812, 1]);
+    }
+}
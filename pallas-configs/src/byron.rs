@@ -0,0 +1,148 @@
+//! Parsing of Byron genesis files
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{support::deserialize_string_number, Error};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoftForkRule {
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub init_thd: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub min_thd: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub thd_decrement: u64,
+}
+
+/// Linear fee policy, both values are expressed in nano-lovelace
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxFeePolicy {
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub multiplier: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub summand: u64,
+}
+
+/// The protocol parameters of the Byron era
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockVersionData {
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub heavy_del_thd: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub max_block_size: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub max_header_size: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub max_proposal_size: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub max_tx_size: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub mpc_thd: u64,
+
+    pub script_version: u16,
+
+    /// Slot duration in milliseconds
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub slot_duration: u64,
+
+    pub softfork_rule: SoftForkRule,
+
+    pub tx_fee_policy: TxFeePolicy,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub unlock_stake_epoch: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub update_implicit: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub update_proposal_thd: u64,
+
+    #[serde(deserialize_with = "deserialize_string_number")]
+    pub update_vote_thd: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolConsts {
+    pub k: u64,
+    pub protocol_magic: u32,
+    pub vss_min_ttl: Option<u32>,
+    pub vss_max_ttl: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeavyDelegation {
+    pub issuer_pk: String,
+    pub delegate_pk: String,
+    pub cert: String,
+    pub omega: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VssCert {
+    pub vss_key: String,
+    pub expiry_epoch: u64,
+    pub signature: String,
+    pub signing_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisFile {
+    pub avvm_distr: HashMap<String, String>,
+    pub block_version_data: BlockVersionData,
+    pub fts_seed: Option<String>,
+    pub protocol_consts: ProtocolConsts,
+
+    /// Start of the chain as a unix timestamp in seconds
+    pub start_time: u64,
+
+    pub boot_stakeholders: HashMap<String, u64>,
+    pub heavy_delegation: HashMap<String, HeavyDelegation>,
+    pub non_avvm_balances: HashMap<String, String>,
+    pub vss_certs: Option<HashMap<String, VssCert>>,
+}
+
+pub fn from_file(path: &std::path::Path) -> Result<GenesisFile, Error> {
+    let file = std::fs::File::open(path)?;
+    let reader = std::io::BufReader::new(file);
+    let parsed = serde_json::from_reader(reader)?;
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file() {
+        let json = include_str!("../../test_data/devnet-byron-genesis.json");
+        let genesis: GenesisFile = serde_json::from_str(json).unwrap();
+
+        assert_eq!(genesis.protocol_consts.k, 2160);
+        assert_eq!(genesis.block_version_data.slot_duration, 20000);
+        assert_eq!(
+            genesis.block_version_data.tx_fee_policy.summand,
+            155381000000000
+        );
+        assert_eq!(genesis.block_version_data.unlock_stake_epoch, u64::MAX);
+        assert_eq!(genesis.heavy_delegation.len(), 1);
+    }
+}
@@ -0,0 +1,234 @@
+//! Parsing of Conway genesis files
+
+use std::collections::HashMap;
+
+use pallas_primitives::alonzo::{CostModel, RationalNumber};
+use serde::Deserialize;
+
+use crate::{
+    support::{deserialize_option_rational, deserialize_rational},
+    Error,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolVotingThresholds {
+    #[serde(
+        alias = "pvtCommitteeNormal",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub committee_normal: RationalNumber,
+
+    #[serde(
+        alias = "pvtCommitteeNoConfidence",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub committee_no_confidence: RationalNumber,
+
+    #[serde(
+        alias = "pvtHardForkInitiation",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub hard_fork_initiation: RationalNumber,
+
+    #[serde(
+        alias = "pvtMotionNoConfidence",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub motion_no_confidence: RationalNumber,
+
+    /// Only present in later revisions of the Conway genesis format
+    #[serde(
+        default,
+        alias = "pvtPPSecurityGroup",
+        deserialize_with = "deserialize_option_rational"
+    )]
+    pub pp_security_group: Option<RationalNumber>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DRepVotingThresholds {
+    #[serde(
+        alias = "dvtMotionNoConfidence",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub motion_no_confidence: RationalNumber,
+
+    #[serde(
+        alias = "dvtCommitteeNormal",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub committee_normal: RationalNumber,
+
+    #[serde(
+        alias = "dvtCommitteeNoConfidence",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub committee_no_confidence: RationalNumber,
+
+    #[serde(
+        alias = "dvtUpdateToConstitution",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub update_to_constitution: RationalNumber,
+
+    #[serde(
+        alias = "dvtHardForkInitiation",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub hard_fork_initiation: RationalNumber,
+
+    #[serde(alias = "dvtPPNetworkGroup", deserialize_with = "deserialize_rational")]
+    pub pp_network_group: RationalNumber,
+
+    #[serde(
+        alias = "dvtPPEconomicGroup",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub pp_economic_group: RationalNumber,
+
+    #[serde(
+        alias = "dvtPPTechnicalGroup",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub pp_technical_group: RationalNumber,
+
+    #[serde(alias = "dvtPPGovGroup", deserialize_with = "deserialize_rational")]
+    pub pp_gov_group: RationalNumber,
+
+    #[serde(
+        alias = "dvtTreasuryWithdrawal",
+        deserialize_with = "deserialize_rational"
+    )]
+    pub treasury_withdrawal: RationalNumber,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Anchor {
+    pub url: String,
+    pub data_hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Constitution {
+    pub anchor: Anchor,
+    pub script: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Committee {
+    /// Credentials of the members mapped to the epoch their term expires
+    pub members: HashMap<String, u64>,
+
+    #[serde(alias = "quorum", deserialize_with = "deserialize_rational")]
+    pub threshold: RationalNumber,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisFile {
+    pub pool_voting_thresholds: PoolVotingThresholds,
+    pub d_rep_voting_thresholds: DRepVotingThresholds,
+    pub committee_min_size: u64,
+    pub committee_max_term_length: u64,
+    pub gov_action_lifetime: u64,
+    pub gov_action_deposit: u64,
+    pub d_rep_deposit: u64,
+    pub d_rep_activity: u64,
+
+    #[serde(default, deserialize_with = "deserialize_option_rational")]
+    pub min_fee_ref_script_cost_per_byte: Option<RationalNumber>,
+
+    #[serde(rename = "plutusV3CostModel")]
+    pub plutus_v3_cost_model: Option<CostModel>,
+
+    pub constitution: Constitution,
+    pub committee: Committee,
+}
+
+pub fn from_file(path: &std::path::Path) -> Result<GenesisFile, Error> {
+    let file = std::fs::File::open(path)?;
+    let reader = std::io::BufReader::new(file);
+    let parsed = serde_json::from_reader(reader)?;
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file() {
+        let json = include_str!("../../test_data/devnet-conway-genesis.json");
+        let genesis: GenesisFile = serde_json::from_str(json).unwrap();
+
+        assert_eq!(genesis.gov_action_lifetime, 6);
+        assert_eq!(genesis.committee.members.len(), 1);
+
+        assert_eq!(
+            genesis.d_rep_voting_thresholds.update_to_constitution,
+            RationalNumber {
+                numerator: 3,
+                denominator: 4
+            }
+        );
+
+        assert_eq!(
+            genesis.committee.threshold,
+            RationalNumber {
+                numerator: 2,
+                denominator: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_legacy_keys() {
+        let json = r#"{
+            "poolVotingThresholds": {
+                "pvtCommitteeNormal": 0.51,
+                "pvtCommitteeNoConfidence": 0.51,
+                "pvtHardForkInitiation": 0.51,
+                "pvtMotionNoConfidence": 0.51
+            },
+            "dRepVotingThresholds": {
+                "dvtMotionNoConfidence": 0.51,
+                "dvtCommitteeNormal": 0.51,
+                "dvtCommitteeNoConfidence": 0.51,
+                "dvtUpdateToConstitution": 0.51,
+                "dvtHardForkInitiation": 0.51,
+                "dvtPPNetworkGroup": 0.51,
+                "dvtPPEconomicGroup": 0.51,
+                "dvtPPTechnicalGroup": 0.51,
+                "dvtPPGovGroup": 0.51,
+                "dvtTreasuryWithdrawal": 0.51
+            },
+            "committeeMinSize": 0,
+            "committeeMaxTermLength": 60,
+            "govActionLifetime": 14,
+            "govActionDeposit": 0,
+            "dRepDeposit": 0,
+            "dRepActivity": 0,
+            "constitution": {
+                "anchor": {
+                    "url": "",
+                    "dataHash": "0000000000000000000000000000000000000000000000000000000000000000"
+                }
+            },
+            "committee": {
+                "members": {},
+                "quorum": 0
+            }
+        }"#;
+
+        let genesis: GenesisFile = serde_json::from_str(json).unwrap();
+
+        assert_eq!(genesis.pool_voting_thresholds.pp_security_group, None);
+        assert_eq!(genesis.committee.threshold.numerator, 0);
+    }
+}
@@ -0,0 +1,32 @@
+//! Config structs and utilities matching the Haskell implementation
+
+use thiserror::Error;
+
+mod support;
+
+pub mod alonzo;
+pub mod byron;
+pub mod conway;
+pub mod params;
+pub mod shelley;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid JSON structure: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+
+    #[error("Invalid config value: {0}")]
+    InvalidValue(String),
+
+    #[error("Invalid chain data: {0}")]
+    Traverse(#[from] pallas_traverse::Error),
+}
+
+impl Error {
+    pub fn invalid_value(msg: impl std::fmt::Display) -> Self {
+        Error::InvalidValue(format!("{msg}"))
+    }
+}
@@ -0,0 +1,566 @@
+//! Protocol parameters derived from genesis files and on-chain updates
+
+use std::collections::BTreeMap;
+
+use pallas_primitives::{
+    alonzo::{
+        self, Coin, CostModel, Epoch, ExUnitPrices, ExUnits, Nonce, ProtocolVersion,
+        RationalNumber, UnitInterval,
+    },
+    babbage::{self, CostMdls},
+    shelley,
+};
+use pallas_traverse::{MultiEraBlock, MultiEraTx, MultiEraUpdate};
+
+use crate::{
+    alonzo::GenesisFile as AlonzoGenesis,
+    conway::{DRepVotingThresholds, GenesisFile as ConwayGenesis, PoolVotingThresholds},
+    shelley::GenesisFile as ShelleyGenesis,
+    Error,
+};
+
+/// The full set of protocol parameters for the post-Byron eras
+///
+/// Params are introduced incrementally by each era, the genesis file of the
+/// corresponding era provides the initial value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolParams {
+    // Shelley
+    pub minfee_a: u32,
+    pub minfee_b: u32,
+    pub max_block_body_size: u32,
+    pub max_transaction_size: u32,
+    pub max_block_header_size: u32,
+    pub key_deposit: Coin,
+    pub pool_deposit: Coin,
+    pub maximum_epoch: Epoch,
+    pub desired_number_of_stake_pools: u32,
+    pub pool_pledge_influence: RationalNumber,
+    pub expansion_rate: UnitInterval,
+    pub treasury_growth_rate: UnitInterval,
+    pub decentralization_constant: UnitInterval,
+    pub extra_entropy: Nonce,
+    pub protocol_version: ProtocolVersion,
+    pub min_utxo_value: Coin,
+    pub min_pool_cost: Coin,
+
+    // Alonzo
+    pub coins_per_utxo_word: Coin,
+    pub cost_models_for_script_languages: CostMdls,
+    pub execution_costs: ExUnitPrices,
+    pub max_tx_ex_units: ExUnits,
+    pub max_block_ex_units: ExUnits,
+    pub max_value_size: u32,
+    pub collateral_percentage: u32,
+    pub max_collateral_inputs: u32,
+
+    // Babbage
+    pub ada_per_utxo_byte: Coin,
+
+    // Conway
+    pub pool_voting_thresholds: PoolVotingThresholds,
+    pub drep_voting_thresholds: DRepVotingThresholds,
+    pub min_committee_size: u64,
+    pub committee_term_limit: Epoch,
+    pub governance_action_validity_period: Epoch,
+    pub governance_action_deposit: Coin,
+    pub drep_deposit: Coin,
+    pub drep_inactivity_period: Epoch,
+    pub plutus_v3_cost_model: Option<CostModel>,
+    pub min_fee_ref_script_cost_per_byte: Option<RationalNumber>,
+}
+
+impl ProtocolParams {
+    /// Builds the initial set of params from the genesis files of each era
+    pub fn from_genesis(
+        shelley: &ShelleyGenesis,
+        alonzo: &AlonzoGenesis,
+        conway: &ConwayGenesis,
+    ) -> Result<Self, Error> {
+        let shelley_params = &shelley.protocol_params;
+
+        Ok(Self {
+            minfee_a: shelley_params.min_fee_a,
+            minfee_b: shelley_params.min_fee_b,
+            max_block_body_size: shelley_params.max_block_body_size,
+            max_transaction_size: shelley_params.max_tx_size,
+            max_block_header_size: shelley_params.max_block_header_size,
+            key_deposit: shelley_params.key_deposit,
+            pool_deposit: shelley_params.pool_deposit,
+            maximum_epoch: shelley_params.e_max,
+            desired_number_of_stake_pools: shelley_params.n_opt,
+            pool_pledge_influence: shelley_params.a0.clone(),
+            expansion_rate: shelley_params.rho.clone(),
+            treasury_growth_rate: shelley_params.tau.clone(),
+            decentralization_constant: shelley_params.decentralisation_param.clone(),
+            extra_entropy: Nonce::try_from(&shelley_params.extra_entropy)?,
+            protocol_version: (
+                shelley_params.protocol_version.major,
+                shelley_params.protocol_version.minor,
+            ),
+            min_utxo_value: shelley_params.min_utxo_value,
+            min_pool_cost: shelley_params.min_pool_cost,
+
+            coins_per_utxo_word: alonzo.lovelace_per_utxo_word,
+            cost_models_for_script_languages: CostMdls {
+                plutus_v1: alonzo.cost_models.plutus_v1.as_ref().map(CostModel::from),
+                plutus_v2: alonzo.cost_models.plutus_v2.as_ref().map(CostModel::from),
+                layout: Default::default(),
+            },
+            execution_costs: ExUnitPrices::from(&alonzo.execution_prices),
+            max_tx_ex_units: ExUnits::from(&alonzo.max_tx_ex_units),
+            max_block_ex_units: ExUnits::from(&alonzo.max_block_ex_units),
+            max_value_size: alonzo.max_value_size,
+            collateral_percentage: alonzo.collateral_percentage,
+            max_collateral_inputs: alonzo.max_collateral_inputs,
+
+            // same translation the ledger performs at the Babbage hard-fork
+            ada_per_utxo_byte: alonzo.lovelace_per_utxo_word / 8,
+
+            pool_voting_thresholds: conway.pool_voting_thresholds.clone(),
+            drep_voting_thresholds: conway.d_rep_voting_thresholds.clone(),
+            min_committee_size: conway.committee_min_size,
+            committee_term_limit: conway.committee_max_term_length,
+            governance_action_validity_period: conway.gov_action_lifetime,
+            governance_action_deposit: conway.gov_action_deposit,
+            drep_deposit: conway.d_rep_deposit,
+            drep_inactivity_period: conway.d_rep_activity,
+            plutus_v3_cost_model: conway.plutus_v3_cost_model.clone(),
+            min_fee_ref_script_cost_per_byte: conway.min_fee_ref_script_cost_per_byte.clone(),
+        })
+    }
+
+    /// Computes the minimum fee of a tx of the given size, ignoring scripts
+    pub fn linear_fee(&self, tx_size: u64) -> Coin {
+        self.minfee_a as u64 * tx_size + self.minfee_b as u64
+    }
+
+    /// Applies an update proposal of any of the Shelley, Allegra or Mary eras
+    pub fn apply_shelley_update(&mut self, update: &shelley::ProtocolParamUpdate) {
+        macro_rules! set {
+            ($field:ident) => {
+                if let Some(x) = &update.$field {
+                    self.$field = x.clone();
+                }
+            };
+        }
+
+        set!(minfee_a);
+        set!(minfee_b);
+        set!(max_block_body_size);
+        set!(max_transaction_size);
+        set!(max_block_header_size);
+        set!(key_deposit);
+        set!(pool_deposit);
+        set!(maximum_epoch);
+        set!(desired_number_of_stake_pools);
+        set!(pool_pledge_influence);
+        set!(expansion_rate);
+        set!(treasury_growth_rate);
+        set!(decentralization_constant);
+        set!(extra_entropy);
+        set!(protocol_version);
+        set!(min_utxo_value);
+        set!(min_pool_cost);
+    }
+
+    /// Applies an update proposal of the Alonzo era
+    ///
+    /// The `ada_per_utxo_byte` key of the CDDL carries the coins per UTxO
+    /// word in this era, so it updates `coins_per_utxo_word`.
+    pub fn apply_alonzo_update(&mut self, update: &alonzo::ProtocolParamUpdate) {
+        macro_rules! set {
+            ($field:ident) => {
+                if let Some(x) = &update.$field {
+                    self.$field = x.clone();
+                }
+            };
+        }
+
+        set!(minfee_a);
+        set!(minfee_b);
+        set!(max_block_body_size);
+        set!(max_transaction_size);
+        set!(max_block_header_size);
+        set!(key_deposit);
+        set!(pool_deposit);
+        set!(maximum_epoch);
+        set!(desired_number_of_stake_pools);
+        set!(pool_pledge_influence);
+        set!(expansion_rate);
+        set!(treasury_growth_rate);
+        set!(decentralization_constant);
+        set!(extra_entropy);
+        set!(protocol_version);
+        set!(min_pool_cost);
+        set!(execution_costs);
+        set!(max_tx_ex_units);
+        set!(max_block_ex_units);
+        set!(max_value_size);
+        set!(collateral_percentage);
+        set!(max_collateral_inputs);
+
+        if let Some(x) = update.ada_per_utxo_byte {
+            self.coins_per_utxo_word = x;
+        }
+
+        if let Some(x) = &update.cost_models_for_script_languages {
+            self.cost_models_for_script_languages = CostMdls {
+                plutus_v1: x
+                    .iter()
+                    .find(|(k, _)| *k == alonzo::Language::PlutusV1)
+                    .map(|(_, v)| v.clone()),
+                plutus_v2: None,
+                layout: Default::default(),
+            };
+        }
+    }
+
+    /// Applies an update proposal of the Babbage era
+    pub fn apply_babbage_update(&mut self, update: &babbage::ProtocolParamUpdate) {
+        macro_rules! set {
+            ($field:ident) => {
+                if let Some(x) = &update.$field {
+                    self.$field = x.clone();
+                }
+            };
+        }
+
+        set!(minfee_a);
+        set!(minfee_b);
+        set!(max_block_body_size);
+        set!(max_transaction_size);
+        set!(max_block_header_size);
+        set!(key_deposit);
+        set!(pool_deposit);
+        set!(maximum_epoch);
+        set!(desired_number_of_stake_pools);
+        set!(pool_pledge_influence);
+        set!(expansion_rate);
+        set!(treasury_growth_rate);
+        set!(protocol_version);
+        set!(min_pool_cost);
+        set!(ada_per_utxo_byte);
+        set!(cost_models_for_script_languages);
+        set!(execution_costs);
+        set!(max_tx_ex_units);
+        set!(max_block_ex_units);
+        set!(max_value_size);
+        set!(collateral_percentage);
+        set!(max_collateral_inputs);
+    }
+
+    pub fn apply_update(&mut self, update: &ProposedUpdate) {
+        match update {
+            ProposedUpdate::Shelley(x) => self.apply_shelley_update(x),
+            ProposedUpdate::AlonzoCompatible(x) => self.apply_alonzo_update(x),
+            ProposedUpdate::Babbage(x) => self.apply_babbage_update(x),
+        }
+    }
+}
+
+/// A param update proposed by a single genesis delegate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProposedUpdate {
+    Shelley(shelley::ProtocolParamUpdate),
+    AlonzoCompatible(alonzo::ProtocolParamUpdate),
+    Babbage(babbage::ProtocolParamUpdate),
+}
+
+/// Keeps track of the update proposals found on-chain
+///
+/// Proposals target a specific epoch. When that epoch ends, a proposal is
+/// adopted if at least `quorum` genesis delegates voted for the exact same
+/// update. A later proposal by the same delegate for the same epoch replaces
+/// the previous one, same as the ledger does.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateTracker {
+    quorum: usize,
+    proposals: BTreeMap<Epoch, BTreeMap<Vec<u8>, ProposedUpdate>>,
+}
+
+impl UpdateTracker {
+    pub fn new(quorum: usize) -> Self {
+        Self {
+            quorum,
+            proposals: Default::default(),
+        }
+    }
+
+    pub fn from_genesis(shelley: &ShelleyGenesis) -> Self {
+        Self::new(shelley.update_quorum as usize)
+    }
+
+    pub fn track_update(&mut self, update: &MultiEraUpdate) {
+        let proposals = self.proposals.entry(update.epoch()).or_default();
+
+        match update {
+            MultiEraUpdate::Babbage(x) => {
+                for (genesis, proposal) in x.proposed_protocol_parameter_updates.iter() {
+                    let proposal = ProposedUpdate::Babbage(proposal.clone());
+                    proposals.insert(genesis.to_vec(), proposal);
+                }
+            }
+            MultiEraUpdate::Shelley(x) => {
+                for (genesis, proposal) in x.proposed_protocol_parameter_updates.iter() {
+                    let proposal = ProposedUpdate::Shelley(proposal.clone());
+                    proposals.insert(genesis.to_vec(), proposal);
+                }
+            }
+            MultiEraUpdate::AlonzoCompatible(x) => {
+                for (genesis, proposal) in x.proposed_protocol_parameter_updates.iter() {
+                    let proposal = ProposedUpdate::AlonzoCompatible(proposal.clone());
+                    proposals.insert(genesis.to_vec(), proposal);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Tracks the update of a tx, if any. Updates in txs that failed phase-2
+    /// validation are ignored.
+    pub fn track_tx(&mut self, tx: &MultiEraTx) -> Result<(), Error> {
+        if !tx.is_valid() {
+            return Ok(());
+        }
+
+        if let Some(update) = tx.update()? {
+            self.track_update(&update);
+        }
+
+        Ok(())
+    }
+
+    pub fn track_block(&mut self, block: &MultiEraBlock) -> Result<(), Error> {
+        for tx in block.txs() {
+            self.track_tx(&tx)?;
+        }
+
+        Ok(())
+    }
+
+    /// The update that reached the quorum for the given epoch, if any
+    pub fn voted_update(&self, epoch: Epoch) -> Option<&ProposedUpdate> {
+        let proposals = self.proposals.get(&epoch)?;
+
+        proposals.values().find(|candidate| {
+            let votes = proposals.values().filter(|x| x == candidate).count();
+            votes >= self.quorum
+        })
+    }
+
+    /// Applies the update voted during `ending_epoch` to the params and
+    /// discards any proposal that can't be adopted anymore
+    ///
+    /// Returns `true` if the params were changed.
+    pub fn apply_epoch_boundary(
+        &mut self,
+        ending_epoch: Epoch,
+        params: &mut ProtocolParams,
+    ) -> bool {
+        let voted = self.voted_update(ending_epoch).cloned();
+
+        self.proposals = self.proposals.split_off(&(ending_epoch + 1));
+
+        match voted {
+            Some(update) => {
+                params.apply_update(&update);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use pallas_codec::utils::KeyValuePairs;
+
+    use super::*;
+
+    fn load_params() -> ProtocolParams {
+        let shelley =
+            serde_json::from_str(include_str!("../../test_data/devnet-shelley-genesis.json"))
+                .unwrap();
+
+        let alonzo =
+            serde_json::from_str(include_str!("../../test_data/devnet-alonzo-genesis.json"))
+                .unwrap();
+
+        let conway =
+            serde_json::from_str(include_str!("../../test_data/devnet-conway-genesis.json"))
+                .unwrap();
+
+        ProtocolParams::from_genesis(&shelley, &alonzo, &conway).unwrap()
+    }
+
+    fn empty_update() -> babbage::ProtocolParamUpdate {
+        babbage::ProtocolParamUpdate {
+            minfee_a: None,
+            minfee_b: None,
+            max_block_body_size: None,
+            max_transaction_size: None,
+            max_block_header_size: None,
+            key_deposit: None,
+            pool_deposit: None,
+            maximum_epoch: None,
+            desired_number_of_stake_pools: None,
+            pool_pledge_influence: None,
+            expansion_rate: None,
+            treasury_growth_rate: None,
+            protocol_version: None,
+            min_pool_cost: None,
+            ada_per_utxo_byte: None,
+            cost_models_for_script_languages: None,
+            execution_costs: None,
+            max_tx_ex_units: None,
+            max_block_ex_units: None,
+            max_value_size: None,
+            collateral_percentage: None,
+            max_collateral_inputs: None,
+            layout: Default::default(),
+        }
+    }
+
+    fn proposal(
+        epoch: Epoch,
+        voters: &[u8],
+        update: babbage::ProtocolParamUpdate,
+    ) -> babbage::Update {
+        let votes = voters
+            .iter()
+            .map(|v| (vec![*v; 28].into(), update.clone()))
+            .collect::<Vec<_>>();
+
+        babbage::Update {
+            proposed_protocol_parameter_updates: KeyValuePairs::Def(votes),
+            epoch,
+        }
+    }
+
+    #[test]
+    fn params_from_genesis() {
+        let params = load_params();
+
+        assert_eq!(params.minfee_a, 44);
+        assert_eq!(params.minfee_b, 155381);
+        assert_eq!(params.protocol_version, (2, 0));
+        assert_eq!(params.coins_per_utxo_word, 34482);
+        assert_eq!(params.ada_per_utxo_byte, 4310);
+        assert_eq!(params.max_block_ex_units.mem, 50000000);
+        assert_eq!(params.drep_deposit, 500000000);
+        assert_eq!(params.linear_fee(200), 44 * 200 + 155381);
+    }
+
+    #[test]
+    fn update_applies_after_quorum() {
+        let mut params = load_params();
+        let mut tracker = UpdateTracker::new(2);
+
+        let update = babbage::ProtocolParamUpdate {
+            minfee_a: Some(50),
+            protocol_version: Some((8, 0)),
+            ..empty_update()
+        };
+
+        // a single vote doesn't reach the quorum
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(
+            10,
+            &[1],
+            update.clone(),
+        )));
+        assert!(!tracker.apply_epoch_boundary(10, &mut params));
+        assert_eq!(params.minfee_a, 44);
+
+        // proposals for the ended epoch are discarded
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(
+            11,
+            &[1],
+            update.clone(),
+        )));
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(11, &[2], update)));
+        assert!(tracker.voted_update(10).is_none());
+
+        assert!(tracker.apply_epoch_boundary(11, &mut params));
+        assert_eq!(params.minfee_a, 50);
+        assert_eq!(params.protocol_version, (8, 0));
+        assert_eq!(params.minfee_b, 155381);
+    }
+
+    #[test]
+    fn shelley_update_sets_min_utxo_value() {
+        let mut params = load_params();
+        let mut tracker = UpdateTracker::new(1);
+
+        let update = shelley::ProtocolParamUpdate {
+            minfee_a: None,
+            minfee_b: None,
+            max_block_body_size: None,
+            max_transaction_size: None,
+            max_block_header_size: None,
+            key_deposit: None,
+            pool_deposit: None,
+            maximum_epoch: None,
+            desired_number_of_stake_pools: None,
+            pool_pledge_influence: None,
+            expansion_rate: None,
+            treasury_growth_rate: None,
+            decentralization_constant: None,
+            extra_entropy: None,
+            protocol_version: Some((3, 0)),
+            min_utxo_value: Some(2_000_000),
+            min_pool_cost: None,
+            layout: Default::default(),
+        };
+
+        let update = shelley::Update {
+            proposed_protocol_parameter_updates: KeyValuePairs::Def(vec![(
+                vec![1; 28].into(),
+                update,
+            )]),
+            epoch: 5,
+        };
+
+        tracker.track_update(&MultiEraUpdate::Shelley(Box::new(Cow::Owned(update))));
+        assert!(tracker.apply_epoch_boundary(5, &mut params));
+        assert_eq!(params.min_utxo_value, 2_000_000);
+        assert_eq!(params.protocol_version, (3, 0));
+    }
+
+    #[test]
+    fn diverging_votes_dont_apply() {
+        let mut params = load_params();
+        let mut tracker = UpdateTracker::new(2);
+
+        let update_a = babbage::ProtocolParamUpdate {
+            minfee_a: Some(50),
+            ..empty_update()
+        };
+
+        let update_b = babbage::ProtocolParamUpdate {
+            minfee_a: Some(51),
+            ..empty_update()
+        };
+
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(
+            3,
+            &[1],
+            update_a.clone(),
+        )));
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(3, &[2], update_b)));
+        assert!(!tracker.apply_epoch_boundary(3, &mut params));
+
+        // a delegate can change its vote within the same epoch
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(
+            4,
+            &[1],
+            update_a.clone(),
+        )));
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(4, &[2], empty_update())));
+        tracker.track_update(&MultiEraUpdate::Babbage(&proposal(4, &[2], update_a)));
+        assert!(tracker.apply_epoch_boundary(4, &mut params));
+        assert_eq!(params.minfee_a, 50);
+    }
+}
@@ -0,0 +1,155 @@
+//! Parsing of Shelley genesis files
+
+use std::collections::HashMap;
+
+use pallas_crypto::hash::Hash;
+use pallas_primitives::alonzo::{Nonce, NonceVariant, RationalNumber};
+use serde::Deserialize;
+
+use crate::{support::deserialize_rational, Error};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GenDeleg {
+    pub delegate: String,
+    pub vrf: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProtocolVersion {
+    pub major: u64,
+    pub minor: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExtraEntropy {
+    pub tag: String,
+    pub contents: Option<String>,
+}
+
+impl TryFrom<&ExtraEntropy> for Nonce {
+    type Error = Error;
+
+    fn try_from(value: &ExtraEntropy) -> Result<Self, Self::Error> {
+        match (value.tag.as_str(), &value.contents) {
+            ("NeutralNonce", _) => Ok(Nonce {
+                variant: NonceVariant::NeutralNonce,
+                hash: None,
+            }),
+            ("Nonce", Some(contents)) => {
+                let hash: Hash<32> = contents.parse().map_err(Error::invalid_value)?;
+
+                Ok(Nonce {
+                    variant: NonceVariant::Nonce,
+                    hash: Some(hash),
+                })
+            }
+            (tag, _) => Err(Error::invalid_value(format!("extra entropy tag {tag}"))),
+        }
+    }
+}
+
+/// The initial protocol parameters as they appear in the Shelley genesis
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolParams {
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub a0: RationalNumber,
+
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub decentralisation_param: RationalNumber,
+
+    pub e_max: u64,
+    pub extra_entropy: ExtraEntropy,
+    pub key_deposit: u64,
+    pub max_block_body_size: u32,
+    pub max_block_header_size: u32,
+    pub max_tx_size: u32,
+    pub min_fee_a: u32,
+    pub min_fee_b: u32,
+    pub min_pool_cost: u64,
+    #[serde(rename = "minUTxOValue")]
+    pub min_utxo_value: u64,
+    pub n_opt: u32,
+    pub pool_deposit: u64,
+    pub protocol_version: ProtocolVersion,
+
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub rho: RationalNumber,
+
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub tau: RationalNumber,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisFile {
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub active_slots_coeff: RationalNumber,
+
+    pub epoch_length: u64,
+    pub gen_delegs: HashMap<String, GenDeleg>,
+    pub initial_funds: HashMap<String, u64>,
+
+    #[serde(rename = "maxKESEvolutions")]
+    pub max_kes_evolutions: u64,
+
+    pub max_lovelace_supply: u64,
+    pub network_id: String,
+    pub network_magic: u32,
+    pub protocol_params: ProtocolParams,
+    pub security_param: u64,
+
+    /// Slot length in seconds, devnets usually define sub-second values
+    #[serde(deserialize_with = "deserialize_rational")]
+    pub slot_length: RationalNumber,
+
+    #[serde(rename = "slotsPerKESPeriod")]
+    pub slots_per_kes_period: u64,
+
+    /// Start of the chain as an ISO-8601 UTC date
+    pub system_start: String,
+
+    pub update_quorum: u32,
+}
+
+pub fn from_file(path: &std::path::Path) -> Result<GenesisFile, Error> {
+    let file = std::fs::File::open(path)?;
+    let reader = std::io::BufReader::new(file);
+    let parsed = serde_json::from_reader(reader)?;
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file() {
+        let json = include_str!("../../test_data/devnet-shelley-genesis.json");
+        let genesis: GenesisFile = serde_json::from_str(json).unwrap();
+
+        assert_eq!(genesis.epoch_length, 432000);
+        assert_eq!(genesis.update_quorum, 2);
+        assert_eq!(genesis.gen_delegs.len(), 3);
+
+        assert_eq!(
+            genesis.active_slots_coeff,
+            RationalNumber {
+                numerator: 1,
+                denominator: 20
+            }
+        );
+
+        assert_eq!(
+            genesis.protocol_params.rho,
+            RationalNumber {
+                numerator: 3,
+                denominator: 1000
+            }
+        );
+
+        let nonce = Nonce::try_from(&genesis.protocol_params.extra_entropy).unwrap();
+        assert_eq!(nonce.variant, NonceVariant::NeutralNonce);
+    }
+}
@@ -0,0 +1,143 @@
+//! Internal serde helpers shared by the different genesis files
+
+use pallas_primitives::alonzo::RationalNumber;
+use serde::{de::Error as _, Deserialize, Deserializer};
+use serde_json::Value;
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+/// Builds an exact rational from the shortest decimal representation of a
+/// float, which is how the Haskell node interprets these JSON values.
+pub(crate) fn float_to_rational(value: f64) -> Option<RationalNumber> {
+    if !value.is_finite() || value.is_sign_negative() {
+        return None;
+    }
+
+    let repr = format!("{value}");
+
+    let (int, frac) = match repr.split_once('.') {
+        Some((int, frac)) => (int, frac),
+        None => (repr.as_str(), ""),
+    };
+
+    let denominator = 10u64.checked_pow(frac.len() as u32)?;
+
+    let numerator = int
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(denominator)?
+        .checked_add(if frac.is_empty() {
+            0
+        } else {
+            frac.parse().ok()?
+        })?;
+
+    let gcd = gcd(numerator, denominator);
+
+    Some(RationalNumber {
+        numerator: numerator / gcd,
+        denominator: denominator / gcd,
+    })
+}
+
+#[derive(Deserialize)]
+struct Fraction {
+    numerator: u64,
+    denominator: u64,
+}
+
+/// Accepts either a JSON number or a `{ numerator, denominator }` object
+///
+/// Untagged enums can't be used for these: serde_json is built with
+/// `arbitrary_precision` by pallas-primitives, which breaks numbers buffered
+/// by serde. The value is read as JSON first instead.
+pub(crate) fn deserialize_rational<'de, D>(deserializer: D) -> Result<RationalNumber, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Number(x) => {
+            let x = x
+                .as_f64()
+                .ok_or_else(|| D::Error::custom(format!("invalid rational {x}")))?;
+
+            float_to_rational(x)
+                .ok_or_else(|| D::Error::custom(format!("can't represent {x} as a rational")))
+        }
+        x => {
+            let Fraction {
+                numerator,
+                denominator,
+            } = serde_json::from_value(x).map_err(D::Error::custom)?;
+
+            Ok(RationalNumber {
+                numerator,
+                denominator,
+            })
+        }
+    }
+}
+
+pub(crate) fn deserialize_option_rational<'de, D>(
+    deserializer: D,
+) -> Result<Option<RationalNumber>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "deserialize_rational")] RationalNumber);
+
+    let value = Option::<Wrapper>::deserialize(deserializer)?;
+
+    Ok(value.map(|Wrapper(x)| x))
+}
+
+/// Byron genesis files encode most of their numbers as JSON strings
+pub(crate) fn deserialize_string_number<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Number(x) => x
+            .as_u64()
+            .ok_or_else(|| D::Error::custom(format!("invalid number {x}"))),
+        Value::String(x) => x.parse().map_err(D::Error::custom),
+        x => Err(D::Error::custom(format!("expected a number, found {x}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_conversion_is_exact() {
+        let cases = [
+            (0.05, 1, 20),
+            (0.003, 3, 1000),
+            (0.2, 1, 5),
+            (0.3, 3, 10),
+            (1.0, 1, 1),
+            (0.0, 0, 1),
+            (0.0577, 577, 10000),
+            (7.21e-5, 721, 10000000),
+        ];
+
+        for (value, numerator, denominator) in cases {
+            let rational = float_to_rational(value).unwrap();
+            assert_eq!(rational.numerator, numerator, "numerator of {value}");
+            assert_eq!(rational.denominator, denominator, "denominator of {value}");
+        }
+    }
+
+    #[test]
+    fn negative_floats_are_rejected() {
+        assert!(float_to_rational(-0.5).is_none());
+    }
+}
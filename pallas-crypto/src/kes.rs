@@ -0,0 +1,403 @@
+//! Key Evolving Signatures, as used to sign the block headers
+//!
+//! Cardano uses the `Sum6KES` scheme: a binary sum composition (as described
+//! in [MMM](https://eprint.iacr.org/2001/034)) of depth 6 over Ed25519, with
+//! Blake2b 256 used to hash the pair of verification keys at each level. A
+//! key is valid for `2^6 = 64` periods and the signature for a given period
+//! embeds all the verification keys required to reach the root key.
+//!
+//! Only verification is implemented here. The hot key of a block producer
+//! is certified by its cold key through an [`OpCert`], which can also be
+//! verified with this module.
+
+use crate::hash::Hasher;
+use crate::key::ed25519;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// The depth of the sum composition used by Cardano
+pub const DEPTH: u32 = 6;
+
+/// The number of periods a KES key can be used for
+pub const MAX_PERIODS: u32 = 1 << DEPTH;
+
+/// Error type used when verifying KES signatures and operational certs
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KesError {
+    #[error("Invalid size, expecting {0}")]
+    InvalidSize(usize),
+
+    #[error("Period {0} is outside the lifetime of the key")]
+    PeriodOutOfRange(u32),
+
+    #[error("Verification keys of the signature don't match the public key")]
+    KeyMismatch,
+
+    #[error("Signature verification failed")]
+    VerificationFailed,
+
+    #[error("Operational certificate signature verification failed")]
+    InvalidOpCertSignature,
+
+    #[error("Operational certificate counter {1} is lower than the last seen {0}")]
+    CounterTooSmall(u64, u64),
+
+    #[error("Operational certificate counter {1} increments the last seen {0} by more than one")]
+    CounterOverIncremented(u64, u64),
+
+    #[error("Current KES period {1} is before the start of the certificate {0}")]
+    KesBeforeStart(u64, u64),
+
+    #[error("Current KES period {1} is past the end of the certificate {0}")]
+    KesAfterEnd(u64, u64),
+}
+
+/// Sum6KES Public Key, the Blake2b 256 hash of the root pair of keys
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey([u8; Self::SIZE]);
+
+/// Sum6KES Signature, an Ed25519 signature followed by the pair of
+/// verification keys of each level of the tree, from the leaves to the root
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Signature([u8; Self::SIZE]);
+
+impl PublicKey {
+    /// This is the size of the type in bytes.
+    pub const SIZE: usize = 32;
+
+    /// verify the [`Signature`] of the message for the given KES period
+    pub fn verify<T>(&self, period: u32, msg: T, signature: &Signature) -> Result<(), KesError>
+    where
+        T: AsRef<[u8]>,
+    {
+        if period >= MAX_PERIODS {
+            return Err(KesError::PeriodOutOfRange(period));
+        }
+
+        verify_sum(DEPTH, &self.0, period, msg.as_ref(), &signature.0)
+    }
+}
+
+impl Signature {
+    /// This is the size of the type in bytes.
+    pub const SIZE: usize = signature_size(DEPTH);
+}
+
+const fn signature_size(depth: u32) -> usize {
+    ed25519::Signature::SIZE + depth as usize * 2 * PublicKey::SIZE
+}
+
+/// Verifies a signature of the sum composition of the given depth
+///
+/// The signature of the level is the signature of the level below followed by
+/// the two verification keys of the level; the period selects which of the
+/// subtrees was used to sign.
+fn verify_sum(
+    depth: u32,
+    vk: &[u8; PublicKey::SIZE],
+    period: u32,
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<(), KesError> {
+    if depth == 0 {
+        let vk = ed25519::PublicKey::from(*vk);
+        let sig = ed25519::Signature::try_from(sig)
+            .map_err(|_| KesError::InvalidSize(ed25519::Signature::SIZE))?;
+
+        return match vk.verify(msg, &sig) {
+            true => Ok(()),
+            false => Err(KesError::VerificationFailed),
+        };
+    }
+
+    let (inner, keys) = sig.split_at(signature_size(depth - 1));
+
+    if Hasher::<256>::hash(keys).as_ref() != vk {
+        return Err(KesError::KeyMismatch);
+    }
+
+    let (vk0, vk1) = keys.split_at(PublicKey::SIZE);
+    let half = 1 << (depth - 1);
+
+    let (vk, period) = match period < half {
+        true => (vk0, period),
+        false => (vk1, period - half),
+    };
+
+    let vk = vk.try_into().expect("slice with verification key size");
+
+    verify_sum(depth - 1, vk, period, msg, inner)
+}
+
+/// Operational certificate, the delegation of block signing rights from the
+/// cold key of a stake pool to a KES hot key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpCert {
+    pub hot_vkey: PublicKey,
+    pub sequence_number: u64,
+    pub kes_period: u64,
+    pub sigma: ed25519::Signature,
+}
+
+impl OpCert {
+    /// The bytes signed by the cold key: the hot key followed by the counter
+    /// and the start period, as big-endian 64 bit words
+    pub fn signable(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(PublicKey::SIZE + 16);
+        out.extend_from_slice(&self.hot_vkey.0);
+        out.extend_from_slice(&self.sequence_number.to_be_bytes());
+        out.extend_from_slice(&self.kes_period.to_be_bytes());
+        out
+    }
+
+    /// verify the signature of the certificate against the pool cold key
+    pub fn verify(&self, cold_vkey: &ed25519::PublicKey) -> Result<(), KesError> {
+        match cold_vkey.verify(self.signable(), &self.sigma) {
+            true => Ok(()),
+            false => Err(KesError::InvalidOpCertSignature),
+        }
+    }
+
+    /// check the counter against the last one seen for the same pool
+    ///
+    /// A new certificate can only increment the counter by one, pools that
+    /// haven't produced a block yet start with a counter of zero.
+    pub fn check_counter(&self, last_seen: u64) -> Result<(), KesError> {
+        if self.sequence_number < last_seen {
+            return Err(KesError::CounterTooSmall(last_seen, self.sequence_number));
+        }
+
+        if self.sequence_number > last_seen.saturating_add(1) {
+            return Err(KesError::CounterOverIncremented(
+                last_seen,
+                self.sequence_number,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// the period of the hot key to use for the current (absolute) KES
+    /// period, given the maximum number of evolutions allowed by the network
+    pub fn evolution(&self, current: u64, max_evolutions: u64) -> Result<u32, KesError> {
+        if current < self.kes_period {
+            return Err(KesError::KesBeforeStart(self.kes_period, current));
+        }
+
+        let period = current - self.kes_period;
+
+        if period >= max_evolutions.min(MAX_PERIODS as u64) {
+            return Err(KesError::KesAfterEnd(self.kes_period, current));
+        }
+
+        Ok(period as u32)
+    }
+}
+
+/* Format ****************************************************************** */
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.as_ref()))
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.as_ref()))
+    }
+}
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PublicKey<Kes>")
+            .field(&hex::encode(self.as_ref()))
+            .finish()
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Signature<Kes>")
+            .field(&hex::encode(self.as_ref()))
+            .finish()
+    }
+}
+
+/* AsRef ******************************************************************* */
+
+impl AsRef<[u8]> for PublicKey {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/* Conversion ************************************************************** */
+
+impl From<[u8; Self::SIZE]> for PublicKey {
+    fn from(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<PublicKey> for [u8; PublicKey::SIZE] {
+    fn from(pk: PublicKey) -> Self {
+        pk.0
+    }
+}
+
+impl From<[u8; Self::SIZE]> for Signature {
+    fn from(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for PublicKey {
+    type Error = KesError;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| KesError::InvalidSize(Self::SIZE))?;
+
+        Ok(Self(bytes))
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Signature {
+    type Error = KesError;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| KesError::InvalidSize(Self::SIZE))?;
+
+        Ok(Self(bytes))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = hex::FromHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = [0; Self::SIZE];
+        hex::decode_to_slice(s, &mut r)?;
+        Ok(Self(r))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = hex::FromHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = [0; Self::SIZE];
+        hex::decode_to_slice(s, &mut r)?;
+        Ok(Self(r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// expands a seed into the seeds of the two subtrees
+    fn expand(seed: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+        let r0 = Hasher::<256>::hash(&[&[1], &seed[..]].concat());
+        let r1 = Hasher::<256>::hash(&[&[2], &seed[..]].concat());
+        (*r0, *r1)
+    }
+
+    fn keygen(depth: u32, seed: &[u8; 32]) -> [u8; 32] {
+        if depth == 0 {
+            return ed25519::SecretKey::from(*seed).public_key().into();
+        }
+
+        let (r0, r1) = expand(seed);
+        let keys = [keygen(depth - 1, &r0), keygen(depth - 1, &r1)].concat();
+
+        *Hasher::<256>::hash(&keys)
+    }
+
+    fn sign(depth: u32, seed: &[u8; 32], period: u32, msg: &[u8]) -> Vec<u8> {
+        if depth == 0 {
+            return ed25519::SecretKey::from(*seed).sign(msg).as_ref().to_vec();
+        }
+
+        let (r0, r1) = expand(seed);
+        let half = 1 << (depth - 1);
+
+        let mut sig = match period < half {
+            true => sign(depth - 1, &r0, period, msg),
+            false => sign(depth - 1, &r1, period - half, msg),
+        };
+
+        sig.extend(keygen(depth - 1, &r0));
+        sig.extend(keygen(depth - 1, &r1));
+        sig
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let seed = [7; 32];
+        let pk = PublicKey::from(keygen(DEPTH, &seed));
+
+        for period in [0, 1, 15, 31, 32, 47, 62, 63] {
+            let sig = sign(DEPTH, &seed, period, b"header body");
+            let sig = Signature::try_from(sig.as_slice()).unwrap();
+
+            assert_eq!(pk.verify(period, b"header body", &sig), Ok(()));
+
+            let other = (period + 1) % MAX_PERIODS;
+            assert!(pk.verify(other, b"header body", &sig).is_err());
+            assert!(pk.verify(period, b"another body", &sig).is_err());
+        }
+
+        let sig = Signature::try_from(sign(DEPTH, &seed, 0, b"x").as_slice()).unwrap();
+        assert_eq!(
+            pk.verify(MAX_PERIODS, b"x", &sig),
+            Err(KesError::PeriodOutOfRange(MAX_PERIODS))
+        );
+
+        let other = PublicKey::from(keygen(DEPTH, &[8; 32]));
+        assert_eq!(other.verify(0, b"x", &sig), Err(KesError::KeyMismatch));
+    }
+
+    #[test]
+    fn opcert_checks() {
+        let cold = ed25519::SecretKey::from([3; 32]);
+
+        let mut cert = OpCert {
+            hot_vkey: PublicKey::from(keygen(DEPTH, &[7; 32])),
+            sequence_number: 4,
+            kes_period: 100,
+            sigma: ed25519::Signature::from([0; 64]),
+        };
+
+        cert.sigma = cold.sign(cert.signable());
+        assert_eq!(cert.verify(&cold.public_key()), Ok(()));
+
+        let other = ed25519::SecretKey::from([4; 32]).public_key();
+        assert_eq!(cert.verify(&other), Err(KesError::InvalidOpCertSignature));
+
+        assert!(cert.check_counter(3).is_ok());
+        assert!(cert.check_counter(4).is_ok());
+        assert_eq!(cert.check_counter(5), Err(KesError::CounterTooSmall(5, 4)));
+        assert_eq!(
+            cert.check_counter(2),
+            Err(KesError::CounterOverIncremented(2, 4))
+        );
+
+        assert_eq!(cert.evolution(100, 62), Ok(0));
+        assert_eq!(cert.evolution(161, 62), Ok(61));
+        assert_eq!(
+            cert.evolution(99, 62),
+            Err(KesError::KesBeforeStart(100, 99))
+        );
+        assert_eq!(
+            cert.evolution(162, 62),
+            Err(KesError::KesAfterEnd(100, 162))
+        );
+    }
+}
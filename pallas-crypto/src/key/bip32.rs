@@ -0,0 +1,552 @@
+//! BIP32-Ed25519 hierarchical deterministic keys
+//!
+//! This module implements the derivation scheme described in
+//! [BIP32-Ed25519](https://input-output-hk.github.io/adrestia/static/Ed25519_BIP.pdf)
+//! in its second version (`V2`), which is the one used by every Shelley
+//! wallet. The legacy first version (`V1`), used by the random wallets of
+//! Byron, is available via [`XPrv::derive_with`] and [`XPub::derive_with`].
+//! An [`XPrv`] is an [`SecretKeyExtended`] along with a chain code, an
+//! [`XPub`] is the associated [`PublicKey`] with the same chain code.
+//!
+//! Private keys support both hardened and soft derivation, public keys only
+//! support soft derivation. The paths defined by
+//! [CIP-1852](https://cips.cardano.org/cips/cip1852/) are available via
+//! [`XPrv::derive_account`] and [`XPrv::derive_key`] / [`XPub::derive_key`].
+
+use crate::hash::{Hash, Hasher};
+use crate::key::ed25519::{PublicKey, SecretKeyExtended, Signature};
+use crate::memsec::Scrubbed as _;
+use cryptoxide::{hmac::Hmac, mac::Mac, sha2::Sha512};
+use curve25519_dalek::{edwards::CompressedEdwardsY, EdwardsPoint, Scalar};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// Indexes equal or above this value are hardened derivations
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// The purpose of the CIP-1852 derivation paths
+pub const PURPOSE: u32 = harden(1852);
+
+/// The coin type of Cardano in the derivation paths, as per SLIP-0044
+pub const COIN_TYPE: u32 = harden(1815);
+
+/// returns the hardened version of the given index
+pub const fn harden(index: u32) -> u32 {
+    index | HARDENED
+}
+
+/// Error type used when building or deriving extended keys
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Bip32Error {
+    #[error("Invalid size, expecting {0}")]
+    InvalidSize(usize),
+
+    #[error("The extended secret key doesn't have the expected bit structure")]
+    InvalidStructure,
+
+    #[error("Public keys can't do hardened derivation (index {0})")]
+    HardenedDerivation(u32),
+
+    #[error("Public key is not a valid curve point")]
+    InvalidPublicKey,
+}
+
+/// The role of a key in a CIP-1852 account, the fourth element of the path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Payment keys of addresses meant to be shared
+    External = 0,
+    /// Payment keys of change addresses
+    Internal = 1,
+    /// Stake keys, used for delegation and rewards
+    Stake = 2,
+    /// Delegated representative keys, as per CIP-105
+    DRep = 3,
+}
+
+/// The version of the derivation algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DerivationScheme {
+    /// Legacy scheme of the Byron random (Daedalus) wallets. Indexes are
+    /// serialized big-endian and the additions are reduced modulo the curve
+    /// order, which means derived keys don't keep the bit structure
+    V1,
+    /// Scheme of the Icarus and Shelley wallets
+    #[default]
+    V2,
+}
+
+/// Extended private key: an [`SecretKeyExtended`] and a chain code
+#[derive(Clone)]
+pub struct XPrv([u8; Self::SIZE]);
+
+/// Extended public key: a [`PublicKey`] and a chain code
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XPub([u8; Self::SIZE]);
+
+impl XPrv {
+    /// This is the size of the type in bytes.
+    pub const SIZE: usize = 96;
+
+    /// build the extended key from its 96 bytes, checking that the secret has
+    /// the bit structure required by the derivation scheme
+    pub fn from_bytes(bytes: [u8; Self::SIZE]) -> Result<Self, Bip32Error> {
+        let key = Self(bytes);
+
+        if !key.check_structure() {
+            return Err(Bip32Error::InvalidStructure);
+        }
+
+        Ok(key)
+    }
+
+    /// build the extended key from its 96 bytes without checking the bit
+    /// structure of the secret, as needed by the keys of legacy Byron wallets
+    /// using [`DerivationScheme::V1`]
+    pub fn from_bytes_unchecked(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    /// build the extended key from an extended secret key and a chain code
+    pub fn from_extended(key: SecretKeyExtended, chain_code: [u8; 32]) -> Result<Self, Bip32Error> {
+        let mut secret: [u8; 64] = key.into();
+
+        let mut bytes = [0; Self::SIZE];
+        bytes[..64].copy_from_slice(&secret);
+        secret.scrub();
+
+        bytes[64..].copy_from_slice(&chain_code);
+
+        Self::from_bytes(bytes)
+    }
+
+    /// besides the usual Ed25519 clamping, the third highest bit needs to be
+    /// cleared so that derived keys never overflow
+    #[inline]
+    #[allow(clippy::verbose_bit_mask)]
+    fn check_structure(&self) -> bool {
+        (self.0[0] & 0b0000_0111) == 0 && (self.0[31] & 0b1110_0000) == 0b0100_0000
+    }
+
+    /// the extended secret key, without the chain code
+    pub fn extended_secret_key(&self) -> SecretKeyExtended {
+        let mut bytes = [0; 64];
+        bytes.copy_from_slice(&self.0[..64]);
+        SecretKeyExtended::from(bytes)
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        let mut cc = [0; 32];
+        cc.copy_from_slice(&self.0[64..]);
+        cc
+    }
+
+    /// get the [`XPub`] associated to this key
+    pub fn public(&self) -> XPub {
+        let pk = self.extended_secret_key().public_key();
+
+        let mut bytes = [0; XPub::SIZE];
+        bytes[..32].copy_from_slice(pk.as_ref());
+        bytes[32..].copy_from_slice(&self.0[64..]);
+
+        XPub(bytes)
+    }
+
+    /// create a [`Signature`] for the given message with this key
+    pub fn sign<T>(&self, msg: T) -> Signature
+    where
+        T: AsRef<[u8]>,
+    {
+        self.extended_secret_key().sign(msg)
+    }
+
+    /// derive the child key at the given index, hardened if the index is
+    /// equal or above [`HARDENED`]
+    pub fn derive(&self, index: u32) -> Self {
+        self.derive_with(DerivationScheme::V2, index)
+    }
+
+    /// derive the child key at the given index with the given scheme
+    pub fn derive_with(&self, scheme: DerivationScheme, index: u32) -> Self {
+        let (kl, kr, cc) = (&self.0[..32], &self.0[32..64], &self.0[64..]);
+        let index_bytes = serialize_index(scheme, index);
+
+        let (mut z, i) = if index >= HARDENED {
+            (
+                hmac(cc, &[&[0x00], kl, kr, &index_bytes]),
+                hmac(cc, &[&[0x01], kl, kr, &index_bytes]),
+            )
+        } else {
+            let pk = self.public();
+            let pk = pk.public_key();
+            (
+                hmac(cc, &[&[0x02], pk.as_ref(), &index_bytes]),
+                hmac(cc, &[&[0x03], pk.as_ref(), &index_bytes]),
+            )
+        };
+
+        let mut child = [0; Self::SIZE];
+
+        match scheme {
+            DerivationScheme::V1 => {
+                child[..32].copy_from_slice(&add_mul8_v1(kl, &z[..32]));
+                child[32..64].copy_from_slice(&add_256bits_v1(kr, &z[32..]));
+            }
+            DerivationScheme::V2 => {
+                child[..32].copy_from_slice(&add_28_mul8(kl, &z[..28]));
+                child[32..64].copy_from_slice(&add_256bits(kr, &z[32..]));
+            }
+        }
+
+        child[64..].copy_from_slice(&i[32..]);
+
+        z.scrub();
+
+        Self(child)
+    }
+
+    /// derive the key at the end of the path, a sequence of indexes
+    pub fn derive_path(&self, path: &[u32]) -> Self {
+        path.iter()
+            .fold(self.clone(), |key, index| key.derive(*index))
+    }
+
+    /// derive the CIP-1852 account key from a root key:
+    /// `m / 1852' / 1815' / account'`
+    pub fn derive_account(&self, account: u32) -> Self {
+        self.derive_path(&[PURPOSE, COIN_TYPE, harden(account)])
+    }
+
+    /// derive the CIP-1852 key of the given role and index from an account
+    /// key: `account / role / index`
+    pub fn derive_key(&self, role: Role, index: u32) -> Self {
+        self.derive_path(&[role as u32, index])
+    }
+}
+
+impl XPub {
+    /// This is the size of the type in bytes.
+    pub const SIZE: usize = 64;
+
+    pub fn public_key(&self) -> PublicKey {
+        let mut pk = [0; 32];
+        pk.copy_from_slice(&self.0[..32]);
+        PublicKey::from(pk)
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        let mut cc = [0; 32];
+        cc.copy_from_slice(&self.0[32..]);
+        cc
+    }
+
+    /// the Blake2b 224 hash of the public key, as used in addresses and
+    /// certificates to identify the key
+    pub fn key_hash(&self) -> Hash<28> {
+        Hasher::<224>::hash(&self.0[..32])
+    }
+
+    /// derive the child key at the given (soft) index
+    pub fn derive(&self, index: u32) -> Result<Self, Bip32Error> {
+        self.derive_with(DerivationScheme::V2, index)
+    }
+
+    /// derive the child key at the given (soft) index with the given scheme
+    pub fn derive_with(&self, scheme: DerivationScheme, index: u32) -> Result<Self, Bip32Error> {
+        if index >= HARDENED {
+            return Err(Bip32Error::HardenedDerivation(index));
+        }
+
+        let (pk, cc) = (&self.0[..32], &self.0[32..]);
+        let index_bytes = serialize_index(scheme, index);
+
+        let z = hmac(cc, &[&[0x02], pk, &index_bytes]);
+        let i = hmac(cc, &[&[0x03], pk, &index_bytes]);
+
+        let mut point = [0; 32];
+        point.copy_from_slice(pk);
+
+        let point = CompressedEdwardsY(point)
+            .decompress()
+            .ok_or(Bip32Error::InvalidPublicKey)?;
+
+        let zl8 = match scheme {
+            DerivationScheme::V1 => add_mul8_v1(&[0; 32], &z[..32]),
+            DerivationScheme::V2 => add_28_mul8(&[0; 32], &z[..28]),
+        };
+
+        let child = point + EdwardsPoint::mul_base(&Scalar::from_bytes_mod_order(zl8));
+
+        let mut bytes = [0; Self::SIZE];
+        bytes[..32].copy_from_slice(child.compress().as_bytes());
+        bytes[32..].copy_from_slice(&i[32..]);
+
+        Ok(Self(bytes))
+    }
+
+    /// derive the key at the end of the path, a sequence of soft indexes
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, Bip32Error> {
+        path.iter().try_fold(*self, |key, index| key.derive(*index))
+    }
+
+    /// derive the CIP-1852 key of the given role and index from an account
+    /// public key: `account / role / index`
+    pub fn derive_key(&self, role: Role, index: u32) -> Result<Self, Bip32Error> {
+        self.derive_path(&[role as u32, index])
+    }
+}
+
+fn hmac(key: &[u8], parts: &[&[u8]]) -> [u8; 64] {
+    let mut mac = Hmac::new(Sha512::new(), key);
+
+    for part in parts {
+        mac.input(part);
+    }
+
+    let mut out = [0; 64];
+    mac.raw_result(&mut out);
+    out
+}
+
+fn serialize_index(scheme: DerivationScheme, index: u32) -> [u8; 4] {
+    match scheme {
+        DerivationScheme::V1 => index.to_be_bytes(),
+        DerivationScheme::V2 => index.to_le_bytes(),
+    }
+}
+
+/// `x + 8 * y`, where `y` is a 28 bytes little-endian number
+fn add_28_mul8(x: &[u8], y: &[u8]) -> [u8; 32] {
+    let mut out = [0; 32];
+    let mut carry: u16 = 0;
+
+    for i in 0..32 {
+        let y = if i < 28 { (y[i] as u16) << 3 } else { 0 };
+        let r = x[i] as u16 + y + carry;
+        out[i] = (r & 0xff) as u8;
+        carry = r >> 8;
+    }
+
+    out
+}
+
+/// `x + y mod 2^256`, both little-endian numbers
+fn add_256bits(x: &[u8], y: &[u8]) -> [u8; 32] {
+    let mut out = [0; 32];
+    let mut carry: u16 = 0;
+
+    for i in 0..32 {
+        let r = x[i] as u16 + y[i] as u16 + carry;
+        out[i] = (r & 0xff) as u8;
+        carry = r >> 8;
+    }
+
+    out
+}
+
+/// `x + 8 * y mod ℓ` as done by the `V1` scheme: every byte of `y` is
+/// shifted on its own, dropping the bits that overflow it
+fn add_mul8_v1(x: &[u8], y: &[u8]) -> [u8; 32] {
+    let mut wide = [0; 64];
+    let mut carry: u16 = 0;
+
+    for i in 0..32 {
+        let r = x[i] as u16 + (y[i] << 3) as u16 + carry;
+        wide[i] = (r & 0xff) as u8;
+        carry = r >> 8;
+    }
+    wide[32] = carry as u8;
+
+    Scalar::from_bytes_mod_order_wide(&wide).to_bytes()
+}
+
+/// `x + y` as done by the `V1` scheme: byte per byte, without carry
+fn add_256bits_v1(x: &[u8], y: &[u8]) -> [u8; 32] {
+    let mut out = [0; 32];
+
+    for i in 0..32 {
+        out[i] = x[i].wrapping_add(y[i]);
+    }
+
+    out
+}
+
+/* Drop ******************************************************************** */
+
+impl Drop for XPrv {
+    fn drop(&mut self) {
+        self.0.scrub()
+    }
+}
+
+/* Format ****************************************************************** */
+
+impl fmt::Display for XPub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.as_ref()))
+    }
+}
+
+impl fmt::Debug for XPub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("XPub")
+            .field(&hex::encode(self.as_ref()))
+            .finish()
+    }
+}
+
+impl fmt::Debug for XPrv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XPrv").finish_non_exhaustive()
+    }
+}
+
+/* AsRef ******************************************************************* */
+
+impl AsRef<[u8]> for XPub {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/* Conversion ************************************************************** */
+
+impl From<XPrv> for [u8; XPrv::SIZE] {
+    fn from(key: XPrv) -> Self {
+        key.0
+    }
+}
+
+impl From<[u8; Self::SIZE]> for XPub {
+    fn from(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<XPub> for [u8; XPub::SIZE] {
+    fn from(key: XPub) -> Self {
+        key.0
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for XPrv {
+    type Error = Bip32Error;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| Bip32Error::InvalidSize(Self::SIZE))?;
+
+        Self::from_bytes(bytes)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for XPub {
+    type Error = Bip32Error;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| Bip32Error::InvalidSize(Self::SIZE))?;
+
+        Ok(Self(bytes))
+    }
+}
+
+impl FromStr for XPub {
+    type Err = hex::FromHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = [0; Self::SIZE];
+        hex::decode_to_slice(s, &mut r)?;
+        Ok(Self(r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // vectors generated with the `ed25519-bip32` crate (V2 scheme)
+    const ROOT: &str = "000a11181f262d343b424950575e656c737a81888f969da4abb2b9c0c7ced55ce3eaf1f8ff060d141b222930373e454c535a61686f767d848b9299a0a7aeb5bcc3cad1d8dfe6edf4fb020910171e252c333a41484f565d646b727980878e959c";
+    const ACCOUNT: &str = "18b730d1dcc05d910fce7488dd905559f2c44ec802611b015ffeaa46d0ced55c17ea52b62500410dfd2df92ddf30d49b5d2bb1c45fca27ad97b3e02bb2fd7046e0fdee60688462f8108789a5359733aadddbd604233a08b8e27b52f9f37a2381";
+    const PAYMENT: &str = "c73f4aee58b8aece9316b33ecb4439bc085632f6f95c96d6fe4fc140ecdb2d17bb061d5e361d9373ebe2b97619b8ee345d199483030252417f8399096b5b803b";
+    const STAKE: &str = "49f7f07a35df3c0b3be9e79fc434d007d117fc46cd1ea7074309398e3e078923fbc0bec75cff4e6e85e5480dc1771a8bc46bbf61a0446f8fc5d823b21b60609e";
+    const CHANGE_5: &str = "3e7718419cd3f429d2e9cb4e4f3718bbb7e74079a6108b5ebd4f886fc0c9fe6f13cf69fa04254b6f6e3d5e612ba8bf7a0e0e11e744853ad68fa3c25f56860fa7";
+    const DREP: &str = "144b18d1a77ab1560371c209f4af50150d97ae528a0fb3a58e1af407d9de6ad1a3f4d22346a15478584ab440703783a077b24a10f4549c208d4985476edc74e0";
+    const SIGNATURE: &str = "d8aa6b3e245cf2c2a60a23c91e4b23caf833694432f6d10c0817a7397407ba774a7321a37a0be9389181a1ca6dd3a99fa167bbfe2c84769de7e5850dcde6e60b";
+
+    fn root() -> XPrv {
+        XPrv::try_from(hex::decode(ROOT).unwrap().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn cip1852_derivation() {
+        let account = root().derive_account(0);
+        let bytes: [u8; XPrv::SIZE] = account.clone().into();
+        assert_eq!(hex::encode(bytes), ACCOUNT);
+
+        let payment = account.derive_key(Role::External, 0);
+        assert_eq!(payment.public().to_string(), PAYMENT);
+
+        let stake = account.derive_key(Role::Stake, 0);
+        assert_eq!(stake.public().to_string(), STAKE);
+
+        let drep = account.derive_key(Role::DRep, 0);
+        assert_eq!(drep.public().to_string(), DREP);
+
+        let signature = payment.sign(b"hello");
+        assert_eq!(signature.to_string(), SIGNATURE);
+        assert!(payment.public().public_key().verify(b"hello", &signature));
+    }
+
+    #[test]
+    fn public_derivation_matches_private() {
+        let account = root().derive_account(0);
+
+        let change = account.public().derive_key(Role::Internal, 5).unwrap();
+        assert_eq!(change.to_string(), CHANGE_5);
+        assert_eq!(change, account.derive_key(Role::Internal, 5).public());
+
+        assert_eq!(
+            account.public().derive(harden(0)),
+            Err(Bip32Error::HardenedDerivation(harden(0)))
+        );
+    }
+
+    // vectors generated with the `ed25519-bip32` crate (V1 scheme), from a
+    // root with the third highest bit set as legacy wallets may have
+    const V1_KEY: &str = "931102162a68d70a8373f7769d81788ca97bca69e81fc6244593bac90828ce05d5652c6a8b163c815e843e68e52c2ab8f386313334669f236edb36fef0486023524fbc730d340ed288a0caaf48b5d00047c39cbf33796079e838b59fab5e85ae";
+    const V1_SOFT: &str = "edc6f9a4c91bddc092163064178b1ec8f37d3779ecc6586756e0067aa11c22c54b33e892a6ac7a6868e6d6769902addeeb6a31ab9d5f1e18dfdde1f4bb4112c3";
+    const V1_SIGNATURE: &str = "6c08dcd884df816e3ba0cfe236431203d185d8d3b6eb3474c2823216e635b97f4d1c7c50150c8bf9688a7a3a340506bb9a4b939fd43d545cfc08ec4294c15901";
+
+    #[test]
+    fn legacy_v1_derivation() {
+        let mut bytes = [0; XPrv::SIZE];
+        hex::decode_to_slice(ROOT, &mut bytes).unwrap();
+        bytes[31] |= 0b0010_0000;
+        let root = XPrv::from_bytes_unchecked(bytes);
+
+        let account = root.derive_with(DerivationScheme::V1, harden(0));
+
+        let key = account.derive_with(DerivationScheme::V1, harden(5));
+        let bytes: [u8; XPrv::SIZE] = key.clone().into();
+        assert_eq!(hex::encode(bytes), V1_KEY);
+        assert_eq!(key.sign(b"hello").to_string(), V1_SIGNATURE);
+
+        let soft = account.derive_with(DerivationScheme::V1, 3).public();
+        assert_eq!(soft.to_string(), V1_SOFT);
+        assert_eq!(
+            account.public().derive_with(DerivationScheme::V1, 3),
+            Ok(soft)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_structure() {
+        let mut bytes = hex::decode(ROOT).unwrap();
+        bytes[31] |= 0b0010_0000;
+
+        assert_eq!(
+            XPrv::try_from(bytes.as_slice()).err(),
+            Some(Bip32Error::InvalidStructure)
+        );
+    }
+}
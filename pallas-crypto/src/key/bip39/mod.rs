@@ -0,0 +1,358 @@
+//! BIP39 mnemonics and the derivation of Cardano root keys
+//!
+//! A [`Mnemonic`] is the human readable encoding of some entropy, as described
+//! in [BIP39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki),
+//! using the English word list. The entropy can then be turned into the root
+//! [`XPrv`] of a wallet following one of the schemes described in
+//! [CIP-3](https://cips.cardano.org/cips/cip3/):
+//!
+//! * Icarus, used by most software wallets (Daedalus, Yoroi, cardano-wallet),
+//!   derives the key from the entropy itself.
+//! * Ledger, used by Ledger and Trezor hardware wallets, derives the key from
+//!   the BIP39 seed of the mnemonic.
+//!
+//! Passphrases, the "second factor" of the mnemonic, are expected to be in
+//! Unicode NFKD form; no normalization is performed by this module.
+
+use crate::key::bip32::XPrv;
+use crate::memsec::Scrubbed as _;
+use cryptoxide::{
+    hashing::sha256,
+    hmac::Hmac,
+    mac::Mac,
+    pbkdf2::pbkdf2,
+    sha2::{Sha256, Sha512},
+};
+use rand_core::{CryptoRng, RngCore};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+const ENGLISH: &str = include_str!("english.txt");
+
+/// The word list, sorted alphabetically
+fn words() -> impl Iterator<Item = &'static str> {
+    ENGLISH.lines()
+}
+
+fn is_valid_word_count(count: usize) -> bool {
+    count.is_multiple_of(3) && (12..=24).contains(&count)
+}
+
+/// Error type used when parsing or building a [`Mnemonic`]
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Bip39Error {
+    #[error("Invalid entropy size {0}, expecting 16, 20, 24, 28 or 32 bytes")]
+    InvalidEntropySize(usize),
+
+    #[error("Invalid word count {0}, expecting 12, 15, 18, 21 or 24 words")]
+    InvalidWordCount(usize),
+
+    #[error("Unknown word: {0}")]
+    UnknownWord(String),
+
+    #[error("Invalid mnemonic checksum")]
+    InvalidChecksum,
+}
+
+/// A BIP39 mnemonic, kept as the entropy it encodes
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic(Vec<u8>);
+
+impl Mnemonic {
+    /// build the mnemonic that encodes the given entropy
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, Bip39Error> {
+        match entropy.len() {
+            16 | 20 | 24 | 28 | 32 => Ok(Self(entropy.to_vec())),
+            x => Err(Bip39Error::InvalidEntropySize(x)),
+        }
+    }
+
+    /// generate a new mnemonic of the given number of words with the given
+    /// random number generator
+    pub fn generate<Rng>(mut rng: Rng, word_count: usize) -> Result<Self, Bip39Error>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        if !is_valid_word_count(word_count) {
+            return Err(Bip39Error::InvalidWordCount(word_count));
+        }
+
+        let mut entropy = vec![0; word_count / 3 * 4];
+        rng.fill_bytes(&mut entropy);
+
+        Ok(Self(entropy))
+    }
+
+    /// parse a phrase of space-separated words, validating the checksum
+    pub fn parse(phrase: &str) -> Result<Self, Bip39Error> {
+        let indexes = phrase
+            .split_whitespace()
+            .map(|word| {
+                words()
+                    .position(|x| x == word)
+                    .ok_or_else(|| Bip39Error::UnknownWord(word.to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let word_count = indexes.len();
+
+        if !is_valid_word_count(word_count) {
+            return Err(Bip39Error::InvalidWordCount(word_count));
+        }
+
+        // each word encodes 11 bits, for every 32 bits of entropy there is a
+        // bit of checksum
+        let mut bits = vec![false; word_count * 11];
+
+        for (i, index) in indexes.iter().enumerate() {
+            for j in 0..11 {
+                bits[i * 11 + j] = (index >> (10 - j)) & 1 == 1;
+            }
+        }
+
+        let checksum_bits = word_count / 3;
+        let (entropy_bits, checksum) = bits.split_at(bits.len() - checksum_bits);
+
+        let entropy: Vec<u8> = entropy_bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8))
+            .collect();
+
+        let mnemonic = Self(entropy);
+
+        if mnemonic.checksum_bits() != checksum {
+            return Err(Bip39Error::InvalidChecksum);
+        }
+
+        Ok(mnemonic)
+    }
+
+    /// the first `ENT / 32` bits of the SHA256 of the entropy
+    fn checksum_bits(&self) -> Vec<bool> {
+        let hash = sha256(&self.0);
+
+        (0..self.0.len() / 4)
+            .map(|i| (hash[i / 8] >> (7 - i % 8)) & 1 == 1)
+            .collect()
+    }
+
+    pub fn entropy(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// the words of the mnemonic, in order
+    pub fn words(&self) -> Vec<&'static str> {
+        let bits: Vec<bool> = self
+            .0
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> (7 - i)) & 1 == 1))
+            .chain(self.checksum_bits())
+            .collect();
+
+        let list: Vec<_> = words().collect();
+
+        bits.chunks(11)
+            .map(|chunk| chunk.iter().fold(0, |acc, bit| (acc << 1) | *bit as usize))
+            .map(|index| list[index])
+            .collect()
+    }
+
+    /// the phrase of the mnemonic, words separated by a single space
+    pub fn phrase(&self) -> String {
+        self.words().join(" ")
+    }
+
+    /// the BIP39 seed of the mnemonic
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        let mut mac = Hmac::new(Sha512::new(), self.phrase().as_bytes());
+        let salt = format!("mnemonic{passphrase}");
+
+        let mut seed = [0; 64];
+        pbkdf2(&mut mac, salt.as_bytes(), 2048, &mut seed);
+        seed
+    }
+
+    /// the root key of the wallet, following the Icarus scheme
+    pub fn to_icarus_root(&self, passphrase: &str) -> XPrv {
+        let mut mac = Hmac::new(Sha512::new(), passphrase.as_bytes());
+
+        let mut bytes = [0; XPrv::SIZE];
+        pbkdf2(&mut mac, &self.0, 4096, &mut bytes);
+
+        bytes[0] &= 0b1111_1000;
+        bytes[31] &= 0b0001_1111;
+        bytes[31] |= 0b0100_0000;
+
+        XPrv::from_bytes(bytes).expect("tweaked bytes have a valid structure")
+    }
+
+    /// the root key of the wallet, following the Ledger scheme (also used by
+    /// Trezor wallets with 24 words)
+    pub fn to_ledger_root(&self, passphrase: &str) -> XPrv {
+        let mut seed = self.to_seed(passphrase);
+
+        let mut chain_code = [0; 32];
+        let mut mac = Hmac::new(Sha256::new(), b"ed25519 seed");
+        mac.input(&[0x01]);
+        mac.input(&seed);
+        mac.raw_result(&mut chain_code);
+
+        let mut i = [0; 64];
+        let mut mac = Hmac::new(Sha512::new(), b"ed25519 seed");
+        mac.input(&seed);
+        mac.raw_result(&mut i);
+
+        // keys with the third highest bit set are discarded by hashing again
+        while i[31] & 0b0010_0000 != 0 {
+            let mut mac = Hmac::new(Sha512::new(), b"ed25519 seed");
+            mac.input(&i);
+            mac.raw_result(&mut i);
+        }
+
+        i[0] &= 0b1111_1000;
+        i[31] &= 0b0111_1111;
+        i[31] |= 0b0100_0000;
+
+        let mut bytes = [0; XPrv::SIZE];
+        bytes[..64].copy_from_slice(&i);
+        bytes[64..].copy_from_slice(&chain_code);
+
+        seed.scrub();
+        i.scrub();
+
+        XPrv::from_bytes(bytes).expect("tweaked bytes have a valid structure")
+    }
+}
+
+/* Drop ******************************************************************** */
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        self.0.scrub()
+    }
+}
+
+/* Format ****************************************************************** */
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.phrase())
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mnemonic").finish_non_exhaustive()
+    }
+}
+
+/* Conversion ************************************************************** */
+
+impl FromStr for Mnemonic {
+    type Err = Bip39Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test vectors from CIP-3
+    const ICARUS: &str =
+        "eight country switch draw meat scout mystery blade tip drift useless good keep usage title";
+    const LEDGER: &str = "recall grace sport punch exhibit mad harbor stand obey short width stem awkward used stairs wool ugly trap season stove worth toward congress jaguar";
+
+    fn root_hex(key: XPrv) -> String {
+        let bytes: [u8; XPrv::SIZE] = key.into();
+        hex::encode(bytes)
+    }
+
+    #[test]
+    fn entropy_roundtrip() {
+        let mnemonic = Mnemonic::parse(ICARUS).unwrap();
+        assert_eq!(
+            hex::encode(mnemonic.entropy()),
+            "46e62370a138a182a498b8e2885bc032379ddf38"
+        );
+        assert_eq!(mnemonic.to_string(), ICARUS);
+
+        let mnemonic = Mnemonic::from_entropy(&[0; 16]).unwrap();
+        assert_eq!(
+            mnemonic.phrase(),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+
+        let mnemonic = Mnemonic::from_entropy(&[0xff; 32]).unwrap();
+        assert_eq!(
+            mnemonic.phrase(),
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote"
+        );
+
+        let generated = Mnemonic::generate(rand::thread_rng(), 24).unwrap();
+        assert_eq!(Mnemonic::parse(&generated.phrase()).unwrap(), generated);
+    }
+
+    #[test]
+    fn invalid_phrases() {
+        let swapped = ICARUS.replace("eight country", "country eight");
+        assert_eq!(Mnemonic::parse(&swapped), Err(Bip39Error::InvalidChecksum));
+
+        let unknown = ICARUS.replace("eight", "cardano");
+        assert_eq!(
+            Mnemonic::parse(&unknown),
+            Err(Bip39Error::UnknownWord("cardano".into()))
+        );
+
+        assert_eq!(
+            Mnemonic::parse("abandon abandon about"),
+            Err(Bip39Error::InvalidWordCount(3))
+        );
+
+        assert_eq!(
+            Mnemonic::from_entropy(&[0; 15]),
+            Err(Bip39Error::InvalidEntropySize(15))
+        );
+    }
+
+    #[test]
+    fn bip39_seed() {
+        // test vector from the BIP39 reference implementation
+        let mnemonic = Mnemonic::from_entropy(&[0; 16]).unwrap();
+        assert_eq!(
+            hex::encode(mnemonic.to_seed("TREZOR")),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+
+    #[test]
+    fn icarus_root_key() {
+        let mnemonic = Mnemonic::parse(ICARUS).unwrap();
+
+        assert_eq!(
+            root_hex(mnemonic.to_icarus_root("")),
+            "c065afd2832cd8b087c4d9ab7011f481ee1e0721e78ea5dd609f3ab3f156d245d176bd8fd4ec60b4731c3918a2a72a0226c0cd119ec35b47e4d55884667f552a23f7fdcd4a10c6cd2c7393ac61d877873e248f417634aa3d812af327ffe9d620"
+        );
+
+        assert_eq!(
+            root_hex(mnemonic.to_icarus_root("foo")),
+            "70531039904019351e1afb361cd1b312a4d0565d4ff9f8062d38acf4b15cce41d7b5738d9c893feea55512a3004acb0d222c35d3e3d5cde943a15a9824cbac59443cf67e589614076ba01e354b1a432e0e6db3b59e37fc56b5fb0222970a010e"
+        );
+    }
+
+    #[test]
+    fn ledger_root_key() {
+        let mnemonic = Mnemonic::parse(LEDGER).unwrap();
+
+        assert_eq!(
+            root_hex(mnemonic.to_ledger_root("")),
+            "a08cf85b564ecf3b947d8d4321fb96d70ee7bb760877e371899b14e2ccf88658104b884682b57efd97decbb318a45c05a527b9cc5c2f64f7352935a049ceea60680d52308194ccef2a18e6812b452a5815fbd7f5babc083856919aaf668fe7e4"
+        );
+
+        assert_eq!(
+            root_hex(mnemonic.to_ledger_root("foo")),
+            "488b13cdf56ed4ced9b2bcd61924ca7a81d317810d4ddbbd8ab3e86431289a58e0e3f635ab873a97f046c77bca4203419125293c9ab6d97a252a07353fdfa05055209a1e929b4223692cbf6900d41e8b42d14449ede56dbab8c4c7fd981e139a"
+        );
+    }
+}
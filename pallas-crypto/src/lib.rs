@@ -1,3 +1,5 @@
 pub mod hash;
+pub mod kes;
 pub mod key;
 pub mod memsec;
+pub mod vrf;
@@ -0,0 +1,469 @@
+//! Verifiable Random Functions, as used by the Praos consensus protocol
+//!
+//! This module implements ECVRF-ED25519-SHA512-Elligator2 as described in
+//! [draft-irtf-cfrg-vrf-03](https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-vrf-03),
+//! which is the version used by the Cardano node (through its libsodium
+//! fork) to produce the leader and nonce values of the block headers.
+//!
+//! A [`Proof`] is generated by a [`SecretKey`] for a given input (`alpha`)
+//! and can be verified by anyone holding the associated [`PublicKey`]. The
+//! pseudo-random output of the function is obtained from the proof itself
+//! via [`Proof::to_hash`].
+
+use crate::hash::Hash;
+use crate::memsec::Scrubbed as _;
+use cryptoxide::hashing::sha512;
+use curve25519_dalek::{
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::{clamp_integer, Scalar},
+};
+use digest::{consts::U64, FixedOutput, HashMarker, Output, OutputSizeUser, Update};
+use rand_core::{CryptoRng, RngCore};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// The suite string of ECVRF-ED25519-SHA512-Elligator2
+const SUITE: u8 = 0x04;
+
+/// Error type used when verifying a VRF [`Proof`]
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VrfError {
+    #[error("Invalid size, expecting {0}")]
+    InvalidSize(usize),
+
+    #[error("Public key is not a valid curve point")]
+    InvalidPublicKey,
+
+    #[error("Proof is malformed")]
+    MalformedProof,
+
+    #[error("Proof verification failed")]
+    VerificationFailed,
+}
+
+/// VRF Secret Key, the same 32 bytes seed used by Ed25519
+#[derive(Clone)]
+pub struct SecretKey([u8; Self::SIZE]);
+
+/// VRF Public Key. Used to verify a [`Proof`] generated by the associated
+/// [`SecretKey`]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey([u8; Self::SIZE]);
+
+/// VRF Proof, the concatenation of the `Gamma` point, the `c` challenge and
+/// the `s` scalar
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Proof([u8; Self::SIZE]);
+
+impl SecretKey {
+    /// This is the size of the type in bytes.
+    pub const SIZE: usize = 32;
+
+    /// generate a new [`SecretKey`] with the given random number generator
+    pub fn new<Rng>(mut rng: Rng) -> Self
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let mut s = Self([0; Self::SIZE]);
+        rng.fill_bytes(&mut s.0);
+        s
+    }
+
+    /// expands the seed into the secret scalar and the nonce prefix, as in
+    /// Ed25519
+    fn expand(&self) -> (Scalar, [u8; 32]) {
+        let mut h = sha512(&self.0);
+
+        let mut x = [0; 32];
+        x.copy_from_slice(&h[..32]);
+        let x = Scalar::from_bytes_mod_order(clamp_integer(x));
+
+        let mut prefix = [0; 32];
+        prefix.copy_from_slice(&h[32..]);
+
+        h.scrub();
+
+        (x, prefix)
+    }
+
+    /// get the [`PublicKey`] associated to this key
+    pub fn public_key(&self) -> PublicKey {
+        let (x, _) = self.expand();
+        PublicKey(EdwardsPoint::mul_base(&x).compress().to_bytes())
+    }
+
+    /// create a [`Proof`] for the given input with this [`SecretKey`].
+    pub fn prove<T>(&self, alpha: T) -> Proof
+    where
+        T: AsRef<[u8]>,
+    {
+        let (x, mut prefix) = self.expand();
+        let pk = PublicKey(EdwardsPoint::mul_base(&x).compress().to_bytes());
+
+        let h = hash_to_curve(&pk, alpha.as_ref());
+        let h_string = h.compress().to_bytes();
+        let gamma = x * h;
+
+        let k = Scalar::from_bytes_mod_order_wide(&sha512(&[&prefix[..], &h_string].concat()));
+        prefix.scrub();
+
+        let c = hash_points(&h, &gamma, &EdwardsPoint::mul_base(&k), &(k * h));
+        let s = k + challenge_scalar(&c) * x;
+
+        let mut proof = [0; Proof::SIZE];
+        proof[..32].copy_from_slice(gamma.compress().as_bytes());
+        proof[32..48].copy_from_slice(&c);
+        proof[48..].copy_from_slice(s.as_bytes());
+
+        Proof(proof)
+    }
+}
+
+impl PublicKey {
+    /// This is the size of the type in bytes.
+    pub const SIZE: usize = 32;
+
+    fn decompress(&self) -> Result<EdwardsPoint, VrfError> {
+        let point = CompressedEdwardsY(self.0)
+            .decompress()
+            .ok_or(VrfError::InvalidPublicKey)?;
+
+        // reject non-canonical encodings and keys of small order, as done by
+        // the reference implementation
+        if point.compress().0 != self.0 || point.is_small_order() {
+            return Err(VrfError::InvalidPublicKey);
+        }
+
+        Ok(point)
+    }
+
+    /// verify the [`Proof`] against the input `alpha` and the [`PublicKey`]
+    /// `self`, returning the output of the VRF if successful.
+    pub fn verify<T>(&self, alpha: T, proof: &Proof) -> Result<Hash<64>, VrfError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let y = self.decompress()?;
+        let (gamma, c, s) = proof.decode()?;
+
+        let h = hash_to_curve(self, alpha.as_ref());
+
+        let c_scalar = challenge_scalar(&c);
+
+        // U = s*B - c*Y
+        let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c_scalar, &y, &s);
+
+        // V = s*H - c*Gamma
+        let v = s * h - c_scalar * gamma;
+
+        if hash_points(&h, &gamma, &u, &v) != c {
+            return Err(VrfError::VerificationFailed);
+        }
+
+        Ok(gamma_to_hash(&gamma))
+    }
+}
+
+impl Proof {
+    /// This is the size of the type in bytes.
+    pub const SIZE: usize = 80;
+
+    fn decode(&self) -> Result<(EdwardsPoint, [u8; 16], Scalar), VrfError> {
+        let mut gamma = [0; 32];
+        gamma.copy_from_slice(&self.0[..32]);
+
+        let gamma = CompressedEdwardsY(gamma)
+            .decompress()
+            .ok_or(VrfError::MalformedProof)?;
+
+        let mut c = [0; 16];
+        c.copy_from_slice(&self.0[32..48]);
+
+        let mut s = [0; 32];
+        s.copy_from_slice(&self.0[48..]);
+
+        let s = Option::<Scalar>::from(Scalar::from_canonical_bytes(s))
+            .ok_or(VrfError::MalformedProof)?;
+
+        Ok((gamma, c, s))
+    }
+
+    /// compute the VRF output from the proof.
+    ///
+    /// This doesn't check the validity of the proof, use
+    /// [`PublicKey::verify`] to get an output that can be trusted.
+    pub fn to_hash(&self) -> Result<Hash<64>, VrfError> {
+        let (gamma, _, _) = self.decode()?;
+        Ok(gamma_to_hash(&gamma))
+    }
+}
+
+/* Protocol **************************************************************** */
+
+/// Feeds an already computed uniform string to the Elligator2 map provided
+/// by dalek, which insists on hashing the input by itself.
+#[derive(Default)]
+struct Uniform(Vec<u8>);
+
+impl HashMarker for Uniform {}
+
+impl OutputSizeUser for Uniform {
+    type OutputSize = U64;
+}
+
+impl Update for Uniform {
+    fn update(&mut self, data: &[u8]) {
+        self.0.extend_from_slice(data);
+    }
+}
+
+impl FixedOutput for Uniform {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out[..self.0.len()].copy_from_slice(&self.0);
+    }
+}
+
+/// ECVRF_hash_to_curve_elligator2_25519 of the draft
+fn hash_to_curve(pk: &PublicKey, alpha: &[u8]) -> EdwardsPoint {
+    let hash = sha512(&[&[SUITE, 0x01], &pk.0[..], alpha].concat());
+
+    let mut r = [0; 32];
+    r.copy_from_slice(&hash[..32]);
+
+    // the sign bit is cleared so that the resulting point is always the one
+    // with a positive x coordinate
+    r[31] &= 0x7f;
+
+    #[allow(deprecated)]
+    EdwardsPoint::nonspec_map_to_curve::<Uniform>(&r)
+}
+
+/// ECVRF_hash_points of the draft, truncated to 16 bytes
+fn hash_points(
+    h: &EdwardsPoint,
+    gamma: &EdwardsPoint,
+    u: &EdwardsPoint,
+    v: &EdwardsPoint,
+) -> [u8; 16] {
+    let hash = sha512(
+        &[
+            &[SUITE, 0x02][..],
+            h.compress().as_bytes(),
+            gamma.compress().as_bytes(),
+            u.compress().as_bytes(),
+            v.compress().as_bytes(),
+        ]
+        .concat(),
+    );
+
+    let mut c = [0; 16];
+    c.copy_from_slice(&hash[..16]);
+    c
+}
+
+fn challenge_scalar(c: &[u8; 16]) -> Scalar {
+    let mut bytes = [0; 32];
+    bytes[..16].copy_from_slice(c);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+/// ECVRF_proof_to_hash of the draft
+fn gamma_to_hash(gamma: &EdwardsPoint) -> Hash<64> {
+    let cofactor_gamma = gamma.mul_by_cofactor();
+    let hash = sha512(&[&[SUITE, 0x03][..], cofactor_gamma.compress().as_bytes()].concat());
+
+    Hash::new(hash)
+}
+
+/* Drop ******************************************************************** */
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.scrub()
+    }
+}
+
+/* Format ****************************************************************** */
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.as_ref()))
+    }
+}
+
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.as_ref()))
+    }
+}
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PublicKey<Vrf>")
+            .field(&hex::encode(self.as_ref()))
+            .finish()
+    }
+}
+
+impl fmt::Debug for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Proof<Vrf>")
+            .field(&hex::encode(self.as_ref()))
+            .finish()
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey<Vrf>").finish_non_exhaustive()
+    }
+}
+
+/* AsRef ******************************************************************* */
+
+impl AsRef<[u8]> for PublicKey {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl AsRef<[u8]> for Proof {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/* Conversion ************************************************************** */
+
+impl From<[u8; Self::SIZE]> for SecretKey {
+    fn from(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<[u8; Self::SIZE]> for PublicKey {
+    fn from(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<PublicKey> for [u8; PublicKey::SIZE] {
+    fn from(pk: PublicKey) -> Self {
+        pk.0
+    }
+}
+
+impl From<[u8; Self::SIZE]> for Proof {
+    fn from(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Proof> for [u8; Proof::SIZE] {
+    fn from(proof: Proof) -> Self {
+        proof.0
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for PublicKey {
+    type Error = VrfError;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| VrfError::InvalidSize(Self::SIZE))?;
+
+        Ok(Self(bytes))
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Proof {
+    type Error = VrfError;
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| VrfError::InvalidSize(Self::SIZE))?;
+
+        Ok(Self(bytes))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = hex::FromHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = [0; Self::SIZE];
+        hex::decode_to_slice(s, &mut r)?;
+        Ok(Self(r))
+    }
+}
+
+impl FromStr for Proof {
+    type Err = hex::FromHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = [0; Self::SIZE];
+        hex::decode_to_slice(s, &mut r)?;
+        Ok(Self(r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test vector from the appendix of the draft (example 10)
+    const SK: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PK: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const PI: &str = "b6b4699f87d56126c9117a7da55bd0085246f4c56dbc95d20172612e9d38e8d7ca65e573a126ed88d4e30a46f80a666854d675cf3ba81de0de043c3774f061560f55edc256a787afe701677c0f602900";
+    const BETA: &str = "5b49b554d05c0cd5a5325376b3387de59d924fd1e13ded44648ab33c21349a603f25b84ec5ed887995b33da5e3bfcb87cd2f64521c4c62cf825cffabbe5d31cc";
+
+    fn secret_key() -> SecretKey {
+        let mut bytes = [0; SecretKey::SIZE];
+        hex::decode_to_slice(SK, &mut bytes).unwrap();
+        SecretKey::from(bytes)
+    }
+
+    #[test]
+    fn draft_test_vector() {
+        let sk = secret_key();
+        let pk: PublicKey = PK.parse().unwrap();
+        assert_eq!(sk.public_key(), pk);
+
+        let proof = sk.prove([]);
+        assert_eq!(proof.to_string(), PI);
+
+        let beta = pk.verify([], &proof).unwrap();
+        assert_eq!(hex::encode(beta), BETA);
+        assert_eq!(proof.to_hash().unwrap(), beta);
+    }
+
+    #[test]
+    fn prove_and_verify() {
+        let sk = SecretKey::new(rand::thread_rng());
+        let pk = sk.public_key();
+
+        let proof = sk.prove(b"hello world");
+        assert!(pk.verify(b"hello world", &proof).is_ok());
+
+        assert_eq!(
+            pk.verify(b"hello there", &proof),
+            Err(VrfError::VerificationFailed)
+        );
+
+        let other = SecretKey::new(rand::thread_rng()).public_key();
+        assert_eq!(
+            other.verify(b"hello world", &proof),
+            Err(VrfError::VerificationFailed)
+        );
+
+        let mut tampered: [u8; Proof::SIZE] = proof.into();
+        tampered[40] ^= 0x01;
+        assert!(pk.verify(b"hello world", &Proof::from(tampered)).is_err());
+    }
+
+    #[test]
+    fn rejects_small_order_keys() {
+        let identity = PublicKey::from(EdwardsPoint::default().compress().to_bytes());
+        let proof = secret_key().prove([]);
+
+        assert_eq!(identity.verify([], &proof), Err(VrfError::InvalidPublicKey));
+    }
+}
@@ -12,9 +12,9 @@ use crate::miniprotocols::handshake::{n2c, n2n, Confirmation, VersionNumber};
 use crate::miniprotocols::PROTOCOL_N2N_HANDSHAKE;
 use crate::{
     miniprotocols::{
-        blockfetch, chainsync, handshake, localstate, PROTOCOL_N2C_CHAIN_SYNC,
+        blockfetch, chainsync, handshake, localstate, txsubmission, PROTOCOL_N2C_CHAIN_SYNC,
         PROTOCOL_N2C_HANDSHAKE, PROTOCOL_N2C_STATE_QUERY, PROTOCOL_N2N_BLOCK_FETCH,
-        PROTOCOL_N2N_CHAIN_SYNC,
+        PROTOCOL_N2N_CHAIN_SYNC, PROTOCOL_N2N_TX_SUBMISSION,
     },
     multiplexer::{self, Bearer},
 };
@@ -37,6 +37,7 @@ pub struct PeerClient {
     pub handshake: handshake::Confirmation<handshake::n2n::VersionData>,
     pub chainsync: chainsync::N2NClient,
     pub blockfetch: blockfetch::Client,
+    pub txsubmission: txsubmission::Client,
 }
 
 impl PeerClient {
@@ -51,6 +52,7 @@ impl PeerClient {
         let channel0 = plexer.subscribe_client(0);
         let channel2 = plexer.subscribe_client(2);
         let channel3 = plexer.subscribe_client(3);
+        let channel4 = plexer.subscribe_client(4);
 
         let plexer_handle = tokio::spawn(async move { plexer.run().await });
 
@@ -72,6 +74,7 @@ impl PeerClient {
             handshake,
             chainsync: chainsync::Client::new(channel2),
             blockfetch: blockfetch::Client::new(channel3),
+            txsubmission: txsubmission::Client::new(channel4),
         })
     }
 
@@ -83,6 +86,10 @@ impl PeerClient {
         &mut self.blockfetch
     }
 
+    pub fn txsubmission(&mut self) -> &mut txsubmission::Client {
+        &mut self.txsubmission
+    }
+
     pub fn abort(&mut self) {
         self.plexer_handle.abort();
     }
@@ -94,6 +101,7 @@ pub struct PeerServer {
     pub version: (VersionNumber, n2n::VersionData),
     pub chainsync: chainsync::N2NServer,
     pub blockfetch: blockfetch::Server,
+    pub txsubmission: txsubmission::Server,
 }
 
 impl PeerServer {
@@ -107,10 +115,12 @@ impl PeerServer {
         let hs_channel = server_plexer.subscribe_server(PROTOCOL_N2N_HANDSHAKE);
         let cs_channel = server_plexer.subscribe_server(PROTOCOL_N2N_CHAIN_SYNC);
         let bf_channel = server_plexer.subscribe_server(PROTOCOL_N2N_BLOCK_FETCH);
+        let tx_channel = server_plexer.subscribe_server(PROTOCOL_N2N_TX_SUBMISSION);
 
         let mut server_hs: handshake::Server<n2n::VersionData> = handshake::Server::new(hs_channel);
         let server_cs = chainsync::N2NServer::new(cs_channel);
         let server_bf = blockfetch::Server::new(bf_channel);
+        let server_tx = txsubmission::Server::new(tx_channel);
 
         let plexer_handle = tokio::spawn(async move { server_plexer.run().await });
 
@@ -125,6 +135,7 @@ impl PeerServer {
                 version: ver,
                 chainsync: server_cs,
                 blockfetch: server_bf,
+                txsubmission: server_tx,
             })
         } else {
             plexer_handle.abort();
@@ -140,6 +151,10 @@ impl PeerServer {
         &mut self.blockfetch
     }
 
+    pub fn txsubmission(&mut self) -> &mut txsubmission::Server {
+        &mut self.txsubmission
+    }
+
     pub fn abort(&mut self) {
         self.plexer_handle.abort();
     }
@@ -2,8 +2,54 @@ use pallas_codec::minicbor::{decode, encode, Decode, Decoder, Encode, Encoder};
 
 use super::Query;
 
+/// Queries answered by the hard-fork combinator, valid in any era
 #[derive(Debug, Clone)]
-pub struct BlockQuery {}
+pub enum HardForkQuery {
+    /// Summary of the eras known by the node, required to convert between
+    /// slots, epochs and wall-clock time
+    GetInterpreter,
+    GetCurrentEra,
+}
+
+impl Encode<()> for HardForkQuery {
+    fn encode<W: encode::Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _ctx: &mut (),
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.array(1)?;
+
+        match self {
+            Self::GetInterpreter => e.u16(0)?,
+            Self::GetCurrentEra => e.u16(1)?,
+        };
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BlockQuery {
+    HardFork(HardForkQuery),
+}
+
+impl Encode<()> for BlockQuery {
+    fn encode<W: encode::Write>(
+        &self,
+        e: &mut Encoder<W>,
+        ctx: &mut (),
+    ) -> Result<(), encode::Error<W::Error>> {
+        match self {
+            Self::HardFork(x) => {
+                e.array(2)?;
+                e.u16(2)?;
+                e.encode_with(x, ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum RequestV10 {
@@ -17,11 +63,14 @@ impl Encode<()> for RequestV10 {
     fn encode<W: encode::Write>(
         &self,
         e: &mut Encoder<W>,
-        _ctx: &mut (),
+        ctx: &mut (),
     ) -> Result<(), encode::Error<W::Error>> {
         match self {
-            Self::BlockQuery(..) => {
-                todo!()
+            Self::BlockQuery(x) => {
+                e.array(2)?;
+                e.u16(0)?;
+                e.encode_with(x, ctx)?;
+                Ok(())
             }
             Self::GetSystemStart => {
                 e.u16(1)?;
@@ -48,6 +97,13 @@ impl<'b> Decode<'b, ()> for RequestV10 {
 #[derive(Debug, Clone)]
 pub struct GenericResponse(Vec<u8>);
 
+impl GenericResponse {
+    /// The raw CBOR of the response, to be decoded by the caller
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl Encode<()> for GenericResponse {
     fn encode<W: encode::Write>(
         &self,
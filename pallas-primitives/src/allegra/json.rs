@@ -0,0 +1,65 @@
+use serde_json::{json, Value};
+
+use super::{AuxiliaryData, PseudoBlock, TransactionBody, WitnessSet};
+use crate::alonzo::json::{block_txs, list, withdrawals};
+use crate::ToCanonicalJson;
+
+impl ToCanonicalJson for TransactionBody {
+    fn to_json(&self) -> Value {
+        json!({
+            "inputs": self.inputs.to_json(),
+            "outputs": self.outputs.to_json(),
+            "fee": self.fee,
+            "ttl": self.ttl,
+            "certificates": self.certificates.to_json(),
+            "withdrawals": self.withdrawals.as_ref().map(withdrawals),
+            "update": self.update.to_json(),
+            "auxiliaryDataHash": self.auxiliary_data_hash.to_json(),
+            "validityIntervalStart": self.validity_interval_start,
+        })
+    }
+}
+
+impl ToCanonicalJson for WitnessSet {
+    fn to_json(&self) -> Value {
+        json!({
+            "vkeys": list(&self.vkeywitness),
+            "nativeScripts": list(&self.native_script),
+            "bootstraps": list(&self.bootstrap_witness),
+        })
+    }
+}
+
+impl ToCanonicalJson for AuxiliaryData {
+    fn to_json(&self) -> Value {
+        match self {
+            AuxiliaryData::Shelley(x) => json!({
+                "metadata": x.to_json(),
+                "nativeScripts": [],
+                "plutusV1Scripts": [],
+            }),
+            AuxiliaryData::ShelleyMa(x) => x.to_json(),
+        }
+    }
+}
+
+impl<T1, T2, T3, T4> ToCanonicalJson for PseudoBlock<T1, T2, T3, T4>
+where
+    T1: ToCanonicalJson,
+    T2: ToCanonicalJson,
+    T3: ToCanonicalJson,
+    T4: ToCanonicalJson + Clone,
+{
+    fn to_json(&self) -> Value {
+        json!({
+            "header": self.header.to_json(),
+            "transactions": block_txs(
+                &self.transaction_bodies,
+                &self.transaction_witness_sets,
+                &self.auxiliary_data_set,
+                None,
+                |x| x.to_json(),
+            ),
+        })
+    }
+}
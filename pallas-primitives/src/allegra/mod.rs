@@ -0,0 +1,6 @@
+mod model;
+
+#[cfg(feature = "json")]
+pub mod json;
+
+pub use model::*;
@@ -0,0 +1,293 @@
+//! Ledger primitives and cbor codec for the Allegra era
+//!
+//! Handcrafted, idiomatic rust artifacts based on the [Allegra CDDL](https://github.com/input-output-hk/cardano-ledger/blob/master/eras/allegra/test-suite/cddl-files/allegra.cddl) file in IOHK repo.
+
+use serde::{Deserialize, Serialize};
+
+use pallas_codec::minicbor::{Decode, Encode};
+
+use pallas_codec::utils::{Bytes, KeepRaw, KeyValuePairs, MapLayout, MaybeIndefArray};
+
+// required for derive attrs to work
+use pallas_codec::minicbor;
+
+use crate::framework::map_codec;
+
+pub use crate::shelley::VrfCert;
+
+pub use crate::shelley::HeaderBody;
+
+pub use crate::shelley::ProtocolVersion;
+
+pub use crate::shelley::KesSignature;
+
+pub use crate::shelley::Header;
+
+pub use crate::shelley::TransactionInput;
+
+pub use crate::shelley::NonceVariant;
+
+pub use crate::shelley::Nonce;
+
+pub use crate::shelley::Coin;
+
+pub use crate::shelley::TransactionOutput;
+
+pub use crate::shelley::PoolKeyhash;
+
+pub use crate::shelley::Epoch;
+
+pub use crate::shelley::Genesishash;
+
+pub use crate::shelley::GenesisDelegateHash;
+
+pub use crate::shelley::VrfKeyhash;
+
+pub use crate::shelley::InstantaneousRewardSource;
+
+pub use crate::shelley::InstantaneousRewardTarget;
+
+pub use crate::shelley::MoveInstantaneousReward;
+
+pub use crate::shelley::RewardAccount;
+
+pub use crate::shelley::Withdrawals;
+
+pub use crate::shelley::Port;
+
+pub use crate::shelley::IPv4;
+
+pub use crate::shelley::IPv6;
+
+pub use crate::shelley::DnsName;
+
+pub use crate::shelley::Relay;
+
+pub use crate::shelley::PoolMetadataHash;
+
+pub use crate::shelley::PoolMetadata;
+
+pub use crate::shelley::AddrKeyhash;
+
+pub use crate::shelley::Scripthash;
+
+pub use crate::shelley::RationalNumber;
+
+pub use crate::shelley::UnitInterval;
+
+pub use crate::shelley::PositiveInterval;
+
+pub use crate::shelley::StakeCredential;
+
+pub use crate::shelley::Certificate;
+
+pub use crate::shelley::ProtocolParamUpdate;
+
+pub use crate::shelley::Update;
+
+pub use crate::shelley::VKeyWitness;
+
+pub use crate::shelley::BootstrapWitness;
+
+pub use crate::shelley::Metadatum;
+
+pub use crate::shelley::MetadatumLabel;
+
+pub use crate::shelley::Metadata;
+
+pub use crate::shelley::TransactionIndex;
+
+/// Timelock scripts, the multisig scripts of Shelley plus validity bounds
+pub use crate::alonzo::NativeScript;
+
+pub use crate::alonzo::ShelleyMaAuxiliaryData;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TransactionBody {
+    pub inputs: MaybeIndefArray<TransactionInput>,
+
+    pub outputs: MaybeIndefArray<TransactionOutput>,
+
+    pub fee: u64,
+
+    pub ttl: Option<u64>,
+
+    pub certificates: Option<MaybeIndefArray<Certificate>>,
+
+    pub withdrawals: Option<Withdrawals>,
+
+    pub update: Option<Update>,
+
+    pub auxiliary_data_hash: Option<Bytes>,
+
+    pub validity_interval_start: Option<u64>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(TransactionBody {
+    0 => inputs,
+    1 => outputs,
+    2 => fee,
+    3 => ttl,
+    4 => certificates,
+    5 => withdrawals,
+    6 => update,
+    7 => auxiliary_data_hash,
+    8 => validity_interval_start,
+});
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct WitnessSet {
+    pub vkeywitness: Option<MaybeIndefArray<VKeyWitness>>,
+
+    pub native_script: Option<MaybeIndefArray<NativeScript>>,
+
+    pub bootstrap_witness: Option<MaybeIndefArray<BootstrapWitness>>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(WitnessSet {
+    0 => vkeywitness,
+    1 => native_script,
+    2 => bootstrap_witness,
+});
+
+/// Auxiliary data of the Allegra and Mary eras
+///
+/// Unlike the Alonzo variant, there's no support for Plutus scripts; the data
+/// is either plain metadata (as in Shelley) or metadata plus native scripts.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum AuxiliaryData {
+    Shelley(Metadata),
+    ShelleyMa(ShelleyMaAuxiliaryData),
+}
+
+impl<'b, C> minicbor::Decode<'b, C> for AuxiliaryData {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        match d.datatype()? {
+            minicbor::data::Type::Map | minicbor::data::Type::MapIndef => {
+                Ok(AuxiliaryData::Shelley(d.decode_with(ctx)?))
+            }
+            minicbor::data::Type::Array => Ok(AuxiliaryData::ShelleyMa(d.decode_with(ctx)?)),
+            _ => Err(minicbor::decode::Error::message(
+                "Can't infer variant from data type for AuxiliaryData",
+            )),
+        }
+    }
+}
+
+impl<C> minicbor::Encode<C> for AuxiliaryData {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        match self {
+            AuxiliaryData::Shelley(m) => {
+                e.encode_with(m, ctx)?;
+            }
+            AuxiliaryData::ShelleyMa(m) => {
+                e.encode_with(m, ctx)?;
+            }
+        };
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+pub struct PseudoBlock<T1, T2, T3, T4>
+where
+    T4: std::clone::Clone,
+{
+    #[n(0)]
+    pub header: T1,
+
+    #[b(1)]
+    pub transaction_bodies: MaybeIndefArray<T2>,
+
+    #[n(2)]
+    pub transaction_witness_sets: MaybeIndefArray<T3>,
+
+    #[n(3)]
+    pub auxiliary_data_set: KeyValuePairs<TransactionIndex, T4>,
+}
+
+pub type Block = PseudoBlock<Header, TransactionBody, WitnessSet, AuxiliaryData>;
+
+/// A memory representation of an already minted block
+///
+/// This structure is analogous to [Block], but it allows to retrieve the
+/// original CBOR bytes for each structure that might require hashing. In this
+/// way, we make sure that the resulting hash matches what exists on-chain.
+pub type MintedBlock<'b> = PseudoBlock<
+    KeepRaw<'b, Header>,
+    KeepRaw<'b, TransactionBody>,
+    KeepRaw<'b, WitnessSet>,
+    KeepRaw<'b, AuxiliaryData>,
+>;
+
+impl<'b> From<MintedBlock<'b>> for Block {
+    fn from(x: MintedBlock<'b>) -> Self {
+        Block {
+            header: x.header.unwrap(),
+            transaction_bodies: x.transaction_bodies.map(|x| x.unwrap()),
+            transaction_witness_sets: x.transaction_witness_sets.map(|x| x.unwrap()),
+            auxiliary_data_set: x
+                .auxiliary_data_set
+                .to_vec()
+                .into_iter()
+                .map(|(k, v)| (k, v.unwrap()))
+                .collect::<Vec<_>>()
+                .into(),
+        }
+    }
+}
+
+pub use crate::shelley::PseudoTx;
+
+pub type Tx = PseudoTx<TransactionBody, WitnessSet, AuxiliaryData>;
+
+pub type MintedTx<'b> =
+    PseudoTx<KeepRaw<'b, TransactionBody>, KeepRaw<'b, WitnessSet>, KeepRaw<'b, AuxiliaryData>>;
+
+impl<'b> From<MintedTx<'b>> for Tx {
+    fn from(x: MintedTx<'b>) -> Self {
+        Tx {
+            transaction_body: x.transaction_body.unwrap(),
+            transaction_witness_set: x.transaction_witness_set.unwrap(),
+            auxiliary_data: x.auxiliary_data.map(|x| x.unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+
+    use super::MintedBlock;
+
+    type BlockWrapper<'b> = (u16, MintedBlock<'b>);
+
+    #[test]
+    fn block_isomorphic_decoding_encoding() {
+        let test_blocks = [include_str!("../../../test_data/allegra1.block")];
+
+        for (idx, block_str) in test_blocks.iter().enumerate() {
+            println!("decoding test block {}", idx + 1);
+            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {idx}"));
+
+            let block: BlockWrapper = minicbor::decode(&bytes[..])
+                .unwrap_or_else(|e| panic!("error decoding cbor for file {idx}: {e:?}"));
+
+            let bytes2 = minicbor::to_vec(block)
+                .unwrap_or_else(|e| panic!("error encoding block cbor for file {idx}: {e:?}"));
+
+            assert!(bytes.eq(&bytes2), "re-encoded bytes didn't match original");
+        }
+    }
+}
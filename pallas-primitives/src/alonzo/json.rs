@@ -1,8 +1,22 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::ops::Deref;
 
-use serde_json::json;
+use pallas_addresses::Address;
+use pallas_codec::minicbor;
+use pallas_codec::utils::{Int, KeyValuePairs};
+use serde_json::{json, Map, Value};
 
-use crate::ToCanonicalJson;
+use super::{
+    AuxiliaryData, BigInt, Block, BootstrapWitness, Certificate, Constr, CostMdls, ExUnitPrices,
+    ExUnits, Header, HeaderBody, InstantaneousRewardSource, InstantaneousRewardTarget, Language,
+    Metadata, Metadatum, MintedBlock, MintedTx, MintedWitnessSet, MoveInstantaneousReward,
+    Multiasset, NativeScript, NetworkId, Nonce, NonceVariant, PlutusData, PlutusScript,
+    PoolMetadata, PostAlonzoAuxiliaryData, ProtocolParamUpdate, RationalNumber, Redeemer,
+    RedeemerTag, Relay, ShelleyMaAuxiliaryData, StakeCredential, TransactionBody, TransactionIndex,
+    TransactionInput, TransactionOutput, Tx, Update, VKeyWitness, Value as LedgerValue, VrfCert,
+    WitnessSet,
+};
+use crate::{FromCanonicalJson, JsonError, ToCanonicalJson};
 
 impl<A> super::Constr<A> {
     pub fn constructor_value(&self) -> Option<u64> {
@@ -15,38 +29,650 @@ impl<A> super::Constr<A> {
     }
 }
 
+/// a JSON number out of its decimal digits, which can be of any size since
+/// serde_json is built with `arbitrary_precision`
+fn number(digits: &str) -> Value {
+    match digits.parse() {
+        Ok(x) => Value::Number(x),
+        Err(_) => unreachable!("decimal digits are a valid JSON number"),
+    }
+}
+
+/// an integer as a JSON number, even if it doesn't fit in 64 bits
+pub(crate) fn integer(n: i128) -> Value {
+    if let Ok(x) = i64::try_from(n) {
+        json!(x)
+    } else if let Ok(x) = u64::try_from(n) {
+        json!(x)
+    } else {
+        number(&n.to_string())
+    }
+}
+
+/// the decimal digits of a big-endian unsigned integer of arbitrary size
+fn decimal_digits(bytes: &[u8]) -> String {
+    let mut n: Vec<u8> = bytes.iter().copied().skip_while(|x| *x == 0).collect();
+    let mut digits = vec![];
+
+    while !n.is_empty() {
+        let mut rem = 0u16;
+
+        for byte in n.iter_mut() {
+            let current = (rem << 8) | *byte as u16;
+            *byte = (current / 10) as u8;
+            rem = current % 10;
+        }
+
+        digits.push(b'0' + rem as u8);
+        n = n.into_iter().skip_while(|x| *x == 0).collect();
+    }
+
+    if digits.is_empty() {
+        return "0".into();
+    }
+
+    digits.iter().rev().map(|x| *x as char).collect()
+}
+
+/// a CBOR bignum as a JSON integer, `-1 - n` for negative bignums
+fn big_integer(bytes: &[u8], negative: bool) -> Value {
+    let mut magnitude = bytes.to_vec();
+
+    if negative {
+        // add one to the big-endian magnitude, carrying into a new byte if needed
+        let carry = magnitude.iter_mut().rev().all(|x| {
+            let (sum, overflow) = x.overflowing_add(1);
+            *x = sum;
+            overflow
+        });
+
+        if carry {
+            magnitude.insert(0, 1);
+        }
+    }
+
+    let digits = decimal_digits(&magnitude);
+    let text = if negative {
+        format!("-{digits}")
+    } else {
+        digits
+    };
+
+    match text.parse::<i128>() {
+        Ok(n) => integer(n),
+        Err(_) => number(&text),
+    }
+}
+
+impl ToCanonicalJson for Int {
+    fn to_json(&self) -> Value {
+        integer(i128::from(*self.deref()))
+    }
+}
+
+/// an address in its bech32 form, or base58 for Byron addresses
+///
+/// Bytes that can't be parsed as an address are returned as hex.
+pub(crate) fn address(bytes: &[u8]) -> Value {
+    match Address::from_bytes(bytes) {
+        Ok(x) => json!(x.to_string()),
+        Err(_) => json!(hex::encode(bytes)),
+    }
+}
+
+/// a multiasset as a map of policy ids to a map of hex asset names to amounts
+pub(crate) fn multiasset<A>(assets: &Multiasset<A>) -> Map<String, Value>
+where
+    A: Into<i128> + Copy,
+{
+    assets
+        .iter()
+        .map(|(policy, names)| {
+            let names: Map<_, _> = names
+                .iter()
+                .map(|(name, amount)| (hex::encode(name.as_slice()), integer((*amount).into())))
+                .collect();
+
+            (policy.to_string(), Value::Object(names))
+        })
+        .collect()
+}
+
+pub(crate) fn withdrawals(withdrawals: &super::Withdrawals) -> Value {
+    let map: Map<_, _> = withdrawals
+        .iter()
+        .map(|(account, amount)| {
+            let key = match address(account) {
+                Value::String(x) => x,
+                _ => hex::encode(account.as_slice()),
+            };
+
+            (key, json!(u64::from(amount)))
+        })
+        .collect();
+
+    Value::Object(map)
+}
+
+/// a list of the witness set, empty if the list isn't present
+pub(crate) fn list<L: ToCanonicalJson>(items: &Option<L>) -> Value {
+    match items {
+        Some(x) => x.to_json(),
+        None => json!([]),
+    }
+}
+
+/// a Plutus script in the text envelope format of cardano-cli
+pub(crate) fn plutus_script(kind: &str, script: &[u8]) -> Value {
+    let mut cbor = minicbor::Encoder::new(Vec::new());
+    cbor.bytes(script).unwrap();
+
+    json!({
+        "type": kind,
+        "description": "",
+        "cborHex": hex::encode(cbor.into_writer()),
+    })
+}
+
+/// add a key to a JSON object only if the value is present
+pub(crate) fn put(map: &mut Map<String, Value>, key: &str, value: Option<Value>) {
+    if let Some(value) = value {
+        map.insert(key.into(), value);
+    }
+}
+
+/// the params shared by the protocol param updates of all the Shelley-based
+/// eras, using the names of the cardano-cli JSON
+macro_rules! common_param_updates {
+    ($map:expr, $x:expr) => {{
+        use $crate::alonzo::json::put;
+        use serde_json::json;
+
+        put($map, "txFeePerByte", $x.minfee_a.map(|v| json!(v)));
+        put($map, "txFeeFixed", $x.minfee_b.map(|v| json!(v)));
+        put($map, "maxBlockBodySize", $x.max_block_body_size.map(|v| json!(v)));
+        put($map, "maxTxSize", $x.max_transaction_size.map(|v| json!(v)));
+        put($map, "maxBlockHeaderSize", $x.max_block_header_size.map(|v| json!(v)));
+        put($map, "stakeAddressDeposit", $x.key_deposit.map(|v| json!(v)));
+        put($map, "stakePoolDeposit", $x.pool_deposit.map(|v| json!(v)));
+        put($map, "poolRetireMaxEpoch", $x.maximum_epoch.map(|v| json!(v)));
+        put($map, "stakePoolTargetNum", $x.desired_number_of_stake_pools.map(|v| json!(v)));
+        put($map, "poolPledgeInfluence", $x.pool_pledge_influence.as_ref().map(|v| $crate::ToCanonicalJson::to_json(v)));
+        put($map, "monetaryExpansion", $x.expansion_rate.as_ref().map(|v| $crate::ToCanonicalJson::to_json(v)));
+        put($map, "treasuryCut", $x.treasury_growth_rate.as_ref().map(|v| $crate::ToCanonicalJson::to_json(v)));
+        put(
+            $map,
+            "protocolVersion",
+            $x.protocol_version.map(|(major, minor)| json!({ "major": major, "minor": minor })),
+        );
+        put($map, "minPoolCost", $x.min_pool_cost.map(|v| json!(v)));
+    }};
+}
+
+pub(crate) use common_param_updates;
+
+/// a tx as found in a block or standalone
+///
+/// The validity flag is only present from Alonzo onwards.
+pub(crate) fn tx(body: Value, witnesses: Value, is_valid: Option<bool>, auxiliary: Value) -> Value {
+    let mut map = Map::new();
+
+    map.insert("body".into(), body);
+    map.insert("witnesses".into(), witnesses);
+    put(&mut map, "isValid", is_valid.map(Value::Bool));
+    map.insert("auxiliaryData".into(), auxiliary);
+
+    Value::Object(map)
+}
+
+/// the txs of a block, put together from its bodies, witness sets and
+/// auxiliary data
+///
+/// `invalid` is `None` for eras without phase-2 validation.
+pub(crate) fn block_txs<B, W, A>(
+    bodies: &[B],
+    witnesses: &[W],
+    auxiliary: &KeyValuePairs<TransactionIndex, A>,
+    invalid: Option<&[TransactionIndex]>,
+    auxiliary_json: impl Fn(&A) -> Value,
+) -> Value
+where
+    B: ToCanonicalJson,
+    W: ToCanonicalJson,
+    A: Clone,
+{
+    let txs: Vec<_> = bodies
+        .iter()
+        .zip(witnesses)
+        .enumerate()
+        .map(|(idx, (body, witness))| {
+            let idx = idx as TransactionIndex;
+
+            let aux = auxiliary
+                .iter()
+                .find(|(k, _)| *k == idx)
+                .map(|(_, v)| auxiliary_json(v))
+                .unwrap_or(Value::Null);
+
+            let is_valid = invalid.map(|x| !x.contains(&idx));
+
+            tx(body.to_json(), witness.to_json(), is_valid, aux)
+        })
+        .collect();
+
+    Value::Array(txs)
+}
+
+impl ToCanonicalJson for VrfCert {
+    fn to_json(&self) -> Value {
+        json!({
+            "output": hex::encode(self.0.as_slice()),
+            "proof": hex::encode(self.1.as_slice()),
+        })
+    }
+}
+
+impl ToCanonicalJson for HeaderBody {
+    fn to_json(&self) -> Value {
+        json!({
+            "blockNumber": self.block_number,
+            "slot": self.slot,
+            "prevHash": self.prev_hash.to_json(),
+            "issuerVkey": self.issuer_vkey.to_json(),
+            "vrfVkey": self.vrf_vkey.to_json(),
+            "nonceVrf": self.nonce_vrf.to_json(),
+            "leaderVrf": self.leader_vrf.to_json(),
+            "blockBodySize": self.block_body_size,
+            "blockBodyHash": self.block_body_hash.to_json(),
+            "operationalCert": {
+                "hotVkey": self.operational_cert_hot_vkey.to_json(),
+                "sequenceNumber": self.operational_cert_sequence_number,
+                "kesPeriod": self.operational_cert_kes_period,
+                "sigma": self.operational_cert_sigma.to_json(),
+            },
+            "protocolVersion": {
+                "major": self.protocol_major,
+                "minor": self.protocol_minor,
+            },
+        })
+    }
+}
+
+impl ToCanonicalJson for Header {
+    fn to_json(&self) -> Value {
+        json!({
+            "headerBody": self.header_body.to_json(),
+            "signature": self.body_signature.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for TransactionInput {
+    fn to_json(&self) -> Value {
+        json!(format!("{}#{}", self.transaction_id, self.index))
+    }
+}
+
+impl ToCanonicalJson for Nonce {
+    fn to_json(&self) -> Value {
+        match (&self.variant, &self.hash) {
+            (NonceVariant::Nonce, Some(hash)) => hash.to_json(),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl ToCanonicalJson for LedgerValue {
+    fn to_json(&self) -> Value {
+        match self {
+            LedgerValue::Coin(coin) => json!({ "lovelace": coin }),
+            LedgerValue::Multiasset(coin, assets) => {
+                let mut map = multiasset(assets);
+                map.insert("lovelace".into(), json!(coin));
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+impl ToCanonicalJson for TransactionOutput {
+    fn to_json(&self) -> Value {
+        json!({
+            "address": address(&self.address),
+            "value": self.amount.to_json(),
+            "datumhash": self.datum_hash.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for RationalNumber {
+    fn to_json(&self) -> Value {
+        json!(self.numerator as f64 / self.denominator as f64)
+    }
+}
+
+impl ToCanonicalJson for StakeCredential {
+    fn to_json(&self) -> Value {
+        match self {
+            StakeCredential::AddrKeyhash(x) => json!({ "keyHash": x.to_string() }),
+            StakeCredential::Scripthash(x) => json!({ "scriptHash": x.to_string() }),
+        }
+    }
+}
+
+impl ToCanonicalJson for Relay {
+    fn to_json(&self) -> Value {
+        match self {
+            Relay::SingleHostAddr(port, ipv4, ipv6) => {
+                let ipv4 = ipv4.as_ref().and_then(|x| {
+                    let octets = <[u8; 4]>::try_from(x.as_slice()).ok()?;
+                    Some(Ipv4Addr::from(octets).to_string())
+                });
+
+                // the ledger stores the address as four little-endian words
+                let ipv6 = ipv6.as_ref().and_then(|x| {
+                    let mut octets = <[u8; 16]>::try_from(x.as_slice()).ok()?;
+                    octets.chunks_mut(4).for_each(|w| w.reverse());
+                    Some(Ipv6Addr::from(octets).to_string())
+                });
+
+                json!({
+                    "single host address": {
+                        "IPv4": ipv4,
+                        "IPv6": ipv6,
+                        "port": port,
+                    }
+                })
+            }
+            Relay::SingleHostName(port, dns) => json!({
+                "single host name": {
+                    "dnsName": dns,
+                    "port": port,
+                }
+            }),
+            Relay::MultiHostName(dns) => json!({
+                "multi host name": {
+                    "dnsName": dns,
+                }
+            }),
+        }
+    }
+}
+
+impl ToCanonicalJson for PoolMetadata {
+    fn to_json(&self) -> Value {
+        json!({
+            "url": self.url,
+            "hash": self.hash.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for MoveInstantaneousReward {
+    fn to_json(&self) -> Value {
+        let pot = match self.source {
+            InstantaneousRewardSource::Reserves => "reserves",
+            InstantaneousRewardSource::Treasury => "treasury",
+        };
+
+        match &self.target {
+            InstantaneousRewardTarget::StakeCredentials(x) => {
+                let rewards: Vec<_> = x
+                    .iter()
+                    .map(|(credential, amount)| {
+                        json!({ "credential": credential.to_json(), "amount": amount })
+                    })
+                    .collect();
+
+                json!({ "pot": pot, "rewards": rewards })
+            }
+            InstantaneousRewardTarget::OtherAccountingPot(x) => {
+                json!({ "pot": pot, "otherPot": x })
+            }
+        }
+    }
+}
+
+impl ToCanonicalJson for Certificate {
+    fn to_json(&self) -> Value {
+        match self {
+            Certificate::StakeRegistration(x) => json!({
+                "type": "stakeRegistration",
+                "credential": x.to_json(),
+            }),
+            Certificate::StakeDeregistration(x) => json!({
+                "type": "stakeDeregistration",
+                "credential": x.to_json(),
+            }),
+            Certificate::StakeDelegation(credential, pool) => json!({
+                "type": "stakeDelegation",
+                "credential": credential.to_json(),
+                "pool": pool.to_json(),
+            }),
+            Certificate::PoolRegistration {
+                operator,
+                vrf_keyhash,
+                pledge,
+                cost,
+                margin,
+                reward_account,
+                pool_owners,
+                relays,
+                pool_metadata,
+            } => json!({
+                "type": "poolRegistration",
+                "publicKey": operator.to_json(),
+                "vrf": vrf_keyhash.to_json(),
+                "pledge": pledge,
+                "cost": cost,
+                "margin": margin.to_json(),
+                "rewardAccount": address(reward_account),
+                "owners": pool_owners.to_json(),
+                "relays": relays.to_json(),
+                "metadata": pool_metadata.to_json(),
+            }),
+            Certificate::PoolRetirement(pool, epoch) => json!({
+                "type": "poolRetirement",
+                "pool": pool.to_json(),
+                "epoch": epoch,
+            }),
+            Certificate::GenesisKeyDelegation(genesis, delegate, vrf) => json!({
+                "type": "genesisKeyDelegation",
+                "genesisHash": genesis.to_json(),
+                "delegateHash": delegate.to_json(),
+                "vrf": vrf.to_json(),
+            }),
+            Certificate::MoveInstantaneousRewardsCert(x) => {
+                let mut value = x.to_json();
+                value["type"] = json!("moveInstantaneousRewards");
+                value
+            }
+        }
+    }
+}
+
+impl ToCanonicalJson for NetworkId {
+    fn to_json(&self) -> Value {
+        match self {
+            NetworkId::One => json!("testnet"),
+            NetworkId::Two => json!("mainnet"),
+        }
+    }
+}
+
+impl ToCanonicalJson for CostMdls {
+    fn to_json(&self) -> Value {
+        let map: Map<_, _> = self
+            .iter()
+            .map(|(language, model)| match language {
+                Language::PlutusV1 => ("PlutusV1".to_string(), json!(model)),
+            })
+            .collect();
+
+        Value::Object(map)
+    }
+}
+
+impl ToCanonicalJson for ExUnits {
+    fn to_json(&self) -> Value {
+        json!({ "memory": self.mem, "steps": self.steps })
+    }
+}
+
+impl ToCanonicalJson for ExUnitPrices {
+    fn to_json(&self) -> Value {
+        json!({
+            "priceMemory": self.mem_price.to_json(),
+            "priceSteps": self.step_price.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for ProtocolParamUpdate {
+    fn to_json(&self) -> Value {
+        let mut map = Map::new();
+
+        common_param_updates!(&mut map, self);
+
+        let x = self;
+        put(
+            &mut map,
+            "decentralization",
+            x.decentralization_constant.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "extraPraosEntropy",
+            x.extra_entropy.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "utxoCostPerWord",
+            x.ada_per_utxo_byte.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "costModels",
+            x.cost_models_for_script_languages
+                .as_ref()
+                .map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "executionUnitPrices",
+            x.execution_costs.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "maxTxExecutionUnits",
+            x.max_tx_ex_units.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "maxBlockExecutionUnits",
+            x.max_block_ex_units.as_ref().map(|v| v.to_json()),
+        );
+        put(&mut map, "maxValueSize", x.max_value_size.map(|v| json!(v)));
+        put(
+            &mut map,
+            "collateralPercentage",
+            x.collateral_percentage.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "maxCollateralInputs",
+            x.max_collateral_inputs.map(|v| json!(v)),
+        );
+
+        Value::Object(map)
+    }
+}
+
+/// a protocol param update proposal, keyed by the hash of the genesis key
+/// proposing it
+pub(crate) fn update<P: ToCanonicalJson + Clone>(
+    proposals: &KeyValuePairs<super::Genesishash, P>,
+    epoch: u64,
+) -> Value {
+    let proposals: Map<_, _> = proposals
+        .iter()
+        .map(|(genesis, params)| (hex::encode(genesis.as_slice()), params.to_json()))
+        .collect();
+
+    json!({ "proposals": proposals, "epoch": epoch })
+}
+
+impl ToCanonicalJson for Update {
+    fn to_json(&self) -> Value {
+        update(&self.proposed_protocol_parameter_updates, self.epoch)
+    }
+}
+
+impl ToCanonicalJson for TransactionBody {
+    fn to_json(&self) -> Value {
+        json!({
+            "inputs": self.inputs.to_json(),
+            "outputs": self.outputs.to_json(),
+            "fee": self.fee,
+            "ttl": self.ttl,
+            "certificates": self.certificates.to_json(),
+            "withdrawals": self.withdrawals.as_ref().map(withdrawals),
+            "update": self.update.to_json(),
+            "auxiliaryDataHash": self.auxiliary_data_hash.to_json(),
+            "validityIntervalStart": self.validity_interval_start,
+            "mint": self.mint.as_ref().map(multiasset),
+            "scriptDataHash": self.script_data_hash.to_json(),
+            "collateral": self.collateral.to_json(),
+            "requiredSigners": self.required_signers.to_json(),
+            "networkId": self.network_id.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for VKeyWitness {
+    fn to_json(&self) -> Value {
+        json!({
+            "vkey": self.vkey.to_json(),
+            "signature": self.signature.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for BootstrapWitness {
+    fn to_json(&self) -> Value {
+        json!({
+            "publicKey": self.public_key.to_json(),
+            "signature": self.signature.to_json(),
+            "chainCode": self.chain_code.to_json(),
+            "attributes": self.attributes.to_json(),
+        })
+    }
+}
+
 // infered from https://github.com/input-output-hk/cardano-node/blob/c1efb2f97134c0607c982246a36e3da7266ac194/cardano-api/src/Cardano/Api/ScriptData.hs#L254
-impl ToCanonicalJson for super::PlutusData {
-    fn to_json(&self) -> serde_json::Value {
+impl ToCanonicalJson for PlutusData {
+    fn to_json(&self) -> Value {
         match self {
-            super::PlutusData::Constr(x) => {
+            PlutusData::Constr(x) => {
                 let fields: Vec<_> = x.fields.iter().map(|i| i.to_json()).collect();
                 json!({ "constructor": x.constructor_value(), "fields": fields })
             }
-            super::PlutusData::Map(x) => {
+            PlutusData::Map(x) => {
                 let map: Vec<_> = x
                     .iter()
                     .map(|(k, v)| json!({ "k": k.to_json(), "v": v.to_json() }))
                     .collect();
                 json!({ "map": map })
             }
-            super::PlutusData::BigInt(int) => match int {
-                super::BigInt::Int(n) => match i64::try_from(*n.deref()) {
-                    Ok(x) => json!({ "int": x }),
-                    Err(_) => {
-                        json!({ "bignint": hex::encode(i128::from(*n.deref()).to_be_bytes()) })
-                    }
-                },
-                // WARNING / TODO: the CDDL shows a bignum variants of arbitrary length expressed as
-                // bytes, but I can't find the corresponding mapping to JSON in the
-                // Haskell implementation. Not sure what I'm missing. For the time
-                // being, I'll invent a new JSON expression that uses hex strings as
-                // a way to express the values.
-                super::BigInt::BigUInt(x) => json!({ "biguint": hex::encode(x.as_slice())}),
-                super::BigInt::BigNInt(x) => json!({ "bignint": hex::encode(x.as_slice())}),
+            // bignums are plain integers for cardano-api, the ones that don't
+            // fit in a JSON number are written as a decimal string
+            PlutusData::BigInt(int) => match int {
+                BigInt::Int(n) => json!({ "int": n.to_json() }),
+                BigInt::BigUInt(x) => json!({ "int": big_integer(x, false) }),
+                BigInt::BigNInt(x) => json!({ "int": big_integer(x, true) }),
             },
-            super::PlutusData::BoundedBytes(x) => json!({ "bytes": hex::encode(x.as_slice())}),
-            super::PlutusData::Array(x) => {
+            PlutusData::BoundedBytes(x) => json!({ "bytes": hex::encode(x.as_slice())}),
+            PlutusData::Array(x) => {
                 let list: Vec<_> = x.iter().map(|i| i.to_json()).collect();
                 json!({ "list": list })
             }
@@ -54,35 +680,515 @@ impl ToCanonicalJson for super::PlutusData {
     }
 }
 
-impl ToCanonicalJson for super::NativeScript {
-    fn to_json(&self) -> serde_json::Value {
+impl ToCanonicalJson for NativeScript {
+    fn to_json(&self) -> Value {
         match self {
-            super::NativeScript::ScriptPubkey(x) => {
+            NativeScript::ScriptPubkey(x) => {
                 json!({ "keyHash": x.to_string(), "type": "sig"})
             }
-            super::NativeScript::ScriptAll(x) => {
+            NativeScript::ScriptAll(x) => {
                 let scripts: Vec<_> = x.iter().map(|i| i.to_json()).collect();
                 json!({ "type": "all", "scripts": scripts})
             }
-            super::NativeScript::ScriptAny(x) => {
+            NativeScript::ScriptAny(x) => {
                 let scripts: Vec<_> = x.iter().map(|i| i.to_json()).collect();
                 json!({ "type": "any", "scripts": scripts})
             }
-            super::NativeScript::ScriptNOfK(n, k) => {
+            NativeScript::ScriptNOfK(n, k) => {
                 let scripts: Vec<_> = k.iter().map(|i| i.to_json()).collect();
                 json!({ "type": "atLeast", "required": n, "scripts" : scripts })
             }
-            super::NativeScript::InvalidBefore(slot) => json!({ "type": "after", "slot": slot }),
-            super::NativeScript::InvalidHereafter(slot) => json!({"type": "before", "slot": slot }),
+            NativeScript::InvalidBefore(slot) => json!({ "type": "after", "slot": slot }),
+            NativeScript::InvalidHereafter(slot) => json!({"type": "before", "slot": slot }),
         }
     }
 }
 
+impl ToCanonicalJson for PlutusScript {
+    fn to_json(&self) -> Value {
+        plutus_script("PlutusScriptV1", &self.0)
+    }
+}
+
+impl ToCanonicalJson for Redeemer {
+    fn to_json(&self) -> Value {
+        let tag = match self.tag {
+            RedeemerTag::Spend => "spend",
+            RedeemerTag::Mint => "mint",
+            RedeemerTag::Cert => "cert",
+            RedeemerTag::Reward => "reward",
+        };
+
+        json!({
+            "tag": tag,
+            "index": self.index,
+            "data": self.data.to_json(),
+            "exUnits": self.ex_units.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for WitnessSet {
+    fn to_json(&self) -> Value {
+        json!({
+            "vkeys": list(&self.vkeywitness),
+            "nativeScripts": list(&self.native_script),
+            "bootstraps": list(&self.bootstrap_witness),
+            "plutusV1Scripts": list(&self.plutus_script),
+            "plutusData": list(&self.plutus_data),
+            "redeemers": list(&self.redeemer),
+        })
+    }
+}
+
+impl ToCanonicalJson for MintedWitnessSet<'_> {
+    fn to_json(&self) -> Value {
+        json!({
+            "vkeys": list(&self.vkeywitness),
+            "nativeScripts": list(&self.native_script),
+            "bootstraps": list(&self.bootstrap_witness),
+            "plutusV1Scripts": list(&self.plutus_script),
+            "plutusData": list(&self.plutus_data),
+            "redeemers": list(&self.redeemer),
+        })
+    }
+}
+
+// the "detailed schema" of cardano-cli, which round-trips any metadatum
+impl ToCanonicalJson for Metadatum {
+    fn to_json(&self) -> Value {
+        match self {
+            Metadatum::Int(x) => json!({ "int": x.to_json() }),
+            Metadatum::Bytes(x) => json!({ "bytes": x.to_json() }),
+            Metadatum::Text(x) => json!({ "string": x }),
+            Metadatum::Array(x) => json!({ "list": x.to_json() }),
+            Metadatum::Map(x) => {
+                let map: Vec<_> = x
+                    .iter()
+                    .map(|(k, v)| json!({ "k": k.to_json(), "v": v.to_json() }))
+                    .collect();
+                json!({ "map": map })
+            }
+        }
+    }
+}
+
+impl ToCanonicalJson for Metadata {
+    fn to_json(&self) -> Value {
+        let map: Map<_, _> = self
+            .iter()
+            .map(|(label, metadatum)| (label.to_string(), metadatum.to_json()))
+            .collect();
+
+        Value::Object(map)
+    }
+}
+
+impl ToCanonicalJson for ShelleyMaAuxiliaryData {
+    fn to_json(&self) -> Value {
+        json!({
+            "metadata": self.transaction_metadata.to_json(),
+            "nativeScripts": list(&self.auxiliary_scripts),
+            "plutusV1Scripts": [],
+        })
+    }
+}
+
+impl ToCanonicalJson for PostAlonzoAuxiliaryData {
+    fn to_json(&self) -> Value {
+        json!({
+            "metadata": self.metadata.to_json(),
+            "nativeScripts": list(&self.native_scripts),
+            "plutusV1Scripts": list(&self.plutus_scripts),
+        })
+    }
+}
+
+impl ToCanonicalJson for AuxiliaryData {
+    fn to_json(&self) -> Value {
+        match self {
+            AuxiliaryData::Shelley(x) => json!({
+                "metadata": x.to_json(),
+                "nativeScripts": [],
+                "plutusV1Scripts": [],
+            }),
+            AuxiliaryData::ShelleyMa(x) => x.to_json(),
+            AuxiliaryData::PostAlonzo(x) => x.to_json(),
+        }
+    }
+}
+
+impl ToCanonicalJson for Tx {
+    fn to_json(&self) -> Value {
+        tx(
+            self.transaction_body.to_json(),
+            self.transaction_witness_set.to_json(),
+            Some(self.success),
+            self.auxiliary_data.to_json(),
+        )
+    }
+}
+
+impl ToCanonicalJson for MintedTx<'_> {
+    fn to_json(&self) -> Value {
+        tx(
+            self.transaction_body.to_json(),
+            self.transaction_witness_set.to_json(),
+            Some(self.success),
+            self.auxiliary_data.to_json(),
+        )
+    }
+}
+
+impl ToCanonicalJson for Block {
+    fn to_json(&self) -> Value {
+        let invalid: Vec<_> = self
+            .invalid_transactions
+            .as_ref()
+            .map(|x| x.iter().copied().collect())
+            .unwrap_or_default();
+
+        json!({
+            "header": self.header.to_json(),
+            "transactions": block_txs(
+                &self.transaction_bodies,
+                &self.transaction_witness_sets,
+                &self.auxiliary_data_set,
+                Some(&invalid),
+                |x| x.to_json(),
+            ),
+        })
+    }
+}
+
+impl ToCanonicalJson for MintedBlock<'_> {
+    fn to_json(&self) -> Value {
+        let invalid: Vec<_> = self
+            .invalid_transactions
+            .as_ref()
+            .map(|x| x.iter().copied().collect())
+            .unwrap_or_default();
+
+        json!({
+            "header": self.header.to_json(),
+            "transactions": block_txs(
+                &self.transaction_bodies,
+                &self.transaction_witness_sets,
+                &self.auxiliary_data_set,
+                Some(&invalid),
+                |x| x.to_json(),
+            ),
+        })
+    }
+}
+
+/// The schemas of cardano-cli for tx metadata as JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataJsonSchema {
+    /// plain JSON values, with `0x` prefixed strings as bytes
+    NoSchema,
+
+    /// every value tagged with its type, the same as Plutus data
+    DetailedSchema,
+}
+
+const MAX_BYTES_LEN: usize = 64;
+
+fn unexpected(expected: &str, value: &Value) -> JsonError {
+    JsonError::UnexpectedValue(format!("expected {expected}, found {value}"))
+}
+
+/// the single key of an object, as used by the detailed schema to tag values
+fn single_entry(value: &Value) -> Result<(&str, &Value), JsonError> {
+    match value.as_object() {
+        Some(map) if map.len() == 1 => {
+            let (k, v) = map.iter().next().unwrap();
+            Ok((k.as_str(), v))
+        }
+        _ => Err(unexpected("an object with a single key", value)),
+    }
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>, JsonError> {
+    value
+        .as_array()
+        .ok_or_else(|| unexpected("an array", value))
+}
+
+fn hex_bytes(text: &str) -> Result<Vec<u8>, JsonError> {
+    let bytes = hex::decode(text).map_err(|_| JsonError::InvalidHex(text.into()))?;
+
+    match bytes.len() {
+        len if len > MAX_BYTES_LEN => Err(JsonError::BytesTooLong(len)),
+        _ => Ok(bytes),
+    }
+}
+
+fn text(text: &str) -> Result<String, JsonError> {
+    match text.len() {
+        len if len > MAX_BYTES_LEN => Err(JsonError::TextTooLong(len)),
+        _ => Ok(text.into()),
+    }
+}
+
+/// the big-endian bytes of a number given by its decimal digits
+fn decimal_bytes(digits: &str) -> Option<Vec<u8>> {
+    if digits.is_empty() || !digits.bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut bytes: Vec<u8> = vec![];
+
+    for digit in digits.bytes() {
+        let mut carry = (digit - b'0') as u16;
+
+        for byte in bytes.iter_mut().rev() {
+            let current = *byte as u16 * 10 + carry;
+            *byte = current as u8;
+            carry = current >> 8;
+        }
+
+        if carry > 0 {
+            bytes.insert(0, carry as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+/// an integer given as a JSON number of any size or as a string of decimal
+/// digits
+fn json_integer(value: &Value) -> Result<BigInt, JsonError> {
+    let text = match value {
+        Value::Number(n) => n.as_str(),
+        Value::String(x) => x.as_str(),
+        _ => return Err(unexpected("an integer", value)),
+    };
+
+    if let Some(x) = text
+        .parse::<i128>()
+        .ok()
+        .and_then(|x| Int::try_from(x).ok())
+    {
+        return Ok(BigInt::Int(x));
+    }
+
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+
+    let mut magnitude = decimal_bytes(digits).ok_or_else(|| unexpected("an integer", value))?;
+
+    if !negative {
+        return Ok(BigInt::BigUInt(magnitude.into()));
+    }
+
+    // negative bignums hold `-1 - n`, which is the magnitude minus one
+    for byte in magnitude.iter_mut().rev() {
+        let (x, borrow) = byte.overflowing_sub(1);
+        *byte = x;
+
+        if !borrow {
+            break;
+        }
+    }
+
+    let magnitude: Vec<_> = magnitude.into_iter().skip_while(|x| *x == 0).collect();
+
+    Ok(BigInt::BigNInt(magnitude.into()))
+}
+
+fn constr(constructor: u64, fields: Vec<PlutusData>) -> Constr<PlutusData> {
+    let (tag, any_constructor) = match constructor {
+        0..=6 => (121 + constructor, None),
+        7..=127 => (1280 + constructor - 7, None),
+        _ => (102, Some(constructor)),
+    };
+
+    Constr {
+        tag,
+        any_constructor,
+        fields,
+    }
+}
+
+/// the entries of a `map` of the detailed schema
+fn detailed_map<T>(
+    value: &Value,
+    parse: impl Fn(&Value) -> Result<T, JsonError>,
+) -> Result<Vec<(T, T)>, JsonError> {
+    array(value)?
+        .iter()
+        .map(|entry| match entry.as_object() {
+            Some(x) if x.len() == 2 && x.contains_key("k") && x.contains_key("v") => {
+                Ok((parse(&x["k"])?, parse(&x["v"])?))
+            }
+            _ => Err(unexpected("an object with keys `k` and `v`", entry)),
+        })
+        .collect()
+}
+
+// the "detailed schema" of cardano-api, inverse of the `ToCanonicalJson` impl
+impl FromCanonicalJson for PlutusData {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        let Some(map) = value.as_object() else {
+            return Err(unexpected("an object", value));
+        };
+
+        if map.len() == 2 && map.contains_key("constructor") && map.contains_key("fields") {
+            let constructor = map["constructor"]
+                .as_u64()
+                .ok_or_else(|| unexpected("a constructor index", &map["constructor"]))?;
+
+            let fields = array(&map["fields"])?
+                .iter()
+                .map(PlutusData::from_json)
+                .collect::<Result<_, _>>()?;
+
+            return Ok(PlutusData::Constr(constr(constructor, fields)));
+        }
+
+        match single_entry(value)? {
+            ("int", x) => Ok(PlutusData::BigInt(json_integer(x)?)),
+            ("bytes", Value::String(x)) => Ok(PlutusData::BoundedBytes(hex_bytes(x)?.into())),
+            ("list", x) => {
+                let items = array(x)?
+                    .iter()
+                    .map(PlutusData::from_json)
+                    .collect::<Result<_, _>>()?;
+
+                Ok(PlutusData::Array(items))
+            }
+            ("map", x) => Ok(PlutusData::Map(KeyValuePairs::Def(detailed_map(
+                x,
+                PlutusData::from_json,
+            )?))),
+            _ => Err(unexpected("Plutus data in the detailed schema", value)),
+        }
+    }
+}
+
+fn metadata_int(value: &Value) -> Result<Int, JsonError> {
+    match json_integer(value)? {
+        BigInt::Int(x) => Ok(x),
+        _ => Err(JsonError::IntegerOutOfRange(value.to_string())),
+    }
+}
+
+/// a string of the no schema, which is bytes if it's `0x` prefixed hex
+fn no_schema_string(value: &str) -> Result<Metadatum, JsonError> {
+    match value.strip_prefix("0x").and_then(|x| hex::decode(x).ok()) {
+        Some(bytes) if bytes.len() > MAX_BYTES_LEN => Err(JsonError::BytesTooLong(bytes.len())),
+        Some(bytes) => Ok(Metadatum::Bytes(bytes.into())),
+        None => Ok(Metadatum::Text(text(value)?)),
+    }
+}
+
+/// object keys of the no schema are ints if they're made of digits, and
+/// strings otherwise
+fn no_schema_key(key: &str) -> Result<Metadatum, JsonError> {
+    let digits = key.strip_prefix('-').unwrap_or(key);
+
+    if !digits.is_empty() && digits.bytes().all(|x| x.is_ascii_digit()) {
+        return Ok(Metadatum::Int(metadata_int(&Value::String(key.into()))?));
+    }
+
+    no_schema_string(key)
+}
+
+/// parse a single metadatum in one of the JSON schemas of cardano-cli
+pub fn metadatum_from_json(
+    value: &Value,
+    schema: MetadataJsonSchema,
+) -> Result<Metadatum, JsonError> {
+    match schema {
+        MetadataJsonSchema::NoSchema => match value {
+            Value::Number(_) => Ok(Metadatum::Int(metadata_int(value)?)),
+            Value::String(x) => no_schema_string(x),
+            Value::Array(x) => {
+                let items = x
+                    .iter()
+                    .map(|x| metadatum_from_json(x, schema))
+                    .collect::<Result<_, _>>()?;
+
+                Ok(Metadatum::Array(items))
+            }
+            Value::Object(x) => {
+                let entries = x
+                    .iter()
+                    .map(|(k, v)| Ok((no_schema_key(k)?, metadatum_from_json(v, schema)?)))
+                    .collect::<Result<_, JsonError>>()?;
+
+                Ok(Metadatum::Map(KeyValuePairs::Def(entries)))
+            }
+            _ => Err(unexpected("a number, string, array or object", value)),
+        },
+        MetadataJsonSchema::DetailedSchema => match single_entry(value)? {
+            ("int", x) => Ok(Metadatum::Int(metadata_int(x)?)),
+            ("bytes", Value::String(x)) => Ok(Metadatum::Bytes(hex_bytes(x)?.into())),
+            ("string", Value::String(x)) => Ok(Metadatum::Text(text(x)?)),
+            ("list", x) => {
+                let items = array(x)?
+                    .iter()
+                    .map(|x| metadatum_from_json(x, schema))
+                    .collect::<Result<_, _>>()?;
+
+                Ok(Metadatum::Array(items))
+            }
+            ("map", x) => Ok(Metadatum::Map(KeyValuePairs::Def(detailed_map(x, |x| {
+                metadatum_from_json(x, schema)
+            })?))),
+            _ => Err(unexpected("a metadatum in the detailed schema", value)),
+        },
+    }
+}
+
+/// parse the metadata of a tx, an object with labels as keys, in one of the
+/// JSON schemas of cardano-cli
+pub fn metadata_from_json(
+    value: &Value,
+    schema: MetadataJsonSchema,
+) -> Result<Metadata, JsonError> {
+    let Some(map) = value.as_object() else {
+        return Err(unexpected("an object", value));
+    };
+
+    let entries = map
+        .iter()
+        .map(|(label, metadatum)| {
+            let label = label
+                .parse::<u64>()
+                .map_err(|_| JsonError::InvalidKey(label.clone()))?;
+
+            Ok((label, metadatum_from_json(metadatum, schema)?))
+        })
+        .collect::<Result<_, JsonError>>()?;
+
+    Ok(KeyValuePairs::Def(entries))
+}
+
+impl FromCanonicalJson for Metadatum {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        metadatum_from_json(value, MetadataJsonSchema::DetailedSchema)
+    }
+}
+
+impl FromCanonicalJson for Metadata {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        metadata_from_json(value, MetadataJsonSchema::DetailedSchema)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use pallas_codec::minicbor;
 
-    use crate::{alonzo::Block, ToCanonicalJson};
+    use serde_json::json;
+
+    use super::{address, big_integer, metadata_from_json, MetadataJsonSchema};
+    use crate::alonzo::{BigInt, Block, Metadatum, MintedBlock, PlutusData, Relay};
+    use crate::{FromCanonicalJson, JsonError, ToCanonicalJson};
 
     type BlockWrapper = (u16, Block);
 
@@ -145,4 +1251,253 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_big_integers_serialize_as_numbers() {
+        assert_eq!(big_integer(&[0x05], false), serde_json::json!(5));
+        assert_eq!(big_integer(&[0x05], true), serde_json::json!(-6));
+        assert_eq!(big_integer(&[], false), serde_json::json!(0));
+
+        assert_eq!(big_integer(&[0xff; 8], false), serde_json::json!(u64::MAX));
+
+        // -1 - (2^64 - 1) doesn't fit in 64 bits
+        let value = big_integer(&[0xff; 8], true);
+        assert!(value.is_number());
+        assert_eq!(value.to_string(), "-18446744073709551616");
+
+        let value = big_integer(&[0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], false);
+        assert!(value.is_number());
+        assert_eq!(value.to_string(), "79228162514264337593543950336");
+    }
+
+    #[test]
+    fn test_addresses_serialize_as_bech32() {
+        let mut bytes = vec![0x01];
+        bytes.extend([1; 28]);
+        bytes.extend([2; 28]);
+
+        let serde_json::Value::String(bech32) = address(&bytes) else {
+            unreachable!()
+        };
+
+        assert!(bech32.starts_with("addr1"));
+
+        let (hrp, data, _) = bech32::decode(&bech32).unwrap();
+        assert_eq!(hrp, "addr");
+        assert_eq!(bech32::FromBase32::from_base32(&data), Ok(bytes));
+
+        let mut reward = vec![0xe0];
+        reward.extend([7; 28]);
+        assert!(address(&reward)
+            .as_str()
+            .unwrap()
+            .starts_with("stake_test1"));
+    }
+
+    #[test]
+    fn test_relays_serialize_as_expected() {
+        let relay = Relay::SingleHostAddr(
+            Some(3001),
+            Some(vec![192, 168, 0, 1].into()),
+            Some(
+                hex::decode("b80d0120000000000000000001000000")
+                    .unwrap()
+                    .into(),
+            ),
+        );
+
+        assert_eq!(
+            relay.to_json(),
+            serde_json::json!({
+                "single host address": {
+                    "IPv4": "192.168.0.1",
+                    "IPv6": "2001:db8::1",
+                    "port": 3001,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_blocks_serialize_as_expected() {
+        let bytes = hex::decode(include_str!("../../../test_data/alonzo9.block")).unwrap();
+        let (_, block): (u16, MintedBlock) = minicbor::decode(&bytes[..]).unwrap();
+
+        let json = block.to_json();
+
+        assert_eq!(
+            json["header"]["headerBody"]["blockNumber"],
+            block.header.header_body.block_number
+        );
+
+        let txs = json["transactions"].as_array().unwrap();
+        assert_eq!(txs.len(), block.transaction_bodies.len());
+
+        for (tx, body) in txs.iter().zip(block.transaction_bodies.iter()) {
+            assert_eq!(tx["body"]["fee"], body.fee);
+            assert_eq!(tx["isValid"], true);
+
+            let input = &body.inputs[0];
+            assert_eq!(
+                tx["body"]["inputs"][0],
+                format!("{}#{}", input.transaction_id, input.index)
+            );
+
+            for output in tx["body"]["outputs"].as_array().unwrap() {
+                let address = output["address"].as_str().unwrap();
+                // bech32 for Shelley addresses, base58 for Byron ones
+                assert!(
+                    ["addr1", "Ae2", "Ddz"]
+                        .iter()
+                        .any(|x| address.starts_with(x)),
+                    "{address}"
+                );
+                assert!(output["value"]["lovelace"].is_u64());
+            }
+        }
+
+        // the owned and minted versions of a block have the same JSON
+        let owned = Block::from(block);
+        assert_eq!(owned.to_json(), json);
+    }
+
+    #[test]
+    fn test_datums_parse_as_expected() {
+        for line in include_str!("../../../test_data/alonzo9.datums").lines() {
+            let expected: serde_json::Value = serde_json::from_str(line).unwrap();
+            let datum = PlutusData::from_json(&expected).unwrap();
+            assert_eq!(datum.to_json(), expected);
+        }
+
+        // big integers can be given as numbers or as strings of digits
+        let json: serde_json::Value = serde_json::from_str(
+            r#"{
+                "constructor": 200,
+                "fields": [
+                    { "int": "-18446744073709551617" },
+                    { "int": 340282366920938463463374607431768211456 },
+                    { "map": [{ "k": { "bytes": "cafe" }, "v": { "list": [] } }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let datum = PlutusData::from_json(&json).unwrap();
+
+        let PlutusData::Constr(constr) = &datum else {
+            unreachable!()
+        };
+
+        assert_eq!(constr.constructor_value(), Some(200));
+
+        // -1 - (2^64) needs a bignum, whose payload is 2^64
+        assert_eq!(
+            constr.fields[0],
+            PlutusData::BigInt(BigInt::BigNInt(vec![1, 0, 0, 0, 0, 0, 0, 0, 0].into()))
+        );
+
+        assert_eq!(datum.to_json()["fields"][1], json["fields"][1]);
+        assert_eq!(
+            datum.to_json()["fields"][0].to_string(),
+            r#"{"int":-18446744073709551617}"#
+        );
+    }
+
+    #[test]
+    fn test_invalid_datums_are_rejected() {
+        let parse = |x| PlutusData::from_json(&x);
+
+        assert_eq!(
+            parse(json!({ "bytes": "aa".repeat(65) })),
+            Err(JsonError::BytesTooLong(65))
+        );
+        assert!(matches!(
+            parse(json!({ "bytes": "xyz" })),
+            Err(JsonError::InvalidHex(_))
+        ));
+        assert!(matches!(
+            parse(json!({ "int": 1.5 })),
+            Err(JsonError::UnexpectedValue(_))
+        ));
+        assert!(matches!(
+            parse(json!({ "int": 1, "bytes": "" })),
+            Err(JsonError::UnexpectedValue(_))
+        ));
+        assert!(matches!(
+            parse(json!({ "map": [{ "k": { "int": 1 } }] })),
+            Err(JsonError::UnexpectedValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_metadata_parses_as_expected() {
+        let no_schema = json!({
+            "674": {
+                "msg": ["hello", "0xcafe", 42],
+                "7": -1,
+            }
+        });
+
+        let metadata = metadata_from_json(&no_schema, MetadataJsonSchema::NoSchema).unwrap();
+        let (label, metadatum) = &metadata[0];
+        assert_eq!(*label, 674);
+
+        let Metadatum::Map(entries) = metadatum else {
+            unreachable!()
+        };
+
+        assert_eq!(entries[0].0, Metadatum::Int(7.into()));
+        assert_eq!(entries[0].1, Metadatum::Int((-1).into()));
+        assert_eq!(entries[1].0, Metadatum::Text("msg".into()));
+        assert_eq!(
+            entries[1].1,
+            Metadatum::Array(vec![
+                Metadatum::Text("hello".into()),
+                Metadatum::Bytes(vec![0xca, 0xfe].into()),
+                Metadatum::Int(42.into()),
+            ])
+        );
+
+        // the detailed schema round-trips through the JSON output
+        let detailed = metadata.to_json();
+        assert_eq!(
+            metadata_from_json(&detailed, MetadataJsonSchema::DetailedSchema)
+                .unwrap()
+                .to_json(),
+            detailed
+        );
+    }
+
+    #[test]
+    fn test_invalid_metadata_is_rejected() {
+        let no_schema = |x| metadata_from_json(&x, MetadataJsonSchema::NoSchema);
+        let detailed = |x| metadata_from_json(&x, MetadataJsonSchema::DetailedSchema);
+
+        assert_eq!(
+            no_schema(json!({ "label": 1 })),
+            Err(JsonError::InvalidKey("label".into()))
+        );
+        assert_eq!(
+            no_schema(json!({ "1": "a".repeat(65) })),
+            Err(JsonError::TextTooLong(65))
+        );
+        assert_eq!(
+            no_schema(json!({ "1": format!("0x{}", "00".repeat(65)) })),
+            Err(JsonError::BytesTooLong(65))
+        );
+        assert!(matches!(
+            no_schema(json!({ "1": true })),
+            Err(JsonError::UnexpectedValue(_))
+        ));
+
+        // metadata ints can't be bignums
+        assert!(matches!(
+            detailed(json!({ "1": { "int": "18446744073709551616" } })),
+            Err(JsonError::IntegerOutOfRange(_))
+        ));
+        assert!(matches!(
+            detailed(json!({ "1": { "string": 1 } })),
+            Err(JsonError::UnexpectedValue(_))
+        ));
+    }
 }
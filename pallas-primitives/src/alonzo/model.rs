@@ -8,11 +8,15 @@ use std::{fmt, ops::Deref};
 use pallas_codec::minicbor::{data::Tag, Decode, Encode};
 use pallas_crypto::hash::Hash;
 
-use pallas_codec::utils::{Bytes, Int, KeepRaw, KeyValuePairs, MaybeIndefArray, Nullable};
+use pallas_codec::utils::{
+    AnyUInt, ArrayLayout, Bytes, Int, KeepRaw, KeyValuePairs, MapLayout, MaybeIndefArray, Nullable,
+};
 
 // required for derive attrs to work
 use pallas_codec::minicbor;
 
+use crate::framework::map_codec;
+
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
 pub struct VrfCert(#[n(0)] pub Bytes, #[n(1)] pub Bytes);
 
@@ -78,13 +82,42 @@ pub struct Header {
     pub body_signature: Bytes,
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct TransactionInput {
-    #[n(0)]
     pub transaction_id: Hash<32>,
 
-    #[n(1)]
     pub index: u64,
+
+    /// some txs on chain encode their inputs as indef arrays
+    #[serde(skip)]
+    pub layout: ArrayLayout,
+}
+
+impl<'b, C> minicbor::Decode<'b, C> for TransactionInput {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let ((transaction_id, index), layout) =
+            ArrayLayout::decode_items(d, |d| Ok((d.decode_with(ctx)?, d.decode_with(ctx)?)))?;
+
+        Ok(TransactionInput {
+            transaction_id,
+            index,
+            layout,
+        })
+    }
+}
+
+impl<C> minicbor::Encode<C> for TransactionInput {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        self.layout.encode_items(e, 2, |e| {
+            e.encode_with(self.transaction_id, ctx)?;
+            e.encode_with(self.index, ctx)?;
+            Ok(())
+        })
+    }
 }
 
 // $nonce /= [ 0 // 1, bytes .size 32 ]
@@ -285,9 +318,11 @@ pub struct MoveInstantaneousReward {
 
 pub type RewardAccount = Bytes;
 
-pub type Withdrawals = KeyValuePairs<RewardAccount, Coin>;
+/// Withdrawal amounts keep the width of their original encoding, since it's
+/// not always the shortest one
+pub type Withdrawals = KeyValuePairs<RewardAccount, AnyUInt>;
 
-pub type RequiredSigners = Vec<AddrKeyhash>;
+pub type RequiredSigners = MaybeIndefArray<AddrKeyhash>;
 
 pub type Port = u32;
 pub type IPv4 = Bytes;
@@ -647,58 +682,63 @@ pub type CostModel = Vec<i64>;
 
 pub type CostMdls = KeyValuePairs<Language, CostModel>;
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
-#[cbor(map)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ProtocolParamUpdate {
-    #[n(0)]
     pub minfee_a: Option<u32>,
-    #[n(1)]
     pub minfee_b: Option<u32>,
-    #[n(2)]
     pub max_block_body_size: Option<u32>,
-    #[n(3)]
     pub max_transaction_size: Option<u32>,
-    #[n(4)]
     pub max_block_header_size: Option<u32>,
-    #[n(5)]
     pub key_deposit: Option<Coin>,
-    #[n(6)]
     pub pool_deposit: Option<Coin>,
-    #[n(7)]
     pub maximum_epoch: Option<Epoch>,
-    #[n(8)]
     pub desired_number_of_stake_pools: Option<u32>,
-    #[n(9)]
     pub pool_pledge_influence: Option<RationalNumber>,
-    #[n(10)]
     pub expansion_rate: Option<UnitInterval>,
-    #[n(11)]
     pub treasury_growth_rate: Option<UnitInterval>,
-    #[n(12)]
     pub decentralization_constant: Option<UnitInterval>,
-    #[n(13)]
     pub extra_entropy: Option<Nonce>,
-    #[n(14)]
     pub protocol_version: Option<ProtocolVersion>,
-    #[n(16)]
     pub min_pool_cost: Option<Coin>,
-    #[n(17)]
     pub ada_per_utxo_byte: Option<Coin>,
-    #[n(18)]
     pub cost_models_for_script_languages: Option<CostMdls>,
-    #[n(19)]
     pub execution_costs: Option<ExUnitPrices>,
-    #[n(20)]
     pub max_tx_ex_units: Option<ExUnits>,
-    #[n(21)]
     pub max_block_ex_units: Option<ExUnits>,
-    #[n(22)]
     pub max_value_size: Option<u32>,
-    #[n(23)]
     pub collateral_percentage: Option<u32>,
-    #[n(24)]
     pub max_collateral_inputs: Option<u32>,
-}
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(ProtocolParamUpdate {
+    0 => minfee_a,
+    1 => minfee_b,
+    2 => max_block_body_size,
+    3 => max_transaction_size,
+    4 => max_block_header_size,
+    5 => key_deposit,
+    6 => pool_deposit,
+    7 => maximum_epoch,
+    8 => desired_number_of_stake_pools,
+    9 => pool_pledge_influence,
+    10 => expansion_rate,
+    11 => treasury_growth_rate,
+    12 => decentralization_constant,
+    13 => extra_entropy,
+    14 => protocol_version,
+    16 => min_pool_cost,
+    17 => ada_per_utxo_byte,
+    18 => cost_models_for_script_languages,
+    19 => execution_costs,
+    20 => max_tx_ex_units,
+    21 => max_block_ex_units,
+    22 => max_value_size,
+    23 => collateral_percentage,
+    24 => max_collateral_inputs,
+});
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
 pub struct Update {
@@ -711,51 +751,56 @@ pub struct Update {
 
 // Can't derive encode for TransactionBody because it seems to require a very
 // particular order for each key in the map
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
-#[cbor(map)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct TransactionBody {
-    #[n(0)]
-    pub inputs: Vec<TransactionInput>,
+    pub inputs: MaybeIndefArray<TransactionInput>,
 
-    #[n(1)]
-    pub outputs: Vec<TransactionOutput>,
+    pub outputs: MaybeIndefArray<TransactionOutput>,
 
-    #[n(2)]
     pub fee: u64,
 
-    #[n(3)]
     pub ttl: Option<u64>,
 
-    #[n(4)]
-    pub certificates: Option<Vec<Certificate>>,
+    pub certificates: Option<MaybeIndefArray<Certificate>>,
 
-    #[n(5)]
     pub withdrawals: Option<Withdrawals>,
 
-    #[n(6)]
     pub update: Option<Update>,
 
-    #[n(7)]
     pub auxiliary_data_hash: Option<Bytes>,
 
-    #[n(8)]
     pub validity_interval_start: Option<u64>,
 
-    #[n(9)]
     pub mint: Option<Multiasset<i64>>,
 
-    #[n(11)]
     pub script_data_hash: Option<Hash<32>>,
 
-    #[n(13)]
-    pub collateral: Option<Vec<TransactionInput>>,
+    pub collateral: Option<MaybeIndefArray<TransactionInput>>,
 
-    #[n(14)]
     pub required_signers: Option<RequiredSigners>,
 
-    #[n(15)]
     pub network_id: Option<NetworkId>,
-}
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(TransactionBody {
+    0 => inputs,
+    1 => outputs,
+    2 => fee,
+    3 => ttl,
+    4 => certificates,
+    5 => withdrawals,
+    6 => update,
+    7 => auxiliary_data_hash,
+    8 => validity_interval_start,
+    9 => mint,
+    11 => script_data_hash,
+    13 => collateral,
+    14 => required_signers,
+    15 => network_id,
+});
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
 pub struct VKeyWitness {
@@ -769,9 +814,9 @@ pub struct VKeyWitness {
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum NativeScript {
     ScriptPubkey(AddrKeyhash),
-    ScriptAll(Vec<NativeScript>),
-    ScriptAny(Vec<NativeScript>),
-    ScriptNOfK(u32, Vec<NativeScript>),
+    ScriptAll(MaybeIndefArray<NativeScript>),
+    ScriptAny(MaybeIndefArray<NativeScript>),
+    ScriptNOfK(u32, MaybeIndefArray<NativeScript>),
     InvalidBefore(u64),
     InvalidHereafter(u64),
 }
@@ -804,31 +849,35 @@ impl<C> minicbor::encode::Encode<C> for NativeScript {
         e: &mut minicbor::Encoder<W>,
         ctx: &mut C,
     ) -> Result<(), minicbor::encode::Error<W::Error>> {
-        e.array(2)?;
-
         match self {
             NativeScript::ScriptPubkey(v) => {
+                e.array(2)?;
                 e.encode_with(0, ctx)?;
                 e.encode_with(v, ctx)?;
             }
             NativeScript::ScriptAll(v) => {
+                e.array(2)?;
                 e.encode_with(1, ctx)?;
                 e.encode_with(v, ctx)?;
             }
             NativeScript::ScriptAny(v) => {
+                e.array(2)?;
                 e.encode_with(2, ctx)?;
                 e.encode_with(v, ctx)?;
             }
             NativeScript::ScriptNOfK(a, b) => {
+                e.array(3)?;
                 e.encode_with(3, ctx)?;
                 e.encode_with(a, ctx)?;
                 e.encode_with(b, ctx)?;
             }
             NativeScript::InvalidBefore(v) => {
+                e.array(2)?;
                 e.encode_with(4, ctx)?;
                 e.encode_with(v, ctx)?;
             }
             NativeScript::InvalidHereafter(v) => {
+                e.array(2)?;
                 e.encode_with(5, ctx)?;
                 e.encode_with(v, ctx)?;
             }
@@ -1206,10 +1255,10 @@ pub struct ExUnits {
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
 pub struct ExUnitPrices {
     #[n(0)]
-    mem_price: PositiveInterval,
+    pub mem_price: PositiveInterval,
 
     #[n(1)]
-    step_price: PositiveInterval,
+    pub step_price: PositiveInterval,
 }
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
@@ -1262,78 +1311,91 @@ pub struct BootstrapWitness {
     pub attributes: Bytes,
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct WitnessSet {
-    #[n(0)]
-    pub vkeywitness: Option<Vec<VKeyWitness>>,
+    pub vkeywitness: Option<MaybeIndefArray<VKeyWitness>>,
 
-    #[n(1)]
-    pub native_script: Option<Vec<NativeScript>>,
+    pub native_script: Option<MaybeIndefArray<NativeScript>>,
 
-    #[n(2)]
-    pub bootstrap_witness: Option<Vec<BootstrapWitness>>,
+    pub bootstrap_witness: Option<MaybeIndefArray<BootstrapWitness>>,
 
-    #[n(3)]
-    pub plutus_script: Option<Vec<PlutusScript>>,
+    pub plutus_script: Option<MaybeIndefArray<PlutusScript>>,
 
-    #[n(4)]
-    pub plutus_data: Option<Vec<PlutusData>>,
+    pub plutus_data: Option<MaybeIndefArray<PlutusData>>,
 
-    #[n(5)]
-    pub redeemer: Option<Vec<Redeemer>>,
+    pub redeemer: Option<MaybeIndefArray<Redeemer>>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
 }
 
-#[derive(Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+map_codec!(WitnessSet {
+    0 => vkeywitness,
+    1 => native_script,
+    2 => bootstrap_witness,
+    3 => plutus_script,
+    4 => plutus_data,
+    5 => redeemer,
+});
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct MintedWitnessSet<'b> {
-    #[n(0)]
-    pub vkeywitness: Option<Vec<VKeyWitness>>,
+    pub vkeywitness: Option<MaybeIndefArray<VKeyWitness>>,
 
-    #[n(1)]
-    pub native_script: Option<Vec<NativeScript>>,
+    pub native_script: Option<MaybeIndefArray<KeepRaw<'b, NativeScript>>>,
 
-    #[n(2)]
-    pub bootstrap_witness: Option<Vec<BootstrapWitness>>,
+    pub bootstrap_witness: Option<MaybeIndefArray<BootstrapWitness>>,
 
-    #[n(3)]
-    pub plutus_script: Option<Vec<PlutusScript>>,
+    pub plutus_script: Option<MaybeIndefArray<PlutusScript>>,
 
-    #[b(4)]
-    pub plutus_data: Option<Vec<KeepRaw<'b, PlutusData>>>,
+    pub plutus_data: Option<MaybeIndefArray<KeepRaw<'b, PlutusData>>>,
 
-    #[n(5)]
-    pub redeemer: Option<Vec<Redeemer>>,
+    pub redeemer: Option<MaybeIndefArray<Redeemer>>,
+
+    pub layout: MapLayout,
 }
 
+map_codec!(MintedWitnessSet<'b> {
+    0 => vkeywitness,
+    1 => native_script,
+    2 => bootstrap_witness,
+    3 => plutus_script,
+    4 => plutus_data,
+    5 => redeemer,
+});
+
 impl<'b> From<MintedWitnessSet<'b>> for WitnessSet {
     fn from(x: MintedWitnessSet<'b>) -> Self {
         WitnessSet {
             vkeywitness: x.vkeywitness,
-            native_script: x.native_script,
+            native_script: x.native_script.map(|x| x.map(|x| x.unwrap())),
             bootstrap_witness: x.bootstrap_witness,
             plutus_script: x.plutus_script,
-            plutus_data: x
-                .plutus_data
-                .map(|x| x.into_iter().map(|x| x.unwrap()).collect()),
+            plutus_data: x.plutus_data.map(|x| x.map(|x| x.unwrap())),
             redeemer: x.redeemer,
+            layout: x.layout,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct PostAlonzoAuxiliaryData {
-    #[n(0)]
     pub metadata: Option<Metadata>,
 
-    #[n(1)]
-    pub native_scripts: Option<Vec<NativeScript>>,
+    pub native_scripts: Option<MaybeIndefArray<NativeScript>>,
 
-    #[n(2)]
-    pub plutus_scripts: Option<Vec<PlutusScript>>,
+    pub plutus_scripts: Option<MaybeIndefArray<PlutusScript>>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
 }
 
+map_codec!(PostAlonzoAuxiliaryData {
+    0 => metadata,
+    1 => native_scripts,
+    2 => plutus_scripts,
+});
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum Metadatum {
     Int(Int),
@@ -1471,16 +1533,16 @@ pub struct Block {
     pub header: Header,
 
     #[b(1)]
-    pub transaction_bodies: Vec<TransactionBody>,
+    pub transaction_bodies: MaybeIndefArray<TransactionBody>,
 
     #[n(2)]
-    pub transaction_witness_sets: Vec<WitnessSet>,
+    pub transaction_witness_sets: MaybeIndefArray<WitnessSet>,
 
     #[n(3)]
     pub auxiliary_data_set: KeyValuePairs<TransactionIndex, AuxiliaryData>,
 
     #[n(4)]
-    pub invalid_transactions: Option<Vec<TransactionIndex>>,
+    pub invalid_transactions: Option<MaybeIndefArray<TransactionIndex>>,
 }
 
 /// A memory representation of an already minted block
@@ -1510,19 +1572,10 @@ impl<'b> From<MintedBlock<'b>> for Block {
     fn from(x: MintedBlock<'b>) -> Self {
         Block {
             header: x.header.unwrap(),
-            transaction_bodies: x
-                .transaction_bodies
-                .to_vec()
-                .into_iter()
-                .map(|x| x.unwrap())
-                .collect(),
+            transaction_bodies: x.transaction_bodies.map(|x| x.unwrap()),
             transaction_witness_sets: x
                 .transaction_witness_sets
-                .to_vec()
-                .into_iter()
-                .map(|x| x.unwrap())
-                .map(WitnessSet::from)
-                .collect(),
+                .map(|x| WitnessSet::from(x.unwrap())),
             auxiliary_data_set: x
                 .auxiliary_data_set
                 .to_vec()
@@ -1530,7 +1583,7 @@ impl<'b> From<MintedBlock<'b>> for Block {
                 .map(|(k, v)| (k, v.unwrap()))
                 .collect::<Vec<_>>()
                 .into(),
-            invalid_transactions: x.invalid_transactions.map(|x| x.into()),
+            invalid_transactions: x.invalid_transactions,
         }
     }
 }
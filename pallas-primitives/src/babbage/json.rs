@@ -0,0 +1,363 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    CostMdls, Header, HeaderBody, MintedWitnessSet, OperationalCert, PlutusV2Script,
+    PostAlonzoAuxiliaryData, ProtocolParamUpdate, PseudoBlock, PseudoDatumOption,
+    PseudoPostAlonzoTransactionOutput, PseudoTransactionBody, PseudoTransactionOutput, PseudoTx,
+    Script, Update, WitnessSet,
+};
+use crate::alonzo::json::{
+    address, block_txs, common_param_updates, list, multiasset, plutus_script, put, tx, update,
+    withdrawals,
+};
+use crate::ToCanonicalJson;
+
+impl ToCanonicalJson for OperationalCert {
+    fn to_json(&self) -> Value {
+        json!({
+            "hotVkey": self.operational_cert_hot_vkey.to_json(),
+            "sequenceNumber": self.operational_cert_sequence_number,
+            "kesPeriod": self.operational_cert_kes_period,
+            "sigma": self.operational_cert_sigma.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for HeaderBody {
+    fn to_json(&self) -> Value {
+        let (major, minor) = self.protocol_version;
+
+        json!({
+            "blockNumber": self.block_number,
+            "slot": self.slot,
+            "prevHash": self.prev_hash.to_json(),
+            "issuerVkey": self.issuer_vkey.to_json(),
+            "vrfVkey": self.vrf_vkey.to_json(),
+            "vrfResult": self.vrf_result.to_json(),
+            "blockBodySize": self.block_body_size,
+            "blockBodyHash": self.block_body_hash.to_json(),
+            "operationalCert": self.operational_cert.to_json(),
+            "protocolVersion": { "major": major, "minor": minor },
+        })
+    }
+}
+
+impl ToCanonicalJson for Header {
+    fn to_json(&self) -> Value {
+        json!({
+            "headerBody": self.header_body.to_json(),
+            "signature": self.body_signature.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for CostMdls {
+    fn to_json(&self) -> Value {
+        let mut map = Map::new();
+
+        put(
+            &mut map,
+            "PlutusV1",
+            self.plutus_v1.as_ref().map(|x| json!(x)),
+        );
+        put(
+            &mut map,
+            "PlutusV2",
+            self.plutus_v2.as_ref().map(|x| json!(x)),
+        );
+
+        Value::Object(map)
+    }
+}
+
+impl ToCanonicalJson for ProtocolParamUpdate {
+    fn to_json(&self) -> Value {
+        let mut map = Map::new();
+
+        common_param_updates!(&mut map, self);
+
+        let x = self;
+        put(
+            &mut map,
+            "utxoCostPerByte",
+            x.ada_per_utxo_byte.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "costModels",
+            x.cost_models_for_script_languages
+                .as_ref()
+                .map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "executionUnitPrices",
+            x.execution_costs.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "maxTxExecutionUnits",
+            x.max_tx_ex_units.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "maxBlockExecutionUnits",
+            x.max_block_ex_units.as_ref().map(|v| v.to_json()),
+        );
+        put(&mut map, "maxValueSize", x.max_value_size.map(|v| json!(v)));
+        put(
+            &mut map,
+            "collateralPercentage",
+            x.collateral_percentage.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "maxCollateralInputs",
+            x.max_collateral_inputs.map(|v| json!(v)),
+        );
+
+        Value::Object(map)
+    }
+}
+
+impl ToCanonicalJson for Update {
+    fn to_json(&self) -> Value {
+        update(&self.proposed_protocol_parameter_updates, self.epoch)
+    }
+}
+
+impl ToCanonicalJson for PlutusV2Script {
+    fn to_json(&self) -> Value {
+        plutus_script("PlutusScriptV2", &self.0)
+    }
+}
+
+// reference scripts use the format of cardano-cli for scripts in any language
+impl ToCanonicalJson for Script {
+    fn to_json(&self) -> Value {
+        match self {
+            Script::NativeScript(x) => json!({
+                "scriptLanguage": "SimpleScriptLanguage",
+                "script": x.to_json(),
+            }),
+            Script::PlutusV1Script(x) => json!({
+                "scriptLanguage": "PlutusScriptLanguage PlutusScriptV1",
+                "script": x.to_json(),
+            }),
+            Script::PlutusV2Script(x) => json!({
+                "scriptLanguage": "PlutusScriptLanguage PlutusScriptV2",
+                "script": x.to_json(),
+            }),
+        }
+    }
+}
+
+impl<T> ToCanonicalJson for PseudoPostAlonzoTransactionOutput<PseudoDatumOption<T>>
+where
+    T: ToCanonicalJson,
+{
+    fn to_json(&self) -> Value {
+        let (datum_hash, inline_datum) = match &self.datum_option {
+            Some(PseudoDatumOption::Hash(x)) => (x.to_json(), Value::Null),
+            Some(PseudoDatumOption::Data(x)) => (Value::Null, x.0.to_json()),
+            None => (Value::Null, Value::Null),
+        };
+
+        json!({
+            "address": address(&self.address),
+            "value": self.value.to_json(),
+            "datumhash": datum_hash,
+            "inlineDatum": inline_datum,
+            "referenceScript": self.script_ref.as_ref().map(|x| x.0.to_json()),
+        })
+    }
+}
+
+impl<T: ToCanonicalJson> ToCanonicalJson for PseudoTransactionOutput<T> {
+    fn to_json(&self) -> Value {
+        match self {
+            PseudoTransactionOutput::Legacy(x) => {
+                let mut value = x.to_json();
+                value["inlineDatum"] = Value::Null;
+                value["referenceScript"] = Value::Null;
+                value
+            }
+            PseudoTransactionOutput::PostAlonzo(x) => x.to_json(),
+        }
+    }
+}
+
+impl<T: ToCanonicalJson> ToCanonicalJson for PseudoTransactionBody<T> {
+    fn to_json(&self) -> Value {
+        json!({
+            "inputs": self.inputs.to_json(),
+            "outputs": self.outputs.to_json(),
+            "fee": self.fee,
+            "ttl": self.ttl,
+            "certificates": self.certificates.to_json(),
+            "withdrawals": self.withdrawals.as_ref().map(withdrawals),
+            "update": self.update.to_json(),
+            "auxiliaryDataHash": self.auxiliary_data_hash.to_json(),
+            "validityIntervalStart": self.validity_interval_start,
+            "mint": self.mint.as_ref().map(multiasset),
+            "scriptDataHash": self.script_data_hash.to_json(),
+            "collateral": self.collateral.to_json(),
+            "requiredSigners": self.required_signers.to_json(),
+            "networkId": self.network_id.to_json(),
+            "collateralReturn": self.collateral_return.to_json(),
+            "totalCollateral": self.total_collateral,
+            "referenceInputs": self.reference_inputs.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for WitnessSet {
+    fn to_json(&self) -> Value {
+        json!({
+            "vkeys": list(&self.vkeywitness),
+            "nativeScripts": list(&self.native_script),
+            "bootstraps": list(&self.bootstrap_witness),
+            "plutusV1Scripts": list(&self.plutus_v1_script),
+            "plutusV2Scripts": list(&self.plutus_v2_script),
+            "plutusData": list(&self.plutus_data),
+            "redeemers": list(&self.redeemer),
+        })
+    }
+}
+
+impl ToCanonicalJson for MintedWitnessSet<'_> {
+    fn to_json(&self) -> Value {
+        json!({
+            "vkeys": list(&self.vkeywitness),
+            "nativeScripts": list(&self.native_script),
+            "bootstraps": list(&self.bootstrap_witness),
+            "plutusV1Scripts": list(&self.plutus_v1_script),
+            "plutusV2Scripts": list(&self.plutus_v2_script),
+            "plutusData": list(&self.plutus_data),
+            "redeemers": list(&self.redeemer),
+        })
+    }
+}
+
+impl ToCanonicalJson for PostAlonzoAuxiliaryData {
+    fn to_json(&self) -> Value {
+        json!({
+            "metadata": self.metadata.to_json(),
+            "nativeScripts": list(&self.native_scripts),
+            "plutusV1Scripts": list(&self.plutus_v1_scripts),
+            "plutusV2Scripts": list(&self.plutus_v2_scripts),
+        })
+    }
+}
+
+impl<T1, T2, T3> ToCanonicalJson for PseudoTx<T1, T2, T3>
+where
+    T1: ToCanonicalJson + Clone,
+    T2: ToCanonicalJson + Clone,
+    T3: ToCanonicalJson + Clone,
+{
+    fn to_json(&self) -> Value {
+        tx(
+            self.transaction_body.to_json(),
+            self.transaction_witness_set.to_json(),
+            Some(self.success),
+            self.auxiliary_data.to_json(),
+        )
+    }
+}
+
+impl<T1, T2, T3, T4> ToCanonicalJson for PseudoBlock<T1, T2, T3, T4>
+where
+    T1: ToCanonicalJson,
+    T2: ToCanonicalJson,
+    T3: ToCanonicalJson,
+    T4: ToCanonicalJson + Clone,
+{
+    fn to_json(&self) -> Value {
+        let invalid: Vec<_> = self
+            .invalid_transactions
+            .as_ref()
+            .map(|x| x.iter().copied().collect())
+            .unwrap_or_default();
+
+        json!({
+            "header": self.header.to_json(),
+            "transactions": block_txs(
+                &self.transaction_bodies,
+                &self.transaction_witness_sets,
+                &self.auxiliary_data_set,
+                Some(&invalid),
+                |x| x.to_json(),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+
+    use crate::babbage::{Block, MintedBlock, PlutusV2Script, Script};
+    use crate::ToCanonicalJson;
+
+    type BlockWrapper<'b> = (u16, MintedBlock<'b>);
+
+    #[test]
+    fn test_blocks_serialize_as_expected() {
+        let test_blocks = [
+            include_str!("../../../test_data/babbage1.block"),
+            include_str!("../../../test_data/babbage2.block"),
+            include_str!("../../../test_data/babbage3.block"),
+            include_str!("../../../test_data/babbage4.block"),
+            include_str!("../../../test_data/babbage5.block"),
+            include_str!("../../../test_data/babbage6.block"),
+            include_str!("../../../test_data/babbage7.block"),
+            include_str!("../../../test_data/babbage8.block"),
+            include_str!("../../../test_data/babbage9.block"),
+            include_str!("../../../test_data/babbage10.block"),
+        ];
+
+        let mut inline_datums = 0;
+
+        for block_str in test_blocks {
+            let bytes = hex::decode(block_str).unwrap();
+            let (_, block): BlockWrapper = minicbor::decode(&bytes[..]).unwrap();
+
+            let json = block.to_json();
+            let txs = json["transactions"].as_array().unwrap();
+            assert_eq!(txs.len(), block.transaction_bodies.len());
+
+            for tx in txs {
+                for output in tx["body"]["outputs"].as_array().unwrap() {
+                    if !output["inlineDatum"].is_null() {
+                        assert!(output["datumhash"].is_null());
+                        inline_datums += 1;
+                    }
+                }
+            }
+
+            // the owned and minted versions of a block have the same JSON
+            let owned = Block::from(block);
+            assert_eq!(owned.to_json(), json);
+        }
+
+        assert!(inline_datums > 0);
+    }
+
+    #[test]
+    fn test_reference_scripts_serialize_as_expected() {
+        let script = Script::PlutusV2Script(PlutusV2Script(vec![0x4e, 0x4d, 0x01].into()));
+
+        assert_eq!(
+            script.to_json(),
+            serde_json::json!({
+                "scriptLanguage": "PlutusScriptLanguage PlutusScriptV2",
+                "script": {
+                    "type": "PlutusScriptV2",
+                    "description": "",
+                    "cborHex": "434e4d01",
+                },
+            })
+        );
+    }
+}
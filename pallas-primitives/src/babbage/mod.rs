@@ -1,3 +1,6 @@
 mod model;
 
+#[cfg(feature = "json")]
+pub mod json;
+
 pub use model::*;
@@ -7,11 +7,15 @@ use serde::{Deserialize, Serialize};
 use pallas_codec::minicbor::{Decode, Encode};
 use pallas_crypto::hash::Hash;
 
-use pallas_codec::utils::{Bytes, CborWrap, KeepRaw, KeyValuePairs, MaybeIndefArray, Nullable};
+use pallas_codec::utils::{
+    AnyUInt, Bytes, CborWrap, KeepRaw, KeyValuePairs, MapLayout, MaybeIndefArray, Nullable,
+};
 
 // required for derive attrs to work
 use pallas_codec::minicbor;
 
+use crate::framework::map_codec;
+
 pub use crate::alonzo::VrfCert;
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
@@ -115,9 +119,11 @@ pub use crate::alonzo::MoveInstantaneousReward;
 
 pub use crate::alonzo::RewardAccount;
 
-pub type Withdrawals = KeyValuePairs<RewardAccount, Coin>;
+/// Withdrawal amounts keep the width of their original encoding, since it's
+/// not always the shortest one
+pub type Withdrawals = KeyValuePairs<RewardAccount, AnyUInt>;
 
-pub type RequiredSigners = Vec<AddrKeyhash>;
+pub type RequiredSigners = MaybeIndefArray<AddrKeyhash>;
 
 pub use crate::alonzo::Port;
 
@@ -161,65 +167,75 @@ pub enum Language {
 
 pub use crate::alonzo::CostModel;
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
-#[cbor(map)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct CostMdls {
-    #[n(0)]
     pub plutus_v1: Option<CostModel>,
 
-    #[n(1)]
     pub plutus_v2: Option<CostModel>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
-#[cbor(map)]
+map_codec!(CostMdls {
+    0 => plutus_v1,
+    1 => plutus_v2,
+});
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ProtocolParamUpdate {
-    #[n(0)]
     pub minfee_a: Option<u32>,
-    #[n(1)]
     pub minfee_b: Option<u32>,
-    #[n(2)]
     pub max_block_body_size: Option<u32>,
-    #[n(3)]
     pub max_transaction_size: Option<u32>,
-    #[n(4)]
     pub max_block_header_size: Option<u32>,
-    #[n(5)]
     pub key_deposit: Option<Coin>,
-    #[n(6)]
     pub pool_deposit: Option<Coin>,
-    #[n(7)]
     pub maximum_epoch: Option<Epoch>,
-    #[n(8)]
     pub desired_number_of_stake_pools: Option<u32>,
-    #[n(9)]
     pub pool_pledge_influence: Option<RationalNumber>,
-    #[n(10)]
     pub expansion_rate: Option<UnitInterval>,
-    #[n(11)]
     pub treasury_growth_rate: Option<UnitInterval>,
 
-    #[n(14)]
     pub protocol_version: Option<ProtocolVersion>,
-    #[n(16)]
     pub min_pool_cost: Option<Coin>,
-    #[n(17)]
     pub ada_per_utxo_byte: Option<Coin>,
-    #[n(18)]
     pub cost_models_for_script_languages: Option<CostMdls>,
-    #[n(19)]
     pub execution_costs: Option<ExUnitPrices>,
-    #[n(20)]
     pub max_tx_ex_units: Option<ExUnits>,
-    #[n(21)]
     pub max_block_ex_units: Option<ExUnits>,
-    #[n(22)]
     pub max_value_size: Option<u32>,
-    #[n(23)]
     pub collateral_percentage: Option<u32>,
-    #[n(24)]
     pub max_collateral_inputs: Option<u32>,
-}
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(ProtocolParamUpdate {
+    0 => minfee_a,
+    1 => minfee_b,
+    2 => max_block_body_size,
+    3 => max_transaction_size,
+    4 => max_block_header_size,
+    5 => key_deposit,
+    6 => pool_deposit,
+    7 => maximum_epoch,
+    8 => desired_number_of_stake_pools,
+    9 => pool_pledge_influence,
+    10 => expansion_rate,
+    11 => treasury_growth_rate,
+    14 => protocol_version,
+    16 => min_pool_cost,
+    17 => ada_per_utxo_byte,
+    18 => cost_models_for_script_languages,
+    19 => execution_costs,
+    20 => max_tx_ex_units,
+    21 => max_block_ex_units,
+    22 => max_value_size,
+    23 => collateral_percentage,
+    24 => max_collateral_inputs,
+});
 
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
 pub struct Update {
@@ -230,60 +246,64 @@ pub struct Update {
     pub epoch: Epoch,
 }
 
-#[derive(Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PseudoTransactionBody<T1> {
-    #[n(0)]
-    pub inputs: Vec<TransactionInput>,
+    pub inputs: MaybeIndefArray<TransactionInput>,
 
-    #[n(1)]
-    pub outputs: Vec<T1>,
+    pub outputs: MaybeIndefArray<T1>,
 
-    #[n(2)]
     pub fee: u64,
 
-    #[n(3)]
     pub ttl: Option<u64>,
 
-    #[n(4)]
-    pub certificates: Option<Vec<Certificate>>,
+    pub certificates: Option<MaybeIndefArray<Certificate>>,
 
-    #[n(5)]
-    pub withdrawals: Option<KeyValuePairs<RewardAccount, Coin>>,
+    pub withdrawals: Option<Withdrawals>,
 
-    #[n(6)]
     pub update: Option<Update>,
 
-    #[n(7)]
     pub auxiliary_data_hash: Option<Bytes>,
 
-    #[n(8)]
     pub validity_interval_start: Option<u64>,
 
-    #[n(9)]
     pub mint: Option<Multiasset<i64>>,
 
-    #[n(11)]
     pub script_data_hash: Option<Hash<32>>,
 
-    #[n(13)]
-    pub collateral: Option<Vec<TransactionInput>>,
+    pub collateral: Option<MaybeIndefArray<TransactionInput>>,
 
-    #[n(14)]
-    pub required_signers: Option<Vec<AddrKeyhash>>,
+    pub required_signers: Option<RequiredSigners>,
 
-    #[n(15)]
     pub network_id: Option<NetworkId>,
 
-    #[n(16)]
     pub collateral_return: Option<T1>,
 
-    #[n(17)]
     pub total_collateral: Option<Coin>,
 
-    #[n(18)]
-    pub reference_inputs: Option<Vec<TransactionInput>>,
-}
+    pub reference_inputs: Option<MaybeIndefArray<TransactionInput>>,
+
+    pub layout: MapLayout,
+}
+
+map_codec!(PseudoTransactionBody<T1> {
+    0 => inputs,
+    1 => outputs,
+    2 => fee,
+    3 => ttl,
+    4 => certificates,
+    5 => withdrawals,
+    6 => update,
+    7 => auxiliary_data_hash,
+    8 => validity_interval_start,
+    9 => mint,
+    11 => script_data_hash,
+    13 => collateral,
+    14 => required_signers,
+    15 => network_id,
+    16 => collateral_return,
+    17 => total_collateral,
+    18 => reference_inputs,
+});
 
 pub type TransactionBody = PseudoTransactionBody<TransactionOutput>;
 
@@ -293,7 +313,7 @@ impl<'a> From<MintedTransactionBody<'a>> for TransactionBody {
     fn from(value: MintedTransactionBody<'a>) -> Self {
         Self {
             inputs: value.inputs,
-            outputs: value.outputs.into_iter().map(|x| x.into()).collect(),
+            outputs: value.outputs.map(|x| x.into()),
             fee: value.fee,
             ttl: value.ttl,
             certificates: value.certificates,
@@ -309,6 +329,7 @@ impl<'a> From<MintedTransactionBody<'a>> for TransactionBody {
             collateral_return: value.collateral_return.map(|x| x.into()),
             total_collateral: value.total_collateral,
             reference_inputs: value.reference_inputs,
+            layout: value.layout,
         }
     }
 }
@@ -368,22 +389,26 @@ impl<'b> From<MintedTransactionOutput<'b>> for TransactionOutput {
     }
 }
 
-#[derive(Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PseudoPostAlonzoTransactionOutput<T1> {
-    #[n(0)]
     pub address: Bytes,
 
-    #[n(1)]
     pub value: Value,
 
-    #[n(2)]
     pub datum_option: Option<T1>,
 
-    #[n(3)]
     pub script_ref: Option<ScriptRef>,
+
+    pub layout: MapLayout,
 }
 
+map_codec!(PseudoPostAlonzoTransactionOutput<T1> {
+    0 => address,
+    1 => value,
+    2 => datum_option,
+    3 => script_ref,
+});
+
 pub type PostAlonzoTransactionOutput = PseudoPostAlonzoTransactionOutput<DatumOption>;
 
 pub type MintedPostAlonzoTransactionOutput<'b> =
@@ -396,6 +421,7 @@ impl<'b> From<MintedPostAlonzoTransactionOutput<'b>> for PostAlonzoTransactionOu
             value: value.value,
             datum_option: value.datum_option.map(|x| x.into()),
             script_ref: value.script_ref,
+            layout: value.layout,
         }
     }
 }
@@ -432,88 +458,101 @@ pub use crate::alonzo::Redeemer;
 
 pub use crate::alonzo::BootstrapWitness;
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct WitnessSet {
-    #[n(0)]
-    pub vkeywitness: Option<Vec<VKeyWitness>>,
+    pub vkeywitness: Option<MaybeIndefArray<VKeyWitness>>,
 
-    #[n(1)]
-    pub native_script: Option<Vec<NativeScript>>,
+    pub native_script: Option<MaybeIndefArray<NativeScript>>,
 
-    #[n(2)]
-    pub bootstrap_witness: Option<Vec<BootstrapWitness>>,
+    pub bootstrap_witness: Option<MaybeIndefArray<BootstrapWitness>>,
 
-    #[n(3)]
-    pub plutus_v1_script: Option<Vec<PlutusV1Script>>,
+    pub plutus_v1_script: Option<MaybeIndefArray<PlutusV1Script>>,
 
-    #[n(4)]
-    pub plutus_data: Option<Vec<PlutusData>>,
+    pub plutus_data: Option<MaybeIndefArray<PlutusData>>,
 
-    #[n(5)]
-    pub redeemer: Option<Vec<Redeemer>>,
+    pub redeemer: Option<MaybeIndefArray<Redeemer>>,
 
-    #[n(6)]
-    pub plutus_v2_script: Option<Vec<PlutusV2Script>>,
+    pub plutus_v2_script: Option<MaybeIndefArray<PlutusV2Script>>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
 }
 
-#[derive(Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+map_codec!(WitnessSet {
+    0 => vkeywitness,
+    1 => native_script,
+    2 => bootstrap_witness,
+    3 => plutus_v1_script,
+    4 => plutus_data,
+    5 => redeemer,
+    6 => plutus_v2_script,
+});
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct MintedWitnessSet<'b> {
-    #[n(0)]
-    pub vkeywitness: Option<Vec<VKeyWitness>>,
+    pub vkeywitness: Option<MaybeIndefArray<VKeyWitness>>,
 
-    #[n(1)]
-    pub native_script: Option<Vec<NativeScript>>,
+    pub native_script: Option<MaybeIndefArray<KeepRaw<'b, NativeScript>>>,
 
-    #[n(2)]
-    pub bootstrap_witness: Option<Vec<BootstrapWitness>>,
+    pub bootstrap_witness: Option<MaybeIndefArray<BootstrapWitness>>,
 
-    #[n(3)]
-    pub plutus_v1_script: Option<Vec<PlutusV1Script>>,
+    pub plutus_v1_script: Option<MaybeIndefArray<PlutusV1Script>>,
 
-    #[b(4)]
-    pub plutus_data: Option<Vec<KeepRaw<'b, PlutusData>>>,
+    pub plutus_data: Option<MaybeIndefArray<KeepRaw<'b, PlutusData>>>,
 
-    #[n(5)]
-    pub redeemer: Option<Vec<Redeemer>>,
+    pub redeemer: Option<MaybeIndefArray<Redeemer>>,
 
-    #[n(6)]
-    pub plutus_v2_script: Option<Vec<PlutusV2Script>>,
+    pub plutus_v2_script: Option<MaybeIndefArray<PlutusV2Script>>,
+
+    pub layout: MapLayout,
 }
 
+map_codec!(MintedWitnessSet<'b> {
+    0 => vkeywitness,
+    1 => native_script,
+    2 => bootstrap_witness,
+    3 => plutus_v1_script,
+    4 => plutus_data,
+    5 => redeemer,
+    6 => plutus_v2_script,
+});
+
 impl<'b> From<MintedWitnessSet<'b>> for WitnessSet {
     fn from(x: MintedWitnessSet<'b>) -> Self {
         WitnessSet {
             vkeywitness: x.vkeywitness,
-            native_script: x.native_script,
+            native_script: x.native_script.map(|x| x.map(|x| x.unwrap())),
             bootstrap_witness: x.bootstrap_witness,
             plutus_v1_script: x.plutus_v1_script,
-            plutus_data: x
-                .plutus_data
-                .map(|x| x.into_iter().map(|x| x.unwrap()).collect()),
+            plutus_data: x.plutus_data.map(|x| x.map(|x| x.unwrap())),
             redeemer: x.redeemer,
             plutus_v2_script: x.plutus_v2_script,
+            layout: x.layout,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
-#[cbor(map)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct PostAlonzoAuxiliaryData {
-    #[n(0)]
     pub metadata: Option<Metadata>,
 
-    #[n(1)]
-    pub native_scripts: Option<Vec<NativeScript>>,
+    pub native_scripts: Option<MaybeIndefArray<NativeScript>>,
 
-    #[n(2)]
-    pub plutus_v1_scripts: Option<Vec<PlutusV1Script>>,
+    pub plutus_v1_scripts: Option<MaybeIndefArray<PlutusV1Script>>,
 
-    #[n(3)]
-    pub plutus_v2_scripts: Option<Vec<PlutusV2Script>>,
+    pub plutus_v2_scripts: Option<MaybeIndefArray<PlutusV2Script>>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
 }
 
+map_codec!(PostAlonzoAuxiliaryData {
+    0 => metadata,
+    1 => native_scripts,
+    2 => plutus_v1_scripts,
+    3 => plutus_v2_scripts,
+});
+
 pub type DatumHash = Hash<32>;
 
 //pub type Data = CborWrap<PlutusData>;
@@ -667,22 +706,12 @@ impl<'b> From<MintedBlock<'b>> for Block {
     fn from(x: MintedBlock<'b>) -> Self {
         Block {
             header: x.header.unwrap(),
-            transaction_bodies: MaybeIndefArray::Def(
-                x.transaction_bodies
-                    .iter()
-                    .cloned()
-                    .map(|x| x.unwrap())
-                    .map(TransactionBody::from)
-                    .collect(),
-            ),
-            transaction_witness_sets: MaybeIndefArray::Def(
-                x.transaction_witness_sets
-                    .iter()
-                    .cloned()
-                    .map(|x| x.unwrap())
-                    .map(WitnessSet::from)
-                    .collect(),
-            ),
+            transaction_bodies: x
+                .transaction_bodies
+                .map(|x| TransactionBody::from(x.unwrap())),
+            transaction_witness_sets: x
+                .transaction_witness_sets
+                .map(|x| WitnessSet::from(x.unwrap())),
             auxiliary_data_set: x
                 .auxiliary_data_set
                 .to_vec()
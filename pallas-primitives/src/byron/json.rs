@@ -0,0 +1,339 @@
+use base58::ToBase58;
+use pallas_codec::minicbor;
+use serde_json::{json, Map, Value};
+
+use super::{
+    Address, BVer, BVerMod, Block, BlockHead, EbBlock, EbbHead, MintedBlock, MintedEbBlock,
+    MintedTxPayload, Twit, Tx, TxFeePol, TxIn, TxOut, TxPayload, Up, UpProp, UpVote,
+};
+use crate::alonzo::json::{put, tx};
+use crate::ToCanonicalJson;
+
+fn block_version((major, minor, alt): &BVer) -> Value {
+    json!(format!("{major}.{minor}.{alt}"))
+}
+
+impl ToCanonicalJson for Address {
+    fn to_json(&self) -> Value {
+        json!(minicbor::to_vec(self).unwrap().to_base58())
+    }
+}
+
+impl ToCanonicalJson for TxIn {
+    fn to_json(&self) -> Value {
+        match self {
+            TxIn::Variant0(x) => json!(format!("{}#{}", x.0 .0, x.0 .1)),
+            TxIn::Other(tag, cbor) => json!({ "type": tag, "cbor": hex::encode(cbor.as_slice()) }),
+        }
+    }
+}
+
+impl ToCanonicalJson for TxOut {
+    fn to_json(&self) -> Value {
+        json!({
+            "address": self.address.to_json(),
+            "value": { "lovelace": self.amount },
+        })
+    }
+}
+
+impl ToCanonicalJson for Tx {
+    fn to_json(&self) -> Value {
+        json!({
+            "inputs": self.inputs.to_json(),
+            "outputs": self.outputs.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for Twit {
+    fn to_json(&self) -> Value {
+        match self {
+            Twit::PkWitness(x) => json!({
+                "type": "pk",
+                "publicKey": hex::encode(x.0 .0.as_slice()),
+                "signature": hex::encode(x.0 .1.as_slice()),
+            }),
+            Twit::ScriptWitness(x) => {
+                let ((validator_version, validator), (redeemer_version, redeemer)) = &x.0;
+
+                json!({
+                    "type": "script",
+                    "validator": {
+                        "version": validator_version,
+                        "script": hex::encode(validator.as_slice()),
+                    },
+                    "redeemer": {
+                        "version": redeemer_version,
+                        "script": hex::encode(redeemer.as_slice()),
+                    },
+                })
+            }
+            Twit::RedeemWitness(x) => json!({
+                "type": "redeem",
+                "publicKey": hex::encode(x.0 .0.as_slice()),
+                "signature": hex::encode(x.0 .1.as_slice()),
+            }),
+            Twit::Other(tag, cbor) => json!({ "type": tag, "cbor": hex::encode(cbor.as_slice()) }),
+        }
+    }
+}
+
+impl ToCanonicalJson for TxPayload {
+    fn to_json(&self) -> Value {
+        tx(
+            self.transaction.to_json(),
+            self.witness.to_json(),
+            None,
+            Value::Null,
+        )
+    }
+}
+
+impl ToCanonicalJson for MintedTxPayload<'_> {
+    fn to_json(&self) -> Value {
+        tx(
+            self.transaction.to_json(),
+            self.witness.to_json(),
+            None,
+            Value::Null,
+        )
+    }
+}
+
+impl ToCanonicalJson for TxFeePol {
+    fn to_json(&self) -> Value {
+        match self {
+            TxFeePol::Variant0(x) => json!({ "summand": x.0 .0, "multiplier": x.0 .1 }),
+            TxFeePol::Other(tag, cbor) => {
+                json!({ "type": tag, "cbor": hex::encode(cbor.as_slice()) })
+            }
+        }
+    }
+}
+
+impl ToCanonicalJson for BVerMod {
+    fn to_json(&self) -> Value {
+        let mut map = Map::new();
+
+        put(
+            &mut map,
+            "scriptVersion",
+            self.script_version.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "slotDuration",
+            self.slot_duration.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "maxBlockSize",
+            self.max_block_size.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "maxHeaderSize",
+            self.max_header_size.map(|v| json!(v)),
+        );
+        put(&mut map, "maxTxSize", self.max_tx_size.map(|v| json!(v)));
+        put(
+            &mut map,
+            "maxProposalSize",
+            self.max_proposal_size.map(|v| json!(v)),
+        );
+        put(&mut map, "mpcThd", self.mpc_thd.map(|v| json!(v)));
+        put(
+            &mut map,
+            "heavyDelThd",
+            self.heavy_del_thd.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "updateVoteThd",
+            self.update_vote_thd.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "updateProposalThd",
+            self.update_proposal_thd.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "updateImplicit",
+            self.update_implicit.map(|v| json!(v)),
+        );
+        put(
+            &mut map,
+            "softforkRule",
+            self.soft_fork_rule.map(|(init, min, decrement)| {
+                json!({ "initThd": init, "minThd": min, "thdDecrement": decrement })
+            }),
+        );
+        put(
+            &mut map,
+            "txFeePolicy",
+            self.tx_fee_policy.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "unlockStakeEpoch",
+            self.unlock_stake_epoch.map(|v| json!(v)),
+        );
+
+        Value::Object(map)
+    }
+}
+
+impl ToCanonicalJson for UpProp {
+    fn to_json(&self) -> Value {
+        json!({
+            "blockVersion": self.block_version.as_ref().map(block_version),
+            "softwareVersion": self
+                .software_version
+                .as_ref()
+                .map(|(name, number)| json!({ "name": name, "number": number })),
+            "parameters": self.block_version_mod.to_json(),
+            "from": self.from.as_ref().map(|x| hex::encode(x.as_slice())),
+            "signature": self.signature.as_ref().map(|x| hex::encode(x.as_slice())),
+        })
+    }
+}
+
+impl ToCanonicalJson for UpVote {
+    fn to_json(&self) -> Value {
+        json!({
+            "voter": hex::encode(self.voter.as_slice()),
+            "proposalId": self.proposal_id.to_json(),
+            "vote": self.vote,
+            "signature": hex::encode(self.signature.as_slice()),
+        })
+    }
+}
+
+impl ToCanonicalJson for Up {
+    fn to_json(&self) -> Value {
+        json!({
+            "proposal": self.proposal.to_json(),
+            "votes": self.votes.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for BlockHead {
+    fn to_json(&self) -> Value {
+        let slot = &self.consensus_data.0;
+        let issuer = &self.consensus_data.1;
+        let difficulty = &self.consensus_data.2;
+        let (name, number) = &self.extra_data.software_version;
+
+        json!({
+            "protocolMagic": self.protocol_magic,
+            "prevHash": self.prev_block.to_json(),
+            "epoch": slot.epoch,
+            "slot": slot.slot,
+            "issuer": hex::encode(issuer.as_slice()),
+            "difficulty": difficulty.first(),
+            "blockVersion": block_version(&self.extra_data.block_version),
+            "softwareVersion": { "name": name, "number": number },
+        })
+    }
+}
+
+impl ToCanonicalJson for Block {
+    fn to_json(&self) -> Value {
+        json!({
+            "header": self.header.to_json(),
+            "transactions": self.body.tx_payload.to_json(),
+            "update": self.body.upd_payload.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for MintedBlock<'_> {
+    fn to_json(&self) -> Value {
+        json!({
+            "header": self.header.to_json(),
+            "transactions": self.body.tx_payload.to_json(),
+            "update": self.body.upd_payload.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for EbbHead {
+    fn to_json(&self) -> Value {
+        json!({
+            "protocolMagic": self.protocol_magic,
+            "prevHash": self.prev_block.to_json(),
+            "epoch": self.consensus_data.epoch_id,
+            "difficulty": self.consensus_data.difficulty.first(),
+        })
+    }
+}
+
+impl ToCanonicalJson for EbBlock {
+    fn to_json(&self) -> Value {
+        json!({
+            "header": self.header.to_json(),
+            "stakeholders": self.body.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for MintedEbBlock<'_> {
+    fn to_json(&self) -> Value {
+        json!({
+            "header": self.header.to_json(),
+            "stakeholders": self.body.to_json(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+
+    use crate::byron::{EbBlock, MintedBlock};
+    use crate::ToCanonicalJson;
+
+    type BlockWrapper<'b> = (u16, MintedBlock<'b>);
+
+    #[test]
+    fn test_blocks_serialize_as_expected() {
+        let bytes = hex::decode(include_str!("../../../test_data/byron1.block")).unwrap();
+        let (_, block): BlockWrapper = minicbor::decode(&bytes[..]).unwrap();
+
+        let json = block.to_json();
+
+        assert_eq!(json["header"]["protocolMagic"], 764824073);
+        assert_eq!(json["header"]["slot"], block.header.consensus_data.0.slot);
+
+        let txs = json["transactions"].as_array().unwrap();
+        assert_eq!(txs.len(), block.body.tx_payload.len());
+
+        for tx in txs {
+            for input in tx["body"]["inputs"].as_array().unwrap() {
+                assert!(input.as_str().unwrap().contains('#'));
+            }
+
+            for output in tx["body"]["outputs"].as_array().unwrap() {
+                let address = output["address"].as_str().unwrap();
+                assert!(["Ae2", "Ddz"].iter().any(|x| address.starts_with(x)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_boundary_blocks_serialize_as_expected() {
+        let bytes = hex::decode(include_str!("../../../test_data/genesis.block")).unwrap();
+        let (_, block): (u16, EbBlock) = minicbor::decode(&bytes[..]).unwrap();
+
+        let json = block.to_json();
+
+        assert_eq!(json["header"]["epoch"], 0);
+        assert_eq!(
+            json["stakeholders"].as_array().unwrap().len(),
+            block.body.len()
+        );
+    }
+}
@@ -2,4 +2,7 @@
 
 mod model;
 
+#[cfg(feature = "json")]
+pub mod json;
+
 pub use model::*;
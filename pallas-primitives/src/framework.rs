@@ -23,7 +23,201 @@ where
     }
 }
 
+/// Implement the cbor codec of a struct encoded as a map
+///
+/// The struct needs a `layout` field to keep the order of the keys and the
+/// kind of length of the map, so that it's re-encoded as it was found. As with
+/// the derived codecs, missing entries are decoded as `None` for optional
+/// fields and `None` fields are left out of the encoded map.
+macro_rules! map_codec {
+    ($name:ident<$lt:lifetime> { $($key:literal => $field:ident),+ $(,)? }) => {
+        impl<'b, C> pallas_codec::minicbor::Decode<'b, C> for $name<'b> {
+            $crate::framework::map_codec!(@decode $($key => $field),+);
+        }
+
+        impl<C> pallas_codec::minicbor::Encode<C> for $name<'_> {
+            $crate::framework::map_codec!(@encode $($key => $field),+);
+        }
+    };
+    ($name:ident$(<$($g:ident),+>)? { $($key:literal => $field:ident),+ $(,)? }) => {
+        impl<'b, C, $($($g),+)?> pallas_codec::minicbor::Decode<'b, C> for $name$(<$($g),+>)?
+        where
+            $($($g: pallas_codec::minicbor::Decode<'b, C>),+)?
+        {
+            $crate::framework::map_codec!(@decode $($key => $field),+);
+        }
+
+        impl<C, $($($g),+)?> pallas_codec::minicbor::Encode<C> for $name$(<$($g),+>)?
+        where
+            $($($g: pallas_codec::minicbor::Encode<C>),+)?
+        {
+            $crate::framework::map_codec!(@encode $($key => $field),+);
+        }
+    };
+    (@decode $($key:literal => $field:ident),+) => {
+        fn decode(
+            d: &mut pallas_codec::minicbor::Decoder<'b>,
+            ctx: &mut C,
+        ) -> Result<Self, pallas_codec::minicbor::decode::Error> {
+            $(let mut $field = None;)+
+
+            let layout = pallas_codec::utils::MapLayout::decode_entries(d, |key, d| {
+                match key {
+                    $($key => $field = Some(d.decode_with(ctx)?),)+
+                    _ => d.skip()?,
+                }
+
+                Ok(())
+            })?;
+
+            Ok(Self {
+                $(
+                    $field: match $field {
+                        Some(x) => x,
+                        None => pallas_codec::minicbor::Decode::<'b, C>::nil().ok_or_else(|| {
+                            pallas_codec::minicbor::decode::Error::missing_value($key)
+                                .with_message(stringify!($field))
+                        })?,
+                    },
+                )+
+                layout,
+            })
+        }
+    };
+    (@encode $($key:literal => $field:ident),+) => {
+        fn encode<W: pallas_codec::minicbor::encode::Write>(
+            &self,
+            e: &mut pallas_codec::minicbor::Encoder<W>,
+            ctx: &mut C,
+        ) -> Result<(), pallas_codec::minicbor::encode::Error<W::Error>> {
+            let mut keys: Vec<u64> = vec![];
+
+            $(
+                if !pallas_codec::minicbor::Encode::<C>::is_nil(&self.$field) {
+                    keys.push($key);
+                }
+            )+
+
+            self.layout.encode_entries(e, &keys, |key, e| {
+                match key {
+                    $($key => {
+                        e.encode_with(&self.$field, ctx)?;
+                    })+
+                    _ => unreachable!(),
+                }
+
+                Ok(())
+            })
+        }
+    };
+}
+
+pub(crate) use map_codec;
+
+/// Conversion into the JSON representation used by cardano-cli and db-sync
 #[cfg(feature = "json")]
 pub trait ToCanonicalJson {
     fn to_json(&self) -> serde_json::Value;
 }
+
+/// Error building a ledger type out of its JSON representation
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    /// the JSON value doesn't have the shape of the expected type
+    UnexpectedValue(String),
+
+    /// a string that should be hex encoded isn't
+    InvalidHex(String),
+
+    /// byte strings can't be longer than 64 bytes
+    BytesTooLong(usize),
+
+    /// text strings can't be longer than 64 bytes once UTF-8 encoded
+    TextTooLong(usize),
+
+    /// an integer doesn't fit in the range allowed by the ledger
+    IntegerOutOfRange(String),
+
+    /// a map key that can't be used as a metadata label
+    InvalidKey(String),
+}
+
+#[cfg(feature = "json")]
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::UnexpectedValue(x) => write!(f, "unexpected JSON value: {x}"),
+            JsonError::InvalidHex(x) => write!(f, "invalid hex string: {x}"),
+            JsonError::BytesTooLong(x) => write!(f, "bytes longer than 64 bytes: {x}"),
+            JsonError::TextTooLong(x) => write!(f, "text longer than 64 bytes: {x}"),
+            JsonError::IntegerOutOfRange(x) => write!(f, "integer out of range: {x}"),
+            JsonError::InvalidKey(x) => write!(f, "invalid key: {x}"),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for JsonError {}
+
+/// Conversion from the JSON representation used by cardano-cli, the inverse
+/// of [ToCanonicalJson]
+#[cfg(feature = "json")]
+pub trait FromCanonicalJson: Sized {
+    fn from_json(value: &serde_json::Value) -> Result<Self, JsonError>;
+}
+
+#[cfg(feature = "json")]
+impl<T: ToCanonicalJson> ToCanonicalJson for Vec<T> {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Array(self.iter().map(|x| x.to_json()).collect())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: ToCanonicalJson> ToCanonicalJson for Option<T> {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Some(x) => x.to_json(),
+            None => serde_json::Value::Null,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: ToCanonicalJson + Clone> ToCanonicalJson for pallas_codec::utils::Nullable<T> {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            pallas_codec::utils::Nullable::Some(x) => x.to_json(),
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: ToCanonicalJson> ToCanonicalJson for pallas_codec::utils::KeepRaw<'_, T> {
+    fn to_json(&self) -> serde_json::Value {
+        (**self).to_json()
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: ToCanonicalJson> ToCanonicalJson for pallas_codec::utils::MaybeIndefArray<T> {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Array(self.iter().map(|x| x.to_json()).collect())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<const BYTES: usize> ToCanonicalJson for pallas_crypto::hash::Hash<BYTES> {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::String(self.to_string())
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToCanonicalJson for pallas_codec::utils::Bytes {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::String(hex::encode(self.as_slice()))
+    }
+}
@@ -2,8 +2,11 @@
 
 mod framework;
 
+pub mod allegra;
 pub mod alonzo;
 pub mod babbage;
 pub mod byron;
+pub mod mary;
+pub mod shelley;
 
 pub use framework::*;
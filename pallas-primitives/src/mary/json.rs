@@ -0,0 +1,65 @@
+use serde_json::{json, Value};
+
+use super::{TransactionBody, TransactionOutput};
+use crate::alonzo::json::{address, multiasset, withdrawals};
+use crate::ToCanonicalJson;
+
+impl ToCanonicalJson for TransactionOutput {
+    fn to_json(&self) -> Value {
+        json!({
+            "address": address(&self.address),
+            "value": self.amount.to_json(),
+        })
+    }
+}
+
+impl ToCanonicalJson for TransactionBody {
+    fn to_json(&self) -> Value {
+        json!({
+            "inputs": self.inputs.to_json(),
+            "outputs": self.outputs.to_json(),
+            "fee": self.fee,
+            "ttl": self.ttl,
+            "certificates": self.certificates.to_json(),
+            "withdrawals": self.withdrawals.as_ref().map(withdrawals),
+            "update": self.update.to_json(),
+            "auxiliaryDataHash": self.auxiliary_data_hash.to_json(),
+            "validityIntervalStart": self.validity_interval_start,
+            "mint": self.mint.as_ref().map(multiasset),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+
+    use crate::mary::MintedBlock;
+    use crate::ToCanonicalJson;
+
+    type BlockWrapper<'b> = (u16, MintedBlock<'b>);
+
+    #[test]
+    fn test_blocks_serialize_as_expected() {
+        let bytes = hex::decode(include_str!("../../../test_data/mary1.block")).unwrap();
+        let (_, block): BlockWrapper = minicbor::decode(&bytes[..]).unwrap();
+
+        let json = block.to_json();
+
+        let txs = json["transactions"].as_array().unwrap();
+        assert_eq!(txs.len(), block.transaction_bodies.len());
+
+        for (tx, body) in txs.iter().zip(block.transaction_bodies.iter()) {
+            assert_eq!(tx["body"]["fee"], body.fee);
+
+            for (output, expected) in tx["body"]["outputs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .zip(body.outputs.iter())
+            {
+                assert_eq!(output["value"], expected.amount.to_json());
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Ledger primitives and cbor codec for the Mary era
+//!
+//! Handcrafted, idiomatic rust artifacts based on the [Mary CDDL](https://github.com/input-output-hk/cardano-ledger/blob/master/eras/mary/test-suite/cddl-files/mary.cddl) file in IOHK repo.
+
+use serde::{Deserialize, Serialize};
+
+use pallas_codec::minicbor::{Decode, Encode};
+
+use pallas_codec::utils::{Bytes, KeepRaw, MapLayout, MaybeIndefArray};
+
+// required for derive attrs to work
+use pallas_codec::minicbor;
+
+use crate::framework::map_codec;
+
+pub use crate::allegra::VrfCert;
+
+pub use crate::allegra::HeaderBody;
+
+pub use crate::allegra::ProtocolVersion;
+
+pub use crate::allegra::KesSignature;
+
+pub use crate::allegra::Header;
+
+pub use crate::allegra::TransactionInput;
+
+pub use crate::allegra::NonceVariant;
+
+pub use crate::allegra::Nonce;
+
+pub use crate::allegra::Coin;
+
+pub use crate::allegra::PoolKeyhash;
+
+pub use crate::allegra::Epoch;
+
+pub use crate::allegra::Genesishash;
+
+pub use crate::allegra::GenesisDelegateHash;
+
+pub use crate::allegra::VrfKeyhash;
+
+pub use crate::allegra::InstantaneousRewardSource;
+
+pub use crate::allegra::InstantaneousRewardTarget;
+
+pub use crate::allegra::MoveInstantaneousReward;
+
+pub use crate::allegra::RewardAccount;
+
+pub use crate::allegra::Withdrawals;
+
+pub use crate::allegra::Port;
+
+pub use crate::allegra::IPv4;
+
+pub use crate::allegra::IPv6;
+
+pub use crate::allegra::DnsName;
+
+pub use crate::allegra::Relay;
+
+pub use crate::allegra::PoolMetadataHash;
+
+pub use crate::allegra::PoolMetadata;
+
+pub use crate::allegra::AddrKeyhash;
+
+pub use crate::allegra::Scripthash;
+
+pub use crate::allegra::RationalNumber;
+
+pub use crate::allegra::UnitInterval;
+
+pub use crate::allegra::PositiveInterval;
+
+pub use crate::allegra::StakeCredential;
+
+pub use crate::allegra::Certificate;
+
+pub use crate::allegra::ProtocolParamUpdate;
+
+pub use crate::allegra::Update;
+
+pub use crate::allegra::VKeyWitness;
+
+pub use crate::allegra::BootstrapWitness;
+
+pub use crate::allegra::Metadatum;
+
+pub use crate::allegra::MetadatumLabel;
+
+pub use crate::allegra::Metadata;
+
+pub use crate::allegra::TransactionIndex;
+
+pub use crate::allegra::NativeScript;
+
+pub use crate::allegra::WitnessSet;
+
+pub use crate::allegra::AuxiliaryData;
+
+pub use crate::alonzo::ScriptHash;
+
+pub use crate::alonzo::PolicyId;
+
+pub use crate::alonzo::AssetName;
+
+pub use crate::alonzo::Multiasset;
+
+pub use crate::alonzo::Mint;
+
+pub use crate::alonzo::Value;
+
+/// Same as in Shelley, but the amount can now hold native assets too
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct TransactionOutput {
+    #[n(0)]
+    pub address: Bytes,
+
+    #[n(1)]
+    pub amount: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TransactionBody {
+    pub inputs: MaybeIndefArray<TransactionInput>,
+
+    pub outputs: MaybeIndefArray<TransactionOutput>,
+
+    pub fee: u64,
+
+    pub ttl: Option<u64>,
+
+    pub certificates: Option<MaybeIndefArray<Certificate>>,
+
+    pub withdrawals: Option<Withdrawals>,
+
+    pub update: Option<Update>,
+
+    pub auxiliary_data_hash: Option<Bytes>,
+
+    pub validity_interval_start: Option<u64>,
+
+    pub mint: Option<Mint>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(TransactionBody {
+    0 => inputs,
+    1 => outputs,
+    2 => fee,
+    3 => ttl,
+    4 => certificates,
+    5 => withdrawals,
+    6 => update,
+    7 => auxiliary_data_hash,
+    8 => validity_interval_start,
+    9 => mint,
+});
+
+pub use crate::allegra::PseudoBlock;
+
+pub type Block = PseudoBlock<Header, TransactionBody, WitnessSet, AuxiliaryData>;
+
+/// A memory representation of an already minted block
+///
+/// This structure is analogous to [Block], but it allows to retrieve the
+/// original CBOR bytes for each structure that might require hashing. In this
+/// way, we make sure that the resulting hash matches what exists on-chain.
+pub type MintedBlock<'b> = PseudoBlock<
+    KeepRaw<'b, Header>,
+    KeepRaw<'b, TransactionBody>,
+    KeepRaw<'b, WitnessSet>,
+    KeepRaw<'b, AuxiliaryData>,
+>;
+
+impl<'b> From<MintedBlock<'b>> for Block {
+    fn from(x: MintedBlock<'b>) -> Self {
+        Block {
+            header: x.header.unwrap(),
+            transaction_bodies: x.transaction_bodies.map(|x| x.unwrap()),
+            transaction_witness_sets: x.transaction_witness_sets.map(|x| x.unwrap()),
+            auxiliary_data_set: x
+                .auxiliary_data_set
+                .to_vec()
+                .into_iter()
+                .map(|(k, v)| (k, v.unwrap()))
+                .collect::<Vec<_>>()
+                .into(),
+        }
+    }
+}
+
+pub use crate::allegra::PseudoTx;
+
+pub type Tx = PseudoTx<TransactionBody, WitnessSet, AuxiliaryData>;
+
+pub type MintedTx<'b> =
+    PseudoTx<KeepRaw<'b, TransactionBody>, KeepRaw<'b, WitnessSet>, KeepRaw<'b, AuxiliaryData>>;
+
+impl<'b> From<MintedTx<'b>> for Tx {
+    fn from(x: MintedTx<'b>) -> Self {
+        Tx {
+            transaction_body: x.transaction_body.unwrap(),
+            transaction_witness_set: x.transaction_witness_set.unwrap(),
+            auxiliary_data: x.auxiliary_data.map(|x| x.unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+
+    use super::MintedBlock;
+
+    type BlockWrapper<'b> = (u16, MintedBlock<'b>);
+
+    #[test]
+    fn block_isomorphic_decoding_encoding() {
+        let test_blocks = [include_str!("../../../test_data/mary1.block")];
+
+        for (idx, block_str) in test_blocks.iter().enumerate() {
+            println!("decoding test block {}", idx + 1);
+            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {idx}"));
+
+            let block: BlockWrapper = minicbor::decode(&bytes[..])
+                .unwrap_or_else(|e| panic!("error decoding cbor for file {idx}: {e:?}"));
+
+            let bytes2 = minicbor::to_vec(block)
+                .unwrap_or_else(|e| panic!("error encoding block cbor for file {idx}: {e:?}"));
+
+            assert!(bytes.eq(&bytes2), "re-encoded bytes didn't match original");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    MultisigScript, ProtocolParamUpdate, PseudoBlock, PseudoTx, TransactionBody, TransactionOutput,
+    Update, WitnessSet,
+};
+use crate::alonzo::json::{
+    address, block_txs, common_param_updates, list, put, tx, update, withdrawals,
+};
+use crate::ToCanonicalJson;
+
+impl ToCanonicalJson for TransactionOutput {
+    fn to_json(&self) -> Value {
+        json!({
+            "address": address(&self.address),
+            "value": { "lovelace": self.amount },
+        })
+    }
+}
+
+impl ToCanonicalJson for ProtocolParamUpdate {
+    fn to_json(&self) -> Value {
+        let mut map = Map::new();
+
+        common_param_updates!(&mut map, self);
+
+        let x = self;
+        put(
+            &mut map,
+            "decentralization",
+            x.decentralization_constant.as_ref().map(|v| v.to_json()),
+        );
+        put(
+            &mut map,
+            "extraPraosEntropy",
+            x.extra_entropy.as_ref().map(|v| v.to_json()),
+        );
+        put(&mut map, "minUTxOValue", x.min_utxo_value.map(|v| json!(v)));
+
+        Value::Object(map)
+    }
+}
+
+impl ToCanonicalJson for Update {
+    fn to_json(&self) -> Value {
+        update(&self.proposed_protocol_parameter_updates, self.epoch)
+    }
+}
+
+impl ToCanonicalJson for TransactionBody {
+    fn to_json(&self) -> Value {
+        json!({
+            "inputs": self.inputs.to_json(),
+            "outputs": self.outputs.to_json(),
+            "fee": self.fee,
+            "ttl": self.ttl,
+            "certificates": self.certificates.to_json(),
+            "withdrawals": self.withdrawals.as_ref().map(withdrawals),
+            "update": self.update.to_json(),
+            "auxiliaryDataHash": self.metadata_hash.to_json(),
+        })
+    }
+}
+
+// same shape as the native scripts of later eras, which extend multisig
+// scripts with timelocks
+impl ToCanonicalJson for MultisigScript {
+    fn to_json(&self) -> Value {
+        match self {
+            MultisigScript::Pubkey(x) => json!({ "type": "sig", "keyHash": x.to_string() }),
+            MultisigScript::All(x) => json!({ "type": "all", "scripts": x.to_json() }),
+            MultisigScript::Any(x) => json!({ "type": "any", "scripts": x.to_json() }),
+            MultisigScript::NOfK(n, x) => {
+                json!({ "type": "atLeast", "required": n, "scripts": x.to_json() })
+            }
+        }
+    }
+}
+
+impl ToCanonicalJson for WitnessSet {
+    fn to_json(&self) -> Value {
+        json!({
+            "vkeys": list(&self.vkeywitness),
+            "nativeScripts": list(&self.multisig_script),
+            "bootstraps": list(&self.bootstrap_witness),
+        })
+    }
+}
+
+/// Txs of every era up to Mary, there's no validity flag
+///
+/// In Shelley the auxiliary data is just metadata, which is written as a map
+/// of labels to metadatum.
+impl<T1, T2, T3> ToCanonicalJson for PseudoTx<T1, T2, T3>
+where
+    T1: ToCanonicalJson + Clone,
+    T2: ToCanonicalJson + Clone,
+    T3: ToCanonicalJson + Clone,
+{
+    fn to_json(&self) -> Value {
+        tx(
+            self.transaction_body.to_json(),
+            self.transaction_witness_set.to_json(),
+            None,
+            self.auxiliary_data.to_json(),
+        )
+    }
+}
+
+impl<T1, T2, T3, T4> ToCanonicalJson for PseudoBlock<T1, T2, T3, T4>
+where
+    T1: ToCanonicalJson,
+    T2: ToCanonicalJson,
+    T3: ToCanonicalJson,
+    T4: ToCanonicalJson + Clone,
+{
+    fn to_json(&self) -> Value {
+        json!({
+            "header": self.header.to_json(),
+            "transactions": block_txs(
+                &self.transaction_bodies,
+                &self.transaction_witness_sets,
+                &self.transaction_metadata_set,
+                None,
+                |x| x.to_json(),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+
+    use crate::shelley::MintedBlock;
+    use crate::ToCanonicalJson;
+
+    type BlockWrapper<'b> = (u16, MintedBlock<'b>);
+
+    #[test]
+    fn test_blocks_serialize_as_expected() {
+        let bytes = hex::decode(include_str!("../../../test_data/shelley1.block")).unwrap();
+        let (_, block): BlockWrapper = minicbor::decode(&bytes[..]).unwrap();
+
+        let json = block.to_json();
+
+        assert_eq!(
+            json["header"]["headerBody"]["slot"],
+            block.header.header_body.slot
+        );
+
+        let txs = json["transactions"].as_array().unwrap();
+        assert_eq!(txs.len(), block.transaction_bodies.len());
+
+        for (tx, body) in txs.iter().zip(block.transaction_bodies.iter()) {
+            assert_eq!(tx["body"]["ttl"], body.ttl);
+            assert_eq!(tx["body"]["fee"], body.fee);
+
+            // there's no script validation before Alonzo
+            assert!(tx.get("isValid").is_none());
+
+            for output in tx["body"]["outputs"].as_array().unwrap() {
+                assert!(output["value"]["lovelace"].is_u64());
+            }
+        }
+    }
+}
@@ -0,0 +1,384 @@
+//! Ledger primitives and cbor codec for the Shelley era
+//!
+//! Handcrafted, idiomatic rust artifacts based on the [Shelley CDDL](https://github.com/input-output-hk/cardano-ledger/blob/master/eras/shelley/test-suite/cddl-files/shelley.cddl) file in IOHK repo.
+
+use serde::{Deserialize, Serialize};
+
+use pallas_codec::minicbor::{Decode, Encode};
+
+use pallas_codec::utils::{Bytes, KeepRaw, KeyValuePairs, MapLayout, MaybeIndefArray, Nullable};
+
+// required for derive attrs to work
+use pallas_codec::minicbor;
+
+use crate::framework::map_codec;
+
+pub use crate::alonzo::VrfCert;
+
+pub use crate::alonzo::HeaderBody;
+
+pub use crate::alonzo::ProtocolVersion;
+
+pub use crate::alonzo::KesSignature;
+
+pub use crate::alonzo::Header;
+
+pub use crate::alonzo::TransactionInput;
+
+pub use crate::alonzo::NonceVariant;
+
+pub use crate::alonzo::Nonce;
+
+pub use crate::alonzo::Coin;
+
+pub use crate::alonzo::PoolKeyhash;
+
+pub use crate::alonzo::Epoch;
+
+pub use crate::alonzo::Genesishash;
+
+pub use crate::alonzo::GenesisDelegateHash;
+
+pub use crate::alonzo::VrfKeyhash;
+
+pub use crate::alonzo::InstantaneousRewardSource;
+
+pub use crate::alonzo::InstantaneousRewardTarget;
+
+pub use crate::alonzo::MoveInstantaneousReward;
+
+pub use crate::alonzo::RewardAccount;
+
+pub use crate::alonzo::Withdrawals;
+
+pub use crate::alonzo::Port;
+
+pub use crate::alonzo::IPv4;
+
+pub use crate::alonzo::IPv6;
+
+pub use crate::alonzo::DnsName;
+
+pub use crate::alonzo::Relay;
+
+pub use crate::alonzo::PoolMetadataHash;
+
+pub use crate::alonzo::PoolMetadata;
+
+pub use crate::alonzo::AddrKeyhash;
+
+pub use crate::alonzo::Scripthash;
+
+pub use crate::alonzo::RationalNumber;
+
+pub use crate::alonzo::UnitInterval;
+
+pub use crate::alonzo::PositiveInterval;
+
+pub use crate::alonzo::StakeCredential;
+
+pub use crate::alonzo::Certificate;
+
+pub use crate::alonzo::VKeyWitness;
+
+pub use crate::alonzo::BootstrapWitness;
+
+pub use crate::alonzo::Metadatum;
+
+pub use crate::alonzo::MetadatumLabel;
+
+pub use crate::alonzo::Metadata;
+
+pub use crate::alonzo::TransactionIndex;
+
+/// Outputs in Shelley can only hold ADA, there's no multi-asset support yet
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct TransactionOutput {
+    #[n(0)]
+    pub address: Bytes,
+
+    #[n(1)]
+    pub amount: Coin,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ProtocolParamUpdate {
+    pub minfee_a: Option<u32>,
+    pub minfee_b: Option<u32>,
+    pub max_block_body_size: Option<u32>,
+    pub max_transaction_size: Option<u32>,
+    pub max_block_header_size: Option<u32>,
+    pub key_deposit: Option<Coin>,
+    pub pool_deposit: Option<Coin>,
+    pub maximum_epoch: Option<Epoch>,
+    pub desired_number_of_stake_pools: Option<u32>,
+    pub pool_pledge_influence: Option<RationalNumber>,
+    pub expansion_rate: Option<UnitInterval>,
+    pub treasury_growth_rate: Option<UnitInterval>,
+    pub decentralization_constant: Option<UnitInterval>,
+    pub extra_entropy: Option<Nonce>,
+    pub protocol_version: Option<ProtocolVersion>,
+    pub min_utxo_value: Option<Coin>,
+    pub min_pool_cost: Option<Coin>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(ProtocolParamUpdate {
+    0 => minfee_a,
+    1 => minfee_b,
+    2 => max_block_body_size,
+    3 => max_transaction_size,
+    4 => max_block_header_size,
+    5 => key_deposit,
+    6 => pool_deposit,
+    7 => maximum_epoch,
+    8 => desired_number_of_stake_pools,
+    9 => pool_pledge_influence,
+    10 => expansion_rate,
+    11 => treasury_growth_rate,
+    12 => decentralization_constant,
+    13 => extra_entropy,
+    14 => protocol_version,
+    15 => min_utxo_value,
+    16 => min_pool_cost,
+});
+
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct Update {
+    #[n(0)]
+    pub proposed_protocol_parameter_updates: KeyValuePairs<Genesishash, ProtocolParamUpdate>,
+
+    #[n(1)]
+    pub epoch: Epoch,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TransactionBody {
+    pub inputs: MaybeIndefArray<TransactionInput>,
+
+    pub outputs: MaybeIndefArray<TransactionOutput>,
+
+    pub fee: u64,
+
+    /// In Shelley, the time-to-live is a mandatory field of the tx
+    pub ttl: u64,
+
+    pub certificates: Option<MaybeIndefArray<Certificate>>,
+
+    pub withdrawals: Option<Withdrawals>,
+
+    pub update: Option<Update>,
+
+    pub metadata_hash: Option<Bytes>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(TransactionBody {
+    0 => inputs,
+    1 => outputs,
+    2 => fee,
+    3 => ttl,
+    4 => certificates,
+    5 => withdrawals,
+    6 => update,
+    7 => metadata_hash,
+});
+
+/// The native scripts of the Shelley era, without any timelock support
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum MultisigScript {
+    Pubkey(AddrKeyhash),
+    All(Vec<MultisigScript>),
+    Any(Vec<MultisigScript>),
+    NOfK(u32, Vec<MultisigScript>),
+}
+
+impl<'b, C> minicbor::decode::Decode<'b, C> for MultisigScript {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+        let variant = d.u32()?;
+
+        match variant {
+            0 => Ok(MultisigScript::Pubkey(d.decode_with(ctx)?)),
+            1 => Ok(MultisigScript::All(d.decode_with(ctx)?)),
+            2 => Ok(MultisigScript::Any(d.decode_with(ctx)?)),
+            3 => Ok(MultisigScript::NOfK(
+                d.decode_with(ctx)?,
+                d.decode_with(ctx)?,
+            )),
+            _ => Err(minicbor::decode::Error::message(
+                "unknown variant id for multisig script",
+            )),
+        }
+    }
+}
+
+impl<C> minicbor::encode::Encode<C> for MultisigScript {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        match self {
+            MultisigScript::Pubkey(v) => {
+                e.array(2)?;
+                e.encode_with(0, ctx)?;
+                e.encode_with(v, ctx)?;
+            }
+            MultisigScript::All(v) => {
+                e.array(2)?;
+                e.encode_with(1, ctx)?;
+                e.encode_with(v, ctx)?;
+            }
+            MultisigScript::Any(v) => {
+                e.array(2)?;
+                e.encode_with(2, ctx)?;
+                e.encode_with(v, ctx)?;
+            }
+            MultisigScript::NOfK(a, b) => {
+                e.array(3)?;
+                e.encode_with(3, ctx)?;
+                e.encode_with(a, ctx)?;
+                e.encode_with(b, ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct WitnessSet {
+    pub vkeywitness: Option<MaybeIndefArray<VKeyWitness>>,
+
+    pub multisig_script: Option<MaybeIndefArray<MultisigScript>>,
+
+    pub bootstrap_witness: Option<MaybeIndefArray<BootstrapWitness>>,
+
+    #[serde(skip)]
+    pub layout: MapLayout,
+}
+
+map_codec!(WitnessSet {
+    0 => vkeywitness,
+    1 => multisig_script,
+    2 => bootstrap_witness,
+});
+
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Clone)]
+pub struct PseudoBlock<T1, T2, T3, T4>
+where
+    T4: std::clone::Clone,
+{
+    #[n(0)]
+    pub header: T1,
+
+    #[b(1)]
+    pub transaction_bodies: MaybeIndefArray<T2>,
+
+    #[n(2)]
+    pub transaction_witness_sets: MaybeIndefArray<T3>,
+
+    #[n(3)]
+    pub transaction_metadata_set: KeyValuePairs<TransactionIndex, T4>,
+}
+
+pub type Block = PseudoBlock<Header, TransactionBody, WitnessSet, Metadata>;
+
+/// A memory representation of an already minted block
+///
+/// This structure is analogous to [Block], but it allows to retrieve the
+/// original CBOR bytes for each structure that might require hashing. In this
+/// way, we make sure that the resulting hash matches what exists on-chain.
+pub type MintedBlock<'b> = PseudoBlock<
+    KeepRaw<'b, Header>,
+    KeepRaw<'b, TransactionBody>,
+    KeepRaw<'b, WitnessSet>,
+    KeepRaw<'b, Metadata>,
+>;
+
+impl<'b> From<MintedBlock<'b>> for Block {
+    fn from(x: MintedBlock<'b>) -> Self {
+        Block {
+            header: x.header.unwrap(),
+            transaction_bodies: x.transaction_bodies.map(|x| x.unwrap()),
+            transaction_witness_sets: x.transaction_witness_sets.map(|x| x.unwrap()),
+            transaction_metadata_set: x
+                .transaction_metadata_set
+                .to_vec()
+                .into_iter()
+                .map(|(k, v)| (k, v.unwrap()))
+                .collect::<Vec<_>>()
+                .into(),
+        }
+    }
+}
+
+/// A standalone Shelley tx, there's no script validity flag in this era
+#[derive(Clone, Serialize, Deserialize, Encode, Decode, Debug)]
+pub struct PseudoTx<T1, T2, T3>
+where
+    T1: std::clone::Clone,
+    T2: std::clone::Clone,
+    T3: std::clone::Clone,
+{
+    #[n(0)]
+    pub transaction_body: T1,
+
+    #[n(1)]
+    pub transaction_witness_set: T2,
+
+    /// In Shelley this is just metadata, later eras allow scripts too
+    #[n(2)]
+    pub auxiliary_data: Nullable<T3>,
+}
+
+pub type Tx = PseudoTx<TransactionBody, WitnessSet, Metadata>;
+
+pub type MintedTx<'b> =
+    PseudoTx<KeepRaw<'b, TransactionBody>, KeepRaw<'b, WitnessSet>, KeepRaw<'b, Metadata>>;
+
+impl<'b> From<MintedTx<'b>> for Tx {
+    fn from(x: MintedTx<'b>) -> Self {
+        Tx {
+            transaction_body: x.transaction_body.unwrap(),
+            transaction_witness_set: x.transaction_witness_set.unwrap(),
+            auxiliary_data: x.auxiliary_data.map(|x| x.unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+
+    use super::{Block, MintedBlock};
+
+    type BlockWrapper<'b> = (u16, MintedBlock<'b>);
+
+    #[test]
+    fn block_isomorphic_decoding_encoding() {
+        let test_blocks = [include_str!("../../../test_data/shelley1.block")];
+
+        for (idx, block_str) in test_blocks.iter().enumerate() {
+            println!("decoding test block {}", idx + 1);
+            let bytes = hex::decode(block_str).unwrap_or_else(|_| panic!("bad block file {idx}"));
+
+            let block: BlockWrapper = minicbor::decode(&bytes[..])
+                .unwrap_or_else(|e| panic!("error decoding cbor for file {idx}: {e:?}"));
+
+            let bytes2 = minicbor::to_vec(&block)
+                .unwrap_or_else(|e| panic!("error encoding block cbor for file {idx}: {e:?}"));
+
+            assert!(bytes.eq(&bytes2), "re-encoded bytes didn't match original");
+
+            // the owned version should hold the same data
+            let owned = Block::from(block.1);
+            assert!(owned.transaction_bodies.iter().all(|tx| tx.ttl > 0));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Decoding and re-encoding every CBOR file of the test data must give back
+//! the original bytes, byte for byte
+
+use std::path::Path;
+
+use pallas_codec::minicbor;
+use pallas_primitives::{allegra, alonzo, babbage, byron, mary, shelley};
+
+type Check = fn(&[u8]) -> Vec<u8>;
+
+fn roundtrip<T>(bytes: &[u8]) -> Vec<u8>
+where
+    T: for<'b> minicbor::Decode<'b, ()> + minicbor::Encode<()>,
+{
+    let value: T = minicbor::decode(bytes).unwrap();
+    minicbor::to_vec(value).unwrap()
+}
+
+fn minted_roundtrip<'b, M, T>(bytes: &'b [u8]) -> Vec<u8>
+where
+    M: minicbor::Decode<'b, ()>,
+    T: From<M> + minicbor::Encode<()>,
+{
+    let minted: M = minicbor::decode(bytes).unwrap();
+    minicbor::to_vec(T::from(minted)).unwrap()
+}
+
+fn minted_block_roundtrip<'b, M, T>(bytes: &'b [u8]) -> Vec<u8>
+where
+    M: minicbor::Decode<'b, ()>,
+    T: From<M> + minicbor::Encode<()>,
+{
+    let (era, minted): (u16, M) = minicbor::decode(bytes).unwrap();
+    minicbor::to_vec((era, T::from(minted))).unwrap()
+}
+
+fn minted_reencode<'b, M>(bytes: &'b [u8]) -> Vec<u8>
+where
+    M: minicbor::Decode<'b, ()> + minicbor::Encode<()>,
+{
+    let minted: M = minicbor::decode(bytes).unwrap();
+    minicbor::to_vec(minted).unwrap()
+}
+
+/// files with plutus data whose lists aren't laid out the way the haskell
+/// implementation builds them. The owned models normalise plutus data, so
+/// these only give back their bytes through the minted models, which keep
+/// the datums raw.
+const RAW_DATUMS: &[&str] = &[
+    "alonzo9.block",
+    "alonzo14.block",
+    "babbage2.tx",
+    "babbage6.block",
+    "babbage9.block",
+];
+
+/// the codecs to check for a file of the test data, `None` for files that
+/// aren't CBOR
+fn checks(name: &str) -> Option<Vec<Check>> {
+    let checks: Vec<Check> = match name {
+        x if RAW_DATUMS.contains(&x) && x.ends_with(".tx") => {
+            vec![|x| minted_reencode::<babbage::MintedTx>(x)]
+        }
+        x if RAW_DATUMS.contains(&x) && x.starts_with("alonzo") => {
+            vec![|x| minted_reencode::<(u16, alonzo::MintedBlock)>(x)]
+        }
+        x if RAW_DATUMS.contains(&x) => {
+            vec![|x| minted_reencode::<(u16, babbage::MintedBlock)>(x)]
+        }
+        "genesis.block" => vec![roundtrip::<(u16, byron::EbBlock)>],
+        "byron1.header" => vec![roundtrip::<byron::BlockHead>],
+        "alonzo26.header" => vec![roundtrip::<alonzo::Header>],
+        "babbage1.fr" => vec![roundtrip::<Vec<babbage::TransactionOutput>>],
+        "jpgstore.plutus" => vec![roundtrip::<alonzo::PlutusScript>],
+        "v2script.plutus" => vec![roundtrip::<babbage::PlutusV2Script>],
+        "indef.timelock" => vec![roundtrip::<alonzo::NativeScript>],
+        x if x.ends_with(".tx") => vec![roundtrip::<babbage::Tx>, |x| {
+            minted_roundtrip::<babbage::MintedTx, babbage::Tx>(x)
+        }],
+        x if x.starts_with("byron") => vec![roundtrip::<(u16, byron::Block)>],
+        x if x.starts_with("shelley") => vec![roundtrip::<(u16, shelley::Block)>, |x| {
+            minted_block_roundtrip::<shelley::MintedBlock, shelley::Block>(x)
+        }],
+        x if x.starts_with("allegra") => vec![roundtrip::<(u16, allegra::Block)>, |x| {
+            minted_block_roundtrip::<allegra::MintedBlock, allegra::Block>(x)
+        }],
+        x if x.starts_with("mary") => vec![roundtrip::<(u16, mary::Block)>, |x| {
+            minted_block_roundtrip::<mary::MintedBlock, mary::Block>(x)
+        }],
+        x if x.starts_with("alonzo") && x.ends_with(".block") => {
+            vec![roundtrip::<(u16, alonzo::Block)>, |x| {
+                minted_block_roundtrip::<alonzo::MintedBlock, alonzo::Block>(x)
+            }]
+        }
+        x if x.starts_with("babbage") && x.ends_with(".block") => {
+            vec![roundtrip::<(u16, babbage::Block)>, |x| {
+                minted_block_roundtrip::<babbage::MintedBlock, babbage::Block>(x)
+            }]
+        }
+        // JSON files, covered by the tests of the JSON representations
+        x if x.ends_with(".json") || x.ends_with(".datums") || x.ends_with(".native") => {
+            return None
+        }
+        x => panic!("unknown kind of test data file {x}"),
+    };
+
+    Some(checks)
+}
+
+#[test]
+fn test_data_roundtrips_byte_for_byte() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_data");
+
+    let mut files = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+
+        let checks = match checks(&name) {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let bytes = hex::decode(content.trim()).unwrap();
+
+        for check in checks {
+            let bytes2 = check(&bytes);
+            assert!(bytes == bytes2, "re-encoded bytes of {name} didn't match");
+        }
+
+        files += 1;
+    }
+
+    assert!(files > 50);
+}
@@ -0,0 +1,335 @@
+//! Append-only block store with a rollback-aware chain index
+
+use std::ops::Bound;
+use std::path::Path;
+
+use pallas_crypto::hash::Hash;
+use pallas_traverse::MultiEraBlock;
+use redb::{Database, ReadableTable, TableDefinition};
+
+use crate::Error;
+
+/// Raw block CBOR indexed by block hash, never pruned on rollbacks
+const BLOCKS: TableDefinition<&[u8; 32], &[u8]> = TableDefinition::new("blocks");
+
+/// Hashes of the blocks in the current chain, indexed by slot
+///
+/// Byron epoch boundary blocks share the slot with the first block of the
+/// epoch, the second element of the key keeps track of the order of arrival
+/// for blocks within the same slot.
+const CHAIN: TableDefinition<(u64, u8), &[u8; 32]> = TableDefinition::new("chain");
+
+/// A chain point, expressed as the slot and hash of a block
+pub type Point = (u64, Hash<32>);
+
+/// A durable store of blocks that follows the chain as it evolves
+///
+/// Blocks are written once and kept even after being rolled back, only the
+/// chain index is affected by rollbacks.
+pub struct BlockStore {
+    db: Database,
+}
+
+impl BlockStore {
+    /// Opens the store at the given path, creating it if required
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let db = Database::create(path)?;
+
+        // make sure the tables exist so that read txs don't fail on empty dbs
+        let wx = db.begin_write()?;
+        wx.open_table(BLOCKS)?;
+        wx.open_table(CHAIN)?;
+        wx.commit()?;
+
+        Ok(Self { db })
+    }
+
+    /// Appends a block to the chain, returning the point it represents
+    ///
+    /// The block needs to be at a slot equal or greater than the current tip.
+    /// Equal slots are allowed to support Byron epoch boundary blocks.
+    pub fn roll_forward(&self, cbor: &[u8]) -> Result<Point, Error> {
+        let block = MultiEraBlock::decode(cbor).map_err(Error::invalid_cbor)?;
+        let (slot, hash) = (block.slot(), block.hash());
+
+        let wx = self.db.begin_write()?;
+
+        {
+            let mut chain = wx.open_table(CHAIN)?;
+
+            let tip = chain
+                .last()?
+                .map(|(k, v)| (k.value(), Hash::new(*v.value())));
+
+            let seq = match tip {
+                Some(((tip_slot, _), _)) if slot < tip_slot => {
+                    return Err(Error::NotExtendingTip(slot, tip_slot))
+                }
+                Some(((tip_slot, seq), tip_hash)) if slot == tip_slot => {
+                    if tip_hash == hash {
+                        return Err(Error::NotExtendingTip(slot, tip_slot));
+                    }
+
+                    seq.checked_add(1)
+                        .ok_or(Error::NotExtendingTip(slot, tip_slot))?
+                }
+                _ => 0,
+            };
+
+            chain.insert((slot, seq), &*hash)?;
+
+            let mut blocks = wx.open_table(BLOCKS)?;
+            blocks.insert(&*hash, cbor)?;
+        }
+
+        wx.commit()?;
+
+        Ok((slot, hash))
+    }
+
+    /// Removes from the chain any block after the given point
+    ///
+    /// Returns the points that were rolled back, from older to newer. Fails
+    /// without altering the store if the point is not part of the chain.
+    pub fn rollback_to(&self, slot: u64, hash: &Hash<32>) -> Result<Vec<Point>, Error> {
+        let wx = self.db.begin_write()?;
+
+        let reverted = {
+            let mut chain = wx.open_table(CHAIN)?;
+
+            let mut found = None;
+
+            for entry in chain.range((slot, 0)..=(slot, u8::MAX))? {
+                let (k, v) = entry?;
+
+                if v.value() == &**hash {
+                    found = Some(k.value());
+                }
+            }
+
+            let key = found.ok_or(Error::UnknownRollbackPoint(slot, *hash))?;
+
+            let mut reverted = vec![];
+
+            for entry in chain.drain::<(u64, u8)>((Bound::Excluded(key), Bound::Unbounded))? {
+                let (k, v) = entry?;
+                reverted.push((k.value().0, Hash::new(*v.value())));
+            }
+
+            reverted
+        };
+
+        wx.commit()?;
+
+        Ok(reverted)
+    }
+
+    /// The latest point of the chain, if any
+    pub fn tip(&self) -> Result<Option<Point>, Error> {
+        let rx = self.db.begin_read()?;
+        let chain = rx.open_table(CHAIN)?;
+
+        let tip = chain
+            .last()?
+            .map(|(k, v)| (k.value().0, Hash::new(*v.value())));
+
+        Ok(tip)
+    }
+
+    /// Returns the CBOR of a block by hash, even if it was rolled back
+    pub fn get_block(&self, hash: &Hash<32>) -> Result<Option<Vec<u8>>, Error> {
+        let rx = self.db.begin_read()?;
+        let blocks = rx.open_table(BLOCKS)?;
+
+        let cbor = blocks.get(&**hash)?.map(|x| x.value().to_vec());
+
+        Ok(cbor)
+    }
+
+    /// Returns the CBOR of the chain block at the given slot
+    ///
+    /// If more than one block shares the slot (epoch boundary blocks), the
+    /// latest one is returned.
+    pub fn get_block_by_slot(&self, slot: u64) -> Result<Option<Vec<u8>>, Error> {
+        let rx = self.db.begin_read()?;
+        let chain = rx.open_table(CHAIN)?;
+
+        let hash = match chain.range((slot, 0)..=(slot, u8::MAX))?.next_back() {
+            Some(entry) => Hash::new(*entry?.1.value()),
+            None => return Ok(None),
+        };
+
+        let blocks = rx.open_table(BLOCKS)?;
+        let cbor = blocks.get(&*hash)?.map(|x| x.value().to_vec());
+
+        Ok(cbor)
+    }
+
+    /// Checks if the given point is part of the current chain
+    pub fn contains(&self, slot: u64, hash: &Hash<32>) -> Result<bool, Error> {
+        let rx = self.db.begin_read()?;
+        let chain = rx.open_table(CHAIN)?;
+
+        for entry in chain.range((slot, 0)..=(slot, u8::MAX))? {
+            if entry?.1.value() == &**hash {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns up to `max` chain points at or before the slot, newer first
+    pub fn ancestors(&self, slot: u64, max: usize) -> Result<Vec<Point>, Error> {
+        let rx = self.db.begin_read()?;
+        let chain = rx.open_table(CHAIN)?;
+
+        let points = chain
+            .range(..=(slot, u8::MAX))?
+            .rev()
+            .take(max)
+            .map(|entry| {
+                let (k, v) = entry?;
+                Ok((k.value().0, Hash::new(*v.value())))
+            })
+            .collect();
+
+        points
+    }
+
+    /// Returns up to `max` chain points at or after the slot, older first
+    pub fn chain_page(&self, slot: u64, max: usize) -> Result<Vec<Point>, Error> {
+        let rx = self.db.begin_read()?;
+        let chain = rx.open_table(CHAIN)?;
+
+        let points = chain
+            .range((slot, 0)..)?
+            .take(max)
+            .map(|entry| {
+                let (k, v) = entry?;
+                Ok((k.value().0, Hash::new(*v.value())))
+            })
+            .collect();
+
+        points
+    }
+
+    /// Returns the first of the given points that is part of the chain
+    ///
+    /// Points are expected in order of preference, usually newer first, as
+    /// in the chain-sync intersection negotiation.
+    pub fn find_intersect(&self, points: &[Point]) -> Result<Option<Point>, Error> {
+        for (slot, hash) in points {
+            if self.contains(*slot, hash)? {
+                return Ok(Some((*slot, *hash)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_blocks() -> Vec<Vec<u8>> {
+        let blocks = [
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/alonzo2.block"),
+            include_str!("../../test_data/alonzo3.block"),
+            include_str!("../../test_data/babbage1.block"),
+        ];
+
+        let mut blocks: Vec<_> = blocks
+            .iter()
+            .map(|x| hex::decode(x).expect("invalid hex"))
+            .collect();
+
+        blocks.sort_by_key(|x| MultiEraBlock::decode(x).unwrap().slot());
+
+        blocks
+    }
+
+    #[test]
+    fn roll_forward_and_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlockStore::open(dir.path().join("blocks.redb")).unwrap();
+
+        assert_eq!(store.tip().unwrap(), None);
+
+        let points: Vec<_> = load_blocks()
+            .iter()
+            .map(|cbor| store.roll_forward(cbor).unwrap())
+            .collect();
+
+        assert_eq!(store.tip().unwrap(), points.last().cloned());
+
+        let (slot, hash) = points[1];
+        assert!(store.contains(slot, &hash).unwrap());
+        assert!(store.get_block_by_slot(slot).unwrap().is_some());
+
+        let reverted = store.rollback_to(slot, &hash).unwrap();
+        assert_eq!(reverted, points[2..]);
+        assert_eq!(store.tip().unwrap(), Some((slot, hash)));
+
+        // rolled back blocks are no longer part of the chain but are kept
+        let (slot, hash) = points[3];
+        assert!(!store.contains(slot, &hash).unwrap());
+        assert!(store.get_block_by_slot(slot).unwrap().is_none());
+        assert!(store.get_block(&hash).unwrap().is_some());
+
+        let unknown = store.rollback_to(slot, &hash);
+        assert!(matches!(unknown, Err(Error::UnknownRollbackPoint(..))));
+    }
+
+    #[test]
+    fn rejects_blocks_behind_tip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlockStore::open(dir.path().join("blocks.redb")).unwrap();
+
+        let blocks = load_blocks();
+
+        store.roll_forward(&blocks[1]).unwrap();
+
+        let older = store.roll_forward(&blocks[0]);
+        assert!(matches!(older, Err(Error::NotExtendingTip(..))));
+
+        let repeated = store.roll_forward(&blocks[1]);
+        assert!(matches!(repeated, Err(Error::NotExtendingTip(..))));
+    }
+
+    #[test]
+    fn survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocks.redb");
+
+        let blocks = load_blocks();
+
+        let points: Vec<_> = {
+            let store = BlockStore::open(&path).unwrap();
+
+            blocks
+                .iter()
+                .map(|cbor| store.roll_forward(cbor).unwrap())
+                .collect()
+        };
+
+        let store = BlockStore::open(&path).unwrap();
+
+        assert_eq!(store.tip().unwrap(), points.last().cloned());
+
+        let (slot, hash) = points[2];
+        assert_eq!(store.get_block(&hash).unwrap().as_ref(), Some(&blocks[2]));
+
+        let ancestors = store.ancestors(slot, 2).unwrap();
+        assert_eq!(ancestors, vec![points[2], points[1]]);
+
+        let page = store.chain_page(points[1].0, 10).unwrap();
+        assert_eq!(page, points[1..]);
+
+        let unknown = (0, Hash::new([0; 32]));
+        let intersect = store.find_intersect(&[unknown, points[2]]).unwrap();
+        assert_eq!(intersect, Some(points[2]));
+    }
+}
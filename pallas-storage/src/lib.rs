@@ -0,0 +1,50 @@
+//! Durable storage of blocks and UTxOs backed by an embedded database
+
+use std::fmt::Display;
+
+use pallas_crypto::hash::Hash;
+use thiserror::Error;
+
+pub mod blocks;
+pub mod utxos;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Database error: {0}")]
+    Database(#[from] redb::Error),
+
+    #[error("Invalid CBOR structure: {0}")]
+    InvalidCbor(String),
+
+    #[error("Block at slot {0} doesn't extend the current tip at slot {1}")]
+    NotExtendingTip(u64, u64),
+
+    #[error("Can't rollback to unknown point: {0} {1}")]
+    UnknownRollbackPoint(u64, Hash<32>),
+}
+
+impl Error {
+    pub fn invalid_cbor(error: impl Display) -> Self {
+        Error::InvalidCbor(format!("{error}"))
+    }
+}
+
+macro_rules! from_redb_error {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Error {
+                fn from(value: $ty) -> Self {
+                    Error::Database(value.into())
+                }
+            }
+        )*
+    };
+}
+
+from_redb_error!(
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
@@ -0,0 +1,641 @@
+//! Durable UTxO set indexed by output ref, address and policy id
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use pallas_addresses::Address;
+use pallas_applying::utxo::EraCbor;
+use pallas_codec::minicbor::{self, Decode, Encode};
+use pallas_codec::utils::Bytes;
+use pallas_crypto::hash::Hash;
+use pallas_traverse::{Era, MultiEraBlock, MultiEraTx, OutputRef};
+use redb::{
+    Database, MultimapTable, MultimapTableDefinition, ReadOnlyTable, ReadableMultimapTable,
+    ReadableTable, Table, TableDefinition, WriteTransaction,
+};
+
+use crate::blocks::Point;
+use crate::Error;
+
+type UtxoKey = (&'static [u8; 32], u64);
+
+/// Output CBOR tagged with the era tag, indexed by tx hash and output index
+const UTXOS: TableDefinition<UtxoKey, (u16, &[u8])> = TableDefinition::new("utxos");
+
+/// Output refs indexed by the raw bytes of the address that locks them
+const BY_ADDRESS: MultimapTableDefinition<&[u8], UtxoKey> =
+    MultimapTableDefinition::new("utxos_by_address");
+
+/// Output refs indexed by the policy ids of the assets they hold
+const BY_POLICY: MultimapTableDefinition<&[u8; 28], UtxoKey> =
+    MultimapTableDefinition::new("utxos_by_policy");
+
+/// CBOR-encoded changes introduced by each block, indexed by slot
+///
+/// Same as the chain index of the block store, the second element of the key
+/// keeps the order of arrival of blocks within the same slot.
+const UNDO: TableDefinition<(u64, u8), &[u8]> = TableDefinition::new("utxos_undo");
+
+/// Slot, sequence and hash of the latest applied block
+///
+/// Kept apart from the undo info, which doesn't survive pruning.
+const TIP: TableDefinition<(), (u64, u8, &[u8; 32])> = TableDefinition::new("utxos_tip");
+
+#[derive(Encode, Decode, Debug)]
+struct UndoRecord {
+    #[n(0)]
+    hash: Hash<32>,
+
+    #[n(1)]
+    consumed: Vec<(Hash<32>, u64, u16, Bytes)>,
+
+    #[n(2)]
+    produced: Vec<(Hash<32>, u64)>,
+}
+
+fn decode_output(era: u16, cbor: &[u8]) -> Result<EraCbor, Error> {
+    let era = Era::try_from(era).map_err(Error::invalid_cbor)?;
+    Ok(EraCbor(era, cbor.to_vec()))
+}
+
+/// The keys of the secondary indexes that apply to an output
+fn index_keys(output: &EraCbor) -> (Option<Vec<u8>>, BTreeSet<Hash<28>>) {
+    match output.output() {
+        Ok(decoded) => {
+            let address = decoded.address().ok().map(|x| x.to_vec());
+
+            let policies = decoded
+                .non_ada_assets()
+                .iter()
+                .map(|x| *x.policy())
+                .collect();
+
+            (address, policies)
+        }
+        Err(_) => (None, BTreeSet::new()),
+    }
+}
+
+struct WriteTables<'db, 'txn> {
+    utxos: Table<'db, 'txn, UtxoKey, (u16, &'static [u8])>,
+    by_address: MultimapTable<'db, 'txn, &'static [u8], UtxoKey>,
+    by_policy: MultimapTable<'db, 'txn, &'static [u8; 28], UtxoKey>,
+}
+
+impl<'db, 'txn> WriteTables<'db, 'txn> {
+    fn open(wx: &'txn WriteTransaction<'db>) -> Result<Self, Error> {
+        Ok(Self {
+            utxos: wx.open_table(UTXOS)?,
+            by_address: wx.open_multimap_table(BY_ADDRESS)?,
+            by_policy: wx.open_multimap_table(BY_POLICY)?,
+        })
+    }
+
+    fn insert(&mut self, utxo: &OutputRef, output: &EraCbor) -> Result<(), Error> {
+        let key = (&**utxo.hash(), utxo.index());
+
+        self.utxos
+            .insert(key, (u16::from(output.era()), output.cbor()))?;
+
+        let (address, policies) = index_keys(output);
+
+        if let Some(address) = address {
+            self.by_address.insert(address.as_slice(), key)?;
+        }
+
+        for policy in policies {
+            self.by_policy.insert(&*policy, key)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&mut self, utxo: &OutputRef) -> Result<Option<EraCbor>, Error> {
+        let key = (&**utxo.hash(), utxo.index());
+
+        let output = match self.utxos.remove(key)? {
+            Some(x) => {
+                let (era, cbor) = x.value();
+                decode_output(era, cbor)?
+            }
+            None => return Ok(None),
+        };
+
+        let (address, policies) = index_keys(&output);
+
+        if let Some(address) = address {
+            self.by_address.remove(address.as_slice(), key)?;
+        }
+
+        for policy in policies {
+            self.by_policy.remove(&*policy, key)?;
+        }
+
+        Ok(Some(output))
+    }
+
+    fn apply_tx(&mut self, tx: &MultiEraTx, undo: &mut UndoRecord) -> Result<(), Error> {
+        // invalid txs consume the collateral instead of the inputs, the logic
+        // lives in the traverse helpers
+        for input in tx.consumes() {
+            let utxo = input.output_ref();
+
+            if let Some(output) = self.remove(&utxo)? {
+                undo.consumed.push((
+                    *utxo.hash(),
+                    utxo.index(),
+                    output.era().into(),
+                    output.1.into(),
+                ));
+            }
+        }
+
+        let hash = tx.hash();
+
+        for (idx, cbor) in tx.produces_cbor().map_err(Error::invalid_cbor)? {
+            let utxo = OutputRef::new(hash, idx as u64);
+            let output = EraCbor(tx.era(), cbor.to_vec());
+
+            self.insert(&utxo, &output)?;
+            undo.produced.push((hash, idx as u64));
+        }
+
+        Ok(())
+    }
+
+    fn revert(&mut self, undo: UndoRecord) -> Result<(), Error> {
+        // outputs produced and consumed within the block are in both lists,
+        // restoring them first makes sure they end up removed
+        for (hash, idx, era, cbor) in undo.consumed.iter().rev() {
+            let output = decode_output(*era, cbor)?;
+            self.insert(&OutputRef::new(*hash, *idx), &output)?;
+        }
+
+        for (hash, idx) in undo.produced.iter().rev() {
+            self.remove(&OutputRef::new(*hash, *idx))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn collect_refs<'a>(
+    refs: impl Iterator<Item = Result<redb::AccessGuard<'a, UtxoKey>, redb::StorageError>>,
+    utxos: &ReadOnlyTable<UtxoKey, (u16, &'static [u8])>,
+) -> Result<Vec<(OutputRef, EraCbor)>, Error> {
+    let mut out = vec![];
+
+    for entry in refs {
+        let entry = entry?;
+        let (hash, idx) = entry.value();
+
+        if let Some(x) = utxos.get((hash, idx))? {
+            let (era, cbor) = x.value();
+            out.push((
+                OutputRef::new(Hash::new(*hash), idx),
+                decode_output(era, cbor)?,
+            ));
+        }
+    }
+
+    Ok(out)
+}
+
+/// A durable set of unspent outputs that follows the chain block by block
+///
+/// Same as the in-memory set, inputs that are not present in the store are
+/// silently ignored when applying a block. Outputs that are not created by
+/// blocks (eg: genesis funds) can be added manually using `insert`.
+pub struct UtxoStore {
+    db: Database,
+}
+
+impl UtxoStore {
+    /// Opens the store at the given path, creating it if required
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let db = Database::create(path)?;
+
+        // make sure the tables exist so that read txs don't fail on empty dbs
+        let wx = db.begin_write()?;
+        WriteTables::open(&wx)?;
+        wx.open_table(UNDO)?;
+        wx.open_table(TIP)?;
+        wx.commit()?;
+
+        Ok(Self { db })
+    }
+
+    pub fn get(&self, utxo: &OutputRef) -> Result<Option<EraCbor>, Error> {
+        let rx = self.db.begin_read()?;
+        let utxos = rx.open_table(UTXOS)?;
+
+        let output = match utxos.get((&**utxo.hash(), utxo.index()))? {
+            Some(x) => {
+                let (era, cbor) = x.value();
+                Some(decode_output(era, cbor)?)
+            }
+            None => None,
+        };
+
+        Ok(output)
+    }
+
+    pub fn len(&self) -> Result<u64, Error> {
+        let rx = self.db.begin_read()?;
+        let utxos = rx.open_table(UTXOS)?;
+
+        Ok(utxos.len()?)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns all of the unspent outputs locked by the given address
+    pub fn by_address(&self, address: &Address) -> Result<Vec<(OutputRef, EraCbor)>, Error> {
+        let rx = self.db.begin_read()?;
+        let utxos = rx.open_table(UTXOS)?;
+        let index = rx.open_multimap_table(BY_ADDRESS)?;
+
+        let refs = index.get(address.to_vec().as_slice())?;
+
+        collect_refs(refs, &utxos)
+    }
+
+    /// Returns all of the unspent outputs holding assets of the given policy
+    pub fn by_policy(&self, policy: &Hash<28>) -> Result<Vec<(OutputRef, EraCbor)>, Error> {
+        let rx = self.db.begin_read()?;
+        let utxos = rx.open_table(UTXOS)?;
+        let index = rx.open_multimap_table(BY_POLICY)?;
+
+        let refs = index.get(&**policy)?;
+
+        collect_refs(refs, &utxos)
+    }
+
+    /// Adds an output to the store, outside of the undo tracking
+    pub fn insert(&self, utxo: &OutputRef, output: &EraCbor) -> Result<(), Error> {
+        let wx = self.db.begin_write()?;
+
+        {
+            let mut tables = WriteTables::open(&wx)?;
+            tables.remove(utxo)?;
+            tables.insert(utxo, output)?;
+        }
+
+        wx.commit()?;
+
+        Ok(())
+    }
+
+    /// Applies the txs of the block in a single db transaction
+    ///
+    /// The changes are recorded by point so that they can be reverted later
+    /// on. The block needs to be at a slot equal or greater than the latest
+    /// applied one, equal slots are allowed to support Byron epoch boundary
+    /// blocks.
+    pub fn apply_block(&self, block: &MultiEraBlock) -> Result<(), Error> {
+        let (slot, hash) = (block.slot(), block.hash());
+
+        let mut undo = UndoRecord {
+            hash,
+            consumed: vec![],
+            produced: vec![],
+        };
+
+        let wx = self.db.begin_write()?;
+
+        {
+            let mut records = wx.open_table(UNDO)?;
+            let mut tip = wx.open_table(TIP)?;
+
+            let current = tip.get(())?.map(|x| {
+                let (slot, seq, hash) = x.value();
+                (slot, seq, Hash::new(*hash))
+            });
+
+            let seq = match current {
+                Some((tip_slot, _, _)) if slot < tip_slot => {
+                    return Err(Error::NotExtendingTip(slot, tip_slot))
+                }
+                Some((tip_slot, seq, tip_hash)) if slot == tip_slot => {
+                    if tip_hash == hash {
+                        return Err(Error::NotExtendingTip(slot, tip_slot));
+                    }
+
+                    seq.checked_add(1)
+                        .ok_or(Error::NotExtendingTip(slot, tip_slot))?
+                }
+                _ => 0,
+            };
+
+            let mut tables = WriteTables::open(&wx)?;
+
+            for tx in block.txs() {
+                tables.apply_tx(&tx, &mut undo)?;
+            }
+
+            let cbor = minicbor::to_vec(&undo).map_err(Error::invalid_cbor)?;
+
+            records.insert((slot, seq), cbor.as_slice())?;
+            tip.insert((), (slot, seq, &*hash))?;
+        }
+
+        wx.commit()?;
+
+        Ok(())
+    }
+
+    /// The slot and hash of the latest applied block
+    ///
+    /// The tip is kept even after its undo info is pruned.
+    pub fn tip(&self) -> Result<Option<Point>, Error> {
+        let rx = self.db.begin_read()?;
+        let tip = rx.open_table(TIP)?;
+
+        let tip = tip.get(())?.map(|x| {
+            let (slot, _, hash) = x.value();
+            (slot, Hash::new(*hash))
+        });
+
+        Ok(tip)
+    }
+
+    /// Reverts every block applied after the given point
+    ///
+    /// Returns the points that were reverted, from newer to older. All of the
+    /// changes happen in a single db transaction. Fails without altering the
+    /// store if the point is not found within the available undo info.
+    pub fn rollback_to(&self, slot: u64, hash: &Hash<32>) -> Result<Vec<Point>, Error> {
+        let wx = self.db.begin_write()?;
+
+        let mut reverted = vec![];
+
+        {
+            let mut tables = WriteTables::open(&wx)?;
+            let mut records = wx.open_table(UNDO)?;
+            let mut tip = wx.open_table(TIP)?;
+
+            let mut found = None;
+
+            for entry in records.range((slot, 0)..=(slot, u8::MAX))? {
+                let (k, v) = entry?;
+                let undo: UndoRecord = minicbor::decode(v.value()).map_err(Error::invalid_cbor)?;
+
+                if undo.hash == *hash {
+                    found = Some(k.value());
+                }
+            }
+
+            let point = found.ok_or(Error::UnknownRollbackPoint(slot, *hash))?;
+
+            loop {
+                let (key, cbor) = match records.last()? {
+                    Some((k, v)) if k.value() > point => (k.value(), v.value().to_vec()),
+                    _ => break,
+                };
+
+                records.remove(key)?;
+
+                let undo: UndoRecord = minicbor::decode(&cbor).map_err(Error::invalid_cbor)?;
+
+                reverted.push((key.0, undo.hash));
+                tables.revert(undo)?;
+            }
+
+            tip.insert((), (point.0, point.1, &**hash))?;
+        }
+
+        wx.commit()?;
+
+        Ok(reverted)
+    }
+
+    /// Discards the undo info of blocks before the given slot
+    ///
+    /// Rollbacks in Cardano can't be deeper than the security param (`k`), so
+    /// there's no point in keeping undo info for older blocks.
+    pub fn prune_undo(&self, before_slot: u64) -> Result<(), Error> {
+        let wx = self.db.begin_write()?;
+
+        {
+            let mut records = wx.open_table(UNDO)?;
+            records.drain::<(u64, u8)>(..(before_slot, 0))?;
+        }
+
+        wx.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn load_block(content: &str) -> Vec<u8> {
+        hex::decode(content).expect("invalid hex")
+    }
+
+    #[test]
+    fn apply_and_rollback_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UtxoStore::open(dir.path().join("utxos.redb")).unwrap();
+
+        let blocks = [
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+        ];
+
+        let mut blocks: Vec<_> = blocks.iter().map(|x| load_block(x)).collect();
+        blocks.sort_by_key(|x| MultiEraBlock::decode(x).unwrap().slot());
+
+        // an epoch boundary block without txs to rollback to
+        let cbor = load_block(include_str!("../../test_data/genesis.block"));
+        let genesis = MultiEraBlock::decode(&cbor).unwrap();
+        store.apply_block(&genesis).unwrap();
+
+        for cbor in blocks.iter() {
+            let block = MultiEraBlock::decode(cbor).unwrap();
+            store.apply_block(&block).unwrap();
+
+            for tx in block.txs() {
+                let produced = tx.produces().into_iter().zip(tx.produces_cbor().unwrap());
+
+                for ((idx, output), (_, cbor)) in produced {
+                    let utxo = OutputRef::new(tx.hash(), idx as u64);
+
+                    // outputs might have been spent by a later tx of the block
+                    if let Some(stored) = store.get(&utxo).unwrap() {
+                        assert_eq!(stored.cbor(), cbor);
+
+                        let address = output.address().unwrap();
+                        let found = store.by_address(&address).unwrap();
+                        assert!(found.iter().any(|(r, _)| *r == utxo));
+
+                        for assets in output.non_ada_assets() {
+                            let found = store.by_policy(assets.policy()).unwrap();
+                            assert!(found.iter().any(|(r, _)| *r == utxo));
+                        }
+                    }
+                }
+            }
+        }
+
+        let last = MultiEraBlock::decode(blocks.last().unwrap()).unwrap();
+        assert_eq!(store.tip().unwrap(), Some((last.slot(), last.hash())));
+
+        let reverted = store.rollback_to(genesis.slot(), &genesis.hash()).unwrap();
+        assert_eq!(reverted.len(), blocks.len());
+        assert_eq!(store.tip().unwrap(), Some((genesis.slot(), genesis.hash())));
+        assert!(store.is_empty().unwrap());
+    }
+
+    #[test]
+    fn rollback_restores_consumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("utxos.redb");
+
+        let cbor = load_block(include_str!("../../test_data/babbage1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+
+        // seed the store with the inputs consumed by the block using a dummy
+        // output so we can check they are restored after the rollback
+        let dummy = EraCbor(Era::Babbage, txs[0].outputs()[0].encode());
+        let address = txs[0].outputs()[0].address().unwrap();
+
+        let seeded: HashSet<_> = txs
+            .iter()
+            .flat_map(|tx| tx.consumes())
+            .map(|x| x.output_ref())
+            .collect();
+
+        let cbor = load_block(include_str!("../../test_data/genesis.block"));
+        let genesis = MultiEraBlock::decode(&cbor).unwrap();
+
+        {
+            let store = UtxoStore::open(&path).unwrap();
+
+            for utxo in seeded.iter() {
+                store.insert(utxo, &dummy).unwrap();
+            }
+
+            store.apply_block(&genesis).unwrap();
+            store.apply_block(&block).unwrap();
+        }
+
+        // the data needs to survive reopening the db
+        let store = UtxoStore::open(&path).unwrap();
+
+        for utxo in seeded.iter() {
+            assert!(store.get(utxo).unwrap().is_none());
+        }
+
+        let reverted = store.rollback_to(genesis.slot(), &genesis.hash()).unwrap();
+        assert_eq!(reverted, vec![(block.slot(), block.hash())]);
+
+        for utxo in seeded.iter() {
+            assert_eq!(store.get(utxo).unwrap().as_ref(), Some(&dummy));
+        }
+
+        let found: HashSet<_> = store
+            .by_address(&address)
+            .unwrap()
+            .into_iter()
+            .map(|(r, _)| r)
+            .collect();
+
+        assert_eq!(found, seeded);
+    }
+
+    #[test]
+    fn prune_limits_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UtxoStore::open(dir.path().join("utxos.redb")).unwrap();
+
+        let cbor = load_block(include_str!("../../test_data/alonzo1.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        store.apply_block(&block).unwrap();
+        let len = store.len().unwrap();
+
+        store.prune_undo(block.slot() + 1).unwrap();
+        assert_eq!(store.tip().unwrap(), Some((block.slot(), block.hash())));
+
+        let pruned = store.rollback_to(block.slot(), &block.hash());
+        assert!(matches!(pruned, Err(Error::UnknownRollbackPoint(..))));
+        assert_eq!(store.len().unwrap(), len);
+
+        // the tip survives the pruning, blocks still need to extend it
+        let older = load_block(include_str!("../../test_data/shelley1.block"));
+        let older = MultiEraBlock::decode(&older).unwrap();
+
+        let behind = store.apply_block(&older);
+        assert!(matches!(behind, Err(Error::NotExtendingTip(..))));
+
+        let repeated = store.apply_block(&block);
+        assert!(matches!(repeated, Err(Error::NotExtendingTip(..))));
+    }
+
+    #[test]
+    fn rollback_removes_outputs_spent_within_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UtxoStore::open(dir.path().join("utxos.redb")).unwrap();
+
+        let cbor = load_block(include_str!("../../test_data/mary1.block"));
+        let first = MultiEraBlock::decode(&cbor).unwrap();
+
+        store.apply_block(&first).unwrap();
+        let len = store.len().unwrap();
+
+        let cbor = load_block(include_str!("../../test_data/alonzo14.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+
+        // the block spends outputs of a previous tx within the same block
+        let spent = OutputRef::new(txs[8].hash(), 0);
+        assert!(txs[9].consumes().iter().any(|x| x.output_ref() == spent));
+
+        store.apply_block(&block).unwrap();
+        assert!(store.get(&spent).unwrap().is_none());
+
+        let reverted = store.rollback_to(first.slot(), &first.hash()).unwrap();
+        assert_eq!(reverted, vec![(block.slot(), block.hash())]);
+
+        assert!(store.get(&spent).unwrap().is_none());
+        assert_eq!(store.len().unwrap(), len);
+    }
+
+    #[test]
+    fn rejects_unknown_points() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UtxoStore::open(dir.path().join("utxos.redb")).unwrap();
+
+        let older = load_block(include_str!("../../test_data/alonzo1.block"));
+        let older = MultiEraBlock::decode(&older).unwrap();
+
+        let cbor = load_block(include_str!("../../test_data/alonzo2.block"));
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        store.apply_block(&block).unwrap();
+        let len = store.len().unwrap();
+
+        let repeated = store.apply_block(&block);
+        assert!(matches!(repeated, Err(Error::NotExtendingTip(..))));
+
+        let behind = store.apply_block(&older);
+        assert!(matches!(behind, Err(Error::NotExtendingTip(..))));
+
+        // same slot, different hash
+        let unknown = store.rollback_to(block.slot(), &older.hash());
+        assert!(matches!(unknown, Err(Error::UnknownRollbackPoint(..))));
+
+        assert_eq!(store.tip().unwrap(), Some((block.slot(), block.hash())));
+        assert_eq!(store.len().unwrap(), len);
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pallas_traverse::{parallel::par_decode_map, MultiEraBlock};
+
+/// the blocks of the test data, repeated to get a decently sized archive
+fn corpus() -> Vec<Vec<u8>> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_data");
+
+    let mut blocks: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.extension().map(|x| x == "block").unwrap_or(false))
+        .map(|x| hex::decode(std::fs::read_to_string(x).unwrap().trim()).unwrap())
+        .collect();
+
+    blocks.sort();
+
+    blocks
+        .iter()
+        .cycle()
+        .take(blocks.len() * 20)
+        .cloned()
+        .collect()
+}
+
+fn decode(c: &mut Criterion) {
+    let blocks = corpus();
+
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(blocks.len() as u64));
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            blocks
+                .iter()
+                .map(|x| MultiEraBlock::decode(x).map(|x| x.tx_count()))
+                .collect::<Vec<_>>()
+        })
+    });
+
+    for batch_size in [64, 1_000] {
+        group.bench_with_input(
+            BenchmarkId::new("parallel", batch_size),
+            &batch_size,
+            |b, batch_size| {
+                b.iter(|| {
+                    par_decode_map(&blocks, |x| x.tx_count())
+                        .batch_size(*batch_size)
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -73,6 +73,25 @@ impl<'b> MultiEraBlock<'b> {
         }
     }
 
+    pub fn encode(&self) -> Vec<u8> {
+        // to_vec is infallible
+        match self {
+            MultiEraBlock::EpochBoundary(x) => minicbor::to_vec((0u16, x)).unwrap(),
+            MultiEraBlock::Byron(x) => minicbor::to_vec((1u16, x)).unwrap(),
+            MultiEraBlock::AlonzoCompatible(x, era) => {
+                let tag: u16 = match era {
+                    Era::Shelley => 2,
+                    Era::Allegra => 3,
+                    Era::Mary => 4,
+                    _ => 5,
+                };
+
+                minicbor::to_vec((tag, x)).unwrap()
+            }
+            MultiEraBlock::Babbage(x) => minicbor::to_vec((6u16, x)).unwrap(),
+        }
+    }
+
     pub fn header(&self) -> MultiEraHeader<'_> {
         match self {
             MultiEraBlock::EpochBoundary(x) => {
@@ -205,4 +224,99 @@ mod tests {
             assert_eq!(block.txs().len(), tx_count);
         }
     }
+
+    #[test]
+    fn produced_outputs_keep_original_cbor() {
+        let blocks = [
+            include_str!("../../test_data/byron2.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage6.block"),
+        ];
+
+        for block_str in blocks {
+            let cbor = hex::decode(block_str).expect("invalid hex");
+            let block = MultiEraBlock::decode(&cbor).expect("invalid cbor");
+
+            for tx in block.txs() {
+                let produced = tx.produces();
+                let raw = tx.produces_cbor().unwrap();
+                assert_eq!(produced.len(), raw.len());
+
+                for ((idx, output), (raw_idx, raw)) in produced.iter().zip(raw) {
+                    assert_eq!(*idx, raw_idx);
+
+                    let decoded = crate::MultiEraOutput::decode(tx.era(), raw).unwrap();
+                    assert_eq!(decoded.encode(), output.encode());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn era_specific_models_match() {
+        use std::ops::Deref;
+
+        use pallas_codec::utils::KeepRaw;
+        use pallas_crypto::hash::Hasher;
+        use pallas_primitives::{allegra, mary, shelley};
+
+        fn body_hashes<T>(bodies: &[KeepRaw<T>]) -> Vec<Hash<32>> {
+            bodies
+                .iter()
+                .map(|x| Hasher::<256>::hash(x.raw_cbor()))
+                .collect()
+        }
+
+        // blocks of these eras are traversed through the Alonzo-compatible
+        // model, the hashes need to match the ones of the exact era models
+        // and txs need to expose the exact bodies
+        let cbor = hex::decode(include_str!("../../test_data/shelley1.block")).unwrap();
+        let (_, exact): (u16, shelley::MintedBlock) = minicbor::decode(&cbor).unwrap();
+        let hashes = body_hashes(&exact.transaction_bodies);
+
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        assert_eq!(block.era(), Era::Shelley);
+        assert_eq!(
+            block.txs().iter().map(|x| x.hash()).collect::<Vec<_>>(),
+            hashes
+        );
+
+        for (tx, body) in block.txs().iter().zip(exact.transaction_bodies.iter()) {
+            assert_eq!(tx.as_shelley_body().unwrap().as_ref(), Some(body.deref()));
+            assert!(tx.as_mary_body().unwrap().is_none());
+        }
+
+        let cbor = hex::decode(include_str!("../../test_data/allegra1.block")).unwrap();
+        let (_, exact): (u16, allegra::MintedBlock) = minicbor::decode(&cbor).unwrap();
+        let hashes = body_hashes(&exact.transaction_bodies);
+
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        assert_eq!(block.era(), Era::Allegra);
+        assert_eq!(
+            block.txs().iter().map(|x| x.hash()).collect::<Vec<_>>(),
+            hashes
+        );
+
+        for (tx, body) in block.txs().iter().zip(exact.transaction_bodies.iter()) {
+            assert_eq!(tx.as_allegra_body().unwrap().as_ref(), Some(body.deref()));
+            assert!(tx.as_shelley_body().unwrap().is_none());
+        }
+
+        let cbor = hex::decode(include_str!("../../test_data/mary1.block")).unwrap();
+        let (_, exact): (u16, mary::MintedBlock) = minicbor::decode(&cbor).unwrap();
+        let hashes = body_hashes(&exact.transaction_bodies);
+
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        assert_eq!(block.era(), Era::Mary);
+        assert_eq!(
+            block.txs().iter().map(|x| x.hash()).collect::<Vec<_>>(),
+            hashes
+        );
+
+        for (tx, body) in block.txs().iter().zip(exact.transaction_bodies.iter()) {
+            assert_eq!(tx.as_mary_body().unwrap().as_ref(), Some(body.deref()));
+            assert!(tx.as_allegra_body().unwrap().is_none());
+        }
+    }
 }
@@ -67,6 +67,12 @@ impl ComputeHash<28> for alonzo::NativeScript {
     }
 }
 
+impl OriginalHash<28> for KeepRaw<'_, alonzo::NativeScript> {
+    fn original_hash(&self) -> Hash<28> {
+        Hasher::<224>::hash_tagged(self.raw_cbor(), 0)
+    }
+}
+
 impl ComputeHash<28> for alonzo::PlutusScript {
     fn compute_hash(&self) -> Hash<28> {
         Hasher::<224>::hash_tagged(&self.0, 1)
@@ -147,7 +153,7 @@ mod tests {
     use crate::{Era, MultiEraTx};
 
     use super::{ComputeHash, OriginalHash};
-    use pallas_codec::utils::Int;
+    use pallas_codec::utils::{Int, MaybeIndefArray};
     use pallas_codec::{minicbor, utils::Bytes};
     use pallas_crypto::hash::Hash;
     use pallas_primitives::babbage::MintedDatumOption;
@@ -224,13 +230,13 @@ mod tests {
     #[test]
     fn native_script_hashes_as_cardano_cli() {
         // construct an arbitrary script to use as example
-        let ns = alonzo::NativeScript::ScriptAll(vec![
+        let ns = alonzo::NativeScript::ScriptAll(MaybeIndefArray::Def(vec![
             alonzo::NativeScript::ScriptPubkey(
                 Hash::<28>::from_str("4d04380dcb9fbad5aff8e2f4e19394ef4e5e11b37932838f01984a12")
                     .unwrap(),
             ),
             alonzo::NativeScript::InvalidBefore(112500819),
-        ]);
+        ]));
 
         // hash that we assume correct since it was generated through the cardano-cli
         let cardano_cli_output = "d6a8ced01ecdfbb26c90850010a06fbc20a7c23632fc92f531667f36";
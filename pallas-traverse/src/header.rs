@@ -3,6 +3,7 @@ use std::ops::Deref;
 
 use pallas_codec::minicbor;
 use pallas_crypto::hash::{Hash, Hasher};
+use pallas_crypto::kes;
 use pallas_primitives::{alonzo, babbage, byron};
 
 use crate::{wellknown::GenesisValues, Era, Error, MultiEraHeader, OriginalHash};
@@ -131,6 +132,65 @@ impl<'b> MultiEraHeader<'b> {
         }
     }
 
+    /// The original CBOR of the header body, which is what the KES
+    /// signature of the header signs
+    pub fn header_body_cbor(&self) -> Option<&'b [u8]> {
+        match self {
+            MultiEraHeader::AlonzoCompatible(_) | MultiEraHeader::Babbage(_) => {
+                let cbor = self.cbor();
+                let mut d = minicbor::Decoder::new(cbor);
+
+                d.array().ok()?;
+                let start = d.position();
+                d.skip().ok()?;
+
+                Some(&cbor[start..d.position()])
+            }
+            MultiEraHeader::EpochBoundary(_) => None,
+            MultiEraHeader::Byron(_) => None,
+        }
+    }
+
+    pub fn body_signature(&self) -> Option<&[u8]> {
+        match self {
+            MultiEraHeader::AlonzoCompatible(x) => Some(x.body_signature.as_ref()),
+            MultiEraHeader::Babbage(x) => Some(x.body_signature.as_ref()),
+            MultiEraHeader::EpochBoundary(_) => None,
+            MultiEraHeader::Byron(_) => None,
+        }
+    }
+
+    pub fn operational_cert(&self) -> Result<kes::OpCert, Error> {
+        let (hot_vkey, sequence_number, kes_period, sigma) = match self {
+            MultiEraHeader::AlonzoCompatible(x) => (
+                &x.header_body.operational_cert_hot_vkey,
+                x.header_body.operational_cert_sequence_number,
+                x.header_body.operational_cert_kes_period,
+                &x.header_body.operational_cert_sigma,
+            ),
+            MultiEraHeader::Babbage(x) => (
+                &x.header_body.operational_cert.operational_cert_hot_vkey,
+                x.header_body
+                    .operational_cert
+                    .operational_cert_sequence_number,
+                x.header_body.operational_cert.operational_cert_kes_period,
+                &x.header_body.operational_cert.operational_cert_sigma,
+            ),
+            MultiEraHeader::EpochBoundary(_) => return Err(Error::InvalidEra(Era::Byron)),
+            MultiEraHeader::Byron(_) => return Err(Error::InvalidEra(Era::Byron)),
+        };
+
+        Ok(kes::OpCert {
+            hot_vkey: hot_vkey
+                .as_slice()
+                .try_into()
+                .map_err(Error::invalid_cbor)?,
+            sequence_number,
+            kes_period,
+            sigma: sigma.as_slice().try_into().map_err(Error::invalid_cbor)?,
+        })
+    }
+
     pub fn as_eb(&self) -> Option<&byron::EbbHead> {
         match self {
             MultiEraHeader::EpochBoundary(x) => Some(x.deref().deref()),
@@ -159,3 +219,77 @@ impl<'b> MultiEraHeader<'b> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pallas_crypto::kes;
+    use pallas_crypto::key::ed25519;
+    use pallas_crypto::vrf::Proof;
+
+    use crate::MultiEraBlock;
+
+    #[test]
+    fn vrf_outputs_match_proofs() {
+        let blocks = [
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+        ];
+
+        for block_str in blocks {
+            let cbor = hex::decode(block_str).expect("invalid hex");
+            let block = MultiEraBlock::decode(&cbor).expect("invalid cbor");
+            let header = block.header();
+
+            let certs = match (header.as_alonzo(), header.as_babbage()) {
+                (Some(x), _) => vec![&x.header_body.leader_vrf, &x.header_body.nonce_vrf],
+                (_, Some(x)) => vec![&x.header_body.vrf_result],
+                _ => unreachable!(),
+            };
+
+            for cert in certs {
+                let proof = Proof::try_from(cert.1.as_slice()).unwrap();
+                assert_eq!(proof.to_hash().unwrap().as_ref(), cert.0.as_slice());
+            }
+        }
+    }
+
+    #[test]
+    fn kes_signatures_are_valid() {
+        const MAX_KES_EVOLUTIONS: u64 = 62;
+
+        // the babbage block comes from a test network with shorter KES periods
+        let blocks = [
+            (include_str!("../../test_data/shelley1.block"), 129600),
+            (include_str!("../../test_data/alonzo1.block"), 129600),
+            (include_str!("../../test_data/babbage1.block"), 86400),
+        ];
+
+        for (block_str, slots_per_kes_period) in blocks {
+            let cbor = hex::decode(block_str).expect("invalid hex");
+            let block = MultiEraBlock::decode(&cbor).expect("invalid cbor");
+            let header = block.header();
+
+            let opcert = header.operational_cert().unwrap();
+            let cold_vkey = ed25519::PublicKey::try_from(header.issuer_vkey().unwrap()).unwrap();
+            assert_eq!(opcert.verify(&cold_vkey), Ok(()));
+
+            let period = opcert
+                .evolution(header.slot() / slots_per_kes_period, MAX_KES_EVOLUTIONS)
+                .unwrap();
+
+            let signature = kes::Signature::try_from(header.body_signature().unwrap()).unwrap();
+            let body = header.header_body_cbor().unwrap();
+
+            assert_eq!(opcert.hot_vkey.verify(period, body, &signature), Ok(()));
+            assert!(opcert
+                .hot_vkey
+                .verify(period + 1, body, &signature)
+                .is_err());
+            assert!(opcert
+                .hot_vkey
+                .verify(period, &body[1..], &signature)
+                .is_err());
+        }
+    }
+}
@@ -0,0 +1,622 @@
+//! Hard-fork aware conversion between slots, epochs and wall-clock time
+//!
+//! The history of a chain is described as a sequence of era summaries, each
+//! one with its own slot length and epoch size. This is the same model used by
+//! the hard-fork combinator of the Haskell node and it can be loaded directly
+//! from the result of a `GetInterpreter` local state query.
+
+use pallas_codec::minicbor::{self, data::Tag, data::Type, Decode, Encode};
+
+use crate::{
+    time::{Epoch, Slot, SubSlot},
+    wellknown::GenesisValues,
+    Error,
+};
+
+const PICOS_PER_MILLI: u128 = 1_000_000_000;
+
+/// The point where an era starts or ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bound {
+    /// Milliseconds elapsed since the system start
+    pub time: u64,
+    pub slot: Slot,
+    pub epoch: Epoch,
+}
+
+impl Bound {
+    pub fn origin() -> Self {
+        Self {
+            time: 0,
+            slot: 0,
+            epoch: 0,
+        }
+    }
+}
+
+// relative times are encoded as an integer of picoseconds, which might not fit
+// in 64 bits so we need to handle bignums too
+fn decode_picos(d: &mut minicbor::Decoder) -> Result<u128, minicbor::decode::Error> {
+    match d.datatype()? {
+        Type::Tag => match d.tag()? {
+            Tag::PosBignum => {
+                let bytes = d.bytes()?;
+
+                if bytes.len() > 16 {
+                    return Err(minicbor::decode::Error::message("relative time overflow"));
+                }
+
+                Ok(bytes.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128))
+            }
+            _ => Err(minicbor::decode::Error::message(
+                "unexpected tag for relative time",
+            )),
+        },
+        _ => Ok(d.u64()? as u128),
+    }
+}
+
+fn encode_picos<W: minicbor::encode::Write>(
+    picos: u128,
+    e: &mut minicbor::Encoder<W>,
+) -> Result<(), minicbor::encode::Error<W::Error>> {
+    match u64::try_from(picos) {
+        Ok(x) => {
+            e.u64(x)?;
+        }
+        Err(_) => {
+            let bytes = picos.to_be_bytes();
+            let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+            e.tag(Tag::PosBignum)?;
+            e.bytes(&bytes[first..])?;
+        }
+    }
+
+    Ok(())
+}
+
+impl<'b, C> Decode<'b, C> for Bound {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+
+        let picos = decode_picos(d)?;
+
+        Ok(Bound {
+            time: (picos / PICOS_PER_MILLI) as u64,
+            slot: d.decode_with(ctx)?,
+            epoch: d.decode_with(ctx)?,
+        })
+    }
+}
+
+impl<C> Encode<C> for Bound {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        e.array(3)?;
+        encode_picos(self.time as u128 * PICOS_PER_MILLI, e)?;
+        e.encode_with(self.slot, ctx)?;
+        e.encode_with(self.epoch, ctx)?;
+
+        Ok(())
+    }
+}
+
+/// Parameters that remain constant for the whole duration of an era
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EraParams {
+    /// Number of slots in each epoch
+    pub epoch_size: u64,
+
+    /// Length of each slot in milliseconds
+    pub slot_length: u64,
+
+    /// Number of slots from the tip where the era is guaranteed not to end,
+    /// `None` means that the era can't end without prior notice
+    pub safe_zone: Option<u64>,
+}
+
+impl<'b, C> Decode<'b, C> for EraParams {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?;
+
+        let epoch_size = d.decode_with(ctx)?;
+        let slot_length = d.decode_with(ctx)?;
+
+        d.array()?;
+
+        let safe_zone = match d.u8()? {
+            0 => {
+                let slots = d.decode_with(ctx)?;
+                // safe-before-epoch, deprecated and always `NoLowerBound`
+                d.skip()?;
+                Some(slots)
+            }
+            1 => None,
+            _ => {
+                return Err(minicbor::decode::Error::message(
+                    "unknown safe zone variant",
+                ))
+            }
+        };
+
+        // newer versions of the node append the genesis window
+        if let Some(len) = len {
+            for _ in 3..len {
+                d.skip()?;
+            }
+        }
+
+        Ok(EraParams {
+            epoch_size,
+            slot_length,
+            safe_zone,
+        })
+    }
+}
+
+impl<C> Encode<C> for EraParams {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        e.array(3)?;
+        e.encode_with(self.epoch_size, ctx)?;
+        e.encode_with(self.slot_length, ctx)?;
+
+        match self.safe_zone {
+            Some(slots) => {
+                e.array(3)?;
+                e.u8(0)?;
+                e.encode_with(slots, ctx)?;
+                e.array(1)?;
+                e.u8(0)?;
+            }
+            None => {
+                e.array(1)?;
+                e.u8(1)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The bounds and params of a single era
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EraSummary {
+    pub start: Bound,
+
+    /// The end of the era, `None` if it's not known yet
+    pub end: Option<Bound>,
+
+    pub params: EraParams,
+}
+
+impl EraSummary {
+    fn contains_slot(&self, slot: Slot) -> bool {
+        slot >= self.start.slot && self.end.as_ref().map(|x| slot < x.slot).unwrap_or(true)
+    }
+
+    fn contains_epoch(&self, epoch: Epoch) -> bool {
+        epoch >= self.start.epoch && self.end.as_ref().map(|x| epoch < x.epoch).unwrap_or(true)
+    }
+
+    fn contains_time(&self, time: u64) -> bool {
+        time >= self.start.time && self.end.as_ref().map(|x| time < x.time).unwrap_or(true)
+    }
+}
+
+impl<'b, C> Decode<'b, C> for EraSummary {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+
+        let start = d.decode_with(ctx)?;
+
+        let end = match d.datatype()? {
+            Type::Null => {
+                d.skip()?;
+                None
+            }
+            _ => Some(d.decode_with(ctx)?),
+        };
+
+        let params = d.decode_with(ctx)?;
+
+        Ok(EraSummary { start, end, params })
+    }
+}
+
+impl<C> Encode<C> for EraSummary {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        e.array(3)?;
+        e.encode_with(&self.start, ctx)?;
+
+        match &self.end {
+            Some(x) => e.encode_with(x, ctx)?,
+            None => e.null()?,
+        };
+
+        e.encode_with(&self.params, ctx)?;
+
+        Ok(())
+    }
+}
+
+/// The sequence of eras of a chain, sorted from oldest to newest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EraHistory {
+    /// Wall-clock time of slot zero as a unix timestamp in milliseconds
+    pub system_start: u64,
+    pub eras: Vec<EraSummary>,
+}
+
+impl EraHistory {
+    pub fn new(system_start: u64, eras: Vec<EraSummary>) -> Self {
+        Self { system_start, eras }
+    }
+
+    /// Decodes the CBOR response of a `GetInterpreter` query
+    ///
+    /// The interpreter doesn't include the system start, it needs to be
+    /// provided separately (eg: via a `GetSystemStart` query or from the
+    /// genesis file) as a unix timestamp in milliseconds.
+    pub fn decode_interpreter(system_start: u64, cbor: &[u8]) -> Result<Self, Error> {
+        let eras = minicbor::decode(cbor).map_err(Error::invalid_cbor)?;
+
+        Ok(Self::new(system_start, eras))
+    }
+
+    /// Encodes the eras using the same structure as a `GetInterpreter` query
+    pub fn encode_interpreter(&self) -> Vec<u8> {
+        // to_vec is infallible
+        minicbor::to_vec(&self.eras).unwrap()
+    }
+
+    fn era_for_slot(&self, slot: Slot) -> Result<&EraSummary, Error> {
+        self.eras
+            .iter()
+            .find(|x| x.contains_slot(slot))
+            .ok_or(Error::PastHorizon)
+    }
+
+    fn era_for_epoch(&self, epoch: Epoch) -> Result<&EraSummary, Error> {
+        self.eras
+            .iter()
+            .find(|x| x.contains_epoch(epoch))
+            .ok_or(Error::PastHorizon)
+    }
+
+    fn era_for_time(&self, time: u64) -> Result<&EraSummary, Error> {
+        self.eras
+            .iter()
+            .find(|x| x.contains_time(time))
+            .ok_or(Error::PastHorizon)
+    }
+
+    /// Returns the epoch of the slot and the position of the slot within it
+    pub fn slot_to_epoch(&self, slot: Slot) -> Result<(Epoch, SubSlot), Error> {
+        let era = self.era_for_slot(slot)?;
+        let era_slot = slot - era.start.slot;
+
+        let epoch = era.start.epoch + era_slot / era.params.epoch_size;
+        let sub_slot = era_slot % era.params.epoch_size;
+
+        Ok((epoch, sub_slot))
+    }
+
+    /// Returns the absolute slot for a slot relative to the start of an epoch
+    pub fn epoch_to_slot(&self, epoch: Epoch, sub_slot: SubSlot) -> Result<Slot, Error> {
+        let era = self.era_for_epoch(epoch)?;
+        let epoch_start = era.start.slot + (epoch - era.start.epoch) * era.params.epoch_size;
+        let slot = epoch_start + sub_slot;
+
+        // the sub-slot might overflow into a different era
+        self.era_for_slot(slot)?;
+
+        Ok(slot)
+    }
+
+    /// Returns the unix timestamp in milliseconds when the slot starts
+    pub fn slot_to_posix_millis(&self, slot: Slot) -> Result<u64, Error> {
+        let era = self.era_for_slot(slot)?;
+        let relative = era.start.time + (slot - era.start.slot) * era.params.slot_length;
+
+        Ok(self.system_start + relative)
+    }
+
+    /// Returns the unix timestamp in seconds when the slot starts
+    pub fn slot_to_wallclock(&self, slot: Slot) -> Result<u64, Error> {
+        self.slot_to_posix_millis(slot).map(|x| x / 1000)
+    }
+
+    /// Returns the slot that contains the unix timestamp in milliseconds
+    pub fn posix_millis_to_slot(&self, millis: u64) -> Result<Slot, Error> {
+        let relative = millis
+            .checked_sub(self.system_start)
+            .ok_or(Error::PastHorizon)?;
+
+        let era = self.era_for_time(relative)?;
+        let slot = era.start.slot + (relative - era.start.time) / era.params.slot_length;
+
+        Ok(slot)
+    }
+
+    /// Returns the era summary index for the slot, useful to lookup the
+    /// ledger era when combined with the known era sequence of the network
+    pub fn era_index(&self, slot: Slot) -> Result<usize, Error> {
+        self.eras
+            .iter()
+            .position(|x| x.contains_slot(slot))
+            .ok_or(Error::PastHorizon)
+    }
+}
+
+impl From<&GenesisValues> for EraHistory {
+    /// Approximates the history of a network using its well-known values,
+    /// which only distinguish between the Byron and post-Byron eras
+    fn from(genesis: &GenesisValues) -> Self {
+        let byron_slot_length = genesis.byron_slot_length as u64 * 1000;
+        let shelley_slot_length = genesis.shelley_slot_length as u64 * 1000;
+
+        let byron_epoch_size = (genesis.byron_epoch_length / genesis.byron_slot_length) as u64;
+        let shelley_epoch_size =
+            (genesis.shelley_epoch_length / genesis.shelley_slot_length) as u64;
+
+        let shelley_start = Bound {
+            time: (genesis.shelley_known_time - genesis.byron_known_time) * 1000,
+            slot: genesis.shelley_known_slot,
+            epoch: genesis.shelley_start_epoch(),
+        };
+
+        let byron = EraSummary {
+            start: Bound::origin(),
+            end: Some(shelley_start.clone()),
+            params: EraParams {
+                epoch_size: byron_epoch_size,
+                slot_length: byron_slot_length,
+                safe_zone: None,
+            },
+        };
+
+        let shelley = EraSummary {
+            start: shelley_start,
+            end: None,
+            params: EraParams {
+                epoch_size: shelley_epoch_size,
+                slot_length: shelley_slot_length,
+                safe_zone: None,
+            },
+        };
+
+        EraHistory::new(genesis.byron_known_time * 1000, vec![byron, shelley])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mainnet_eras() -> EraHistory {
+        let era = |start: (u64, u64, u64), end: Option<(u64, u64, u64)>, size, length, zone| {
+            let bound = |(time, slot, epoch)| Bound { time, slot, epoch };
+
+            EraSummary {
+                start: bound(start),
+                end: end.map(bound),
+                params: EraParams {
+                    epoch_size: size,
+                    slot_length: length,
+                    safe_zone: zone,
+                },
+            }
+        };
+
+        EraHistory::new(
+            1506203091000,
+            vec![
+                // byron
+                era(
+                    (0, 0, 0),
+                    Some((89856000000, 4492800, 208)),
+                    21600,
+                    20000,
+                    Some(4320),
+                ),
+                // shelley
+                era(
+                    (89856000000, 4492800, 208),
+                    Some((101952000000, 16588800, 236)),
+                    432000,
+                    1000,
+                    Some(129600),
+                ),
+                // allegra
+                era(
+                    (101952000000, 16588800, 236),
+                    Some((108432000000, 23068800, 251)),
+                    432000,
+                    1000,
+                    Some(129600),
+                ),
+                // mary
+                era(
+                    (108432000000, 23068800, 251),
+                    Some((125280000000, 39916800, 290)),
+                    432000,
+                    1000,
+                    Some(129600),
+                ),
+                // alonzo
+                era(
+                    (125280000000, 39916800, 290),
+                    Some((157680000000, 72316800, 365)),
+                    432000,
+                    1000,
+                    Some(129600),
+                ),
+                // babbage, with the horizon as reported by a node
+                era(
+                    (157680000000, 72316800, 365),
+                    Some((192240000000, 106876800, 445)),
+                    432000,
+                    1000,
+                    Some(129600),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn mainnet_conversions_match() {
+        let history = mainnet_eras();
+
+        // byron middle
+        assert_eq!(history.slot_to_wallclock(2160007).unwrap(), 1549403231);
+        assert_eq!(history.slot_to_epoch(2160007).unwrap(), (100, 7));
+
+        // shelley start
+        assert_eq!(history.slot_to_wallclock(4492800).unwrap(), 1596059091);
+        assert_eq!(history.slot_to_epoch(4492800).unwrap(), (208, 0));
+
+        // mary middle
+        assert_eq!(history.slot_to_wallclock(25000000).unwrap(), 1616566291);
+        assert_eq!(history.slot_to_epoch(51580240).unwrap(), (316, 431440));
+
+        assert_eq!(history.epoch_to_slot(100, 7).unwrap(), 2160007);
+        assert_eq!(history.epoch_to_slot(316, 431440).unwrap(), 51580240);
+
+        assert_eq!(
+            history.posix_millis_to_slot(1549403231000).unwrap(),
+            2160007
+        );
+
+        // mid-slot times resolve to the containing byron slot
+        assert_eq!(
+            history.posix_millis_to_slot(1549403245000).unwrap(),
+            2160007
+        );
+
+        assert_eq!(
+            history.posix_millis_to_slot(1646171317000).unwrap(),
+            54605026
+        );
+    }
+
+    #[test]
+    fn past_horizon_fails() {
+        let history = mainnet_eras();
+
+        assert!(history.slot_to_epoch(106876799).is_ok());
+        assert!(matches!(
+            history.slot_to_epoch(106876800),
+            Err(Error::PastHorizon)
+        ));
+
+        assert!(matches!(
+            history.epoch_to_slot(444, 432000),
+            Err(Error::PastHorizon)
+        ));
+    }
+
+    #[test]
+    fn interpreter_roundtrip() {
+        let history = mainnet_eras();
+
+        let cbor = history.encode_interpreter();
+        let decoded = EraHistory::decode_interpreter(history.system_start, &cbor).unwrap();
+
+        assert_eq!(history, decoded);
+    }
+
+    #[test]
+    fn decode_node_interpreter() {
+        // single-era devnet as returned by a node that includes the genesis
+        // window in the era params
+        let cbor = hex::decode(concat!(
+            "9f",
+            "83",
+            "83000000",
+            "f6",
+            "841901f418648300189681001901f4",
+            "ff"
+        ))
+        .unwrap();
+
+        let history = EraHistory::decode_interpreter(1700000000000, &cbor).unwrap();
+
+        assert_eq!(history.eras.len(), 1);
+        assert_eq!(history.eras[0].end, None);
+        assert_eq!(history.eras[0].params.epoch_size, 500);
+        assert_eq!(history.eras[0].params.slot_length, 100);
+        assert_eq!(history.eras[0].params.safe_zone, Some(150));
+    }
+
+    #[test]
+    fn bignum_relative_time() {
+        let bound = Bound {
+            time: 200_000_000_000,
+            slot: 1,
+            epoch: 2,
+        };
+
+        let cbor = minicbor::to_vec(&bound).unwrap();
+
+        // 200_000_000_000 ms in picoseconds doesn't fit in a u64
+        assert_eq!(cbor[1], 0xc2);
+
+        let decoded: Bound = minicbor::decode(&cbor).unwrap();
+        assert_eq!(decoded, bound);
+    }
+
+    #[test]
+    fn devnet_with_short_slots() {
+        let history = EraHistory::new(
+            1700000000000,
+            vec![EraSummary {
+                start: Bound::origin(),
+                end: None,
+                params: EraParams {
+                    epoch_size: 500,
+                    slot_length: 100,
+                    safe_zone: Some(150),
+                },
+            }],
+        );
+
+        assert_eq!(history.slot_to_posix_millis(15).unwrap(), 1700000001500);
+        assert_eq!(history.slot_to_epoch(1234).unwrap(), (2, 234));
+        assert_eq!(history.posix_millis_to_slot(1700000001550).unwrap(), 15);
+    }
+
+    #[test]
+    fn matches_genesis_values() {
+        for genesis in [
+            GenesisValues::mainnet(),
+            GenesisValues::testnet(),
+            GenesisValues::preview(),
+            GenesisValues::preprod(),
+        ] {
+            let history = EraHistory::from(&genesis);
+
+            for slot in [0, 20, 21600, 1031, 86400, 4492800, 51580240, 38580791] {
+                assert_eq!(
+                    history.slot_to_wallclock(slot).unwrap(),
+                    genesis.slot_to_wallclock(slot)
+                );
+
+                assert_eq!(
+                    history.slot_to_epoch(slot).unwrap(),
+                    genesis.absolute_slot_to_relative(slot)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Lazy decoding of blocks
+//!
+//! Decoding a [MultiEraBlock] decodes every tx body, witness set and
+//! auxiliary data of the block, even when only the header or a single tx is
+//! needed. A [LazyBlock] instead walks the CBOR once to find where each of
+//! those components start and end, and decodes them only when they're
+//! accessed. Nothing is copied, the components borrow from the block bytes.
+
+use std::borrow::Cow;
+
+use pallas_codec::minicbor::{self, data::Type, decode, Decoder};
+use pallas_codec::utils::Nullable;
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::{alonzo, babbage, byron};
+
+use crate::{probe, Era, Error, MultiEraBlock, MultiEraHeader, MultiEraTx};
+
+/// the location of the components of a tx within the block bytes
+#[derive(Debug, Clone)]
+struct TxSpans<'b> {
+    body: &'b [u8],
+    witness: &'b [u8],
+    aux: Option<&'b [u8]>,
+    success: bool,
+}
+
+/// A block that decodes its components on demand
+#[derive(Debug, Clone)]
+pub struct LazyBlock<'b> {
+    cbor: &'b [u8],
+    era: Era,
+    epoch_boundary: bool,
+    header: &'b [u8],
+    txs: Vec<TxSpans<'b>>,
+}
+
+/// the bytes of the next data item, without decoding it
+fn span<'b>(d: &mut Decoder<'b>) -> Result<&'b [u8], decode::Error> {
+    let start = d.position();
+    d.skip()?;
+
+    Ok(&d.input()[start..d.position()])
+}
+
+/// iterates the items of a definite or indefinite array or map
+fn each_item<'b>(
+    d: &mut Decoder<'b>,
+    len: Option<u64>,
+    mut item: impl FnMut(&mut Decoder<'b>) -> Result<(), decode::Error>,
+) -> Result<(), decode::Error> {
+    match len {
+        Some(len) => (0..len).try_for_each(|_| item(d)),
+        None => {
+            while d.datatype()? != Type::Break {
+                item(d)?;
+            }
+
+            d.skip()
+        }
+    }
+}
+
+fn array_spans<'b>(d: &mut Decoder<'b>) -> Result<Vec<&'b [u8]>, decode::Error> {
+    let mut spans = vec![];
+    let len = d.array()?;
+    each_item(d, len, |d| span(d).map(|x| spans.push(x)))?;
+
+    Ok(spans)
+}
+
+/// the spans of the txs of a byron block, the header already consumed
+fn byron_txs<'b>(d: &mut Decoder<'b>) -> Result<Vec<TxSpans<'b>>, decode::Error> {
+    let mut txs = vec![];
+
+    // block body, the tx payload is its first member
+    d.array()?;
+    let len = d.array()?;
+
+    each_item(d, len, |d| {
+        let len = d.array()?;
+        let body = span(d)?;
+        let witness = span(d)?;
+
+        if len.is_none() {
+            d.skip()?;
+        }
+
+        txs.push(TxSpans {
+            body,
+            witness,
+            aux: None,
+            success: true,
+        });
+
+        Ok(())
+    })?;
+
+    Ok(txs)
+}
+
+/// the spans of the txs of a post-byron block, the header already consumed
+fn shelley_txs<'b>(
+    d: &mut Decoder<'b>,
+    len: Option<u64>,
+) -> Result<Vec<TxSpans<'b>>, decode::Error> {
+    let bodies = array_spans(d)?;
+    let witnesses = array_spans(d)?;
+
+    let mut aux = vec![];
+    let aux_len = d.map()?;
+    each_item(d, aux_len, |d| {
+        let index = d.u32()?;
+        aux.push((index, span(d)?));
+        Ok(())
+    })?;
+
+    // the invalid txs are only there since alonzo
+    let mut invalid = vec![];
+    let has_invalid = match len {
+        Some(len) => len > 4,
+        None => d.datatype()? != Type::Break,
+    };
+
+    if has_invalid {
+        let len = d.array()?;
+        each_item(d, len, |d| d.u32().map(|x| invalid.push(x)))?;
+    }
+
+    if bodies.len() != witnesses.len() {
+        return Err(decode::Error::message(
+            "tx bodies and witness sets don't match",
+        ));
+    }
+
+    let txs = bodies
+        .into_iter()
+        .zip(witnesses)
+        .enumerate()
+        .map(|(index, (body, witness))| TxSpans {
+            body,
+            witness,
+            aux: aux
+                .iter()
+                .find(|(idx, _)| *idx as usize == index)
+                .map(|(_, x)| *x),
+            success: !invalid.contains(&(index as u32)),
+        })
+        .collect();
+
+    Ok(txs)
+}
+
+impl<'b> LazyBlock<'b> {
+    /// indexes the components of a block, without decoding them
+    pub fn decode(cbor: &'b [u8]) -> Result<Self, Error> {
+        let (era, epoch_boundary) = match probe::block_era(cbor) {
+            probe::Outcome::EpochBoundary => (Era::Byron, true),
+            probe::Outcome::Matched(era) => (era, false),
+            probe::Outcome::Inconclusive => return Err(Error::unknown_cbor(cbor)),
+        };
+
+        let mut d = Decoder::new(cbor);
+
+        // the era wrapper, already checked by the probe
+        d.array().map_err(Error::invalid_cbor)?;
+        d.u16().map_err(Error::invalid_cbor)?;
+
+        let len = d.array().map_err(Error::invalid_cbor)?;
+        let header = span(&mut d).map_err(Error::invalid_cbor)?;
+
+        let txs = match (era, epoch_boundary) {
+            (_, true) => vec![],
+            (Era::Byron, _) => byron_txs(&mut d).map_err(Error::invalid_cbor)?,
+            _ => shelley_txs(&mut d, len).map_err(Error::invalid_cbor)?,
+        };
+
+        Ok(Self {
+            cbor,
+            era,
+            epoch_boundary,
+            header,
+            txs,
+        })
+    }
+
+    pub fn era(&self) -> Era {
+        self.era
+    }
+
+    /// the bytes of the whole block
+    pub fn cbor(&self) -> &'b [u8] {
+        self.cbor
+    }
+
+    /// Decodes the header of the block
+    pub fn header(&self) -> Result<MultiEraHeader<'b>, Error> {
+        let header = match (self.era, self.epoch_boundary) {
+            (_, true) => MultiEraHeader::EpochBoundary(Cow::Owned(decode_raw(self.header)?)),
+            (Era::Byron, _) => MultiEraHeader::Byron(Cow::Owned(decode_raw(self.header)?)),
+            (Era::Babbage, _) => MultiEraHeader::Babbage(Cow::Owned(decode_raw(self.header)?)),
+            _ => MultiEraHeader::AlonzoCompatible(Cow::Owned(decode_raw(self.header)?)),
+        };
+
+        Ok(header)
+    }
+
+    /// Returns the count of txs in the block
+    pub fn tx_count(&self) -> usize {
+        self.txs.len()
+    }
+
+    /// Returns true if the there're no tx in the block
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// Computes the hash of the tx at the given index, without decoding it
+    pub fn tx_hash(&self, index: usize) -> Option<Hash<32>> {
+        self.txs.get(index).map(|x| Hasher::<256>::hash(x.body))
+    }
+
+    /// Decodes the tx at the given index
+    pub fn tx_at(&self, index: usize) -> Option<Result<MultiEraTx<'b>, Error>> {
+        self.txs.get(index).map(|x| self.decode_tx(x))
+    }
+
+    /// Iterates the txs of the block, decoding each one as it's reached
+    pub fn txs(&self) -> impl Iterator<Item = Result<MultiEraTx<'b>, Error>> + '_ {
+        self.txs.iter().map(|x| self.decode_tx(x))
+    }
+
+    /// Decodes the whole block
+    pub fn to_block(&self) -> Result<MultiEraBlock<'b>, Error> {
+        MultiEraBlock::decode(self.cbor)
+    }
+
+    fn decode_tx(&self, spans: &TxSpans<'b>) -> Result<MultiEraTx<'b>, Error> {
+        let aux = match spans.aux {
+            Some(x) => Nullable::Some(decode_raw(x)?),
+            None => Nullable::Null,
+        };
+
+        let tx = match self.era {
+            Era::Byron => MultiEraTx::Byron(Box::new(Cow::Owned(byron::MintedTxPayload {
+                transaction: decode_raw(spans.body)?,
+                witness: decode_raw(spans.witness)?,
+            }))),
+            Era::Babbage => MultiEraTx::Babbage(Box::new(Cow::Owned(babbage::MintedTx {
+                transaction_body: decode_raw(spans.body)?,
+                transaction_witness_set: decode_raw(spans.witness)?,
+                success: spans.success,
+                auxiliary_data: aux,
+            }))),
+            era => MultiEraTx::AlonzoCompatible(
+                Box::new(Cow::Owned(alonzo::MintedTx {
+                    transaction_body: decode_raw(spans.body)?,
+                    transaction_witness_set: decode_raw(spans.witness)?,
+                    success: spans.success,
+                    auxiliary_data: aux,
+                })),
+                era,
+            ),
+        };
+
+        Ok(tx)
+    }
+}
+
+fn decode_raw<'b, T: minicbor::Decode<'b, ()>>(cbor: &'b [u8]) -> Result<T, Error> {
+    minicbor::decode(cbor).map_err(Error::invalid_cbor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lazy_block_matches_full_decoding() {
+        let blocks = vec![
+            include_str!("../../test_data/genesis.block"),
+            include_str!("../../test_data/byron2.block"),
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/allegra1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/alonzo27.block"),
+            include_str!("../../test_data/babbage1.block"),
+            include_str!("../../test_data/babbage3.block"),
+        ];
+
+        for block_str in blocks {
+            let cbor = hex::decode(block_str).expect("invalid hex");
+            let block = MultiEraBlock::decode(&cbor).expect("invalid cbor");
+            let lazy = LazyBlock::decode(&cbor).expect("invalid cbor");
+
+            assert_eq!(lazy.era(), block.era());
+            assert_eq!(lazy.header().unwrap().hash(), block.hash());
+            assert_eq!(lazy.tx_count(), block.tx_count());
+
+            let txs = block.txs();
+            let lazy_txs: Vec<_> = lazy.txs().map(|x| x.unwrap()).collect();
+
+            for (index, (tx, lazy_tx)) in txs.iter().zip(&lazy_txs).enumerate() {
+                assert_eq!(lazy.tx_hash(index), Some(tx.hash()));
+                assert_eq!(lazy_tx.hash(), tx.hash());
+                assert_eq!(lazy_tx.is_valid(), tx.is_valid());
+                assert_eq!(lazy_tx.encode(), tx.encode());
+            }
+
+            assert!(lazy.tx_at(lazy.tx_count()).is_none());
+        }
+    }
+}
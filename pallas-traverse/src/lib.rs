@@ -7,7 +7,7 @@ use thiserror::Error;
 
 use pallas_codec::utils::{KeepRaw, KeyValuePairs};
 use pallas_crypto::hash::Hash;
-use pallas_primitives::{alonzo, babbage, byron};
+use pallas_primitives::{alonzo, babbage, byron, shelley};
 
 mod support;
 
@@ -19,20 +19,30 @@ pub mod era;
 pub mod fees;
 pub mod hashes;
 pub mod header;
+pub mod history;
 pub mod input;
+pub mod lazy;
 pub mod meta;
+pub mod nft;
 pub mod output;
+pub mod owned;
 pub mod probe;
+pub mod script_data;
+pub mod sign;
 pub mod signers;
 pub mod size;
 pub mod time;
 pub mod tx;
+pub mod update;
 pub mod withdrawals;
 pub mod witnesses;
 
 // TODO: move to genesis crate
 pub mod wellknown;
 
+#[cfg(feature = "parallel")]
+pub mod parallel;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[non_exhaustive]
 pub enum Era {
@@ -140,6 +150,14 @@ pub enum MultiEraWithdrawals<'b> {
     AlonzoCompatible(&'b alonzo::Withdrawals),
 }
 
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum MultiEraUpdate<'b> {
+    Shelley(Box<Cow<'b, shelley::Update>>),
+    AlonzoCompatible(&'b alonzo::Update),
+    Babbage(&'b babbage::Update),
+}
+
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum MultiEraSigners<'b> {
@@ -167,6 +185,9 @@ pub enum Error {
 
     #[error("Invalid UTxO ref: {0}")]
     InvalidUtxoRef(String),
+
+    #[error("Requested point is past the horizon of the era history")]
+    PastHorizon,
 }
 
 impl Error {
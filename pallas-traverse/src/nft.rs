@@ -0,0 +1,643 @@
+//! Metadata of native assets, following the CIP-25 and CIP-68 standards
+//!
+//! CIP-25 attaches the metadata of an asset to the tx that mints it, under
+//! the 721 metadata label. CIP-68 keeps it in the inline datum of an output
+//! holding a reference token, which shares its name with the user tokens
+//! except for the CIP-67 label that prefixes it.
+//!
+//! Metadata found on-chain doesn't always follow the standards to the letter,
+//! so parsing is lenient: fields of unexpected types are left empty and
+//! entries that can't be made sense of are skipped.
+
+use pallas_codec::utils::KeyValuePairs;
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{alonzo, babbage};
+
+use crate::{MultiEraMeta, MultiEraOutput, MultiEraTx};
+
+/// The metadata label of CIP-25 metadata
+pub const CIP25_LABEL: alonzo::MetadatumLabel = 721;
+
+/// The CIP-67 label of CIP-68 reference tokens
+pub const REFERENCE_TOKEN_LABEL: u16 = 100;
+
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+
+    for byte in bytes {
+        crc ^= byte;
+
+        for _ in 0..8 {
+            crc = match crc & 0x80 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x07,
+            };
+        }
+    }
+
+    crc
+}
+
+/// Encodes a CIP-67 label as the prefix of an asset name
+pub fn encode_label(label: u16) -> [u8; 4] {
+    let checksum = crc8(&label.to_be_bytes());
+    let prefix = ((label as u32) << 12) | ((checksum as u32) << 4);
+
+    prefix.to_be_bytes()
+}
+
+/// Decodes the CIP-67 label that prefixes an asset name, if any
+pub fn decode_label(asset_name: &[u8]) -> Option<u16> {
+    let prefix: [u8; 4] = asset_name.get(..4)?.try_into().ok()?;
+    let prefix = u32::from_be_bytes(prefix);
+
+    if prefix & 0xf000_000f != 0 {
+        return None;
+    }
+
+    let label = (prefix >> 12) as u16;
+    let checksum = (prefix >> 4) as u8;
+
+    (crc8(&label.to_be_bytes()) == checksum).then_some(label)
+}
+
+/// A file of the asset, as listed in its metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetFile {
+    pub name: Option<String>,
+    pub media_type: Option<String>,
+    pub src: String,
+}
+
+/// The well-known fields of the metadata of an asset
+///
+/// NFTs are described by the first fields, fungible tokens by the last ones.
+/// Fields outside of the standards are available through the raw metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetMetadata {
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub media_type: Option<String>,
+    pub description: Option<String>,
+    pub files: Vec<AssetFile>,
+    pub ticker: Option<String>,
+    pub url: Option<String>,
+    pub logo: Option<String>,
+    pub decimals: Option<u64>,
+}
+
+/// a value of the metadata of an asset, which might come from tx metadata or
+/// from a datum
+#[derive(Clone, Copy)]
+enum Value<'a> {
+    Meta(&'a alonzo::Metadatum),
+    Datum(&'a alonzo::PlutusData),
+}
+
+impl<'a> Value<'a> {
+    fn chunk(self) -> Option<String> {
+        match self {
+            Value::Meta(alonzo::Metadatum::Text(x)) => Some(x.clone()),
+            Value::Datum(alonzo::PlutusData::BoundedBytes(x)) => String::from_utf8(x.to_vec()).ok(),
+            _ => None,
+        }
+    }
+
+    /// strings longer than the 64 bytes allowed by metadata are split in an
+    /// array of chunks, datums do the same for consistency
+    fn text(self) -> Option<String> {
+        match self.items() {
+            Some(chunks) => chunks
+                .into_iter()
+                .map(Value::chunk)
+                .collect::<Option<Vec<_>>>()
+                .map(|x| x.concat()),
+            None => self.chunk(),
+        }
+    }
+
+    fn uint(self) -> Option<u64> {
+        let int = match self {
+            Value::Meta(alonzo::Metadatum::Int(x)) => x,
+            Value::Datum(alonzo::PlutusData::BigInt(alonzo::BigInt::Int(x))) => x,
+            _ => return None,
+        };
+
+        u64::try_from(i128::from(*int)).ok()
+    }
+
+    fn items(self) -> Option<Vec<Value<'a>>> {
+        match self {
+            Value::Meta(alonzo::Metadatum::Array(x)) => Some(x.iter().map(Value::Meta).collect()),
+            Value::Datum(alonzo::PlutusData::Array(x)) => {
+                Some(x.iter().map(Value::Datum).collect())
+            }
+            _ => None,
+        }
+    }
+
+    fn get(self, key: &str) -> Option<Value<'a>> {
+        match self {
+            Value::Meta(alonzo::Metadatum::Map(x)) => x
+                .iter()
+                .find(|(k, _)| Value::Meta(k).chunk().as_deref() == Some(key))
+                .map(|(_, v)| Value::Meta(v)),
+            Value::Datum(alonzo::PlutusData::Map(x)) => x
+                .iter()
+                .find(|(k, _)| Value::Datum(k).chunk().as_deref() == Some(key))
+                .map(|(_, v)| Value::Datum(v)),
+            _ => None,
+        }
+    }
+
+    fn get_text(self, key: &str) -> Option<String> {
+        self.get(key).and_then(Value::text)
+    }
+
+    fn file(self) -> Option<AssetFile> {
+        Some(AssetFile {
+            name: self.get_text("name"),
+            media_type: self.get_text("mediaType"),
+            src: self.get_text("src")?,
+        })
+    }
+
+    fn asset_metadata(self) -> AssetMetadata {
+        let files = self
+            .get("files")
+            .and_then(Value::items)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(Value::file)
+            .collect();
+
+        AssetMetadata {
+            name: self.get_text("name"),
+            image: self.get_text("image"),
+            media_type: self.get_text("mediaType"),
+            description: self.get_text("description"),
+            files,
+            ticker: self.get_text("ticker"),
+            url: self.get_text("url"),
+            logo: self.get_text("logo"),
+            decimals: self.get("decimals").and_then(Value::uint),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cip25Version {
+    /// policies and asset names are keyed by their hex and utf-8 text
+    V1,
+
+    /// policies and asset names are keyed by their raw bytes
+    V2,
+}
+
+/// The CIP-25 metadata of an asset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cip25Asset {
+    pub version: Cip25Version,
+    pub policy: Hash<28>,
+
+    /// the name of the asset, as keyed in the metadata
+    ///
+    /// V1 metadata keys assets by their utf-8 name, but some of it uses the
+    /// hex encoding of the name instead. The assets reported by
+    /// [MultiEraTx::cip25_mints] hold the actual name of the minted asset.
+    pub name: Vec<u8>,
+
+    pub metadata: AssetMetadata,
+
+    /// the whole metadata of the asset, including non-standard properties
+    pub raw: alonzo::Metadatum,
+}
+
+impl Cip25Asset {
+    /// Checks if the metadata describes the asset with the given name
+    pub fn is_for(&self, name: &[u8]) -> bool {
+        if self.name == name {
+            return true;
+        }
+
+        self.version == Cip25Version::V1
+            && self.name.eq_ignore_ascii_case(hex::encode(name).as_bytes())
+    }
+}
+
+fn cip25_version(root: Value) -> Cip25Version {
+    match root.get("version") {
+        Some(Value::Meta(alonzo::Metadatum::Text(x))) if x.starts_with('2') => Cip25Version::V2,
+        Some(x) if x.uint() == Some(2) => Cip25Version::V2,
+        _ => Cip25Version::V1,
+    }
+}
+
+fn cip25_policy(key: &alonzo::Metadatum) -> Option<Hash<28>> {
+    let bytes = match key {
+        alonzo::Metadatum::Text(x) => hex::decode(x).ok()?,
+        alonzo::Metadatum::Bytes(x) => x.to_vec(),
+        _ => return None,
+    };
+
+    let bytes: [u8; 28] = bytes.try_into().ok()?;
+
+    Some(Hash::from(bytes))
+}
+
+fn cip25_asset_name(key: &alonzo::Metadatum) -> Option<Vec<u8>> {
+    match key {
+        alonzo::Metadatum::Text(x) => Some(x.as_bytes().to_vec()),
+        alonzo::Metadatum::Bytes(x) => Some(x.to_vec()),
+        _ => None,
+    }
+}
+
+impl<'b> MultiEraMeta<'b> {
+    /// The assets described by the CIP-25 metadata, if any
+    pub fn cip25(&self) -> Vec<Cip25Asset> {
+        let (root, policies) = match self.find(CIP25_LABEL) {
+            Some(x @ alonzo::Metadatum::Map(policies)) => (x, policies),
+            _ => return vec![],
+        };
+
+        let version = cip25_version(Value::Meta(root));
+
+        let mut out = vec![];
+
+        for (policy, assets) in policies.iter() {
+            let policy = match cip25_policy(policy) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            let assets = match assets {
+                alonzo::Metadatum::Map(x) => x,
+                _ => continue,
+            };
+
+            for (name, raw) in assets.iter() {
+                let name = match cip25_asset_name(name) {
+                    Some(x) => x,
+                    None => continue,
+                };
+
+                out.push(Cip25Asset {
+                    version,
+                    policy,
+                    name,
+                    metadata: Value::Meta(raw).asset_metadata(),
+                    raw: raw.clone(),
+                });
+            }
+        }
+
+        out
+    }
+}
+
+/// The class of the user tokens of CIP-68
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cip68Class {
+    /// non-fungible tokens, labeled 222
+    Nft,
+
+    /// fungible tokens, labeled 333
+    Ft,
+
+    /// rich fungible tokens, labeled 444
+    Rft,
+}
+
+impl Cip68Class {
+    pub fn label(&self) -> u16 {
+        match self {
+            Cip68Class::Nft => 222,
+            Cip68Class::Ft => 333,
+            Cip68Class::Rft => 444,
+        }
+    }
+
+    pub fn from_label(label: u16) -> Option<Self> {
+        match label {
+            222 => Some(Cip68Class::Nft),
+            333 => Some(Cip68Class::Ft),
+            444 => Some(Cip68Class::Rft),
+            _ => None,
+        }
+    }
+}
+
+/// The datum of a CIP-68 reference token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cip68Datum {
+    pub version: u64,
+    pub metadata: AssetMetadata,
+
+    /// the whole metadata map, including non-standard properties
+    pub raw: KeyValuePairs<alonzo::PlutusData, alonzo::PlutusData>,
+
+    /// custom data of the datum, missing from the first version of the datums
+    pub extra: Option<alonzo::PlutusData>,
+}
+
+impl Cip68Datum {
+    /// Decodes the datum of a reference token, as `Constr 0 [metadata,
+    /// version, extra]`
+    pub fn decode(datum: &alonzo::PlutusData) -> Option<Self> {
+        let fields = match datum {
+            alonzo::PlutusData::Constr(x) if x.tag == 121 => &x.fields,
+            _ => return None,
+        };
+
+        let raw = match fields.first()? {
+            alonzo::PlutusData::Map(x) => x,
+            _ => return None,
+        };
+
+        Some(Cip68Datum {
+            version: Value::Datum(fields.get(1)?).uint()?,
+            metadata: Value::Datum(&fields[0]).asset_metadata(),
+            raw: raw.clone(),
+            extra: fields.get(2).cloned(),
+        })
+    }
+}
+
+impl<'b> MultiEraOutput<'b> {
+    /// The CIP-68 datum held inline by the output, if any
+    pub fn cip68_datum(&self) -> Option<Cip68Datum> {
+        match self.datum()? {
+            babbage::PseudoDatumOption::Data(x) => Cip68Datum::decode(&x.0),
+            babbage::PseudoDatumOption::Hash(_) => None,
+        }
+    }
+}
+
+/// A CIP-68 reference token produced by a tx, along with its metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cip68Reference {
+    pub policy: Hash<28>,
+
+    /// the name shared by the reference and the user tokens, without label
+    pub asset_name: Vec<u8>,
+
+    /// the index of the output that holds the reference token
+    pub output_index: usize,
+
+    /// the class of the user token minted along with the reference token,
+    /// none when the tx only updates the metadata
+    pub minted_class: Option<Cip68Class>,
+
+    pub datum: Cip68Datum,
+}
+
+impl Cip68Reference {
+    pub fn reference_token_name(&self) -> Vec<u8> {
+        [
+            &encode_label(REFERENCE_TOKEN_LABEL),
+            self.asset_name.as_slice(),
+        ]
+        .concat()
+    }
+
+    pub fn user_token_name(&self, class: Cip68Class) -> Vec<u8> {
+        [&encode_label(class.label()), self.asset_name.as_slice()].concat()
+    }
+}
+
+impl<'b> MultiEraTx<'b> {
+    fn minted_assets(&self) -> Vec<(Hash<28>, Vec<u8>)> {
+        self.mints()
+            .iter()
+            .flat_map(|x| x.assets())
+            .filter(|x| x.any_coin() > 0)
+            .map(|x| (*x.policy(), x.name().to_vec()))
+            .collect()
+    }
+
+    /// The CIP-25 metadata of the assets minted by the tx
+    ///
+    /// Metadata entries are matched with the minted assets by policy and
+    /// name, entries of assets that the tx doesn't mint are left out.
+    pub fn cip25_mints(&self) -> Vec<Cip25Asset> {
+        let entries = self.metadata().cip25();
+
+        if entries.is_empty() {
+            return vec![];
+        }
+
+        self.minted_assets()
+            .into_iter()
+            .filter_map(|(policy, name)| {
+                let mut entry = entries
+                    .iter()
+                    .find(|x| x.policy == policy && x.is_for(&name))?
+                    .clone();
+
+                entry.name = name;
+
+                Some(entry)
+            })
+            .collect()
+    }
+
+    /// The CIP-68 reference tokens produced by the tx with their metadata
+    ///
+    /// Reference tokens are reported whether the tx mints them or just moves
+    /// them to update their metadata, as long as their output holds a CIP-68
+    /// datum inline.
+    pub fn cip68_references(&self) -> Vec<Cip68Reference> {
+        let minted = self.minted_assets();
+
+        let mut out = vec![];
+
+        for (output_index, output) in self.produces() {
+            let assets = output.non_ada_assets();
+
+            let references: Vec<_> = assets
+                .iter()
+                .flat_map(|x| x.assets())
+                .filter(|x| decode_label(x.name()) == Some(REFERENCE_TOKEN_LABEL))
+                .collect();
+
+            if references.is_empty() {
+                continue;
+            }
+
+            let datum = match output.cip68_datum() {
+                Some(x) => x,
+                None => continue,
+            };
+
+            for reference in references {
+                let policy = *reference.policy();
+                let asset_name = reference.name()[4..].to_vec();
+
+                let minted_class = minted
+                    .iter()
+                    .filter(|(p, name)| *p == policy && name.get(4..) == Some(&asset_name[..]))
+                    .find_map(|(_, name)| Cip68Class::from_label(decode_label(name)?));
+
+                out.push(Cip68Reference {
+                    policy,
+                    asset_name,
+                    output_index,
+                    minted_class,
+                    datum: datum.clone(),
+                });
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultiEraBlock;
+
+    #[test]
+    fn labels_encode_and_decode() {
+        let expected = [
+            (100, "000643b0"),
+            (222, "000de140"),
+            (333, "0014df10"),
+            (444, "001bc280"),
+        ];
+
+        for (label, prefix) in expected {
+            assert_eq!(hex::encode(encode_label(label)), prefix);
+
+            let name = [&encode_label(label)[..], b"Egg"].concat();
+            assert_eq!(decode_label(&name), Some(label));
+        }
+
+        assert_eq!(decode_label(&hex::decode("000643b1").unwrap()), None);
+        assert_eq!(decode_label(&hex::decode("000643a0").unwrap()), None);
+        assert_eq!(decode_label(b"Egg"), None);
+    }
+
+    #[test]
+    fn cip25_mints_are_found() {
+        let cbor = hex::decode(include_str!("../../test_data/alonzo14.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().swap_remove(5);
+
+        let mints = tx.cip25_mints();
+        assert_eq!(mints.len(), 1);
+
+        let asset = &mints[0];
+        assert_eq!(asset.version, Cip25Version::V1);
+        assert_eq!(asset.name, b"ab");
+
+        // the description is split in chunks
+        let metadata = &asset.metadata;
+        assert_eq!(metadata.name.as_deref(), Some("ab"));
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("This is a test NFTThis is a test NFT-2")
+        );
+        assert_eq!(metadata.media_type.as_deref(), Some("image/png"));
+        assert_eq!(metadata.files.len(), 1);
+        assert_eq!(metadata.files[0].name.as_deref(), Some("file-1"));
+
+        let cbor = hex::decode(include_str!("../../test_data/mary1.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().swap_remove(11);
+
+        assert_eq!(tx.metadata().cip25().len(), 77);
+        assert_eq!(tx.cip25_mints().len(), 77);
+    }
+
+    #[test]
+    fn cip25_v2_and_hex_keys_are_understood() {
+        let policy = [7u8; 28];
+        let name = b"\x00\x01Token".to_vec();
+
+        let text = |x: &str| alonzo::Metadatum::Text(x.to_owned());
+        let map = |x: Vec<(alonzo::Metadatum, alonzo::Metadatum)>| {
+            alonzo::Metadatum::Map(KeyValuePairs::from(x))
+        };
+
+        let asset = map(vec![
+            (text("name"), text("Token")),
+            (
+                text("image"),
+                alonzo::Metadatum::Array(vec![text("ipfs://"), text("Qm")]),
+            ),
+        ]);
+
+        let v2 = map(vec![
+            (
+                alonzo::Metadatum::Bytes(policy.to_vec().into()),
+                map(vec![(
+                    alonzo::Metadatum::Bytes(name.clone().into()),
+                    asset.clone(),
+                )]),
+            ),
+            (text("version"), text("2.0")),
+        ]);
+
+        let metadata: alonzo::Metadata = vec![(CIP25_LABEL, v2)].into();
+        let assets = MultiEraMeta::AlonzoCompatible(&metadata).cip25();
+
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].version, Cip25Version::V2);
+        assert_eq!(assets[0].policy, Hash::from(policy));
+        assert_eq!(assets[0].metadata.image.as_deref(), Some("ipfs://Qm"));
+        assert!(assets[0].is_for(&name));
+
+        let v1 = map(vec![
+            (
+                text(&hex::encode(policy)),
+                map(vec![(text(&hex::encode(&name)), asset)]),
+            ),
+            (text("bogus"), text("entry")),
+        ]);
+
+        let metadata: alonzo::Metadata = vec![(CIP25_LABEL, v1)].into();
+        let assets = MultiEraMeta::AlonzoCompatible(&metadata).cip25();
+
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].version, Cip25Version::V1);
+        assert!(assets[0].is_for(&name));
+        assert!(!assets[0].is_for(b"Token"));
+    }
+
+    #[test]
+    fn cip68_references_are_found() {
+        let cbor = hex::decode(include_str!("../../test_data/babbage9.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().swap_remove(34);
+
+        let references = tx.cip68_references();
+        assert_eq!(references.len(), 1);
+
+        let reference = &references[0];
+        assert_eq!(reference.asset_name, b"StrangeEgg22");
+        assert_eq!(reference.output_index, 0);
+        assert_eq!(reference.minted_class, Some(Cip68Class::Nft));
+
+        assert_eq!(
+            hex::encode(reference.reference_token_name()),
+            "000643b0537472616e67654567673232"
+        );
+        assert_eq!(
+            hex::encode(reference.user_token_name(Cip68Class::Nft)),
+            "000de140537472616e67654567673232"
+        );
+
+        let datum = &reference.datum;
+        assert_eq!(datum.version, 1);
+        assert_eq!(datum.extra, None);
+        assert_eq!(datum.raw.len(), 7);
+        assert_eq!(datum.metadata.name.as_deref(), Some("StrangeEgg #22"));
+        assert_eq!(datum.metadata.media_type.as_deref(), Some("image/png"));
+        assert_eq!(datum.metadata.files.len(), 1);
+
+        let output = tx.produces_at(0).unwrap();
+        assert_eq!(output.cip68_datum().as_ref(), Some(datum));
+    }
+}
@@ -0,0 +1,262 @@
+//! Owned counterparts of the multi-era types
+//!
+//! The multi-era types borrow from the buffer they were decoded from, which
+//! gets in the way when they need to be cached, sent to other tasks or
+//! returned next to the bytes. The types in this module keep the cbor in a
+//! shared buffer next to the decoded view, so they have no lifetime and can be
+//! cloned cheaply. The traversal API is available through `view`.
+
+use std::sync::Arc;
+
+use pallas_codec::minicbor;
+
+use crate::{Era, Error, MultiEraBlock, MultiEraOutput, MultiEraTx};
+
+/// Extends the lifetime of the bytes of a shared buffer
+///
+/// # Safety
+///
+/// The returned slice must not outlive the buffer. The owned types keep the
+/// buffer next to the views built out of the slice and declare it after them,
+/// so that the views are dropped first. The bytes are on the heap, moving the
+/// owned types around doesn't move them.
+unsafe fn extend(cbor: &Arc<[u8]>) -> &'static [u8] {
+    &*Arc::as_ptr(cbor)
+}
+
+/// A [MultiEraBlock] that owns its cbor
+#[derive(Debug, Clone)]
+pub struct OwnedMultiEraBlock {
+    view: MultiEraBlock<'static>,
+    cbor: Arc<[u8]>,
+}
+
+impl OwnedMultiEraBlock {
+    pub fn decode(cbor: impl Into<Arc<[u8]>>) -> Result<Self, Error> {
+        let cbor = cbor.into();
+        let view = MultiEraBlock::decode(unsafe { extend(&cbor) })?;
+
+        Ok(Self { view, cbor })
+    }
+
+    /// the borrowed block, to traverse its data
+    pub fn view(&self) -> &MultiEraBlock<'_> {
+        &self.view
+    }
+
+    pub fn cbor(&self) -> &[u8] {
+        &self.cbor
+    }
+
+    /// Builds a vec with the Txs of the block, sharing its cbor
+    pub fn txs(&self) -> Vec<OwnedMultiEraTx> {
+        self.view
+            .txs()
+            .into_iter()
+            .map(|tx| {
+                // the txs only borrow from the cbor of the block, see the
+                // invariant documented at support::clone_alonzo_txs
+                let view: MultiEraTx<'static> = unsafe { std::mem::transmute(tx) };
+
+                OwnedMultiEraTx {
+                    view,
+                    cbor: self.cbor.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&MultiEraBlock<'_>> for OwnedMultiEraBlock {
+    type Error = Error;
+
+    /// Re-encodes the block, prefer [OwnedMultiEraBlock::decode] when the
+    /// original bytes are at hand
+    fn try_from(block: &MultiEraBlock<'_>) -> Result<Self, Self::Error> {
+        Self::decode(block.encode())
+    }
+}
+
+/// A [MultiEraTx] that owns its cbor
+#[derive(Debug, Clone)]
+pub struct OwnedMultiEraTx {
+    view: MultiEraTx<'static>,
+    cbor: Arc<[u8]>,
+}
+
+impl OwnedMultiEraTx {
+    pub fn decode(era: Era, cbor: impl Into<Arc<[u8]>>) -> Result<Self, minicbor::decode::Error> {
+        let cbor = cbor.into();
+        let view = MultiEraTx::decode(era, unsafe { extend(&cbor) })?;
+
+        Ok(Self { view, cbor })
+    }
+
+    /// Takes ownership of `tx` using the bytes it was decoded from, which
+    /// saves re-encoding it
+    pub fn from_original(
+        tx: &MultiEraTx<'_>,
+        cbor: impl Into<Arc<[u8]>>,
+    ) -> Result<Self, minicbor::decode::Error> {
+        let owned = Self::decode(tx.era(), cbor)?;
+        debug_assert_eq!(owned.view.hash(), tx.hash());
+
+        Ok(owned)
+    }
+
+    /// the borrowed tx, to traverse its data
+    pub fn view(&self) -> &MultiEraTx<'_> {
+        // MultiEraTx is invariant over its lifetime because of the Cow, but
+        // shortening it behind a shared reference is sound
+        unsafe { std::mem::transmute(&self.view) }
+    }
+
+    /// the cbor the tx was decoded from, which for txs of a block is the cbor
+    /// of the whole block
+    pub fn cbor(&self) -> &[u8] {
+        &self.cbor
+    }
+}
+
+impl TryFrom<&MultiEraTx<'_>> for OwnedMultiEraTx {
+    type Error = Error;
+
+    /// Re-encodes the tx, prefer [OwnedMultiEraTx::from_original] when the
+    /// original bytes are at hand
+    fn try_from(tx: &MultiEraTx<'_>) -> Result<Self, Self::Error> {
+        Self::decode(tx.era(), tx.encode()).map_err(Error::invalid_cbor)
+    }
+}
+
+/// A [MultiEraOutput] that owns its cbor
+#[derive(Debug, Clone)]
+pub struct OwnedMultiEraOutput {
+    view: MultiEraOutput<'static>,
+    cbor: Arc<[u8]>,
+}
+
+impl OwnedMultiEraOutput {
+    pub fn decode(era: Era, cbor: impl Into<Arc<[u8]>>) -> Result<Self, minicbor::decode::Error> {
+        let cbor = cbor.into();
+        let view = MultiEraOutput::decode(era, unsafe { extend(&cbor) })?;
+
+        Ok(Self { view, cbor })
+    }
+
+    /// Takes ownership of `output` using the bytes it was decoded from, which
+    /// saves re-encoding it
+    pub fn from_original(
+        output: &MultiEraOutput<'_>,
+        cbor: impl Into<Arc<[u8]>>,
+    ) -> Result<Self, minicbor::decode::Error> {
+        Self::decode(output_era(output), cbor)
+    }
+
+    /// the borrowed output, to traverse its data
+    pub fn view(&self) -> &MultiEraOutput<'_> {
+        // MultiEraOutput is invariant over its lifetime because of the Cow,
+        // but shortening it behind a shared reference is sound
+        unsafe { std::mem::transmute(&self.view) }
+    }
+
+    pub fn cbor(&self) -> &[u8] {
+        &self.cbor
+    }
+}
+
+/// The era whose output format matches the one of `output`
+fn output_era(output: &MultiEraOutput<'_>) -> Era {
+    match output {
+        MultiEraOutput::Byron(_) => Era::Byron,
+        MultiEraOutput::AlonzoCompatible(_) => Era::Alonzo,
+        MultiEraOutput::Babbage(_) => Era::Babbage,
+    }
+}
+
+impl TryFrom<&MultiEraOutput<'_>> for OwnedMultiEraOutput {
+    type Error = Error;
+
+    /// Re-encodes the output, prefer [OwnedMultiEraOutput::from_original]
+    /// when the original bytes are at hand
+    fn try_from(output: &MultiEraOutput<'_>) -> Result<Self, Self::Error> {
+        Self::decode(output_era(output), output.encode()).map_err(Error::invalid_cbor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned_block(name: &str) -> OwnedMultiEraBlock {
+        let path = format!("{}/../test_data/{name}", env!("CARGO_MANIFEST_DIR"));
+        let cbor = hex::decode(std::fs::read_to_string(path).unwrap().trim()).unwrap();
+
+        OwnedMultiEraBlock::decode(cbor).unwrap()
+    }
+
+    #[test]
+    fn owned_block_matches_borrowed() {
+        let blocks = [
+            "byron1.block",
+            "shelley1.block",
+            "alonzo1.block",
+            "babbage1.block",
+        ];
+
+        for name in blocks {
+            let owned = owned_block(name);
+            let block = MultiEraBlock::decode(owned.cbor()).unwrap();
+
+            assert_eq!(owned.view().hash(), block.hash());
+            assert_eq!(owned.view().tx_count(), block.tx_count());
+            let copy = OwnedMultiEraBlock::try_from(&block).unwrap();
+            assert_eq!(copy.cbor(), owned.cbor());
+
+            let hashes: Vec<_> = block.txs().iter().map(|x| x.hash()).collect();
+            let owned_txs = owned.txs();
+            let owned_hashes: Vec<_> = owned_txs.iter().map(|x| x.view().hash()).collect();
+            assert_eq!(owned_hashes, hashes);
+        }
+    }
+
+    #[test]
+    fn owned_values_outlive_the_block() {
+        fn first_tx(name: &str) -> (OwnedMultiEraTx, OwnedMultiEraOutput) {
+            let block = owned_block(name);
+            let tx = block.txs().remove(0);
+            let output = OwnedMultiEraOutput::try_from(&tx.view().outputs()[0]).unwrap();
+
+            (tx, output)
+        }
+
+        let (tx, output) = std::thread::spawn(|| first_tx("babbage1.block"))
+            .join()
+            .unwrap();
+
+        let copy = OwnedMultiEraTx::try_from(tx.view()).unwrap();
+        assert_eq!(copy.view().hash(), tx.view().hash());
+        assert_eq!(copy.cbor(), tx.view().encode());
+
+        assert_eq!(
+            output.view().lovelace_amount(),
+            tx.view().outputs()[0].lovelace_amount()
+        );
+    }
+
+    #[test]
+    fn owned_values_from_original_bytes() {
+        let cbor = hex::decode(include_str!("../../test_data/babbage11.tx")).unwrap();
+        let tx = MultiEraTx::decode(Era::Babbage, &cbor).unwrap();
+
+        let owned = OwnedMultiEraTx::from_original(&tx, cbor.clone()).unwrap();
+        assert_eq!(owned.view().hash(), tx.hash());
+        assert_eq!(owned.cbor(), cbor);
+
+        let output = &tx.outputs()[0];
+        let cbor = output.encode();
+
+        let owned = OwnedMultiEraOutput::from_original(output, cbor.clone()).unwrap();
+        assert_eq!(owned.view().lovelace_amount(), output.lovelace_amount());
+        assert_eq!(owned.cbor(), cbor);
+    }
+}
@@ -0,0 +1,144 @@
+//! Parallel decoding of block archives
+//!
+//! Decodes a sequence of raw blocks on the rayon thread pool, mapping each
+//! decoded block into whatever the caller needs to keep from it. The blocks
+//! are pulled from the source in batches, so that archives of any size can be
+//! processed without loading them fully in memory, and the results come out
+//! in the same order as the blocks went in.
+
+use rayon::prelude::*;
+
+use crate::{Error, MultiEraBlock};
+
+const DEFAULT_BATCH_SIZE: usize = 1_000;
+
+/// A block of the sequence that couldn't be decoded
+#[derive(Debug, thiserror::Error)]
+#[error("block at position {index} failed to decode: {error}")]
+pub struct BlockFailure {
+    /// position of the block in the source sequence
+    pub index: usize,
+
+    #[source]
+    pub error: Error,
+}
+
+/// Iterator over the mapped blocks of a sequence, see [par_decode_map]
+pub struct ParDecodeMap<I, F, T> {
+    blocks: I,
+    map: F,
+    batch_size: usize,
+    offset: usize,
+    ready: std::vec::IntoIter<Result<T, BlockFailure>>,
+}
+
+impl<I, F, T> ParDecodeMap<I, F, T> {
+    /// Sets how many blocks are decoded in parallel at once
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+impl<I, B, F, T> Iterator for ParDecodeMap<I, F, T>
+where
+    I: Iterator<Item = B>,
+    B: AsRef<[u8]> + Send,
+    F: Fn(MultiEraBlock<'_>) -> T + Sync,
+    T: Send,
+{
+    type Item = Result<T, BlockFailure>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(x) = self.ready.next() {
+            return Some(x);
+        }
+
+        let batch: Vec<_> = self.blocks.by_ref().take(self.batch_size).collect();
+
+        if batch.is_empty() {
+            return None;
+        }
+
+        let offset = self.offset;
+        self.offset += batch.len();
+
+        let map = &self.map;
+
+        let results: Vec<_> = batch
+            .into_par_iter()
+            .enumerate()
+            .map(|(index, cbor)| match MultiEraBlock::decode(cbor.as_ref()) {
+                Ok(block) => Ok(map(block)),
+                Err(error) => Err(BlockFailure {
+                    index: offset + index,
+                    error,
+                }),
+            })
+            .collect();
+
+        self.ready = results.into_iter();
+        self.ready.next()
+    }
+}
+
+/// Decodes and maps a sequence of raw blocks in parallel
+///
+/// The results keep the order of the blocks. A block that fails to decode
+/// yields a [BlockFailure] in its position, without stopping the rest.
+pub fn par_decode_map<I, F, T>(blocks: I, map: F) -> ParDecodeMap<I::IntoIter, F, T>
+where
+    I: IntoIterator,
+    F: Fn(MultiEraBlock<'_>) -> T,
+{
+    ParDecodeMap {
+        blocks: blocks.into_iter(),
+        map,
+        batch_size: DEFAULT_BATCH_SIZE,
+        offset: 0,
+        ready: vec![].into_iter(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_keep_chain_order() {
+        let blocks = [
+            include_str!("../../test_data/byron1.block"),
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/allegra1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/babbage1.block"),
+        ];
+
+        let mut cbors: Vec<_> = blocks.iter().map(|x| hex::decode(x).unwrap()).collect();
+
+        // a broken block in the middle of the sequence
+        cbors.insert(3, vec![0x82, 0x09, 0x80]);
+
+        let expected: Vec<_> = cbors
+            .iter()
+            .map(|x| MultiEraBlock::decode(x).map(|x| x.hash()).ok())
+            .collect();
+
+        let results: Vec<_> = par_decode_map(&cbors, |x| x.hash()).batch_size(2).collect();
+
+        assert_eq!(results.len(), cbors.len());
+
+        for (index, (result, expected)) in results.iter().zip(expected).enumerate() {
+            match result {
+                Ok(hash) => assert_eq!(Some(*hash), expected),
+                Err(failure) => {
+                    assert_eq!(failure.index, index);
+                    assert!(expected.is_none());
+                }
+            }
+        }
+
+        assert!(results[3].is_err());
+    }
+}
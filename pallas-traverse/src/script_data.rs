@@ -0,0 +1,316 @@
+//! Computation of the script data hash of a transaction
+//!
+//! The body of Alonzo and Babbage txs commits to the redeemers, the datums
+//! and the cost models of the Plutus languages used by the tx through the
+//! `script_data_hash` field. Redeemers and datums are hashed using the bytes
+//! found in the witness set, so that the hash can be checked regardless of
+//! the ordering or encoding chosen by the tx author.
+
+use pallas_codec::minicbor::{self, data::Type};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::babbage::{CostMdls, Language};
+
+use crate::{Error, MultiEraTx};
+
+const WITNESS_SET_DATUMS: u64 = 4;
+const WITNESS_SET_REDEEMERS: u64 = 5;
+
+/// encode the language views of the given languages, the way they're
+/// included in the script data hash
+///
+/// Languages without a cost model are left out. For historical reasons, the
+/// view of Plutus V1 has both its key and its cost model (an indefinite
+/// array) wrapped in a CBOR byte string.
+pub fn language_views(cost_models: &CostMdls, languages: &[Language]) -> Vec<u8> {
+    // keys are sorted as canonical CBOR requires: shorter encodings first,
+    // which puts the V2 key (`01`) before the V1 one (`41 00`)
+    let mut views = Vec::new();
+
+    if languages.contains(&Language::PlutusV2) {
+        if let Some(model) = &cost_models.plutus_v2 {
+            let mut e = minicbor::Encoder::new(Vec::new());
+            e.u8(1).unwrap();
+            e.array(model.len() as u64).unwrap();
+            for x in model {
+                e.i64(*x).unwrap();
+            }
+
+            views.push(e.into_writer());
+        }
+    }
+
+    if languages.contains(&Language::PlutusV1) {
+        if let Some(model) = &cost_models.plutus_v1 {
+            let mut params = minicbor::Encoder::new(Vec::new());
+            params.begin_array().unwrap();
+            for x in model {
+                params.i64(*x).unwrap();
+            }
+            params.end().unwrap();
+
+            let mut e = minicbor::Encoder::new(Vec::new());
+            e.bytes(&[0]).unwrap();
+            e.bytes(&params.into_writer()).unwrap();
+
+            views.push(e.into_writer());
+        }
+    }
+
+    let mut e = minicbor::Encoder::new(Vec::new());
+    e.map(views.len() as u64).unwrap();
+
+    for view in views {
+        e.writer_mut().extend_from_slice(&view);
+    }
+
+    e.into_writer()
+}
+
+/// compute the script data hash from the CBOR of the redeemers and datums
+/// arrays and the encoded language views
+///
+/// Returns `None` if there are neither redeemers nor datums, in which case
+/// the body must not have a script data hash.
+pub fn script_data_hash(
+    redeemers: Option<&[u8]>,
+    datums: Option<&[u8]>,
+    language_views: &[u8],
+) -> Option<Hash<32>> {
+    let mut hasher = Hasher::<256>::new();
+
+    match (redeemers, datums) {
+        (None, None) => return None,
+        (Some(redeemers), datums) => {
+            hasher.input(redeemers);
+            hasher.input(datums.unwrap_or_default());
+            hasher.input(language_views);
+        }
+        // no script is run, only the datums are committed to
+        (None, Some(datums)) => {
+            hasher.input(&[0x80]);
+            hasher.input(datums);
+            hasher.input(&[0xa0]);
+        }
+    }
+
+    Some(hasher.finalize())
+}
+
+/// find the original bytes of an entry of a witness set map
+fn witness_set_entry(witness_set: &[u8], key: u64) -> Result<Option<&[u8]>, Error> {
+    let mut d = minicbor::Decoder::new(witness_set);
+    let len = d.map().map_err(Error::invalid_cbor)?;
+
+    let mut i = 0;
+
+    loop {
+        match len {
+            Some(len) if i == len => return Ok(None),
+            None if d.datatype().map_err(Error::invalid_cbor)? == Type::Break => return Ok(None),
+            _ => (),
+        }
+
+        let k = d.u64().map_err(Error::invalid_cbor)?;
+        let start = d.position();
+        d.skip().map_err(Error::invalid_cbor)?;
+
+        if k == key {
+            return Ok(Some(&witness_set[start..d.position()]));
+        }
+
+        i += 1;
+    }
+}
+
+impl<'b> MultiEraTx<'b> {
+    /// the script data hash declared in the body, if any
+    pub fn script_data_hash(&self) -> Option<Hash<32>> {
+        match self {
+            MultiEraTx::AlonzoCompatible(x, _) => x.transaction_body.script_data_hash,
+            MultiEraTx::Babbage(x) => x.transaction_body.script_data_hash,
+            MultiEraTx::Byron(_) => None,
+        }
+    }
+
+    /// the languages of the Plutus scripts in the witness set
+    ///
+    /// Scripts run from reference inputs are not part of the tx, their
+    /// languages need to be found by resolving the inputs.
+    pub fn plutus_languages(&self) -> Vec<Language> {
+        let mut languages = vec![];
+
+        if !self.plutus_v1_scripts().is_empty() {
+            languages.push(Language::PlutusV1);
+        }
+
+        if !self.plutus_v2_scripts().is_empty() {
+            languages.push(Language::PlutusV2);
+        }
+
+        languages
+    }
+
+    /// compute the script data hash of the tx using the original bytes of its
+    /// redeemers and datums and the cost models of the given languages
+    pub fn compute_script_data_hash(
+        &self,
+        cost_models: &CostMdls,
+        languages: &[Language],
+    ) -> Result<Option<Hash<32>>, Error> {
+        let witness_set = match self {
+            MultiEraTx::AlonzoCompatible(x, _) => x.transaction_witness_set.raw_cbor(),
+            MultiEraTx::Babbage(x) => x.transaction_witness_set.raw_cbor(),
+            MultiEraTx::Byron(_) => return Ok(None),
+        };
+
+        let redeemers = match self.redeemers().is_empty() {
+            true => None,
+            false => witness_set_entry(witness_set, WITNESS_SET_REDEEMERS)?,
+        };
+
+        let datums = match self.plutus_data().is_empty() {
+            true => None,
+            false => witness_set_entry(witness_set, WITNESS_SET_DATUMS)?,
+        };
+
+        let views = language_views(cost_models, languages);
+
+        Ok(script_data_hash(redeemers, datums, &views))
+    }
+
+    /// check the script data hash of the body against the one computed from
+    /// the witness set, using the cost models of the given languages
+    pub fn verify_script_data_hash(
+        &self,
+        cost_models: &CostMdls,
+        languages: &[Language],
+    ) -> Result<bool, Error> {
+        let computed = self.compute_script_data_hash(cost_models, languages)?;
+
+        Ok(computed == self.script_data_hash())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor::Encoder;
+
+    use super::*;
+    use crate::MultiEraBlock;
+
+    // the Plutus V1 cost model of the mainnet Alonzo genesis
+    const ALONZO_V1: [i64; 166] = [
+        197209, 0, 1, 1, 396231, 621, 0, 1, 150000, 1000, 0, 1, 150000, 32, 2477736, 29175, 4,
+        29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 100, 100, 29773,
+        100, 150000, 32, 150000, 32, 150000, 32, 150000, 1000, 0, 1, 150000, 32, 150000, 1000, 0,
+        8, 148000, 425507, 118, 0, 1, 1, 150000, 1000, 0, 8, 150000, 112536, 247, 1, 150000, 10000,
+        1, 136542, 1326, 1, 1000, 150000, 1000, 1, 150000, 32, 150000, 32, 150000, 32, 1, 1,
+        150000, 1, 150000, 4, 103599, 248, 1, 103599, 248, 1, 145276, 1366, 1, 179690, 497, 1,
+        150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 148000, 425507,
+        118, 0, 1, 1, 61516, 11218, 0, 1, 150000, 32, 148000, 425507, 118, 0, 1, 1, 148000, 425507,
+        118, 0, 1, 1, 2477736, 29175, 4, 0, 82363, 4, 150000, 5000, 0, 1, 150000, 32, 197209, 0, 1,
+        1, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32,
+        3345831, 1, 1,
+    ];
+
+    fn alonzo_cost_models() -> CostMdls {
+        CostMdls {
+            plutus_v1: Some(ALONZO_V1.to_vec()),
+            plutus_v2: None,
+            layout: Default::default(),
+        }
+    }
+
+    #[test]
+    fn language_views_encoding() {
+        let cost_models = CostMdls {
+            plutus_v1: Some(vec![1, -2]),
+            plutus_v2: Some(vec![3, 4]),
+            layout: Default::default(),
+        };
+
+        let views = language_views(&cost_models, &[Language::PlutusV1, Language::PlutusV2]);
+        assert_eq!(hex::encode(views), "a2018203044100449f0121ff");
+
+        let views = language_views(&cost_models, &[Language::PlutusV1]);
+        assert_eq!(hex::encode(views), "a14100449f0121ff");
+
+        let views = language_views(&alonzo_cost_models(), &[Language::PlutusV2]);
+        assert_eq!(hex::encode(views), "a0");
+    }
+
+    #[test]
+    fn mainnet_script_data_hashes() {
+        let test_blocks = [
+            include_str!("../../test_data/alonzo9.block"),
+            include_str!("../../test_data/alonzo14.block"),
+            include_str!("../../test_data/alonzo27.block"),
+        ];
+
+        let cost_models = alonzo_cost_models();
+
+        let mut with_redeemers = 0;
+        let mut only_datums = 0;
+
+        for block_str in test_blocks {
+            let cbor = hex::decode(block_str).unwrap();
+            let block = MultiEraBlock::decode(&cbor).unwrap();
+
+            for tx in block.txs() {
+                let languages = tx.plutus_languages();
+                assert!(tx
+                    .verify_script_data_hash(&cost_models, &languages)
+                    .unwrap());
+
+                match (tx.script_data_hash(), tx.redeemers().is_empty()) {
+                    (Some(_), false) => with_redeemers += 1,
+                    (Some(_), true) => only_datums += 1,
+                    (None, _) => (),
+                }
+            }
+        }
+
+        assert!(with_redeemers > 0);
+        assert!(only_datums > 0);
+    }
+
+    #[test]
+    fn hash_depends_on_cost_model() {
+        let cbor = hex::decode(include_str!("../../test_data/alonzo14.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let tx = block.txs().swap_remove(9);
+
+        let mut cost_models = alonzo_cost_models();
+        assert!(tx
+            .verify_script_data_hash(&cost_models, &[Language::PlutusV1])
+            .unwrap());
+
+        // the hash of a tx running V1 scripts doesn't commit to V2 params
+        cost_models.plutus_v2 = Some(vec![1, 2, 3]);
+        assert!(tx
+            .verify_script_data_hash(&cost_models, &[Language::PlutusV1])
+            .unwrap());
+
+        cost_models.plutus_v1.as_mut().unwrap()[0] += 1;
+        assert!(!tx
+            .verify_script_data_hash(&cost_models, &[Language::PlutusV1])
+            .unwrap());
+    }
+
+    #[test]
+    fn hash_of_empty_script_data() {
+        assert_eq!(script_data_hash(None, None, &[0xa0]), None);
+
+        // with no redeemers, the datums are hashed with empty redeemers and
+        // language views whatever the languages
+        let mut datums = Encoder::new(Vec::new());
+        datums.array(1).unwrap().u8(42).unwrap();
+        let datums = datums.into_writer();
+
+        let expected = Hasher::<256>::hash(&[&[0x80], datums.as_slice(), &[0xa0]].concat());
+        assert_eq!(
+            script_data_hash(None, Some(&datums), &[0xa1, 0x01, 0x80]),
+            Some(expected)
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Signing of transactions
+//!
+//! A [`Signer`] produces the witness of a transaction body hash, either a
+//! vkey witness for plain and extended Ed25519 keys or a bootstrap witness for
+//! keys spending from Byron addresses. Signing a [`MultiEraTx`] re-encodes the
+//! transaction keeping the original bytes of everything but the vkey and
+//! bootstrap witnesses, so that the body (and its hash) is left untouched.
+
+use pallas_addresses::byron::ByronAddress;
+use pallas_codec::minicbor::{self, data::Type};
+use pallas_codec::utils::MaybeIndefArray;
+use pallas_crypto::{
+    hash::Hash,
+    key::{
+        bip32::XPrv,
+        ed25519::{SecretKey, SecretKeyExtended},
+    },
+};
+use pallas_primitives::{
+    alonzo::{self, BootstrapWitness, VKeyWitness},
+    babbage,
+};
+
+use crate::{ComputeHash, Era, Error, MultiEraTx};
+
+/// A key able to witness a transaction
+pub enum Signer {
+    /// A plain Ed25519 key, as generated by `cardano-cli`
+    Key(SecretKey),
+
+    /// An extended Ed25519 key, as derived by HD wallets
+    Extended(SecretKeyExtended),
+
+    /// The extended key of a Byron address, producing a bootstrap witness.
+    /// The attributes are the CBOR encoded attributes of the address.
+    Bootstrap { key: XPrv, attributes: Vec<u8> },
+}
+
+/// A witness produced by a [`Signer`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Witness {
+    VKey(VKeyWitness),
+    Bootstrap(BootstrapWitness),
+}
+
+impl Signer {
+    /// build the signer of a Byron address, taking the attributes from the
+    /// address
+    pub fn bootstrap(key: XPrv, address: &ByronAddress) -> Result<Self, Error> {
+        let payload = address.decode().map_err(Error::invalid_cbor)?;
+        let attributes = minicbor::to_vec(&payload.attributes).unwrap();
+
+        Ok(Signer::Bootstrap { key, attributes })
+    }
+
+    /// produce the witness of the given transaction body hash
+    pub fn witness(&self, tx_hash: &Hash<32>) -> Witness {
+        match self {
+            Signer::Key(key) => Witness::VKey(VKeyWitness {
+                vkey: Vec::from(key.public_key().as_ref()).into(),
+                signature: Vec::from(key.sign(tx_hash).as_ref()).into(),
+            }),
+            Signer::Extended(key) => Witness::VKey(VKeyWitness {
+                vkey: Vec::from(key.public_key().as_ref()).into(),
+                signature: Vec::from(key.sign(tx_hash).as_ref()).into(),
+            }),
+            Signer::Bootstrap { key, attributes } => Witness::Bootstrap(BootstrapWitness {
+                public_key: Vec::from(key.public().public_key().as_ref()).into(),
+                signature: Vec::from(key.sign(tx_hash).as_ref()).into(),
+                chain_code: Vec::from(key.chain_code()).into(),
+                attributes: attributes.clone().into(),
+            }),
+        }
+    }
+}
+
+/// add the witnesses of the signers to the given lists, skipping the keys
+/// that already have a witness
+fn add_witnesses(
+    tx_hash: &Hash<32>,
+    signers: &[Signer],
+    vkeys: &mut Option<MaybeIndefArray<VKeyWitness>>,
+    bootstraps: &mut Option<MaybeIndefArray<BootstrapWitness>>,
+) {
+    for signer in signers {
+        match signer.witness(tx_hash) {
+            Witness::VKey(w) => {
+                let list = vkeys.get_or_insert_with(|| MaybeIndefArray::Def(vec![]));
+
+                if !list.iter().any(|x| x.vkey == w.vkey) {
+                    list.push(w);
+                }
+            }
+            Witness::Bootstrap(w) => {
+                let list = bootstraps.get_or_insert_with(|| MaybeIndefArray::Def(vec![]));
+
+                if !list.iter().any(|x| x.public_key == w.public_key) {
+                    list.push(w);
+                }
+            }
+        }
+    }
+}
+
+/// re-encode a witness set map with the witnesses of the signers, keeping the
+/// original bytes of the other entries (scripts, datums, redeemers)
+fn merge_witness_set(
+    original: &[u8],
+    tx_hash: &Hash<32>,
+    signers: &[Signer],
+) -> Result<Vec<u8>, Error> {
+    let mut d = minicbor::Decoder::new(original);
+    let len = d.map().map_err(Error::invalid_cbor)?;
+
+    let mut entries = Vec::new();
+    let mut vkeys: Option<MaybeIndefArray<VKeyWitness>> = None;
+    let mut bootstraps: Option<MaybeIndefArray<BootstrapWitness>> = None;
+
+    loop {
+        match len {
+            Some(len) if entries.len() as u64 == len => break,
+            None if d.datatype().map_err(Error::invalid_cbor)? == Type::Break => break,
+            _ => (),
+        }
+
+        let key = d.u64().map_err(Error::invalid_cbor)?;
+        let start = d.position();
+
+        match key {
+            0 => vkeys = Some(d.decode().map_err(Error::invalid_cbor)?),
+            2 => bootstraps = Some(d.decode().map_err(Error::invalid_cbor)?),
+            _ => d.skip().map_err(Error::invalid_cbor)?,
+        }
+
+        entries.push((key, &original[start..d.position()]));
+    }
+
+    add_witnesses(tx_hash, signers, &mut vkeys, &mut bootstraps);
+
+    let mut keys: Vec<_> = entries.iter().map(|(k, _)| *k).collect();
+    keys.extend(vkeys.as_ref().map(|_| 0));
+    keys.extend(bootstraps.as_ref().map(|_| 2));
+    keys.sort();
+    keys.dedup();
+
+    let mut e = minicbor::Encoder::new(Vec::new());
+    e.map(keys.len() as u64).unwrap();
+
+    for key in keys {
+        e.u64(key).unwrap();
+
+        match key {
+            0 => {
+                e.encode(&vkeys).unwrap();
+            }
+            2 => {
+                e.encode(&bootstraps).unwrap();
+            }
+            _ => {
+                let (_, raw) = entries.iter().find(|(k, _)| *k == key).unwrap();
+                e.writer_mut().extend_from_slice(raw);
+            }
+        }
+    }
+
+    Ok(e.into_writer())
+}
+
+impl<'b> MultiEraTx<'b> {
+    /// sign the transaction with the given signers, returning the CBOR of the
+    /// transaction with the new witnesses, in the layout of its era. The bytes
+    /// of the body and of the auxiliary data are kept as they are.
+    pub fn sign(&self, signers: &[Signer]) -> Result<Vec<u8>, Error> {
+        let tx_hash = self.hash();
+
+        let mut e = minicbor::Encoder::new(Vec::new());
+
+        match self {
+            // txs before Alonzo have no validity flag
+            MultiEraTx::AlonzoCompatible(x, era) if *era < Era::Alonzo => {
+                let witness_set =
+                    merge_witness_set(x.transaction_witness_set.raw_cbor(), &tx_hash, signers)?;
+
+                e.array(3).unwrap();
+                e.encode(&x.transaction_body).unwrap();
+                e.writer_mut().extend_from_slice(&witness_set);
+                e.encode(&x.auxiliary_data).unwrap();
+            }
+            MultiEraTx::AlonzoCompatible(x, _) => {
+                let witness_set =
+                    merge_witness_set(x.transaction_witness_set.raw_cbor(), &tx_hash, signers)?;
+
+                e.array(4).unwrap();
+                e.encode(&x.transaction_body).unwrap();
+                e.writer_mut().extend_from_slice(&witness_set);
+                e.bool(x.success).unwrap();
+                e.encode(&x.auxiliary_data).unwrap();
+            }
+            MultiEraTx::Babbage(x) => {
+                let witness_set =
+                    merge_witness_set(x.transaction_witness_set.raw_cbor(), &tx_hash, signers)?;
+
+                e.array(4).unwrap();
+                e.encode(&x.transaction_body).unwrap();
+                e.writer_mut().extend_from_slice(&witness_set);
+                e.bool(x.success).unwrap();
+                e.encode(&x.auxiliary_data).unwrap();
+            }
+            MultiEraTx::Byron(_) => return Err(Error::InvalidEra(Era::Byron)),
+        }
+
+        Ok(e.into_writer())
+    }
+}
+
+/// sign a built Alonzo compatible transaction, adding the witnesses of the
+/// signers to its witness set
+pub fn sign_alonzo(mut tx: alonzo::Tx, signers: &[Signer]) -> alonzo::Tx {
+    let tx_hash = tx.transaction_body.compute_hash();
+    let witness_set = &mut tx.transaction_witness_set;
+
+    add_witnesses(
+        &tx_hash,
+        signers,
+        &mut witness_set.vkeywitness,
+        &mut witness_set.bootstrap_witness,
+    );
+
+    tx
+}
+
+/// sign a built Babbage transaction, adding the witnesses of the signers to
+/// its witness set
+pub fn sign_babbage(mut tx: babbage::Tx, signers: &[Signer]) -> babbage::Tx {
+    let tx_hash = tx.transaction_body.compute_hash();
+    let witness_set = &mut tx.transaction_witness_set;
+
+    add_witnesses(
+        &tx_hash,
+        signers,
+        &mut witness_set.vkeywitness,
+        &mut witness_set.bootstrap_witness,
+    );
+
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_addresses::byron::HdPayloadKey;
+    use pallas_crypto::key::ed25519::PublicKey;
+
+    use super::*;
+    use crate::OriginalHash;
+
+    const ROOT_KEY: &str = "000a11181f262d343b424950575e656c737a81888f969da4abb2b9c0c7ced55ce3eaf1f8ff060d141b222930373e454c535a61686f767d848b9299a0a7aeb5bcc3cad1d8dfe6edf4fb020910171e252c333a41484f565d646b727980878e959c";
+
+    fn root_key() -> XPrv {
+        XPrv::try_from(hex::decode(ROOT_KEY).unwrap().as_slice()).unwrap()
+    }
+
+    fn assert_vkey_witness(witness: &VKeyWitness, tx_hash: &Hash<32>) {
+        let vkey: [u8; 32] = witness.vkey.as_slice().try_into().unwrap();
+        let signature: [u8; 64] = witness.signature.as_slice().try_into().unwrap();
+
+        assert!(PublicKey::from(vkey).verify(tx_hash, &signature.into()));
+    }
+
+    #[test]
+    fn signing_keeps_body_and_scripts() {
+        let tx_hex = include_str!("../../test_data/babbage1.tx");
+        let tx_bytes = hex::decode(tx_hex).unwrap();
+        let tx = MultiEraTx::decode(Era::Babbage, &tx_bytes).unwrap();
+
+        let extended = root_key().derive_path(&[1, 2]).extended_secret_key();
+        let signers = [
+            Signer::Key(SecretKey::from([7; 32])),
+            Signer::Extended(extended),
+        ];
+
+        let signed = tx.sign(&signers).unwrap();
+        let signed = MultiEraTx::decode(Era::Babbage, &signed).unwrap();
+
+        let (before, after) = (tx.as_babbage().unwrap(), signed.as_babbage().unwrap());
+
+        assert_eq!(
+            before.transaction_body.raw_cbor(),
+            after.transaction_body.raw_cbor()
+        );
+        assert_eq!(tx.hash(), signed.hash());
+
+        let datums = |tx: &MultiEraTx| -> Vec<_> {
+            tx.plutus_data().iter().map(|x| x.original_hash()).collect()
+        };
+        assert_eq!(datums(&tx), datums(&signed));
+        assert_eq!(tx.redeemers(), signed.redeemers());
+
+        let existing = tx.vkey_witnesses().len();
+        assert_eq!(signed.vkey_witnesses().len(), existing + 2);
+        assert_eq!(&signed.vkey_witnesses()[..existing], tx.vkey_witnesses());
+
+        for witness in &signed.vkey_witnesses()[existing..] {
+            assert_vkey_witness(witness, &tx.hash());
+        }
+
+        // signing twice with the same keys doesn't duplicate the witnesses
+        let twice = signed.sign(&signers).unwrap();
+        let twice = MultiEraTx::decode(Era::Babbage, &twice).unwrap();
+        assert_eq!(twice.vkey_witnesses(), signed.vkey_witnesses());
+    }
+
+    #[test]
+    fn signing_keeps_the_era_layout() {
+        let cbor = hex::decode(include_str!("../../test_data/shelley1.block")).unwrap();
+        let block = crate::MultiEraBlock::decode(&cbor).unwrap();
+        let txs = block.txs();
+        let tx = &txs[0];
+
+        let signed = tx.sign(&[Signer::Key(SecretKey::from([7; 32]))]).unwrap();
+
+        let mut d = minicbor::Decoder::new(&signed);
+        assert_eq!(d.array().unwrap(), Some(3));
+
+        let body: pallas_codec::utils::KeepRaw<alonzo::TransactionBody> = d.decode().unwrap();
+        assert_eq!(
+            body.raw_cbor(),
+            tx.as_alonzo().unwrap().transaction_body.raw_cbor()
+        );
+
+        let witnesses: alonzo::WitnessSet = d.decode().unwrap();
+        let vkeys = witnesses.vkeywitness.unwrap();
+        assert_eq!(vkeys.len(), tx.vkey_witnesses().len() + 1);
+        assert_vkey_witness(vkeys.last().unwrap(), &tx.hash());
+
+        // the alonzo layout is kept from alonzo onwards
+        let cbor = hex::decode(include_str!("../../test_data/alonzo1.block")).unwrap();
+        let block = crate::MultiEraBlock::decode(&cbor).unwrap();
+        let signed = block.txs()[0].sign(&[]).unwrap();
+
+        let mut d = minicbor::Decoder::new(&signed);
+        assert_eq!(d.array().unwrap(), Some(4));
+    }
+
+    #[test]
+    fn bootstrap_witness() {
+        let root = root_key();
+        let key = root.derive_path(&[0x8000_0000, 0x8000_0001]);
+        let address = ByronAddress::new_random(
+            &key.public(),
+            &HdPayloadKey::new(&root.public()),
+            &[0x8000_0000, 0x8000_0001],
+            None,
+        );
+
+        let tx_hex = include_str!("../../test_data/babbage2.tx");
+        let tx_bytes = hex::decode(tx_hex).unwrap();
+        let tx = MultiEraTx::decode(Era::Babbage, &tx_bytes).unwrap();
+
+        let signer = Signer::bootstrap(key.clone(), &address).unwrap();
+        let signed = tx.sign(&[signer]).unwrap();
+        let signed = MultiEraTx::decode(Era::Babbage, &signed).unwrap();
+
+        let witness = signed.bootstrap_witnesses().last().unwrap();
+        assert_eq!(witness.chain_code.as_slice(), key.chain_code());
+        assert_eq!(
+            witness.attributes.as_slice(),
+            minicbor::to_vec(address.decode().unwrap().attributes).unwrap()
+        );
+        assert_vkey_witness(
+            &VKeyWitness {
+                vkey: witness.public_key.clone(),
+                signature: witness.signature.clone(),
+            },
+            &tx.hash(),
+        );
+    }
+
+    #[test]
+    fn sign_built_tx() {
+        let tx_hex = include_str!("../../test_data/babbage2.tx");
+        let tx_bytes = hex::decode(tx_hex).unwrap();
+        let minted: babbage::MintedTx = minicbor::decode(&tx_bytes).unwrap();
+        let mut tx = babbage::Tx::from(minted);
+        tx.transaction_witness_set.vkeywitness = None;
+
+        let tx = sign_babbage(tx, &[Signer::Key(SecretKey::from([7; 32]))]);
+
+        let bytes = minicbor::to_vec(&tx).unwrap();
+        let signed = MultiEraTx::decode(Era::Babbage, &bytes).unwrap();
+
+        assert_eq!(signed.hash(), tx.transaction_body.compute_hash());
+        assert_eq!(signed.vkey_witnesses().len(), 1);
+        assert_vkey_witness(&signed.vkey_witnesses()[0], &signed.hash());
+    }
+}
@@ -4,7 +4,7 @@ use pallas_primitives::{alonzo, babbage, byron};
 
 macro_rules! clone_tx_fn {
     ($fn_name:ident, $era:tt) => {
-        fn $fn_name<'b>(block: &'b $era::MintedBlock, index: usize) -> Option<$era::MintedTx<'b>> {
+        fn $fn_name<'b>(block: &$era::MintedBlock<'b>, index: usize) -> Option<$era::MintedTx<'b>> {
             let transaction_body = block.transaction_bodies.get(index).cloned()?;
 
             let transaction_witness_set = block.transaction_witness_sets.get(index)?.clone();
@@ -43,20 +43,29 @@ macro_rules! clone_tx_fn {
 clone_tx_fn!(babbage_clone_tx_at, babbage);
 clone_tx_fn!(alonzo_clone_tx_at, alonzo);
 
-pub fn clone_alonzo_txs<'b>(block: &'b alonzo::MintedBlock) -> Vec<alonzo::MintedTx<'b>> {
+// The txs returned by the clone_*_txs functions are built out of clones of the
+// block parts. They own all of their data except for the raw cbor kept by
+// their `KeepRaw` fields, which points into the cbor the block was decoded
+// from, never into the block struct itself. The signatures tie the txs to the
+// lifetime of the cbor rather than to the borrow of the block so that the
+// compiler checks it. `OwnedMultiEraBlock::txs` relies on this invariant to
+// extend the lifetime of the txs to the one of the shared cbor, keep it if
+// these functions change.
+
+pub fn clone_alonzo_txs<'b>(block: &alonzo::MintedBlock<'b>) -> Vec<alonzo::MintedTx<'b>> {
     (0..block.transaction_bodies.len())
         .step_by(1)
         .filter_map(|idx| alonzo_clone_tx_at(block, idx))
         .collect()
 }
 
-pub fn clone_babbage_txs<'b>(block: &'b babbage::MintedBlock) -> Vec<babbage::MintedTx<'b>> {
+pub fn clone_babbage_txs<'b>(block: &babbage::MintedBlock<'b>) -> Vec<babbage::MintedTx<'b>> {
     (0..block.transaction_bodies.len())
         .step_by(1)
         .filter_map(|idx| babbage_clone_tx_at(block, idx))
         .collect()
 }
 
-pub fn clone_byron_txs<'b>(block: &'b byron::MintedBlock) -> Vec<byron::MintedTxPayload<'b>> {
+pub fn clone_byron_txs<'b>(block: &byron::MintedBlock<'b>) -> Vec<byron::MintedTxPayload<'b>> {
     block.body.tx_payload.iter().cloned().collect()
 }
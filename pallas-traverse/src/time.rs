@@ -23,19 +23,29 @@ fn compute_era_epoch(era_slot: Slot, era_slot_length: u64, era_epoch_length: u64
         "epoch length needs to be greater than zero"
     );
 
-    let epoch = (era_slot * era_slot_length) / era_epoch_length;
-    let reminder = era_slot % era_epoch_length;
+    // epoch length is expressed in seconds, we need it in slots
+    let slots_per_epoch = era_epoch_length / era_slot_length;
+
+    let epoch = era_slot / slots_per_epoch;
+    let reminder = era_slot % slots_per_epoch;
 
     (epoch, reminder)
 }
 
+/// Computes the slot relative to the start of the era for a given epoch and
+/// slot within the epoch, the epoch length is expressed in seconds.
+///
+/// Only valid for eras that have constant params, for chains with more than
+/// two eras use the [crate::history::EraHistory] instead.
 pub fn compute_absolute_slot_within_era(
     sub_era_epoch: Epoch,
     sub_epoch_slot: Slot,
     era_epoch_length: u32,
     era_slot_length: u32,
 ) -> u64 {
-    ((sub_era_epoch * era_epoch_length as u64) / era_slot_length as u64) + sub_epoch_slot
+    let slots_per_epoch = (era_epoch_length / era_slot_length) as u64;
+
+    (sub_era_epoch * slots_per_epoch) + sub_epoch_slot
 }
 
 impl GenesisValues {
@@ -200,7 +210,7 @@ mod tests {
 
         // Byron middle, value copied from:
         // https://explorer.cardano-testnet.iohkdev.io/en/block?id=66102c0b80e1eebc9cddf9cab43c1bf912e4f1963d6f3b8ff948952f8409e779
-        assert_slot_matches_timestamp(&genesis, 561595, 1575242316, 25, 129595);
+        assert_slot_matches_timestamp(&genesis, 561595, 1575242316, 25, 21595);
 
         // Shelley start, value copied from:
         // https://explorer.cardano-testnet.iohkdev.io/en/block?id=02b1c561715da9e540411123a6135ee319b02f60b9a11a603d3305556c04329f
@@ -235,13 +245,8 @@ mod tests {
         // https://preprod.cardanoscan.io/block/1
         assert_slot_matches_timestamp(&genesis, 2, 1654041640, 0, 2);
 
-        // Can't make Byron blocks work, not sure what's going on here. Each block jumps
-        // several slots. Timestamps work but epoch calculation doesn't. Since anything
-        // interesting starts from Shelley, I'll commit to this logic and treat this as
-        // a known-issue for later fixing.
-
         // https://preprod.cardanoscan.io/block/11
-        // assert_slot_matches_timestamp(&genesis, 21600, 1654473600, 1, 0);
+        assert_slot_matches_timestamp(&genesis, 21600, 1654473600, 1, 0);
 
         // https://preprod.cardanoscan.io/block/46
         assert_slot_matches_timestamp(&genesis, 86400, 1655769600, 4, 0);
@@ -3,14 +3,14 @@ use std::{borrow::Cow, collections::HashSet, ops::Deref};
 use pallas_codec::{minicbor, utils::KeepRaw};
 use pallas_crypto::hash::Hash;
 use pallas_primitives::{
-    alonzo,
+    allegra, alonzo,
     babbage::{self, NetworkId},
-    byron,
+    byron, mary, shelley,
 };
 
 use crate::{
-    Era, MultiEraCert, MultiEraInput, MultiEraMeta, MultiEraOutput, MultiEraPolicyAssets,
-    MultiEraSigners, MultiEraTx, MultiEraWithdrawals, OriginalHash,
+    Era, Error, MultiEraCert, MultiEraInput, MultiEraMeta, MultiEraOutput, MultiEraPolicyAssets,
+    MultiEraSigners, MultiEraTx, MultiEraUpdate, MultiEraWithdrawals, OriginalHash,
 };
 
 impl<'b> MultiEraTx<'b> {
@@ -284,6 +284,38 @@ impl<'b> MultiEraTx<'b> {
         }
     }
 
+    /// The original CBOR of the outputs returned by [Self::produces]
+    ///
+    /// Outputs aren't kept raw by the models, so the bytes are found by
+    /// walking the original CBOR of the tx body. Prefer these over re-encoding
+    /// the outputs when the exact bytes matter.
+    pub fn produces_cbor(&self) -> Result<Vec<(usize, &'b [u8])>, Error> {
+        let found = match self {
+            MultiEraTx::AlonzoCompatible(x, _) => {
+                body_field_spans(x.transaction_body.raw_cbor(), self.is_valid())
+            }
+            MultiEraTx::Babbage(x) => {
+                body_field_spans(x.transaction_body.raw_cbor(), self.is_valid())
+            }
+            MultiEraTx::Byron(x) => {
+                let mut d = minicbor::Decoder::new(x.transaction.raw_cbor());
+                d.array()
+                    .and_then(|_| d.skip())
+                    .and_then(|_| item_spans(&mut d))
+            }
+        };
+
+        let spans = found.map_err(Error::invalid_cbor)?;
+
+        match self.is_valid() {
+            true => Ok(spans.into_iter().enumerate().collect()),
+            false => Ok(spans
+                .into_iter()
+                .map(|x| (self.outputs().len(), x))
+                .collect()),
+        }
+    }
+
     /// Returns the *produced* output at the given index if one exists
     ///
     /// If the transaction is valid the outputs are produced, otherwise the
@@ -329,6 +361,42 @@ impl<'b> MultiEraTx<'b> {
         }
     }
 
+    /// Return the protocol parameter update proposal, if any
+    ///
+    /// Fails if the body of a Shelley, Allegra or Mary tx can't be decoded
+    /// with the model of its own era.
+    pub fn update(&self) -> Result<Option<MultiEraUpdate<'_>>, Error> {
+        let shelley = |x: Option<shelley::Update>| {
+            x.map(|x| MultiEraUpdate::Shelley(Box::new(Cow::Owned(x))))
+        };
+
+        let update = match self {
+            // the Alonzo model drops the min UTxO value of earlier updates
+            MultiEraTx::AlonzoCompatible(_, Era::Shelley) => {
+                shelley(self.as_shelley_body()?.and_then(|x| x.update))
+            }
+            MultiEraTx::AlonzoCompatible(_, Era::Allegra) => {
+                shelley(self.as_allegra_body()?.and_then(|x| x.update))
+            }
+            MultiEraTx::AlonzoCompatible(_, Era::Mary) => {
+                shelley(self.as_mary_body()?.and_then(|x| x.update))
+            }
+            MultiEraTx::AlonzoCompatible(x, _) => x
+                .transaction_body
+                .update
+                .as_ref()
+                .map(MultiEraUpdate::AlonzoCompatible),
+            MultiEraTx::Babbage(x) => x
+                .transaction_body
+                .update
+                .as_ref()
+                .map(MultiEraUpdate::Babbage),
+            MultiEraTx::Byron(_) => None,
+        };
+
+        Ok(update)
+    }
+
     pub fn fee(&self) -> Option<u64> {
         match self {
             MultiEraTx::AlonzoCompatible(x, _) => Some(x.transaction_body.fee),
@@ -450,6 +518,51 @@ impl<'b> MultiEraTx<'b> {
         }
     }
 
+    /// The body of a Shelley tx, decoded with the model of its own era
+    ///
+    /// Returns `None` for txs of any other era. The body is decoded from its
+    /// original CBOR on each call.
+    pub fn as_shelley_body(&self) -> Result<Option<shelley::TransactionBody>, Error> {
+        match self {
+            MultiEraTx::AlonzoCompatible(x, Era::Shelley) => {
+                minicbor::decode(x.transaction_body.raw_cbor())
+                    .map(Some)
+                    .map_err(Error::invalid_cbor)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The body of an Allegra tx, decoded with the model of its own era
+    ///
+    /// Returns `None` for txs of any other era. The body is decoded from its
+    /// original CBOR on each call.
+    pub fn as_allegra_body(&self) -> Result<Option<allegra::TransactionBody>, Error> {
+        match self {
+            MultiEraTx::AlonzoCompatible(x, Era::Allegra) => {
+                minicbor::decode(x.transaction_body.raw_cbor())
+                    .map(Some)
+                    .map_err(Error::invalid_cbor)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The body of a Mary tx, decoded with the model of its own era
+    ///
+    /// Returns `None` for txs of any other era. The body is decoded from its
+    /// original CBOR on each call.
+    pub fn as_mary_body(&self) -> Result<Option<mary::TransactionBody>, Error> {
+        match self {
+            MultiEraTx::AlonzoCompatible(x, Era::Mary) => {
+                minicbor::decode(x.transaction_body.raw_cbor())
+                    .map(Some)
+                    .map_err(Error::invalid_cbor)
+            }
+            _ => Ok(None),
+        }
+    }
+
     pub fn as_byron(&self) -> Option<&byron::MintedTxPayload> {
         match self {
             MultiEraTx::AlonzoCompatible(_, _) => None,
@@ -458,3 +571,52 @@ impl<'b> MultiEraTx<'b> {
         }
     }
 }
+
+/// The bytes of each item of the array at the position of the decoder
+fn item_spans<'b>(d: &mut minicbor::Decoder<'b>) -> Result<Vec<&'b [u8]>, minicbor::decode::Error> {
+    let input = d.input();
+    let len = d.array()?;
+    let mut spans = vec![];
+
+    while len.is_none_or(|x| spans.len() < x as usize) {
+        if len.is_none() && d.datatype()? == minicbor::data::Type::Break {
+            break;
+        }
+
+        let start = d.position();
+        d.skip()?;
+        spans.push(&input[start..d.position()]);
+    }
+
+    Ok(spans)
+}
+
+/// The bytes of the outputs (key 1) or of the collateral return (key 16) of
+/// a post-Byron tx body
+fn body_field_spans(body: &[u8], outputs: bool) -> Result<Vec<&[u8]>, minicbor::decode::Error> {
+    let mut d = minicbor::Decoder::new(body);
+    let len = d.map()?;
+    let mut seen = 0;
+
+    while len.is_none_or(|x| seen < x) {
+        if len.is_none() && d.datatype()? == minicbor::data::Type::Break {
+            break;
+        }
+
+        let key = d.u32()?;
+
+        match (key, outputs) {
+            (1, true) => return item_spans(&mut d),
+            (16, false) => {
+                let start = d.position();
+                d.skip()?;
+                return Ok(vec![&body[start..d.position()]]);
+            }
+            _ => d.skip()?,
+        }
+
+        seen += 1;
+    }
+
+    Ok(vec![])
+}
@@ -0,0 +1,123 @@
+use pallas_primitives::{alonzo, babbage, shelley};
+
+use crate::MultiEraUpdate;
+
+impl<'b> MultiEraUpdate<'b> {
+    pub fn as_shelley(&self) -> Option<&shelley::Update> {
+        match self {
+            Self::Shelley(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_alonzo(&self) -> Option<&alonzo::Update> {
+        match self {
+            Self::AlonzoCompatible(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_babbage(&self) -> Option<&babbage::Update> {
+        match self {
+            Self::Babbage(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// The epoch in which the proposal is meant to be voted on
+    pub fn epoch(&self) -> u64 {
+        match self {
+            MultiEraUpdate::Shelley(x) => x.epoch,
+            MultiEraUpdate::AlonzoCompatible(x) => x.epoch,
+            MultiEraUpdate::Babbage(x) => x.epoch,
+        }
+    }
+
+    /// Number of genesis delegates voting for a change in this proposal
+    pub fn len(&self) -> usize {
+        match self {
+            MultiEraUpdate::Shelley(x) => x.proposed_protocol_parameter_updates.len(),
+            MultiEraUpdate::AlonzoCompatible(x) => x.proposed_protocol_parameter_updates.len(),
+            MultiEraUpdate::Babbage(x) => x.proposed_protocol_parameter_updates.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_codec::minicbor;
+    use pallas_codec::utils::{KeyValuePairs, MaybeIndefArray, Nullable};
+    use pallas_primitives::{alonzo, shelley};
+
+    use crate::{Era, MultiEraTx};
+
+    fn shelley_param_update() -> shelley::ProtocolParamUpdate {
+        shelley::ProtocolParamUpdate {
+            minfee_a: None,
+            minfee_b: None,
+            max_block_body_size: None,
+            max_transaction_size: None,
+            max_block_header_size: None,
+            key_deposit: None,
+            pool_deposit: None,
+            maximum_epoch: None,
+            desired_number_of_stake_pools: None,
+            pool_pledge_influence: None,
+            expansion_rate: None,
+            treasury_growth_rate: None,
+            decentralization_constant: None,
+            extra_entropy: None,
+            protocol_version: None,
+            min_utxo_value: Some(1_000_000),
+            min_pool_cost: None,
+            layout: Default::default(),
+        }
+    }
+
+    #[test]
+    fn shelley_updates_keep_min_utxo_value() {
+        let body = shelley::TransactionBody {
+            inputs: MaybeIndefArray::Def(vec![]),
+            outputs: MaybeIndefArray::Def(vec![]),
+            fee: 0,
+            ttl: 0,
+            certificates: None,
+            withdrawals: None,
+            update: Some(shelley::Update {
+                proposed_protocol_parameter_updates: KeyValuePairs::Def(vec![(
+                    vec![1; 28].into(),
+                    shelley_param_update(),
+                )]),
+                epoch: 210,
+            }),
+            metadata_hash: None,
+            layout: Default::default(),
+        };
+
+        let body = minicbor::to_vec(&body).unwrap();
+        let witnesses = [0xa0];
+
+        let tx = alonzo::MintedTx {
+            transaction_body: minicbor::decode(&body).unwrap(),
+            transaction_witness_set: minicbor::decode(&witnesses).unwrap(),
+            success: true,
+            auxiliary_data: Nullable::Null,
+        };
+
+        let tx = MultiEraTx::from_alonzo_compatible(&tx, Era::Shelley);
+        let update = tx.update().unwrap().unwrap();
+
+        assert_eq!(update.epoch(), 210);
+        assert_eq!(update.len(), 1);
+
+        let (_, proposal) = &update
+            .as_shelley()
+            .unwrap()
+            .proposed_protocol_parameter_updates[0];
+        assert_eq!(proposal.min_utxo_value, Some(1_000_000));
+    }
+}
@@ -24,9 +24,10 @@ impl<'b> MultiEraWithdrawals<'b> {
         match self {
             MultiEraWithdrawals::NotApplicable => std::iter::empty().collect(),
             MultiEraWithdrawals::Empty => std::iter::empty().collect(),
-            MultiEraWithdrawals::AlonzoCompatible(x) => {
-                x.iter().map(|(k, v)| (k.as_slice(), *v)).collect()
-            }
+            MultiEraWithdrawals::AlonzoCompatible(x) => x
+                .iter()
+                .map(|(k, v)| (k.as_slice(), u64::from(v)))
+                .collect(),
         }
     }
 }
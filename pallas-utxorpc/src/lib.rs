@@ -3,10 +3,40 @@ use std::ops::Deref;
 use pallas_codec::utils::KeyValuePairs;
 use pallas_primitives::{alonzo, babbage};
 use pallas_traverse as trv;
+use thiserror::Error;
 
+use trv::owned::OwnedMultiEraOutput;
 use trv::OriginalHash;
 
-use utxorpc::proto::cardano::v1 as u5c;
+use utxorpc_spec::utxorpc::v1alpha::cardano as u5c;
+
+pub mod reverse;
+#[cfg(feature = "server")]
+pub mod server;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("traverse error: {0}")]
+    Traverse(#[from] trv::Error),
+
+    #[error("{0} can't be represented by the spec")]
+    NotRepresentable(&'static str),
+}
+
+/// Source of the outputs spent by the inputs of a tx
+///
+/// Txs only reference the outputs they consume, a context that knows about
+/// them allows to fill the `as_output` of the mapped inputs. The unit type
+/// can be used as the context when there's nothing to resolve the inputs with.
+pub trait LedgerContext {
+    fn get_utxo(&self, output_ref: &trv::OutputRef) -> Option<OwnedMultiEraOutput>;
+}
+
+impl LedgerContext for () {
+    fn get_utxo(&self, _: &trv::OutputRef) -> Option<OwnedMultiEraOutput> {
+        None
+    }
+}
 
 pub fn map_purpose(x: &alonzo::RedeemerTag) -> u5c::RedeemerPurpose {
     match x {
@@ -24,7 +54,11 @@ pub fn map_redeemer(x: &alonzo::Redeemer) -> u5c::Redeemer {
     }
 }
 
-pub fn map_tx_input(i: &trv::MultiEraInput, tx: &trv::MultiEraTx) -> u5c::TxInput {
+pub fn map_tx_input(
+    i: &trv::MultiEraInput,
+    tx: &trv::MultiEraTx,
+    ctx: &impl LedgerContext,
+) -> u5c::TxInput {
     let redeemer = tx
         .redeemers()
         .iter()
@@ -34,8 +68,9 @@ pub fn map_tx_input(i: &trv::MultiEraInput, tx: &trv::MultiEraTx) -> u5c::TxInpu
         tx_hash: i.hash().to_vec().into(),
         output_index: i.index() as u32,
         redeemer: redeemer.map(map_redeemer),
-        // TODO: map output data from some context
-        as_output: None,
+        as_output: ctx
+            .get_utxo(&i.output_ref())
+            .map(|x| map_tx_output(x.view())),
     }
 }
 
@@ -43,10 +78,7 @@ pub fn map_tx_output(x: &trv::MultiEraOutput) -> u5c::TxOutput {
     u5c::TxOutput {
         address: x.address().map(|a| a.to_vec()).unwrap_or_default().into(),
         coin: x.lovelace_amount(),
-        // TODO: this is wrong, we're crating a new item for each asset even if they share
-        // the same policy id. We need to adjust Pallas' interface to make this mapping more
-        // ergonomic.
-        assets: x.non_ada_assets().iter().map(map_policy_assets).collect(),
+        assets: group_policy_assets(&x.non_ada_assets()),
         datum: match x.datum() {
             Some(babbage::PseudoDatumOption::Data(x)) => map_plutus_datum(&x.0).into(),
             _ => None,
@@ -112,8 +144,12 @@ pub fn map_relay(x: &alonzo::Relay) -> u5c::Relay {
     }
 }
 
-pub fn map_cert(x: &trv::MultiEraCert) -> u5c::Certificate {
-    let inner = match x.as_alonzo().unwrap() {
+pub fn map_cert(x: &trv::MultiEraCert) -> Result<u5c::Certificate, Error> {
+    let cert = x
+        .as_alonzo()
+        .ok_or(Error::NotRepresentable("certificate"))?;
+
+    let inner = match cert {
         babbage::Certificate::StakeRegistration(a) => {
             u5c::certificate::Certificate::StakeRegistration(map_stake_credential(a))
         }
@@ -191,9 +227,9 @@ pub fn map_cert(x: &trv::MultiEraCert) -> u5c::Certificate {
         }
     };
 
-    u5c::Certificate {
+    Ok(u5c::Certificate {
         certificate: inner.into(),
-    }
+    })
 }
 
 pub fn map_withdrawals(x: &(&[u8], u64)) -> u5c::Withdrawal {
@@ -218,6 +254,27 @@ pub fn map_policy_assets(x: &trv::MultiEraPolicyAssets) -> u5c::Multiasset {
     }
 }
 
+/// Maps the assets of several policies, merging the ones that share the same
+/// policy id
+pub fn group_policy_assets(x: &[trv::MultiEraPolicyAssets]) -> Vec<u5c::Multiasset> {
+    let mut groups: Vec<u5c::Multiasset> = vec![];
+
+    for policy in x {
+        let policy_id = policy.policy().to_vec();
+        let assets: Vec<_> = policy.assets().iter().map(map_asset).collect();
+
+        match groups.iter_mut().find(|x| x.policy_id == policy_id) {
+            Some(group) => group.assets.extend(assets),
+            None => groups.push(u5c::Multiasset {
+                policy_id: policy_id.into(),
+                assets,
+            }),
+        }
+    }
+
+    groups
+}
+
 pub fn map_vkey_witness(x: &alonzo::VKeyWitness) -> u5c::VKeyWitness {
     u5c::VKeyWitness {
         vkey: x.vkey.to_vec().into(),
@@ -236,7 +293,7 @@ pub fn map_native_script(x: &alonzo::NativeScript) -> u5c::NativeScript {
             })
         }
         babbage::NativeScript::ScriptAny(x) => {
-            u5c::native_script::NativeScript::ScriptAll(u5c::NativeScriptList {
+            u5c::native_script::NativeScript::ScriptAny(u5c::NativeScriptList {
                 items: x.iter().map(map_native_script).collect(),
             })
         }
@@ -263,7 +320,7 @@ fn collect_all_scripts(tx: &trv::MultiEraTx) -> Vec<u5c::Script> {
     let ns = tx
         .native_scripts()
         .iter()
-        .map(map_native_script)
+        .map(|x| map_native_script(x))
         .map(|x| u5c::Script {
             script: u5c::script::Script::Native(x).into(),
         });
@@ -406,22 +463,31 @@ fn collect_all_aux_scripts(tx: &trv::MultiEraTx) -> Vec<u5c::Script> {
     ns.chain(p1).collect()
 }
 
-pub fn map_tx(tx: &trv::MultiEraTx) -> u5c::Tx {
-    u5c::Tx {
-        inputs: tx.inputs().iter().map(|i| map_tx_input(i, tx)).collect(),
+/// Maps a tx to its spec representation
+///
+/// Fails if the tx holds data the spec can't represent, instead of dropping
+/// it.
+pub fn map_tx(tx: &trv::MultiEraTx, ctx: &impl LedgerContext) -> Result<u5c::Tx, Error> {
+    let tx = u5c::Tx {
+        hash: tx.hash().to_vec().into(),
+        inputs: tx
+            .inputs()
+            .iter()
+            .map(|i| map_tx_input(i, tx, ctx))
+            .collect(),
         outputs: tx.outputs().iter().map(map_tx_output).collect(),
-        certificates: tx.certs().iter().map(map_cert).collect(),
+        certificates: tx.certs().iter().map(map_cert).collect::<Result<_, _>>()?,
         withdrawals: tx
             .withdrawals()
             .collect::<Vec<_>>()
             .iter()
             .map(map_withdrawals)
             .collect(),
-        mint: tx.mints().iter().map(map_policy_assets).collect(),
+        mint: group_policy_assets(&tx.mints()),
         reference_inputs: tx
             .reference_inputs()
             .iter()
-            .map(|x| map_tx_input(x, tx))
+            .map(|x| map_tx_input(x, tx, ctx))
             .collect(),
         witnesses: u5c::WitnessSet {
             vkeywitness: tx.vkey_witnesses().iter().map(map_vkey_witness).collect(),
@@ -437,7 +503,7 @@ pub fn map_tx(tx: &trv::MultiEraTx) -> u5c::Tx {
             collateral: tx
                 .collateral()
                 .iter()
-                .map(|x| map_tx_input(x, tx))
+                .map(|x| map_tx_input(x, tx, ctx))
                 .collect(),
             collateral_return: tx.collateral_return().map(|x| map_tx_output(&x)),
             total_collateral: tx.total_collateral().unwrap_or_default(),
@@ -460,24 +526,145 @@ pub fn map_tx(tx: &trv::MultiEraTx) -> u5c::Tx {
             scripts: collect_all_aux_scripts(tx),
         }
         .into(),
-    }
+    };
+
+    Ok(tx)
 }
 
-pub fn map_block(block: &trv::MultiEraBlock) -> u5c::Block {
-    u5c::Block {
+/// Maps a block to its spec representation
+///
+/// The era of the block is not mapped: the header of the spec (0.3) has no
+/// field for it, consumers need to tell the era apart by the slot of the
+/// block or by the shape of its txs.
+pub fn map_block(
+    block: &trv::MultiEraBlock,
+    ctx: &impl LedgerContext,
+) -> Result<u5c::Block, Error> {
+    let txs = block
+        .txs()
+        .iter()
+        .map(|x| map_tx(x, ctx))
+        .collect::<Result<_, _>>()?;
+
+    Ok(u5c::Block {
         header: u5c::BlockHeader {
             slot: block.slot(),
             hash: block.hash().to_vec().into(),
+            height: block.number(),
         }
         .into(),
-        body: u5c::BlockBody {
-            tx: block.txs().iter().map(map_tx).collect(),
+        body: u5c::BlockBody { tx: txs }.into(),
+    })
+}
+
+pub fn map_block_cbor(raw: &[u8], ctx: &impl LedgerContext) -> Result<u5c::Block, Error> {
+    let block = trv::MultiEraBlock::decode(raw)?;
+    map_block(&block, ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_crypto::hash::Hash;
+
+    use super::*;
+
+    /// resolves every input to the same output
+    struct FixedContext(OwnedMultiEraOutput);
+
+    impl LedgerContext for FixedContext {
+        fn get_utxo(&self, _: &trv::OutputRef) -> Option<OwnedMultiEraOutput> {
+            Some(self.0.clone())
         }
-        .into(),
     }
-}
 
-pub fn map_block_cbor(raw: &[u8]) -> u5c::Block {
-    let block = trv::MultiEraBlock::decode(raw).unwrap();
-    map_block(&block)
+    fn babbage_block() -> Vec<u8> {
+        hex::decode(include_str!("../../test_data/babbage3.block")).unwrap()
+    }
+
+    #[test]
+    fn inputs_are_resolved_through_the_context() {
+        let cbor = babbage_block();
+        let block = trv::MultiEraBlock::decode(&cbor).unwrap();
+
+        let tx = block.txs().remove(0);
+        let output = OwnedMultiEraOutput::try_from(&tx.outputs()[0]).unwrap();
+        let expected = map_tx_output(output.view());
+
+        let mapped = map_block(&block, &FixedContext(output)).unwrap();
+
+        for tx in mapped.body.unwrap().tx {
+            for input in tx.inputs {
+                assert_eq!(input.as_output.as_ref(), Some(&expected));
+            }
+        }
+
+        let mapped = map_block_cbor(&cbor, &()).unwrap();
+
+        for tx in mapped.body.unwrap().tx {
+            assert!(tx.inputs.iter().all(|x| x.as_output.is_none()));
+        }
+    }
+
+    #[test]
+    fn assets_are_grouped_by_policy() {
+        let (first, second) = (Hash::new([1; 28]), Hash::new([2; 28]));
+
+        let kvp = |name: &[u8], amount| KeyValuePairs::from(vec![(name.to_vec().into(), amount)]);
+        let (a, b, c) = (kvp(b"a", 1), kvp(b"b", 2), kvp(b"c", 3));
+
+        // outputs can't repeat a policy but several txs of a block can
+        let assets = [
+            trv::MultiEraPolicyAssets::AlonzoCompatibleOutput(&first, &a),
+            trv::MultiEraPolicyAssets::AlonzoCompatibleOutput(&second, &b),
+            trv::MultiEraPolicyAssets::AlonzoCompatibleOutput(&first, &c),
+        ];
+
+        let grouped = group_policy_assets(&assets);
+        assert_eq!(grouped.len(), 2);
+
+        assert_eq!(grouped[0].policy_id, first.to_vec());
+        let names: Vec<_> = grouped[0].assets.iter().map(|x| x.name.to_vec()).collect();
+        assert_eq!(names, vec![b"a".to_vec(), b"c".to_vec()]);
+        assert_eq!(grouped[0].assets[1].output_coin, 3);
+
+        assert_eq!(grouped[1].policy_id, second.to_vec());
+        assert_eq!(grouped[1].assets.len(), 1);
+    }
+
+    #[test]
+    fn block_header_carries_height() {
+        let cbor = babbage_block();
+        let block = trv::MultiEraBlock::decode(&cbor).unwrap();
+        let mapped = map_block(&block, &()).unwrap();
+
+        let header = mapped.header.unwrap();
+        assert_eq!(header.height, block.number());
+        assert_eq!(header.hash, block.hash().to_vec());
+
+        let hashes: Vec<_> = mapped
+            .body
+            .unwrap()
+            .tx
+            .into_iter()
+            .map(|x| x.hash)
+            .collect();
+        let expected: Vec<_> = block.txs().iter().map(|x| x.hash().to_vec()).collect();
+        assert_eq!(hashes, expected);
+    }
+
+    #[test]
+    fn invalid_block_cbor_is_an_error() {
+        assert!(matches!(
+            map_block_cbor(&[0x82, 0x09, 0x80], &()),
+            Err(Error::Traverse(_))
+        ));
+    }
+
+    #[test]
+    fn unrepresentable_certs_are_errors() {
+        assert!(matches!(
+            map_cert(&trv::MultiEraCert::NotApplicable),
+            Err(Error::NotRepresentable("certificate"))
+        ));
+    }
 }
@@ -0,0 +1,827 @@
+//! Mapping from UTxO RPC messages back into pallas primitives
+//!
+//! The inverse of the mapping at the root of the crate, for clients that
+//! receive txs, datums or scripts through the proto and need to work with them
+//! as ledger primitives.
+//!
+//! The proto doesn't carry everything the ledger does, so not every message
+//! can be turned back into the exact primitive it came from:
+//!
+//! - redeemers lack their index and execution units and the script data hash
+//!   isn't kept, so txs with inputs that carry a redeemer, plutus scripts or
+//!   datums fail to map with [Error::NotCarried].
+//! - the update proposals, required signers, network id, script data hash and
+//!   bootstrap witnesses of a tx aren't part of the proto, they're left empty.
+//! - numeric fields use zero for absent values, so a zero ttl, validity start
+//!   or total collateral maps to none.
+//! - details of the original encoding are lost. Outputs use the legacy format
+//!   unless they hold an inline datum or a script ref, plutus data lists use
+//!   the indefinite arrays of the reference implementation when not empty and
+//!   auxiliary data uses the shelley format when it has no scripts. Inline
+//!   datums might then hash differently than the original ones.
+
+use pallas_codec::utils::{
+    AnyUInt, Bytes, CborWrap, Int, KeyValuePairs, MaybeIndefArray, Nullable,
+};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::{alonzo, babbage};
+use thiserror::Error;
+
+use utxorpc_spec::utxorpc::v1alpha::cardano as u5c;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("missing field {0}")]
+    MissingField(&'static str),
+
+    #[error("invalid length for {field}, expected {expected} bytes but got {actual}")]
+    InvalidLength {
+        field: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("invalid value for {0}")]
+    InvalidValue(&'static str),
+
+    #[error("{0} isn't carried by the proto")]
+    NotCarried(&'static str),
+
+    #[error("{0} can't be represented by the primitives")]
+    Unsupported(&'static str),
+}
+
+fn map_hash<const BYTES: usize>(field: &'static str, x: &[u8]) -> Result<Hash<BYTES>, Error> {
+    let bytes: [u8; BYTES] = x.try_into().map_err(|_| Error::InvalidLength {
+        field,
+        expected: BYTES,
+        actual: x.len(),
+    })?;
+
+    Ok(Hash::from(bytes))
+}
+
+fn map_bytes(x: &[u8]) -> Bytes {
+    x.to_vec().into()
+}
+
+fn non_empty<T>(x: Vec<T>) -> Option<MaybeIndefArray<T>> {
+    match x.is_empty() {
+        true => None,
+        false => Some(MaybeIndefArray::Def(x)),
+    }
+}
+
+fn non_zero(x: u64) -> Option<u64> {
+    match x {
+        0 => None,
+        x => Some(x),
+    }
+}
+
+pub fn map_purpose(x: i32) -> Result<alonzo::RedeemerTag, Error> {
+    match u5c::RedeemerPurpose::from_i32(x) {
+        Some(u5c::RedeemerPurpose::Spend) => Ok(alonzo::RedeemerTag::Spend),
+        Some(u5c::RedeemerPurpose::Mint) => Ok(alonzo::RedeemerTag::Mint),
+        Some(u5c::RedeemerPurpose::Cert) => Ok(alonzo::RedeemerTag::Cert),
+        Some(u5c::RedeemerPurpose::Reward) => Ok(alonzo::RedeemerTag::Reward),
+        _ => Err(Error::InvalidValue("redeemer purpose")),
+    }
+}
+
+pub fn map_tx_input(x: &u5c::TxInput) -> Result<babbage::TransactionInput, Error> {
+    Ok(babbage::TransactionInput {
+        transaction_id: map_hash("input tx hash", &x.tx_hash)?,
+        index: x.output_index as u64,
+        layout: Default::default(),
+    })
+}
+
+pub fn map_tx_output(x: &u5c::TxOutput) -> Result<babbage::TransactionOutput, Error> {
+    let assets = map_output_assets(&x.assets)?;
+
+    let value = match assets.is_empty() {
+        true => babbage::Value::Coin(x.coin),
+        false => babbage::Value::Multiasset(x.coin, assets),
+    };
+
+    let datum = x.datum.as_ref().map(map_plutus_datum).transpose()?;
+
+    let datum_hash = match x.datum_hash.is_empty() {
+        true => None,
+        false => Some(map_hash("datum hash", &x.datum_hash)?),
+    };
+
+    let script = x.script.as_ref().map(map_script).transpose()?;
+
+    if datum.is_none() && script.is_none() {
+        return Ok(babbage::TransactionOutput::Legacy(
+            babbage::LegacyTransactionOutput {
+                address: map_bytes(&x.address),
+                amount: value,
+                datum_hash,
+            },
+        ));
+    }
+
+    let datum_option = match (datum, datum_hash) {
+        (Some(x), _) => Some(babbage::DatumOption::Data(CborWrap(x))),
+        (None, Some(x)) => Some(babbage::DatumOption::Hash(x)),
+        (None, None) => None,
+    };
+
+    Ok(babbage::TransactionOutput::PostAlonzo(
+        babbage::PostAlonzoTransactionOutput {
+            address: map_bytes(&x.address),
+            value,
+            datum_option,
+            script_ref: script.map(CborWrap),
+            layout: Default::default(),
+        },
+    ))
+}
+
+pub fn map_stake_credential(x: &u5c::StakeCredential) -> Result<babbage::StakeCredential, Error> {
+    match &x.stake_credential {
+        Some(u5c::stake_credential::StakeCredential::AddrKeyHash(x)) => Ok(
+            babbage::StakeCredential::AddrKeyhash(map_hash("stake key hash", x)?),
+        ),
+        Some(u5c::stake_credential::StakeCredential::ScriptHash(x)) => Ok(
+            babbage::StakeCredential::Scripthash(map_hash("stake script hash", x)?),
+        ),
+        None => Err(Error::MissingField("stake credential")),
+    }
+}
+
+pub fn map_relay(x: &u5c::Relay) -> babbage::Relay {
+    let port = match x.port {
+        0 => None,
+        x => Some(x),
+    };
+
+    if !x.ip_v4.is_empty() || !x.ip_v6.is_empty() {
+        let ip_v4 = (!x.ip_v4.is_empty()).then(|| map_bytes(&x.ip_v4));
+        let ip_v6 = (!x.ip_v6.is_empty()).then(|| map_bytes(&x.ip_v6));
+
+        return babbage::Relay::SingleHostAddr(port, ip_v4, ip_v6);
+    }
+
+    match port {
+        Some(_) => babbage::Relay::SingleHostName(port, x.dns_name.clone()),
+        None => babbage::Relay::MultiHostName(x.dns_name.clone()),
+    }
+}
+
+pub fn map_cert(x: &u5c::Certificate) -> Result<babbage::Certificate, Error> {
+    let inner = x
+        .certificate
+        .as_ref()
+        .ok_or(Error::MissingField("certificate"))?;
+
+    let cert = match inner {
+        u5c::certificate::Certificate::StakeRegistration(x) => {
+            babbage::Certificate::StakeRegistration(map_stake_credential(x)?)
+        }
+        u5c::certificate::Certificate::StakeDeregistration(x) => {
+            babbage::Certificate::StakeDeregistration(map_stake_credential(x)?)
+        }
+        u5c::certificate::Certificate::StakeDelegation(x) => babbage::Certificate::StakeDelegation(
+            map_stake_credential(
+                x.stake_credential
+                    .as_ref()
+                    .ok_or(Error::MissingField("delegation stake credential"))?,
+            )?,
+            map_hash("pool key hash", &x.pool_keyhash)?,
+        ),
+        u5c::certificate::Certificate::PoolRegistration(x) => {
+            let margin = x
+                .margin
+                .as_ref()
+                .ok_or(Error::MissingField("pool margin"))?;
+
+            babbage::Certificate::PoolRegistration {
+                operator: map_hash("pool operator", &x.operator)?,
+                vrf_keyhash: map_hash("pool vrf key hash", &x.vrf_keyhash)?,
+                pledge: x.pledge,
+                cost: x.cost,
+                margin: babbage::UnitInterval {
+                    numerator: u64::try_from(margin.numerator)
+                        .map_err(|_| Error::InvalidValue("pool margin"))?,
+                    denominator: margin.denominator as u64,
+                },
+                reward_account: map_bytes(&x.reward_account),
+                pool_owners: x
+                    .pool_owners
+                    .iter()
+                    .map(|x| map_hash("pool owner", x))
+                    .collect::<Result<_, _>>()?,
+                relays: x.relays.iter().map(map_relay).collect(),
+                pool_metadata: x
+                    .pool_metadata
+                    .as_ref()
+                    .map(|x| -> Result<_, Error> {
+                        Ok(babbage::PoolMetadata {
+                            url: x.url.clone(),
+                            hash: map_hash("pool metadata hash", &x.hash)?,
+                        })
+                    })
+                    .transpose()?,
+            }
+        }
+        u5c::certificate::Certificate::PoolRetirement(x) => babbage::Certificate::PoolRetirement(
+            map_hash("pool key hash", &x.pool_keyhash)?,
+            x.epoch,
+        ),
+        u5c::certificate::Certificate::GenesisKeyDelegation(x) => {
+            babbage::Certificate::GenesisKeyDelegation(
+                map_bytes(&x.genesis_hash),
+                map_bytes(&x.genesis_delegate_hash),
+                map_hash("genesis vrf key hash", &x.vrf_keyhash)?,
+            )
+        }
+        u5c::certificate::Certificate::MirCert(x) => {
+            let source = match u5c::MirSource::from_i32(x.from) {
+                Some(u5c::MirSource::Reserves) => babbage::InstantaneousRewardSource::Reserves,
+                Some(u5c::MirSource::Treasury) => babbage::InstantaneousRewardSource::Treasury,
+                _ => return Err(Error::InvalidValue("mir source")),
+            };
+
+            // rewards go either to stake credentials or to the other pot
+            let target = match x.to.is_empty() {
+                true => babbage::InstantaneousRewardTarget::OtherAccountingPot(x.other_pot),
+                false => babbage::InstantaneousRewardTarget::StakeCredentials(
+                    x.to.iter()
+                        .map(|x| -> Result<_, Error> {
+                            let credential = x
+                                .stake_credential
+                                .as_ref()
+                                .ok_or(Error::MissingField("mir stake credential"))?;
+
+                            Ok((map_stake_credential(credential)?, x.delta_coin))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into(),
+                ),
+            };
+
+            babbage::Certificate::MoveInstantaneousRewardsCert(babbage::MoveInstantaneousReward {
+                source,
+                target,
+            })
+        }
+    };
+
+    Ok(cert)
+}
+
+pub fn map_withdrawals(x: &u5c::Withdrawal) -> (babbage::RewardAccount, AnyUInt) {
+    (map_bytes(&x.reward_account), AnyUInt::from(x.coin))
+}
+
+fn map_multiasset<A: Clone>(
+    x: &[u5c::Multiasset],
+    amount: impl Fn(&u5c::Asset) -> A,
+) -> Result<alonzo::Multiasset<A>, Error> {
+    let policies = x
+        .iter()
+        .map(|x| -> Result<_, Error> {
+            let assets: Vec<_> = x
+                .assets
+                .iter()
+                .map(|x| (map_bytes(&x.name), amount(x)))
+                .collect();
+
+            Ok((map_hash("policy id", &x.policy_id)?, assets.into()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(policies.into())
+}
+
+/// Maps the assets of an output, taking the output coin of each one
+pub fn map_output_assets(x: &[u5c::Multiasset]) -> Result<alonzo::Multiasset<u64>, Error> {
+    map_multiasset(x, |x| x.output_coin)
+}
+
+/// Maps the assets minted or burned by a tx, taking the mint coin of each one
+pub fn map_mint(x: &[u5c::Multiasset]) -> Result<alonzo::Mint, Error> {
+    map_multiasset(x, |x| x.mint_coin)
+}
+
+pub fn map_vkey_witness(x: &u5c::VKeyWitness) -> babbage::VKeyWitness {
+    babbage::VKeyWitness {
+        vkey: map_bytes(&x.vkey),
+        signature: map_bytes(&x.signature),
+    }
+}
+
+fn map_native_scripts(
+    x: &[u5c::NativeScript],
+) -> Result<MaybeIndefArray<babbage::NativeScript>, Error> {
+    let items = x.iter().map(map_native_script).collect::<Result<_, _>>()?;
+
+    Ok(MaybeIndefArray::Def(items))
+}
+
+pub fn map_native_script(x: &u5c::NativeScript) -> Result<babbage::NativeScript, Error> {
+    let inner = x
+        .native_script
+        .as_ref()
+        .ok_or(Error::MissingField("native script"))?;
+
+    let script = match inner {
+        u5c::native_script::NativeScript::ScriptPubkey(x) => {
+            babbage::NativeScript::ScriptPubkey(map_hash("script pubkey", x)?)
+        }
+        u5c::native_script::NativeScript::ScriptAll(x) => {
+            babbage::NativeScript::ScriptAll(map_native_scripts(&x.items)?)
+        }
+        u5c::native_script::NativeScript::ScriptAny(x) => {
+            babbage::NativeScript::ScriptAny(map_native_scripts(&x.items)?)
+        }
+        u5c::native_script::NativeScript::ScriptNOfK(x) => {
+            babbage::NativeScript::ScriptNOfK(x.k, map_native_scripts(&x.scripts)?)
+        }
+        u5c::native_script::NativeScript::InvalidBefore(x) => {
+            babbage::NativeScript::InvalidBefore(*x)
+        }
+        u5c::native_script::NativeScript::InvalidHereafter(x) => {
+            babbage::NativeScript::InvalidHereafter(*x)
+        }
+    };
+
+    Ok(script)
+}
+
+pub fn map_script(x: &u5c::Script) -> Result<babbage::Script, Error> {
+    match &x.script {
+        Some(u5c::script::Script::Native(x)) => {
+            Ok(babbage::Script::NativeScript(map_native_script(x)?))
+        }
+        Some(u5c::script::Script::PlutusV1(x)) => Ok(babbage::Script::PlutusV1Script(
+            babbage::PlutusV1Script(map_bytes(x)),
+        )),
+        Some(u5c::script::Script::PlutusV2(x)) => Ok(babbage::Script::PlutusV2Script(
+            babbage::PlutusV2Script(map_bytes(x)),
+        )),
+        None => Err(Error::MissingField("script")),
+    }
+}
+
+pub fn map_plutus_constr(x: &u5c::Constr) -> Result<alonzo::Constr<alonzo::PlutusData>, Error> {
+    let tag = x.tag as u64;
+
+    if !matches!(tag, 121..=127 | 1280..=1400 | 102) {
+        return Err(Error::InvalidValue("constr tag"));
+    }
+
+    Ok(alonzo::Constr {
+        tag,
+        // only the general form of the constructor carries its index apart
+        any_constructor: (tag == 102).then_some(x.any_constructor),
+        fields: x
+            .fields
+            .iter()
+            .map(map_plutus_datum)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+pub fn map_plutus_map(
+    x: &u5c::PlutusDataMap,
+) -> Result<KeyValuePairs<alonzo::PlutusData, alonzo::PlutusData>, Error> {
+    let pairs = x
+        .pairs
+        .iter()
+        .map(|x| -> Result<_, Error> {
+            let key = x
+                .key
+                .as_ref()
+                .ok_or(Error::MissingField("plutus map key"))?;
+
+            let value = x
+                .value
+                .as_ref()
+                .ok_or(Error::MissingField("plutus map value"))?;
+
+            Ok((map_plutus_datum(key)?, map_plutus_datum(value)?))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(pairs.into())
+}
+
+pub fn map_plutus_array(x: &u5c::PlutusDataArray) -> Result<Vec<alonzo::PlutusData>, Error> {
+    x.items.iter().map(map_plutus_datum).collect()
+}
+
+pub fn map_plutus_bigint(x: &u5c::BigInt) -> Result<alonzo::BigInt, Error> {
+    match &x.big_int {
+        Some(u5c::big_int::BigInt::Int(x)) => Ok(alonzo::BigInt::Int(Int::from(*x))),
+        Some(u5c::big_int::BigInt::BigUInt(x)) => Ok(alonzo::BigInt::BigUInt(x.to_vec().into())),
+        Some(u5c::big_int::BigInt::BigNInt(x)) => Ok(alonzo::BigInt::BigNInt(x.to_vec().into())),
+        None => Err(Error::MissingField("big int")),
+    }
+}
+
+pub fn map_plutus_datum(x: &u5c::PlutusData) -> Result<alonzo::PlutusData, Error> {
+    match &x.plutus_data {
+        Some(u5c::plutus_data::PlutusData::Constr(x)) => {
+            Ok(alonzo::PlutusData::Constr(map_plutus_constr(x)?))
+        }
+        Some(u5c::plutus_data::PlutusData::Map(x)) => {
+            Ok(alonzo::PlutusData::Map(map_plutus_map(x)?))
+        }
+        Some(u5c::plutus_data::PlutusData::Array(x)) => {
+            Ok(alonzo::PlutusData::Array(map_plutus_array(x)?))
+        }
+        Some(u5c::plutus_data::PlutusData::BigInt(x)) => {
+            Ok(alonzo::PlutusData::BigInt(map_plutus_bigint(x)?))
+        }
+        Some(u5c::plutus_data::PlutusData::BoundedBytes(x)) => {
+            Ok(alonzo::PlutusData::BoundedBytes(x.to_vec().into()))
+        }
+        None => Err(Error::MissingField("plutus data")),
+    }
+}
+
+pub fn map_metadatum(x: &u5c::Metadatum) -> Result<alonzo::Metadatum, Error> {
+    let inner = x
+        .metadatum
+        .as_ref()
+        .ok_or(Error::MissingField("metadatum"))?;
+
+    let datum = match inner {
+        u5c::metadatum::Metadatum::Int(x) => alonzo::Metadatum::Int(Int::from(*x)),
+        u5c::metadatum::Metadatum::Bytes(x) => alonzo::Metadatum::Bytes(map_bytes(x)),
+        u5c::metadatum::Metadatum::Text(x) => alonzo::Metadatum::Text(x.clone()),
+        u5c::metadatum::Metadatum::Array(x) => alonzo::Metadatum::Array(
+            x.items
+                .iter()
+                .map(map_metadatum)
+                .collect::<Result<_, _>>()?,
+        ),
+        u5c::metadatum::Metadatum::Map(x) => alonzo::Metadatum::Map(
+            x.pairs
+                .iter()
+                .map(|x| -> Result<_, Error> {
+                    let key = x.key.as_ref().ok_or(Error::MissingField("metadatum key"))?;
+
+                    let value = x
+                        .value
+                        .as_ref()
+                        .ok_or(Error::MissingField("metadatum value"))?;
+
+                    Ok((map_metadatum(key)?, map_metadatum(value)?))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+        ),
+    };
+
+    Ok(datum)
+}
+
+pub fn map_metadata(
+    x: &u5c::Metadata,
+) -> Result<(alonzo::MetadatumLabel, alonzo::Metadatum), Error> {
+    let value = x
+        .value
+        .as_ref()
+        .ok_or(Error::MissingField("metadata value"))?;
+
+    Ok((x.label, map_metadatum(value)?))
+}
+
+pub fn map_aux_data(x: &u5c::AuxData) -> Result<alonzo::AuxiliaryData, Error> {
+    let metadata: alonzo::Metadata = x
+        .metadata
+        .iter()
+        .map(map_metadata)
+        .collect::<Result<Vec<_>, _>>()?
+        .into();
+
+    if x.scripts.is_empty() {
+        return Ok(alonzo::AuxiliaryData::Shelley(metadata));
+    }
+
+    let mut native_scripts = vec![];
+    let mut plutus_scripts = vec![];
+
+    for script in x.scripts.iter().map(map_script) {
+        match script? {
+            babbage::Script::NativeScript(x) => native_scripts.push(x),
+            babbage::Script::PlutusV1Script(x) => plutus_scripts.push(x),
+            babbage::Script::PlutusV2Script(_) => {
+                return Err(Error::Unsupported("plutus v2 script in auxiliary data"))
+            }
+        }
+    }
+
+    Ok(alonzo::AuxiliaryData::PostAlonzo(
+        alonzo::PostAlonzoAuxiliaryData {
+            metadata: (!metadata.is_empty()).then_some(metadata),
+            native_scripts: non_empty(native_scripts),
+            plutus_scripts: non_empty(plutus_scripts),
+            layout: Default::default(),
+        },
+    ))
+}
+
+pub fn map_witness_set(x: &u5c::WitnessSet) -> Result<babbage::WitnessSet, Error> {
+    // plutus scripts and datums are covered by the script data hash of the
+    // body, which can't be recomputed without the redeemers
+    if !x.plutus_datums.is_empty() {
+        return Err(Error::NotCarried("script data hash"));
+    }
+
+    let native_scripts = x
+        .script
+        .iter()
+        .map(|x| match map_script(x)? {
+            babbage::Script::NativeScript(x) => Ok(x),
+            _ => Err(Error::NotCarried("script data hash")),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(babbage::WitnessSet {
+        vkeywitness: non_empty(x.vkeywitness.iter().map(map_vkey_witness).collect()),
+        native_script: non_empty(native_scripts),
+        bootstrap_witness: None,
+        plutus_v1_script: None,
+        plutus_data: None,
+        redeemer: None,
+        plutus_v2_script: None,
+        layout: Default::default(),
+    })
+}
+
+fn map_tx_inputs(x: &[u5c::TxInput]) -> Result<Vec<babbage::TransactionInput>, Error> {
+    // the proto keeps the redeemer of each input without its index and units
+    if x.iter().any(|x| x.redeemer.is_some()) {
+        return Err(Error::NotCarried("redeemer index and execution units"));
+    }
+
+    x.iter().map(map_tx_input).collect()
+}
+
+pub fn map_tx(x: &u5c::Tx) -> Result<babbage::Tx, Error> {
+    let collateral = x.collateral.clone().unwrap_or_default();
+    let validity = x.validity.clone().unwrap_or_default();
+
+    let auxiliary_data = x
+        .auxiliary
+        .as_ref()
+        .filter(|x| !x.metadata.is_empty() || !x.scripts.is_empty())
+        .map(map_aux_data)
+        .transpose()?;
+
+    let transaction_body = babbage::TransactionBody {
+        inputs: MaybeIndefArray::Def(map_tx_inputs(&x.inputs)?),
+        outputs: MaybeIndefArray::Def(
+            x.outputs
+                .iter()
+                .map(map_tx_output)
+                .collect::<Result<_, _>>()?,
+        ),
+        fee: x.fee,
+        ttl: non_zero(validity.ttl),
+        certificates: non_empty(
+            x.certificates
+                .iter()
+                .map(map_cert)
+                .collect::<Result<_, _>>()?,
+        ),
+        withdrawals: (!x.withdrawals.is_empty()).then(|| {
+            x.withdrawals
+                .iter()
+                .map(map_withdrawals)
+                .collect::<Vec<_>>()
+                .into()
+        }),
+        update: None,
+        auxiliary_data_hash: auxiliary_data
+            .as_ref()
+            .map(|x| Hasher::<256>::hash_cbor(x).to_vec().into()),
+        validity_interval_start: non_zero(validity.start),
+        mint: match x.mint.is_empty() {
+            true => None,
+            false => Some(map_mint(&x.mint)?),
+        },
+        script_data_hash: None,
+        collateral: non_empty(map_tx_inputs(&collateral.collateral)?),
+        required_signers: None,
+        network_id: None,
+        collateral_return: collateral
+            .collateral_return
+            .as_ref()
+            .map(map_tx_output)
+            .transpose()?,
+        total_collateral: non_zero(collateral.total_collateral),
+        reference_inputs: non_empty(map_tx_inputs(&x.reference_inputs)?),
+        layout: Default::default(),
+    };
+
+    let transaction_witness_set = match &x.witnesses {
+        Some(x) => map_witness_set(x)?,
+        None => map_witness_set(&Default::default())?,
+    };
+
+    Ok(babbage::Tx {
+        transaction_body,
+        transaction_witness_set,
+        success: x.successful,
+        auxiliary_data: match auxiliary_data {
+            Some(x) => Nullable::Some(x),
+            None => Nullable::Null,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_traverse as trv;
+
+    use super::*;
+
+    fn test_blocks() -> Vec<Vec<u8>> {
+        [
+            include_str!("../../test_data/shelley1.block"),
+            include_str!("../../test_data/allegra1.block"),
+            include_str!("../../test_data/mary1.block"),
+            include_str!("../../test_data/alonzo1.block"),
+            include_str!("../../test_data/alonzo9.block"),
+            include_str!("../../test_data/babbage1.block"),
+            include_str!("../../test_data/babbage3.block"),
+            include_str!("../../test_data/babbage9.block"),
+        ]
+        .iter()
+        .map(|x| hex::decode(x).unwrap())
+        .collect()
+    }
+
+    /// the hashes of the tx and of inline datums depend on their original
+    /// encoding, which the proto doesn't keep
+    fn without_original_hashes(mut tx: u5c::Tx) -> u5c::Tx {
+        tx.hash = Default::default();
+
+        for output in tx.outputs.iter_mut() {
+            if output.datum.is_some() {
+                output.datum_hash = Default::default();
+            }
+        }
+
+        tx
+    }
+
+    #[test]
+    fn txs_map_back_and_forth() {
+        let mut mapped = 0;
+        let mut rejected = 0;
+
+        for cbor in test_blocks() {
+            let block = trv::MultiEraBlock::decode(&cbor).unwrap();
+
+            for tx in block.txs() {
+                let expected = crate::map_tx(&tx, &()).unwrap();
+
+                let primitive = match map_tx(&expected) {
+                    Ok(x) => x,
+                    Err(Error::NotCarried(_)) => {
+                        assert!(
+                            !tx.redeemers().is_empty()
+                                || !tx.plutus_data().is_empty()
+                                || !tx.plutus_v1_scripts().is_empty()
+                                || !tx.plutus_v2_scripts().is_empty()
+                        );
+                        rejected += 1;
+                        continue;
+                    }
+                    Err(err) => panic!("unexpected error {err}"),
+                };
+
+                let cbor = pallas_codec::minicbor::to_vec(&primitive).unwrap();
+                let tx = trv::MultiEraTx::decode(trv::Era::Babbage, &cbor).unwrap();
+
+                assert_eq!(
+                    without_original_hashes(crate::map_tx(&tx, &()).unwrap()),
+                    without_original_hashes(expected)
+                );
+                mapped += 1;
+            }
+        }
+
+        assert!(mapped > 0);
+        assert!(rejected > 0);
+    }
+
+    #[test]
+    fn plutus_data_maps_back_and_forth() {
+        let mut datums = 0;
+
+        for cbor in test_blocks() {
+            let block = trv::MultiEraBlock::decode(&cbor).unwrap();
+
+            for tx in block.txs() {
+                for datum in tx.plutus_data() {
+                    let expected = crate::map_plutus_datum(datum);
+                    let primitive = map_plutus_datum(&expected).unwrap();
+
+                    assert_eq!(crate::map_plutus_datum(&primitive), expected);
+                    datums += 1;
+                }
+            }
+        }
+
+        assert!(datums > 0);
+    }
+
+    #[test]
+    fn plutus_witnesses_are_not_carried() {
+        let script = u5c::WitnessSet {
+            script: vec![u5c::Script {
+                script: Some(u5c::script::Script::PlutusV2(vec![0x4e].into())),
+            }],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            map_witness_set(&script),
+            Err(Error::NotCarried(_))
+        ));
+
+        let datum = u5c::WitnessSet {
+            plutus_datums: vec![crate::map_plutus_datum(&alonzo::PlutusData::BoundedBytes(
+                vec![1, 2, 3].into(),
+            ))],
+            ..Default::default()
+        };
+
+        assert!(matches!(map_witness_set(&datum), Err(Error::NotCarried(_))));
+
+        let native = u5c::WitnessSet {
+            script: vec![u5c::Script {
+                script: Some(u5c::script::Script::Native(crate::map_native_script(
+                    &alonzo::NativeScript::InvalidBefore(10),
+                ))),
+            }],
+            ..Default::default()
+        };
+
+        assert!(map_witness_set(&native).unwrap().native_script.is_some());
+    }
+
+    #[test]
+    fn missing_fields_are_errors() {
+        let script = u5c::NativeScript {
+            native_script: Some(u5c::native_script::NativeScript::ScriptAny(
+                u5c::NativeScriptList {
+                    items: vec![u5c::NativeScript::default()],
+                },
+            )),
+        };
+
+        assert!(matches!(
+            map_native_script(&script),
+            Err(Error::MissingField(_))
+        ));
+
+        let input = u5c::TxInput {
+            tx_hash: vec![0; 28].into(),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            map_tx_input(&input),
+            Err(Error::InvalidLength {
+                expected: 32,
+                actual: 28,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn constr_tags_are_checked() {
+        for tag in [121, 127, 1280, 1400, 102] {
+            let constr = u5c::Constr {
+                tag,
+                ..Default::default()
+            };
+
+            assert_eq!(map_plutus_constr(&constr).unwrap().tag, tag as u64);
+        }
+
+        for tag in [0, 120, 128, 1279, 1401] {
+            let constr = u5c::Constr {
+                tag,
+                ..Default::default()
+            };
+
+            assert!(matches!(
+                map_plutus_constr(&constr),
+                Err(Error::InvalidValue("constr tag"))
+            ));
+        }
+    }
+}
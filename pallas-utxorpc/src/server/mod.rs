@@ -0,0 +1,425 @@
+//! UTxO RPC services backed by pallas components
+//!
+//! The chain-sync service answers the gRPC requests with data pulled from a
+//! [ChainSource], which can be a node over its Unix socket ([node]), a peer
+//! over the node-to-node protocols ([peer]) or a local block store ([store]).
+//! Each request gets a source of its own, created through a [Connector]. The
+//! submit service ([submit]) forwards txs through sources that can also
+//! submit them.
+
+use std::fmt::Display;
+
+use async_trait::async_trait;
+use pallas_network::miniprotocols::Point;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use utxorpc_spec::utxorpc::v1alpha::sync;
+
+#[cfg(not(target_os = "windows"))]
+pub mod node;
+pub mod peer;
+pub mod store;
+pub mod submit;
+
+pub use submit::{SubmitServiceImpl, SubmitServiceServer};
+pub use sync::chain_sync_service_server::{ChainSyncService, ChainSyncServiceServer};
+
+/// Page size of the history dumps that don't ask for any
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// How many follow-tip events can be waiting for the client to read them
+const FOLLOW_TIP_BUFFER: usize = 10;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("network error: {0}")]
+    Network(String),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] pallas_storage::Error),
+
+    #[error("traverse error: {0}")]
+    Traverse(#[from] pallas_traverse::Error),
+
+    #[error("mapping error: {0}")]
+    Mapping(#[from] crate::Error),
+
+    #[error("invalid tx: {0}")]
+    InvalidTx(String),
+
+    #[error("intersection not found")]
+    IntersectionNotFound,
+
+    #[error("block not found: {0:?}")]
+    BlockNotFound(Point),
+
+    #[error("operation not supported by the chain source")]
+    Unsupported,
+}
+
+impl Error {
+    pub fn network(error: impl Display) -> Self {
+        Error::Network(format!("{error}"))
+    }
+}
+
+impl From<Error> for Status {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::InvalidTx(_) => Status::invalid_argument(value.to_string()),
+            Error::IntersectionNotFound => Status::not_found(value.to_string()),
+            Error::BlockNotFound(_) => Status::not_found(value.to_string()),
+            Error::Unsupported => Status::unimplemented(value.to_string()),
+            Error::Traverse(_) => Status::data_loss(value.to_string()),
+            Error::Mapping(crate::Error::NotRepresentable(_)) => {
+                Status::unimplemented(value.to_string())
+            }
+            Error::Mapping(_) => Status::data_loss(value.to_string()),
+            _ => Status::unavailable(value.to_string()),
+        }
+    }
+}
+
+/// A change in the chain followed by a [ChainSource]
+#[derive(Debug)]
+pub enum ChainEvent {
+    /// the cbor of a block that extends the chain
+    Apply(Vec<u8>),
+
+    /// the chain was rolled back to this point
+    Reset(Point),
+}
+
+/// Access to the blocks of a chain
+///
+/// Sources follow the semantics of the chain-sync mini-protocol: after
+/// finding an intersection, the first event is a reset to the intersection
+/// point, followed by the blocks after it.
+#[async_trait]
+pub trait ChainSource: Send + 'static {
+    /// Finds the first of the points that is part of the chain
+    async fn find_intersect(&mut self, points: Vec<Point>) -> Result<Option<Point>, Error>;
+
+    /// Intersects the chain at its current tip
+    async fn intersect_tip(&mut self) -> Result<Point, Error>;
+
+    /// Waits for the next change of the chain after the intersection
+    async fn next_event(&mut self) -> Result<ChainEvent, Error>;
+
+    /// Returns the cbor of the block at the point
+    async fn fetch_block(&mut self, point: Point) -> Result<Option<Vec<u8>>, Error> {
+        let _ = point;
+        Err(Error::Unsupported)
+    }
+
+    /// Returns up to `max` blocks of the chain starting at the given point,
+    /// along with the point of the block that follows them, if any
+    async fn read_history(
+        &mut self,
+        start: Option<Point>,
+        max: usize,
+    ) -> Result<(Vec<Vec<u8>>, Option<Point>), Error> {
+        let _ = (start, max);
+        Err(Error::Unsupported)
+    }
+}
+
+/// Creates the [ChainSource] that serves each request
+#[async_trait]
+pub trait Connector: Send + Sync + 'static {
+    type Source: ChainSource;
+
+    async fn connect(&self) -> Result<Self::Source, Error>;
+}
+
+fn point_from_ref(x: &sync::BlockRef) -> Point {
+    Point::Specific(x.index, x.hash.to_vec())
+}
+
+fn ref_from_point(x: &Point) -> sync::BlockRef {
+    match x {
+        Point::Origin => sync::BlockRef {
+            index: 0,
+            hash: Default::default(),
+        },
+        Point::Specific(slot, hash) => sync::BlockRef {
+            index: *slot,
+            hash: hash.clone().into(),
+        },
+    }
+}
+
+fn map_any_block(cbor: &[u8]) -> Result<sync::AnyChainBlock, Error> {
+    let block = crate::map_block_cbor(cbor, &())?;
+
+    Ok(sync::AnyChainBlock {
+        chain: sync::any_chain_block::Chain::Cardano(block).into(),
+    })
+}
+
+fn map_event(event: ChainEvent) -> Result<sync::FollowTipResponse, Error> {
+    let action = match event {
+        ChainEvent::Apply(cbor) => sync::follow_tip_response::Action::Apply(map_any_block(&cbor)?),
+        ChainEvent::Reset(point) => {
+            sync::follow_tip_response::Action::Reset(ref_from_point(&point))
+        }
+    };
+
+    Ok(sync::FollowTipResponse {
+        action: action.into(),
+    })
+}
+
+/// Implementation of the chain-sync gRPC service
+///
+/// Wrap it with [ChainSyncServiceServer] to add it to a tonic server.
+pub struct ChainSyncServiceImpl<C> {
+    connector: C,
+}
+
+impl<C: Connector> ChainSyncServiceImpl<C> {
+    pub fn new(connector: C) -> Self {
+        Self { connector }
+    }
+}
+
+#[async_trait]
+impl<C: Connector> ChainSyncService for ChainSyncServiceImpl<C> {
+    async fn fetch_block(
+        &self,
+        request: Request<sync::FetchBlockRequest>,
+    ) -> Result<Response<sync::FetchBlockResponse>, Status> {
+        let mut source = self.connector.connect().await?;
+
+        let mut blocks = vec![];
+
+        for point in request.into_inner().r#ref.iter().map(point_from_ref) {
+            let cbor = source
+                .fetch_block(point.clone())
+                .await?
+                .ok_or(Error::BlockNotFound(point))?;
+
+            blocks.push(map_any_block(&cbor)?);
+        }
+
+        Ok(Response::new(sync::FetchBlockResponse { block: blocks }))
+    }
+
+    async fn dump_history(
+        &self,
+        request: Request<sync::DumpHistoryRequest>,
+    ) -> Result<Response<sync::DumpHistoryResponse>, Status> {
+        let request = request.into_inner();
+        let mut source = self.connector.connect().await?;
+
+        let start = request.start_token.as_ref().map(point_from_ref);
+
+        let max = match request.max_items {
+            0 => DEFAULT_PAGE_SIZE,
+            x => x as usize,
+        };
+
+        let (blocks, next) = source.read_history(start, max).await?;
+
+        let blocks = blocks
+            .iter()
+            .map(|x| map_any_block(x))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Response::new(sync::DumpHistoryResponse {
+            block: blocks,
+            next_token: next.as_ref().map(ref_from_point),
+        }))
+    }
+
+    type FollowTipStream = ReceiverStream<Result<sync::FollowTipResponse, Status>>;
+
+    async fn follow_tip(
+        &self,
+        request: Request<sync::FollowTipRequest>,
+    ) -> Result<Response<Self::FollowTipStream>, Status> {
+        let mut source = self.connector.connect().await?;
+
+        let points: Vec<_> = request
+            .into_inner()
+            .intersect
+            .iter()
+            .map(point_from_ref)
+            .collect();
+
+        if points.is_empty() {
+            source.intersect_tip().await?;
+        } else {
+            source
+                .find_intersect(points)
+                .await?
+                .ok_or(Error::IntersectionNotFound)?;
+        }
+
+        let (sender, receiver) = mpsc::channel(FOLLOW_TIP_BUFFER);
+
+        tokio::spawn(async move {
+            loop {
+                let response = source
+                    .next_event()
+                    .await
+                    .and_then(map_event)
+                    .map_err(Status::from);
+
+                let failed = response.is_err();
+
+                // the client is gone once the receiver is dropped
+                if sender.send(response).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use pallas_storage::blocks::BlockStore;
+    use pallas_traverse::MultiEraBlock;
+    use tokio_stream::StreamExt;
+
+    use super::store::StoreConnector;
+    use super::*;
+
+    fn load_store(dir: &tempfile::TempDir) -> (Arc<BlockStore>, Vec<Point>) {
+        let blocks = [
+            include_str!("../../../test_data/alonzo1.block"),
+            include_str!("../../../test_data/alonzo2.block"),
+            include_str!("../../../test_data/alonzo3.block"),
+            include_str!("../../../test_data/babbage1.block"),
+        ];
+
+        let mut blocks: Vec<_> = blocks.iter().map(|x| hex::decode(x).unwrap()).collect();
+        blocks.sort_by_key(|x| MultiEraBlock::decode(x).unwrap().slot());
+
+        let store = BlockStore::open(dir.path().join("blocks.redb")).unwrap();
+
+        let points = blocks
+            .iter()
+            .map(|x| store.roll_forward(x).unwrap())
+            .map(|(slot, hash)| Point::Specific(slot, hash.to_vec()))
+            .collect();
+
+        (Arc::new(store), points)
+    }
+
+    fn block_hash(block: &sync::AnyChainBlock) -> Vec<u8> {
+        match &block.chain {
+            Some(sync::any_chain_block::Chain::Cardano(x)) => {
+                x.header.as_ref().unwrap().hash.to_vec()
+            }
+            _ => panic!("expected a cardano block"),
+        }
+    }
+
+    fn point_hash(point: &Point) -> Vec<u8> {
+        match point {
+            Point::Specific(_, hash) => hash.clone(),
+            Point::Origin => panic!("expected a specific point"),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_and_dump_from_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let (store, points) = load_store(&dir);
+        let service = ChainSyncServiceImpl::new(StoreConnector::new(store));
+
+        let request = sync::FetchBlockRequest {
+            r#ref: vec![ref_from_point(&points[2])],
+            ..Default::default()
+        };
+
+        let response = service.fetch_block(Request::new(request)).await.unwrap();
+        let blocks = response.into_inner().block;
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(block_hash(&blocks[0]), point_hash(&points[2]));
+
+        let request = sync::DumpHistoryRequest {
+            max_items: 3,
+            ..Default::default()
+        };
+
+        let page = service.dump_history(Request::new(request)).await.unwrap();
+        let page = page.into_inner();
+
+        let hashes: Vec<_> = page.block.iter().map(block_hash).collect();
+        let expected: Vec<_> = points[..3].iter().map(point_hash).collect();
+        assert_eq!(hashes, expected);
+
+        let request = sync::DumpHistoryRequest {
+            start_token: page.next_token,
+            max_items: 3,
+            ..Default::default()
+        };
+
+        let page = service.dump_history(Request::new(request)).await.unwrap();
+        let page = page.into_inner();
+
+        assert_eq!(page.block.len(), 1);
+        assert_eq!(block_hash(&page.block[0]), point_hash(&points[3]));
+        assert!(page.next_token.is_none());
+    }
+
+    async fn next_action(
+        stream: &mut <ChainSyncServiceImpl<StoreConnector> as ChainSyncService>::FollowTipStream,
+    ) -> sync::follow_tip_response::Action {
+        stream.next().await.unwrap().unwrap().action.unwrap()
+    }
+
+    #[tokio::test]
+    async fn follow_tip_from_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let (store, points) = load_store(&dir);
+
+        let connector = StoreConnector {
+            store: store.clone(),
+            poll_interval: Duration::from_millis(10),
+        };
+
+        let service = ChainSyncServiceImpl::new(connector);
+
+        let request = sync::FollowTipRequest {
+            intersect: vec![ref_from_point(&points[0])],
+        };
+
+        let response = service.follow_tip(Request::new(request)).await.unwrap();
+        let mut stream = response.into_inner();
+
+        use sync::follow_tip_response::Action;
+
+        match next_action(&mut stream).await {
+            Action::Reset(x) => assert_eq!(x, ref_from_point(&points[0])),
+            x => panic!("unexpected action {x:?}"),
+        }
+
+        for point in &points[1..] {
+            match next_action(&mut stream).await {
+                Action::Apply(x) => assert_eq!(block_hash(&x), point_hash(point)),
+                x => panic!("unexpected action {x:?}"),
+            }
+        }
+
+        // the source notices the rollback on its next poll
+        let (slot, hash) = store.chain_page(0, 2).unwrap()[1];
+        store.rollback_to(slot, &hash).unwrap();
+
+        match next_action(&mut stream).await {
+            Action::Reset(x) => assert_eq!(x, ref_from_point(&points[1])),
+            x => panic!("unexpected action {x:?}"),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Chain source backed by a node, through its node-to-client Unix socket
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use pallas_network::facades::NodeClient;
+use pallas_network::miniprotocols::{chainsync::NextResponse, Point};
+
+use super::{ChainEvent, ChainSource, Connector, Error};
+
+/// Connects to a node through its Unix socket
+///
+/// The node-to-client protocols can only follow the chain, fetching blocks
+/// by point and dumping the history aren't supported.
+#[derive(Debug, Clone)]
+pub struct NodeConnector {
+    pub socket_path: PathBuf,
+    pub magic: u64,
+}
+
+impl NodeConnector {
+    pub fn new(socket_path: impl Into<PathBuf>, magic: u64) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            magic,
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for NodeConnector {
+    type Source = NodeSource;
+
+    async fn connect(&self) -> Result<Self::Source, Error> {
+        let client = NodeClient::connect(&self.socket_path, self.magic)
+            .await
+            .map_err(Error::network)?;
+
+        Ok(NodeSource(client))
+    }
+}
+
+pub struct NodeSource(NodeClient);
+
+#[async_trait]
+impl ChainSource for NodeSource {
+    async fn find_intersect(&mut self, points: Vec<Point>) -> Result<Option<Point>, Error> {
+        let (point, _) = self
+            .0
+            .chainsync()
+            .find_intersect(points)
+            .await
+            .map_err(Error::network)?;
+
+        Ok(point)
+    }
+
+    async fn intersect_tip(&mut self) -> Result<Point, Error> {
+        self.0
+            .chainsync()
+            .intersect_tip()
+            .await
+            .map_err(Error::network)
+    }
+
+    async fn next_event(&mut self) -> Result<ChainEvent, Error> {
+        let chainsync = self.0.chainsync();
+
+        let next = match chainsync.request_next().await.map_err(Error::network)? {
+            NextResponse::Await => chainsync
+                .recv_while_must_reply()
+                .await
+                .map_err(Error::network)?,
+            x => x,
+        };
+
+        match next {
+            NextResponse::RollForward(block, _) => Ok(ChainEvent::Apply(block.into())),
+            NextResponse::RollBackward(point, _) => Ok(ChainEvent::Reset(point)),
+            NextResponse::Await => Err(Error::network("unexpected await from node")),
+        }
+    }
+}
+
+impl Drop for NodeSource {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_network::facades::NodeServer;
+    use pallas_network::miniprotocols::chainsync::{BlockContent, ClientRequest, Tip};
+    use pallas_traverse::MultiEraBlock;
+    use tokio::net::UnixListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn follows_the_chain_of_a_node() {
+        let cbor = hex::decode(include_str!("../../../test_data/babbage1.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+
+        let point = Point::Specific(block.slot(), block.hash().to_vec());
+        let tip = Tip(point.clone(), block.number());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.socket");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = tokio::spawn({
+            let cbor = cbor.clone();
+
+            async move {
+                let mut node = NodeServer::accept(&listener, 0).await.unwrap();
+                let chainsync = node.chainsync();
+
+                match chainsync.recv_while_idle().await.unwrap() {
+                    Some(ClientRequest::Intersect(points)) => {
+                        assert_eq!(points, vec![tip.0.clone()])
+                    }
+                    _ => panic!("expected an intersect request"),
+                }
+
+                chainsync
+                    .send_intersect_found(tip.0.clone(), tip.clone())
+                    .await
+                    .unwrap();
+
+                assert!(matches!(
+                    chainsync.recv_while_idle().await.unwrap(),
+                    Some(ClientRequest::RequestNext)
+                ));
+
+                chainsync
+                    .send_roll_backward(tip.0.clone(), tip.clone())
+                    .await
+                    .unwrap();
+
+                assert!(matches!(
+                    chainsync.recv_while_idle().await.unwrap(),
+                    Some(ClientRequest::RequestNext)
+                ));
+
+                // the client needs to keep waiting once at the tip
+                chainsync.send_await_reply().await.unwrap();
+
+                chainsync
+                    .send_roll_forward(BlockContent(cbor), tip)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let mut source = NodeConnector::new(&path, 0).connect().await.unwrap();
+
+        let found = source.find_intersect(vec![point.clone()]).await.unwrap();
+        assert_eq!(found, Some(point.clone()));
+
+        match source.next_event().await.unwrap() {
+            ChainEvent::Reset(x) => assert_eq!(x, point),
+            x => panic!("unexpected event {x:?}"),
+        }
+
+        match source.next_event().await.unwrap() {
+            ChainEvent::Apply(x) => assert_eq!(x, cbor),
+            x => panic!("unexpected event {x:?}"),
+        }
+
+        server.await.unwrap();
+    }
+}
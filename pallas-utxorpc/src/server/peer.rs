@@ -0,0 +1,357 @@
+//! Chain source backed by a peer, through the node-to-node protocols
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use pallas_network::facades::PeerClient;
+use pallas_network::miniprotocols::txsubmission::{EraTxBody, EraTxId, Request, TxIdAndSize};
+use pallas_network::miniprotocols::{chainsync::NextResponse, Point};
+use pallas_traverse::MultiEraHeader;
+
+use super::submit::{PendingTx, TxSubmitter};
+use super::{ChainEvent, ChainSource, Connector, Error};
+
+/// Connects to a peer over TCP
+///
+/// The chain is followed through its headers, the blocks are fetched as
+/// their headers arrive. Dumping the history isn't supported. Txs are
+/// submitted through the tx-submission protocol, which lets the peer pull
+/// them at its own pace.
+#[derive(Debug, Clone)]
+pub struct PeerConnector {
+    pub address: String,
+    pub magic: u64,
+}
+
+impl PeerConnector {
+    pub fn new(address: impl Into<String>, magic: u64) -> Self {
+        Self {
+            address: address.into(),
+            magic,
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for PeerConnector {
+    type Source = PeerSource;
+
+    async fn connect(&self) -> Result<Self::Source, Error> {
+        let client = PeerClient::connect(&self.address, self.magic)
+            .await
+            .map_err(Error::network)?;
+
+        Ok(PeerSource(client))
+    }
+}
+
+pub struct PeerSource(PeerClient);
+
+#[async_trait]
+impl ChainSource for PeerSource {
+    async fn find_intersect(&mut self, points: Vec<Point>) -> Result<Option<Point>, Error> {
+        let (point, _) = self
+            .0
+            .chainsync()
+            .find_intersect(points)
+            .await
+            .map_err(Error::network)?;
+
+        Ok(point)
+    }
+
+    async fn intersect_tip(&mut self) -> Result<Point, Error> {
+        self.0
+            .chainsync()
+            .intersect_tip()
+            .await
+            .map_err(Error::network)
+    }
+
+    async fn next_event(&mut self) -> Result<ChainEvent, Error> {
+        let chainsync = self.0.chainsync();
+
+        let next = match chainsync.request_next().await.map_err(Error::network)? {
+            NextResponse::Await => chainsync
+                .recv_while_must_reply()
+                .await
+                .map_err(Error::network)?,
+            x => x,
+        };
+
+        match next {
+            NextResponse::RollForward(header, _) => {
+                let subtag = header.byron_prefix.map(|(x, _)| x);
+                let header = MultiEraHeader::decode(header.variant, subtag, &header.cbor)?;
+                let point = Point::Specific(header.slot(), header.hash().to_vec());
+
+                let block = self
+                    .0
+                    .blockfetch()
+                    .fetch_single(point)
+                    .await
+                    .map_err(Error::network)?;
+
+                Ok(ChainEvent::Apply(block))
+            }
+            NextResponse::RollBackward(point, _) => Ok(ChainEvent::Reset(point)),
+            NextResponse::Await => Err(Error::network("unexpected await from peer")),
+        }
+    }
+
+    async fn fetch_block(&mut self, point: Point) -> Result<Option<Vec<u8>>, Error> {
+        let block = self
+            .0
+            .blockfetch()
+            .fetch_single(point)
+            .await
+            .map_err(Error::network)?;
+
+        Ok(Some(block))
+    }
+}
+
+/// The era of the tx as numbered by the hard fork combinator, which starts
+/// from Byron as zero instead of the one used to tag blocks
+fn era_index(tx: &PendingTx) -> u16 {
+    u16::from(tx.era) - 1
+}
+
+fn era_tx_id(tx: &PendingTx) -> EraTxId {
+    EraTxId(era_index(tx), tx.hash.to_vec())
+}
+
+#[async_trait]
+impl TxSubmitter for PeerSource {
+    async fn submit(&mut self, txs: &[PendingTx]) -> Result<(), Error> {
+        let client = self.0.txsubmission();
+        client.send_init().await.map_err(Error::network)?;
+
+        let mut pending: VecDeque<_> = txs.iter().collect();
+        let mut unacked: VecDeque<&PendingTx> = VecDeque::new();
+
+        loop {
+            let (blocking, ack, req) = match client.next_request().await.map_err(Error::network)? {
+                Request::TxIds(ack, req) => (true, ack, req),
+                Request::TxIdsNonBlocking(ack, req) => (false, ack, req),
+                Request::Txs(ids) => {
+                    let bodies = ids
+                        .iter()
+                        .filter_map(|id| unacked.iter().find(|tx| *tx.hash == *id.1))
+                        .map(|tx| EraTxBody(era_index(tx), tx.cbor.clone()))
+                        .collect();
+
+                    client.reply_txs(bodies).await.map_err(Error::network)?;
+                    continue;
+                }
+            };
+
+            let ack = (ack as usize).min(unacked.len());
+            unacked.drain(..ack);
+
+            // blocking requests can't be answered with an empty list, the peer
+            // only sends them once every tx announced so far is acknowledged
+            if blocking && pending.is_empty() && unacked.is_empty() {
+                client.send_done().await.map_err(Error::network)?;
+                return Ok(());
+            }
+
+            let count = (req as usize).min(pending.len());
+            let mut ids = vec![];
+
+            for tx in pending.drain(..count) {
+                ids.push(TxIdAndSize(era_tx_id(tx), tx.cbor.len() as u32));
+                unacked.push_back(tx);
+            }
+
+            client.reply_tx_ids(ids).await.map_err(Error::network)?;
+        }
+    }
+}
+
+impl Drop for PeerSource {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pallas_network::facades::PeerServer;
+    use pallas_network::miniprotocols::blockfetch::BlockRequest;
+    use pallas_network::miniprotocols::chainsync::{ClientRequest, HeaderContent, Tip};
+    use pallas_network::miniprotocols::txsubmission::Reply;
+    use pallas_traverse::MultiEraBlock;
+    use tokio::net::TcpListener;
+    use tonic::{Code, Request};
+    use utxorpc_spec::utxorpc::v1alpha::submit;
+
+    use super::*;
+    use crate::server::submit::{SubmitService, SubmitServiceImpl};
+
+    async fn mock_peer() -> (TcpListener, PeerConnector) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        (listener, PeerConnector::new(address, 0))
+    }
+
+    #[tokio::test]
+    async fn follows_the_chain_of_a_peer() {
+        let cbor = hex::decode(include_str!("../../../test_data/babbage1.block")).unwrap();
+        let block = MultiEraBlock::decode(&cbor).unwrap();
+        let header = block.header();
+
+        let point = Point::Specific(block.slot(), block.hash().to_vec());
+        let tip = Tip(point.clone(), block.number());
+
+        let (listener, connector) = mock_peer().await;
+
+        let server = tokio::spawn({
+            let (point, cbor) = (point.clone(), cbor.clone());
+            let content = HeaderContent {
+                variant: 5,
+                byron_prefix: None,
+                cbor: header.cbor().to_vec(),
+            };
+
+            async move {
+                let mut peer = PeerServer::accept(&listener, 0).await.unwrap();
+                let chainsync = peer.chainsync();
+
+                match chainsync.recv_while_idle().await.unwrap() {
+                    Some(ClientRequest::Intersect(points)) => {
+                        assert_eq!(points, vec![point.clone()])
+                    }
+                    _ => panic!("expected an intersect request"),
+                }
+
+                chainsync
+                    .send_intersect_found(point.clone(), tip.clone())
+                    .await
+                    .unwrap();
+
+                assert!(matches!(
+                    chainsync.recv_while_idle().await.unwrap(),
+                    Some(ClientRequest::RequestNext)
+                ));
+
+                chainsync
+                    .send_roll_backward(point.clone(), tip.clone())
+                    .await
+                    .unwrap();
+
+                assert!(matches!(
+                    chainsync.recv_while_idle().await.unwrap(),
+                    Some(ClientRequest::RequestNext)
+                ));
+
+                chainsync.send_roll_forward(content, tip).await.unwrap();
+
+                // the block is pulled once its header arrives
+                let BlockRequest(range) =
+                    peer.blockfetch().recv_while_idle().await.unwrap().unwrap();
+                assert_eq!(range, (point.clone(), point));
+
+                peer.blockfetch()
+                    .send_block_range(vec![cbor])
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let mut source = connector.connect().await.unwrap();
+
+        let found = source.find_intersect(vec![point.clone()]).await.unwrap();
+        assert_eq!(found, Some(point.clone()));
+
+        match source.next_event().await.unwrap() {
+            ChainEvent::Reset(x) => assert_eq!(x, point),
+            x => panic!("unexpected event {x:?}"),
+        }
+
+        match source.next_event().await.unwrap() {
+            ChainEvent::Apply(x) => assert_eq!(x, cbor),
+            x => panic!("unexpected event {x:?}"),
+        }
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn submits_txs_to_a_peer() {
+        let cbor = hex::decode(include_str!("../../../test_data/babbage11.tx")).unwrap();
+        let tx = PendingTx::decode(cbor.clone()).unwrap();
+
+        let (listener, connector) = mock_peer().await;
+
+        let server = tokio::spawn({
+            let tx = tx.clone();
+
+            async move {
+                let mut peer = PeerServer::accept(&listener, 0).await.unwrap();
+                let txsubmission = peer.txsubmission();
+
+                txsubmission.wait_for_init().await.unwrap();
+
+                txsubmission
+                    .acknowledge_and_request_tx_ids(true, 0, 10)
+                    .await
+                    .unwrap();
+
+                let ids = match txsubmission.receive_next_reply().await.unwrap() {
+                    Reply::TxIds(x) => x,
+                    _ => panic!("expected tx ids"),
+                };
+
+                // babbage is the fifth era after byron
+                assert_eq!(ids.len(), 1);
+                assert_eq!(ids[0].0, EraTxId(5, tx.hash.to_vec()));
+                assert_eq!(ids[0].1, tx.cbor.len() as u32);
+
+                let ids = ids.into_iter().map(|x| x.0).collect();
+                txsubmission.request_txs(ids).await.unwrap();
+
+                match txsubmission.receive_next_reply().await.unwrap() {
+                    Reply::Txs(bodies) => assert_eq!(bodies, vec![EraTxBody(5, tx.cbor)]),
+                    _ => panic!("expected tx bodies"),
+                }
+
+                txsubmission
+                    .acknowledge_and_request_tx_ids(true, 1, 10)
+                    .await
+                    .unwrap();
+
+                // the client hangs up right after its done message, which
+                // might not get flushed before the connection drops
+                assert!(matches!(
+                    txsubmission.receive_next_reply().await,
+                    Ok(Reply::Done) | Err(_)
+                ));
+            }
+        });
+
+        let service = SubmitServiceImpl::new(connector);
+
+        let any_tx = |cbor: Vec<u8>| submit::AnyChainTx {
+            r#type: submit::any_chain_tx::Type::Raw(cbor.into()).into(),
+        };
+
+        // invalid txs are rejected before connecting to the peer
+        let request = submit::SubmitTxRequest {
+            tx: vec![any_tx(vec![0x80])],
+        };
+
+        let invalid = service.submit_tx(Request::new(request)).await;
+        assert_eq!(invalid.unwrap_err().code(), Code::InvalidArgument);
+
+        let request = submit::SubmitTxRequest {
+            tx: vec![any_tx(cbor)],
+        };
+
+        let response = service.submit_tx(Request::new(request)).await.unwrap();
+        assert_eq!(response.into_inner().r#ref, vec![tx.hash.to_vec()]);
+
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,220 @@
+//! Chain source backed by a local block store
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use pallas_crypto::hash::Hash;
+use pallas_network::miniprotocols::Point;
+use pallas_storage::blocks::{self, BlockStore};
+
+use super::{ChainEvent, ChainSource, Connector, Error};
+
+/// How many of the points sent to a client are kept to find where to reset to
+/// after a rollback, matching the security parameter of mainnet
+const MAX_ROLLBACK: usize = 2160;
+
+/// Serves the chain kept in a [BlockStore]
+///
+/// The store doesn't notify about new blocks, sources following the tip poll
+/// it once they catch up.
+#[derive(Clone)]
+pub struct StoreConnector {
+    pub store: Arc<BlockStore>,
+    pub poll_interval: Duration,
+}
+
+impl StoreConnector {
+    pub fn new(store: Arc<BlockStore>) -> Self {
+        Self {
+            store,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for StoreConnector {
+    type Source = StoreSource;
+
+    async fn connect(&self) -> Result<Self::Source, Error> {
+        Ok(StoreSource {
+            store: self.store.clone(),
+            poll_interval: self.poll_interval,
+            cursor: None,
+            sent: VecDeque::new(),
+            reset: None,
+        })
+    }
+}
+
+pub struct StoreSource {
+    store: Arc<BlockStore>,
+    poll_interval: Duration,
+
+    /// the last point sent to the client, none for the origin
+    cursor: Option<blocks::Point>,
+
+    /// the latest points sent to the client, to find where to reset to
+    sent: VecDeque<blocks::Point>,
+
+    /// a reset waiting to be sent to the client
+    reset: Option<Point>,
+}
+
+fn to_store_point(point: &Point) -> Option<blocks::Point> {
+    match point {
+        Point::Specific(slot, hash) => {
+            let hash: [u8; 32] = hash.as_slice().try_into().ok()?;
+            Some((*slot, Hash::from(hash)))
+        }
+        Point::Origin => None,
+    }
+}
+
+fn from_store_point((slot, hash): &blocks::Point) -> Point {
+    Point::Specific(*slot, hash.to_vec())
+}
+
+impl StoreSource {
+    fn start_at(&mut self, point: Option<blocks::Point>) {
+        self.cursor = point;
+        self.sent = point.into_iter().collect();
+        self.reset = Some(
+            point
+                .as_ref()
+                .map(from_store_point)
+                .unwrap_or(Point::Origin),
+        );
+    }
+
+    /// the point of the chain that comes after the cursor, if any yet
+    fn next_point(&self) -> Result<Option<blocks::Point>, Error> {
+        let next = match self.cursor {
+            None => self.store.chain_page(0, 1)?.into_iter().next(),
+            Some(cursor) => {
+                // blocks sharing the slot of the cursor come before it
+                let page = self.store.chain_page(cursor.0, 4)?;
+
+                page.into_iter().skip_while(|x| *x != cursor).nth(1)
+            }
+        };
+
+        Ok(next)
+    }
+
+    /// moves the cursor back to the latest point still part of the chain
+    fn rollback(&mut self) -> Result<Point, Error> {
+        while let Some(point) = self.sent.pop_back() {
+            if self.store.contains(point.0, &point.1)? {
+                self.sent.push_back(point);
+                self.cursor = Some(point);
+                return Ok(from_store_point(&point));
+            }
+        }
+
+        self.cursor = None;
+
+        Ok(Point::Origin)
+    }
+}
+
+#[async_trait]
+impl ChainSource for StoreSource {
+    async fn find_intersect(&mut self, points: Vec<Point>) -> Result<Option<Point>, Error> {
+        for point in points {
+            if point == Point::Origin {
+                self.start_at(None);
+                return Ok(Some(point));
+            }
+
+            if let Some(x) = to_store_point(&point) {
+                if self.store.contains(x.0, &x.1)? {
+                    self.start_at(Some(x));
+                    return Ok(Some(point));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn intersect_tip(&mut self) -> Result<Point, Error> {
+        let tip = self.store.tip()?;
+        self.start_at(tip);
+
+        Ok(tip.as_ref().map(from_store_point).unwrap_or(Point::Origin))
+    }
+
+    async fn next_event(&mut self) -> Result<ChainEvent, Error> {
+        if let Some(point) = self.reset.take() {
+            return Ok(ChainEvent::Reset(point));
+        }
+
+        loop {
+            if let Some((slot, hash)) = self.cursor {
+                if !self.store.contains(slot, &hash)? {
+                    return Ok(ChainEvent::Reset(self.rollback()?));
+                }
+            }
+
+            let point = match self.next_point()? {
+                Some(x) => x,
+                None => {
+                    tokio::time::sleep(self.poll_interval).await;
+                    continue;
+                }
+            };
+
+            let cbor = self
+                .store
+                .get_block(&point.1)?
+                .ok_or_else(|| Error::BlockNotFound(from_store_point(&point)))?;
+
+            self.cursor = Some(point);
+            self.sent.push_back(point);
+
+            if self.sent.len() > MAX_ROLLBACK {
+                self.sent.pop_front();
+            }
+
+            return Ok(ChainEvent::Apply(cbor));
+        }
+    }
+
+    async fn fetch_block(&mut self, point: Point) -> Result<Option<Vec<u8>>, Error> {
+        match to_store_point(&point) {
+            Some((_, hash)) => Ok(self.store.get_block(&hash)?),
+            None => Ok(None),
+        }
+    }
+
+    async fn read_history(
+        &mut self,
+        start: Option<Point>,
+        max: usize,
+    ) -> Result<(Vec<Vec<u8>>, Option<Point>), Error> {
+        let slot = start.as_ref().map(|x| x.slot_or_default()).unwrap_or(0);
+
+        let mut points = self.store.chain_page(slot, max + 1)?;
+
+        let next = match points.len() > max {
+            true => points.pop().as_ref().map(from_store_point),
+            false => None,
+        };
+
+        let mut blocks = vec![];
+
+        for point in points {
+            let cbor = self
+                .store
+                .get_block(&point.1)?
+                .ok_or_else(|| Error::BlockNotFound(from_store_point(&point)))?;
+
+            blocks.push(cbor);
+        }
+
+        Ok((blocks, next))
+    }
+}
@@ -0,0 +1,117 @@
+//! UTxO RPC submit service backed by pallas components
+//!
+//! Txs are forwarded through the same sources that serve chain-sync, as long
+//! as they know how to submit them ([TxSubmitter]). Only peers do for now,
+//! the node-to-client tx submission protocol is not available yet. Neither
+//! the mempool queries nor the waits are supported.
+
+use async_trait::async_trait;
+use pallas_crypto::hash::Hash;
+use pallas_traverse::{Era, MultiEraTx};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use utxorpc_spec::utxorpc::v1alpha::submit;
+
+use super::{Connector, Error};
+
+pub use submit::submit_service_server::{SubmitService, SubmitServiceServer};
+
+/// A tx waiting to be submitted
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+    pub era: Era,
+    pub hash: Hash<32>,
+    pub cbor: Vec<u8>,
+}
+
+impl PendingTx {
+    /// Decodes a tx in the format of the current era
+    pub fn decode(cbor: Vec<u8>) -> Result<Self, Error> {
+        let tx =
+            MultiEraTx::decode(Era::Babbage, &cbor).map_err(|e| Error::InvalidTx(e.to_string()))?;
+
+        Ok(Self {
+            era: tx.era(),
+            hash: tx.hash(),
+            cbor,
+        })
+    }
+}
+
+/// A [ChainSource](super::ChainSource) that can also submit txs
+#[async_trait]
+pub trait TxSubmitter: Send + 'static {
+    /// Submits the txs, returning once the other end acknowledged all of them
+    async fn submit(&mut self, txs: &[PendingTx]) -> Result<(), Error>;
+}
+
+fn decode_any_tx(x: &submit::AnyChainTx) -> Result<PendingTx, Error> {
+    match &x.r#type {
+        Some(submit::any_chain_tx::Type::Raw(cbor)) => PendingTx::decode(cbor.to_vec()),
+        None => Err(Error::InvalidTx("missing tx content".into())),
+    }
+}
+
+/// Implementation of the submit gRPC service
+///
+/// Wrap it with [SubmitServiceServer] to add it to a tonic server.
+pub struct SubmitServiceImpl<C> {
+    connector: C,
+}
+
+impl<C: Connector> SubmitServiceImpl<C> {
+    pub fn new(connector: C) -> Self {
+        Self { connector }
+    }
+}
+
+#[async_trait]
+impl<C> SubmitService for SubmitServiceImpl<C>
+where
+    C: Connector,
+    C::Source: TxSubmitter,
+{
+    async fn submit_tx(
+        &self,
+        request: Request<submit::SubmitTxRequest>,
+    ) -> Result<Response<submit::SubmitTxResponse>, Status> {
+        let txs = request
+            .into_inner()
+            .tx
+            .iter()
+            .map(decode_any_tx)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut source = self.connector.connect().await?;
+        source.submit(&txs).await?;
+
+        Ok(Response::new(submit::SubmitTxResponse {
+            r#ref: txs.iter().map(|x| x.hash.to_vec().into()).collect(),
+        }))
+    }
+
+    type WaitForTxStream = ReceiverStream<Result<submit::WaitForTxResponse, Status>>;
+
+    async fn wait_for_tx(
+        &self,
+        _request: Request<submit::WaitForTxRequest>,
+    ) -> Result<Response<Self::WaitForTxStream>, Status> {
+        Err(Error::Unsupported.into())
+    }
+
+    async fn read_mempool(
+        &self,
+        _request: Request<submit::ReadMempoolRequest>,
+    ) -> Result<Response<submit::ReadMempoolResponse>, Status> {
+        Err(Error::Unsupported.into())
+    }
+
+    type WatchMempoolStream = ReceiverStream<Result<submit::WatchMempoolResponse, Status>>;
+
+    async fn watch_mempool(
+        &self,
+        _request: Request<submit::WatchMempoolRequest>,
+    ) -> Result<Response<Self::WatchMempoolStream>, Status> {
+        Err(Error::Unsupported.into())
+    }
+}
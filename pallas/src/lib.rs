@@ -23,6 +23,15 @@ pub mod ledger {
 
     #[doc(inline)]
     pub use pallas_addresses as addresses;
+
+    #[doc(inline)]
+    pub use pallas_configs as configs;
+
+    #[doc(inline)]
+    pub use pallas_applying as applying;
+
+    #[doc(inline)]
+    pub use pallas_storage as storage;
 }
 
 #[doc(inline)]